@@ -0,0 +1,209 @@
+//! Client for `graph_db-server`
+//!
+//! ```no_run
+//! use graph_db_client::Client;
+//!
+//! let mut client = Client::connect("127.0.0.1:7687")?;
+//! let sisli = client.add_node("sisli")?;
+//! client.add_label(&sisli, "district")?;
+//! let result = client.query("MATCH (d:district) RETURN d")?;
+//! assert_eq!(result.column("d"), Some(vec![sisli]));
+//! # Ok::<(), graph_db::vec_graph::Error>(())
+//! ```
+//!
+//! Errors raised by the server come back as the same [`Error`] variants the graph returns locally.
+//! A client is one connection, open one per thread to run requests concurrently.
+
+use std::{
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+use graph_db::{
+    json::Json,
+    query::QueryResult,
+    server::protocol::{self, Request},
+    shell::Output,
+    vec_graph::{EdgeIndex, Error, GraphResult, Node, NodeIndex, PropValue},
+};
+
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+    next_id: i64,
+}
+
+fn io_error<S: Into<String>>(context: S) -> impl FnOnce(io::Error) -> Error {
+    move |source| Error::Io {
+        context: context.into(),
+        source,
+    }
+}
+
+fn unexpected(request: &Request, reply: &Json) -> Error {
+    Error::Parse {
+        pos: None,
+        message: format!("unexpected reply {} to {}", reply, request.to_json()),
+    }
+}
+
+impl Client {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> GraphResult<Self> {
+        let stream = TcpStream::connect(addr).map_err(io_error("connecting to server"))?;
+        let reader = stream
+            .try_clone()
+            .map_err(io_error("connecting to server"))?;
+        Ok(Client {
+            reader: BufReader::new(reader),
+            writer: BufWriter::new(stream),
+            next_id: 0,
+        })
+    }
+
+    /// Sends one request and waits for its reply
+    pub fn send(&mut self, request: &Request) -> GraphResult<Json> {
+        self.next_id += 1;
+        let mut message = request.to_json();
+        if let Json::Object(fields) = &mut message {
+            fields.insert("id".to_owned(), Json::Int(self.next_id));
+        }
+        writeln!(self.writer, "{}", message).map_err(io_error("sending request"))?;
+        self.writer.flush().map_err(io_error("sending request"))?;
+        let mut line = String::new();
+        let read = self
+            .reader
+            .read_line(&mut line)
+            .map_err(io_error("reading reply"))?;
+        if read == 0 {
+            return Err(io_error("reading reply")(
+                io::ErrorKind::UnexpectedEof.into(),
+            ));
+        }
+        let (id, result) = protocol::parse_response(&Json::parse(&line)?)?;
+        if id.as_i64() != Some(self.next_id) {
+            return Err(Error::Parse {
+                pos: None,
+                message: format!("reply {} does not answer request {}", id, self.next_id),
+            });
+        }
+        result
+    }
+
+    pub fn ping(&mut self) -> GraphResult<()> {
+        self.send(&Request::Ping).map(|_| ())
+    }
+    pub fn query(&mut self, text: &str) -> GraphResult<QueryResult> {
+        let reply = self.send(&Request::Query {
+            text: text.to_owned(),
+        })?;
+        protocol::result_from_json(&reply)
+    }
+    /// Runs a shell line (`ADD`, `LINK`, `:stats`, ...) as the REPL would
+    pub fn execute(&mut self, line: &str) -> GraphResult<Output> {
+        let reply = self.send(&Request::Execute {
+            line: line.to_owned(),
+        })?;
+        protocol::output_from_json(&reply)
+    }
+
+    pub fn add_node(&mut self, alias: &str) -> GraphResult<NodeIndex> {
+        let reply = self.send(&Request::AddNode {
+            alias: alias.to_owned(),
+        })?;
+        protocol::parse_id(&reply)
+    }
+    pub fn remove_node(&mut self, node: &NodeIndex) -> GraphResult<()> {
+        self.send(&Request::RemoveNode { node: *node }).map(|_| ())
+    }
+    pub fn add_label(&mut self, node: &NodeIndex, label: &str) -> GraphResult<()> {
+        self.send(&Request::AddLabel {
+            node: *node,
+            label: label.to_owned(),
+        })
+        .map(|_| ())
+    }
+    pub fn remove_label(&mut self, node: &NodeIndex, label: &str) -> GraphResult<()> {
+        self.send(&Request::RemoveLabel {
+            node: *node,
+            label: label.to_owned(),
+        })
+        .map(|_| ())
+    }
+    pub fn add_prop<V: Into<PropValue>>(
+        &mut self,
+        node: &NodeIndex,
+        key: &str,
+        value: V,
+    ) -> GraphResult<()> {
+        self.send(&Request::AddProp {
+            node: *node,
+            key: key.to_owned(),
+            value: value.into(),
+        })
+        .map(|_| ())
+    }
+    pub fn remove_prop(&mut self, node: &NodeIndex, key: &str) -> GraphResult<()> {
+        self.send(&Request::RemoveProp {
+            node: *node,
+            key: key.to_owned(),
+        })
+        .map(|_| ())
+    }
+
+    pub fn add_edge(
+        &mut self,
+        relation: &str,
+        from: &NodeIndex,
+        to: &NodeIndex,
+    ) -> GraphResult<EdgeIndex> {
+        let reply = self.send(&Request::AddEdge {
+            relation: relation.to_owned(),
+            from: *from,
+            to: *to,
+        })?;
+        protocol::parse_id(&reply)
+    }
+    /// Links every node aliased `from` to every node aliased `to`, returns the number of new edges
+    pub fn link(&mut self, relation: &str, from: &str, to: &str) -> GraphResult<usize> {
+        let request = Request::Link {
+            relation: relation.to_owned(),
+            from: from.to_owned(),
+            to: to.to_owned(),
+        };
+        let reply = self.send(&request)?;
+        reply
+            .as_i64()
+            .map(|n| n as usize)
+            .ok_or_else(|| unexpected(&request, &reply))
+    }
+    pub fn remove_edge(&mut self, edge: &EdgeIndex) -> GraphResult<()> {
+        self.send(&Request::RemoveEdge { edge: *edge }).map(|_| ())
+    }
+
+    /// Copy of the node with its labels and props
+    pub fn get_node(&mut self, node: &NodeIndex) -> GraphResult<Node> {
+        let reply = self.send(&Request::GetNode { node: *node })?;
+        protocol::node_from_json(&reply)
+    }
+    pub fn nodes_by_alias(&mut self, alias: &str) -> GraphResult<Vec<NodeIndex>> {
+        let reply = self.send(&Request::NodesByAlias {
+            alias: alias.to_owned(),
+        })?;
+        protocol::ids_from_json(&reply)
+    }
+    pub fn nodes_by_label(&mut self, label: &str) -> GraphResult<Vec<NodeIndex>> {
+        let reply = self.send(&Request::NodesByLabel {
+            label: label.to_owned(),
+        })?;
+        protocol::ids_from_json(&reply)
+    }
+    /// Node and edge counts
+    pub fn stats(&mut self) -> GraphResult<(usize, usize)> {
+        let request = Request::Stats;
+        let reply = self.send(&request)?;
+        let count = |key| reply.get(key).and_then(Json::as_i64).map(|n| n as usize);
+        count("nodes")
+            .zip(count("edges"))
+            .ok_or_else(|| unexpected(&request, &reply))
+    }
+}
@@ -0,0 +1,134 @@
+use std::{net::SocketAddr, thread};
+
+use graph_db::{
+    server::{Server, ServerHandle},
+    shell::Output,
+    vec_graph::*,
+};
+use graph_db_client::Client;
+
+fn start() -> (ServerHandle, SocketAddr) {
+    let graph = SharedGraph::new(Graph::new());
+    let server = Server::bind("127.0.0.1:0", graph).unwrap().spawn().unwrap();
+    let addr = server.addr();
+    (server, addr)
+}
+
+#[test]
+fn mutations_and_reads() {
+    let (server, addr) = start();
+    let mut client = Client::connect(addr).unwrap();
+    client.ping().unwrap();
+
+    let sisli = client.add_node("sisli").unwrap();
+    let besiktas = client.add_node("besiktas").unwrap();
+    client.add_label(&sisli, "district").unwrap();
+    client.add_label(&besiktas, "district").unwrap();
+    client.add_prop(&sisli, "population", 274420).unwrap();
+    let coords = PropValue::List(vec![41.06.into(), 28.98.into()]);
+    client.add_prop(&sisli, "coords", coords.clone()).unwrap();
+    client.add_prop(&sisli, "sign", vec![0xffu8, 0]).unwrap();
+    let edge = client.add_edge("komsu", &sisli, &besiktas).unwrap();
+
+    let node = client.get_node(&sisli).unwrap();
+    assert_eq!(node.get_prop("population"), Some(&PropValue::Int(274420)));
+    assert_eq!(node.get_prop("coords"), Some(&coords));
+    assert_eq!(
+        node.get_prop("sign"),
+        Some(&PropValue::Bytes(vec![0xff, 0]))
+    );
+    assert_eq!(
+        client.nodes_by_label("district").unwrap(),
+        [sisli, besiktas]
+    );
+    assert_eq!(client.stats().unwrap(), (2, 1));
+
+    client.remove_edge(&edge).unwrap();
+    client.remove_prop(&sisli, "coords").unwrap();
+    client.remove_label(&besiktas, "district").unwrap();
+    assert_eq!(client.nodes_by_label("district").unwrap(), [sisli]);
+    assert_eq!(client.stats().unwrap(), (2, 0));
+    server.shutdown();
+}
+
+#[test]
+fn query_and_link() {
+    let (server, addr) = start();
+    let mut client = Client::connect(addr).unwrap();
+    client.execute("add (a:first), (b:stop), (c:stop)").unwrap();
+    assert_eq!(client.link("next", "a", "b").unwrap(), 1);
+    assert_eq!(client.link("next", "b", "c").unwrap(), 1);
+
+    let result = client
+        .query("MATCH p = (x:first)-[:next*]->(y) RETURN y, p")
+        .unwrap();
+    let ids = |alias| client_ids(addr, alias);
+    assert_eq!(result.column("y"), Some(vec![ids("b"), ids("c")]));
+    let paths = result.path_column("p").unwrap();
+    assert_eq!(paths[1].nodes, [ids("a"), ids("b"), ids("c")]);
+
+    match client.execute(":stats").unwrap() {
+        Output::Table(table) => assert_eq!(table.rows[0], ["nodes", "3"]),
+        other => panic!("expected a table, got {:?}", other),
+    }
+    server.shutdown();
+}
+
+fn client_ids(addr: SocketAddr, alias: &str) -> NodeIndex {
+    Client::connect(addr)
+        .unwrap()
+        .nodes_by_alias(alias)
+        .unwrap()[0]
+}
+
+#[test]
+fn typed_errors() {
+    let (server, addr) = start();
+    let mut client = Client::connect(addr).unwrap();
+    let missing = NodeIndex::from_parts(4, 2);
+    assert!(matches!(
+        client.get_node(&missing),
+        Err(Error::NodeNotFound { id }) if id == missing
+    ));
+    assert!(matches!(
+        client.link("r", "nobody", "nowhere"),
+        Err(Error::AliasNotFound { alias }) if alias == "nobody"
+    ));
+    assert!(matches!(
+        client.query("MATCH (n RETURN n"),
+        Err(Error::Parse { pos: Some(_), .. })
+    ));
+    //the connection survives failed requests
+    assert_eq!(client.nodes_by_alias("nobody").unwrap(), []);
+    server.shutdown();
+}
+
+#[test]
+fn concurrent_clients_share_graph() {
+    let (server, addr) = start();
+    Client::connect(addr).unwrap().add_node("hub").unwrap();
+    let workers: Vec<_> = (0..8)
+        .map(|t| {
+            thread::spawn(move || {
+                let mut client = Client::connect(addr).unwrap();
+                for i in 0..20 {
+                    let alias = format!("w{}_{}", t, i);
+                    let node = client.add_node(&alias).unwrap();
+                    client.add_label(&node, "worker").unwrap();
+                    client.link("reports", &alias, "hub").unwrap();
+                    client.query("MATCH (w:worker) RETURN w").unwrap();
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    let mut client = Client::connect(addr).unwrap();
+    assert_eq!(client.stats().unwrap(), (161, 160));
+    let result = client
+        .query("MATCH (w:worker)-[:reports]->(h) RETURN w")
+        .unwrap();
+    assert_eq!(result.rows.len(), 160);
+    server.shutdown();
+}
@@ -0,0 +1,97 @@
+//! Pointer adjacency vs index adjacency: the same graph in `vec_graph`, `vgraph` and
+//! `unsafe_graph`, walked breadth first through `GraphStore` and through each backend's own links
+//!
+//! `cargo run --release --example adjacency [nodes]`
+
+use std::{collections::VecDeque, hint::black_box, time::Instant};
+
+use graph_db::{
+    algo::Bfs,
+    store::GraphStore,
+    unsafe_graph, vec_graph,
+    vec_graph::{GraphResult, NodeIndex},
+    vgraph,
+};
+
+const ROUNDS: u32 = 10;
+
+/// Every node links to three others spread over the graph
+fn build<G: GraphStore + ?Sized>(graph: &mut G, nodes: usize) -> GraphResult<Vec<NodeIndex>> {
+    let ids = (0..nodes)
+        .map(|i| graph.create_node(&format!("n{}", i)))
+        .collect::<GraphResult<Vec<_>>>()?;
+    for i in 0..nodes {
+        for to in [(i + 1) % nodes, (i * 7 + 1) % nodes, (i * 13 + 5) % nodes] {
+            graph.create_edge("r", &ids[i], &ids[to])?;
+        }
+    }
+    Ok(ids)
+}
+
+fn time(name: &str, mut walk: impl FnMut() -> usize) {
+    let start = Instant::now();
+    let mut visited = 0;
+    for _ in 0..ROUNDS {
+        visited = black_box(walk());
+    }
+    println!(
+        "{:<28} {:>10.2?} per walk, {} nodes",
+        name,
+        start.elapsed() / ROUNDS,
+        visited
+    );
+}
+
+fn main() -> GraphResult<()> {
+    let nodes = std::env::args()
+        .nth(1)
+        .map(|n| n.parse().expect("node count"))
+        .unwrap_or(100_000);
+
+    let mut vec = vec_graph::Graph::new();
+    let start = build(&mut vec, nodes)?[0];
+    let mut vgraph = vgraph::Graph::new();
+    build(&mut vgraph, nodes)?;
+    let mut pointers = unsafe_graph::Graph::new();
+    build(&mut pointers, nodes)?;
+
+    time("vec_graph Bfs", || Bfs::new(&vec, start).unwrap().count());
+    time("vgraph Bfs", || Bfs::new(&vgraph, start).unwrap().count());
+    time("unsafe_graph Bfs", || {
+        Bfs::new(&pointers, start).unwrap().count()
+    });
+
+    //the backends' own links, with a slot indexed visited list instead of Bfs' hash set
+    time("vgraph links (index)", || {
+        let mut seen = vec![false; nodes];
+        let mut queue = VecDeque::from([vgraph.get_node("n0").unwrap()]);
+        seen[0] = true;
+        let mut visited = 0;
+        while let Some(node) = queue.pop_front() {
+            visited += 1;
+            for link in node.links() {
+                if !std::mem::replace(&mut seen[link.to().index()], true) {
+                    queue.push_back(vgraph.get_node_by_id(link.to()).unwrap());
+                }
+            }
+        }
+        visited
+    });
+    time("unsafe_graph links (pointer)", || {
+        let mut seen = vec![false; nodes];
+        let mut queue = VecDeque::from([pointers.get_node("n0").unwrap()]);
+        seen[0] = true;
+        let mut visited = 0;
+        while let Some(node) = queue.pop_front() {
+            visited += 1;
+            for link in node.links() {
+                let next = link.target();
+                if !std::mem::replace(&mut seen[next.id().index()], true) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        visited
+    });
+    Ok(())
+}
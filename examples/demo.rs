@@ -0,0 +1,87 @@
+use graph_db::vec_graph;
+
+fn main() -> vec_graph::GraphResult<()> {
+    {
+        use vec_graph::Graph;
+
+        let mut graph = Graph::new();
+        graph
+            .add_node("sisli")?
+            .mut_last_node(|sisli| sisli.add_label("sehir")?.add_prop("tur", "ilce"))?;
+
+        println!("{}", graph);
+
+        //all or nothing, a failing step leaves the graph as it was printed above
+        graph.transaction(|tx| {
+            tx.add_node("mcdkoy")?
+                .mut_last_node(|mcd| {
+                    mcd.add_label("mahalle")?
+                        .add_label("bolge")?
+                        .add_prop("tur", "mahalle")
+                })?
+                .add_node("merkez")?
+                .mut_last_node(|merkez| {
+                    merkez
+                        .add_label("mahalle")?
+                        .add_label("bolge")?
+                        .add_prop("tur", "mahalle")
+                })?
+                .add_edges_by_aliases("includes", "sisli", "merkez")?
+                .add_edges_by_aliases("includes", "sisli", "mcdkoy")?
+                .add_node("merkez")?
+                .mut_last_node(|merkez| {
+                    merkez
+                        .add_label("mahalle")?
+                        .add_label("diger")?
+                        .add_prop("tur", "mahalle")?
+                        .add_prop("test", "alternatif")?
+                        .add_prop("test", "alt")
+                })?
+                .add_edges_by_aliases("ayni", "merkez", "merkez")?
+                .add_edges_by_aliases("komsu", "merkez", "mcdkoy")?
+                .add_edges_by_aliases("komsu", "mcdkoy", "merkez")?;
+            Ok(())
+        })?;
+
+        println!("{}", graph);
+
+        let target_alias = "sisli";
+        println!(
+            "outgoings neighbors of {} ->\n{:#?}",
+            target_alias,
+            graph.get_outgoing_neighbors(
+                graph
+                    .get_nodes_by_alias(target_alias)
+                    .unwrap_or_else(|| panic!("cant find {}?", target_alias))
+                    .first()
+                    .expect("no first in vec?")
+            )
+        );
+
+        println!(
+            "incoming neighbors of {} ->\n{:#?}",
+            target_alias,
+            graph.get_outgoing_neighbors(
+                graph
+                    .get_nodes_by_alias(target_alias)
+                    .unwrap_or_else(|| panic!("cant find {}?", target_alias))
+                    .first()
+                    .expect("no first in vec?")
+            )
+        );
+
+        let result = graph.query(
+            r#"MATCH (s:sehir {tur:"ilce"})-[:includes]->(m)-[:komsu]->(n:mahalle) RETURN m, n"#,
+        )?;
+        for row in result.rows.iter() {
+            println!(
+                "{} includes {} which neighbors {}",
+                target_alias,
+                graph.get_alias_by_id(&row[0]).unwrap_or("?"),
+                graph.get_alias_by_id(&row[1]).unwrap_or("?"),
+            );
+        }
+
+        Ok(())
+    }
+}
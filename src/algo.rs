@@ -0,0 +1,16 @@
+//! Graph algorithms over any [`GraphStore`](crate::store::GraphStore), [`vec_graph::Graph`](crate::vec_graph::Graph) unless told otherwise
+//!
+//! - [`Bfs`] and [`Dfs`]: lazy traversals from a start node, by direction, relation and depth
+//! - [`VarLengthPaths`]: every path from a start node within a hop range, without repeated edges or nodes
+//! - [`ShortestPath`]: fewest hops, Dijkstra or A* between two nodes, returned as a [`Path`]
+//!
+//! Weight and heuristic callbacks see `vec_graph` [`Edge`](crate::vec_graph::Edge)/[`Node`](crate::vec_graph::Node)s
+//! on every backend: borrowed from a `vec_graph::Graph`, copied from the others
+
+mod expand;
+mod path;
+mod traverse;
+
+pub use expand::{Uniqueness, VarLengthPaths};
+pub use path::{Path, ShortestPath, Step, Weight};
+pub use traverse::{Bfs, Dfs, Direction, Visit};
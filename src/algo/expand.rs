@@ -0,0 +1,179 @@
+use std::ops::{Bound, RangeBounds};
+
+use super::path::Path;
+use super::traverse::{Direction, Walk};
+use crate::store::{require_node, GraphStore};
+use crate::vec_graph::{EdgeIndex, Graph, GraphResult, NodeIndex};
+
+/// What a variable length path may not repeat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Uniqueness {
+    /// Every edge at most once, nodes can come back (cycles)
+    #[default]
+    Edges,
+    /// Every node at most once, start included, which rules out repeated edges too
+    Nodes,
+}
+
+/// Edges left to try from one node of the current path
+struct Frame {
+    steps: Vec<(EdgeIndex, NodeIndex)>,
+    next: usize,
+}
+
+/// Every path from a start node with a hop count in range, depth first
+///
+/// Lazy like [`Dfs`](super::Dfs), but yields paths instead of nodes,
+/// so a node reachable in several ways shows up once per way
+/// ```
+/// # use graph_db::vec_graph::Graph;
+/// let mut graph = Graph::new();
+/// graph
+///     .add_node("istanbul")?.add_node("sisli")?.add_node("merkez")?
+///     .add_edges_by_aliases("includes", "istanbul", "sisli")?
+///     .add_edges_by_aliases("includes", "sisli", "merkez")?;
+/// let istanbul = graph.get_ids_by_alias("istanbul").unwrap()[0];
+/// let ends: Vec<&str> = graph
+///     .paths_from(istanbul)?
+///     .relations(["includes"])
+///     .hops(1..=3)
+///     .map(|p| p.end().alias.as_str())
+///     .collect();
+/// assert_eq!(ends, vec!["sisli", "merkez"]);
+/// # Ok::<(), graph_db::vec_graph::Error>(())
+/// ```
+pub struct VarLengthPaths<'g, G: GraphStore + ?Sized = Graph> {
+    graph: &'g G,
+    walk: Walk,
+    min: usize,
+    max: Option<usize>,
+    uniqueness: Uniqueness,
+    nodes: Vec<NodeIndex>,
+    edges: Vec<EdgeIndex>,
+    frames: Vec<Frame>,
+    started: bool,
+}
+
+impl<'g, G: GraphStore + ?Sized> VarLengthPaths<'g, G> {
+    /// Fails with `NodeNotFound` if `start` isn't in the graph
+    pub fn new(graph: &'g G, start: NodeIndex) -> GraphResult<Self> {
+        require_node(graph, &start)?;
+        Ok(VarLengthPaths {
+            graph,
+            walk: Walk::default(),
+            min: 1,
+            max: None,
+            uniqueness: Uniqueness::default(),
+            nodes: vec![start],
+            edges: Vec::new(),
+            frames: Vec::new(),
+            started: false,
+        })
+    }
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.walk.direction = direction;
+        self
+    }
+    /// Only follow edges with one of `relations`
+    pub fn relations<I, S>(mut self, relations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.walk.relations = relations.into_iter().map(Into::into).collect();
+        self
+    }
+    /// Hop counts to yield, `1..` by default. `0` yields the start node alone as a path
+    pub fn hops<R: RangeBounds<usize>>(mut self, range: R) -> Self {
+        self.min = match range.start_bound() {
+            Bound::Included(n) => *n,
+            Bound::Excluded(n) => n + 1,
+            Bound::Unbounded => 0,
+        };
+        self.max = match range.end_bound() {
+            Bound::Included(n) => Some(*n),
+            //`..0` is empty, 1 > 0 makes sure nothing is yielded
+            Bound::Excluded(0) => {
+                self.min = 1;
+                Some(0)
+            }
+            Bound::Excluded(n) => Some(n - 1),
+            Bound::Unbounded => None,
+        };
+        self
+    }
+    pub fn uniqueness(mut self, uniqueness: Uniqueness) -> Self {
+        self.uniqueness = uniqueness;
+        self
+    }
+
+    /// Pushes the edges the path can continue with from its last node
+    fn expand(&mut self) {
+        let steps = if self.max.is_none_or(|max| self.edges.len() < max) {
+            let at = *self.nodes.last().expect("Paths have a start node");
+            self.walk.steps(self.graph, at)
+        } else {
+            Vec::new()
+        };
+        self.frames.push(Frame { steps, next: 0 });
+    }
+    #[inline]
+    fn allows(&self, edge: EdgeIndex, next: NodeIndex) -> bool {
+        match self.uniqueness {
+            Uniqueness::Edges => !self.edges.contains(&edge),
+            Uniqueness::Nodes => !self.nodes.contains(&next),
+        }
+    }
+    fn path(&self) -> Path<'g, G> {
+        Path::new(
+            self.graph,
+            self.nodes[0],
+            &self.edges,
+            self.edges.len() as f64,
+        )
+    }
+}
+
+impl<'g, G: GraphStore + ?Sized> Iterator for VarLengthPaths<'g, G> {
+    type Item = Path<'g, G>;
+    fn next(&mut self) -> Option<Path<'g, G>> {
+        if !self.started {
+            self.started = true;
+            if self.max.is_some_and(|max| max < self.min) {
+                return None;
+            }
+            self.expand();
+            if self.min == 0 {
+                return Some(self.path());
+            }
+        }
+        loop {
+            let frame = self.frames.last_mut()?;
+            let Some(&(edge, next)) = frame.steps.get(frame.next) else {
+                //every way on from the last node is done, step back
+                self.frames.pop();
+                self.nodes.pop();
+                self.edges.pop();
+                continue;
+            };
+            frame.next += 1;
+            if !self.allows(edge, next) {
+                continue;
+            }
+            self.edges.push(edge);
+            self.nodes.push(next);
+            self.expand();
+            if self.edges.len() >= self.min {
+                return Some(self.path());
+            }
+        }
+    }
+}
+
+impl Graph {
+    /// Variable length paths from `start`, see [`VarLengthPaths`]
+    #[inline]
+    pub fn paths_from(&self, start: NodeIndex) -> GraphResult<VarLengthPaths<'_>> {
+        VarLengthPaths::new(self, start)
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    fmt,
+};
+
+use super::traverse::{Bfs, Direction, Walk};
+use crate::store::{require_node, GraphStore};
+use crate::vec_graph::{Edge, EdgeIndex, Error, Graph, GraphResult, Node, NodeIndex};
+
+/// One element of a [`Path`]
+#[derive(Debug, Clone, Copy)]
+pub enum Step<'g> {
+    Node(&'g Node),
+    Edge(&'g Edge),
+}
+
+/// Route through the graph: starts and ends with a node, alternating with the edges between them
+///
+/// Holds the ids, paths over a [`Graph`] also hand out the nodes and edges themselves
+pub struct Path<'g, G: GraphStore + ?Sized = Graph> {
+    graph: &'g G,
+    nodes: Vec<NodeIndex>,
+    edges: Vec<EdgeIndex>,
+    cost: f64,
+}
+
+impl<'g, G: GraphStore + ?Sized> Path<'g, G> {
+    /// Path from `start` through `edges`, each edge leaving the node the previous one arrived at
+    /// (in either direction), ids must be live
+    pub(crate) fn new(graph: &'g G, start: NodeIndex, edges: &[EdgeIndex], cost: f64) -> Self {
+        let mut nodes = vec![start];
+        let mut at = start;
+        for id in edges {
+            let (from, to) = graph.edge_ends(id).expect("Path edges are live");
+            at = if from == at { to } else { from };
+            nodes.push(at);
+        }
+        Path {
+            graph,
+            nodes,
+            edges: edges.to_vec(),
+            cost,
+        }
+    }
+    /// One more than [`Path::edge_ids`]
+    #[inline]
+    pub fn node_ids(&self) -> &[NodeIndex] {
+        &self.nodes
+    }
+    #[inline]
+    pub fn edge_ids(&self) -> &[EdgeIndex] {
+        &self.edges
+    }
+    /// Number of edges
+    #[inline]
+    pub fn hops(&self) -> usize {
+        self.edges.len()
+    }
+    /// Sum of the edge weights, or the hop count for unweighted searches
+    #[inline]
+    pub fn cost(&self) -> f64 {
+        self.cost
+    }
+}
+
+impl<'g> Path<'g, Graph> {
+    pub fn steps(&self) -> Vec<Step<'g>> {
+        let mut steps = vec![Step::Node(self.node(0))];
+        for (i, id) in self.edges.iter().enumerate() {
+            steps.push(Step::Edge(
+                self.graph.get_edge(id).expect("Path edges are live"),
+            ));
+            steps.push(Step::Node(self.node(i + 1)));
+        }
+        steps
+    }
+    pub fn nodes(&self) -> impl Iterator<Item = &'g Node> + '_ {
+        (0..self.nodes.len()).map(|i| self.node(i))
+    }
+    pub fn edges(&self) -> impl Iterator<Item = &'g Edge> + '_ {
+        let graph = self.graph;
+        self.edges
+            .iter()
+            .map(move |id| graph.get_edge(id).expect("Path edges are live"))
+    }
+    #[inline]
+    pub fn start(&self) -> &'g Node {
+        self.node(0)
+    }
+    #[inline]
+    pub fn end(&self) -> &'g Node {
+        self.node(self.nodes.len() - 1)
+    }
+    #[inline]
+    fn node(&self, i: usize) -> &'g Node {
+        self.graph
+            .get_node(&self.nodes[i])
+            .expect("Path nodes are live")
+    }
+}
+
+impl<G: GraphStore + ?Sized> Clone for Path<'_, G> {
+    fn clone(&self) -> Self {
+        Path {
+            graph: self.graph,
+            nodes: self.nodes.clone(),
+            edges: self.edges.clone(),
+            cost: self.cost,
+        }
+    }
+}
+
+impl<G: GraphStore + ?Sized> fmt::Debug for Path<'_, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Path")
+            .field("nodes", &self.nodes)
+            .field("edges", &self.edges)
+            .field("cost", &self.cost)
+            .finish()
+    }
+}
+
+/// Where weighted searches get an edge's cost from
+pub enum Weight<'w> {
+    /// Int or float edge prop, edges without it make the search fail
+    Prop(String),
+    /// Computed from the edge, other stores than `vec_graph` hand it a copy, see [`GraphStore::with_edge`]
+    With(Box<dyn Fn(&Edge) -> f64 + 'w>),
+}
+
+impl<'w> Weight<'w> {
+    #[inline]
+    pub fn prop<S: Into<String>>(key: S) -> Self {
+        Weight::Prop(key.into())
+    }
+    #[inline]
+    pub fn with<F: Fn(&Edge) -> f64 + 'w>(f: F) -> Self {
+        Weight::With(Box::new(f))
+    }
+    fn of<G: GraphStore + ?Sized>(&self, graph: &G, edge: EdgeIndex) -> GraphResult<f64> {
+        let weight = match self {
+            Weight::Prop(key) => match graph.edge_prop(&edge, key)? {
+                None => return Err(invalid_weight(edge, format!("no `{}` prop", key))),
+                Some(value) => value.as_number().ok_or_else(|| {
+                    invalid_weight(edge, format!("`{}` is a {}", key, value.type_name()))
+                })?,
+            },
+            Weight::With(f) => {
+                let mut weight = 0.0;
+                graph.with_edge(&edge, &mut |e| weight = f(e))?;
+                weight
+            }
+        };
+        //negative weights break dijkstra's settled-means-final invariant
+        if weight.is_nan() || weight < 0.0 {
+            return Err(invalid_weight(edge, format!("{} is not a cost", weight)));
+        }
+        Ok(weight)
+    }
+}
+
+fn invalid_weight(edge: EdgeIndex, reason: String) -> Error {
+    Error::InvalidWeight { edge, reason }
+}
+
+/// Node waiting in the weighted search's queue, ordered so the heap pops the lowest estimate,
+/// oldest first among equals
+struct Queued {
+    estimate: f64,
+    cost: f64,
+    seq: usize,
+    node: NodeIndex,
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then(other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+/// Shortest path search between two nodes, pick the algorithm with the finishing method
+///
+/// ```
+/// # use graph_db::{algo::Weight, vec_graph::Graph};
+/// let mut graph = Graph::new();
+/// graph
+///     .add_node("a")?.add_node("b")?.add_node("c")?
+///     .add_edges_by_aliases("road", "a", "c")?
+///     .mut_last_edge(|e| e.add_prop("km", 10))?
+///     .add_edges_by_aliases("road", "a", "b")?
+///     .mut_last_edge(|e| e.add_prop("km", 2))?
+///     .add_edges_by_aliases("road", "b", "c")?
+///     .mut_last_edge(|e| e.add_prop("km", 3))?;
+/// let a = graph.get_ids_by_alias("a").unwrap()[0];
+/// let c = graph.get_ids_by_alias("c").unwrap()[0];
+/// assert_eq!(graph.shortest_path(a, c).unweighted()?.hops(), 1);
+/// let path = graph.shortest_path(a, c).dijkstra(Weight::prop("km"))?;
+/// assert_eq!((path.hops(), path.cost()), (2, 5.0));
+/// # Ok::<(), graph_db::vec_graph::Error>(())
+/// ```
+pub struct ShortestPath<'g, G: GraphStore + ?Sized = Graph> {
+    graph: &'g G,
+    from: NodeIndex,
+    to: NodeIndex,
+    walk: Walk,
+}
+
+impl<'g, G: GraphStore + ?Sized> ShortestPath<'g, G> {
+    pub fn new(graph: &'g G, from: NodeIndex, to: NodeIndex) -> Self {
+        ShortestPath {
+            graph,
+            from,
+            to,
+            walk: Walk::default(),
+        }
+    }
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.walk.direction = direction;
+        self
+    }
+    /// Only follow edges with one of `relations`
+    pub fn relations<I, S>(mut self, relations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.walk.relations = relations.into_iter().map(Into::into).collect();
+        self
+    }
+    /// Fewest hops, by breadth-first search
+    pub fn unweighted(self) -> GraphResult<Path<'g, G>> {
+        require_node(self.graph, &self.to)?;
+        let mut bfs = Bfs::new(self.graph, self.from)?;
+        bfs.walk = self.walk.clone();
+        let mut reached_by = HashMap::new();
+        for visit in bfs {
+            if let Some(edge) = visit.edge {
+                reached_by.insert(visit.node, edge);
+            }
+            if visit.node == self.to {
+                return Ok(self.path(&reached_by, visit.depth as f64));
+            }
+        }
+        Err(self.no_path())
+    }
+    /// Lowest total weight, weights must be non negative
+    pub fn dijkstra(self, weight: Weight<'_>) -> GraphResult<Path<'g, G>> {
+        self.weighted(&weight, |_| 0.0)
+    }
+    /// Lowest total weight, guided by `heuristic`: an estimate of the cost left from a node to
+    /// the target that never overestimates it, otherwise the path found may not be the shortest
+    pub fn astar<H>(self, weight: Weight<'_>, heuristic: H) -> GraphResult<Path<'g, G>>
+    where
+        H: Fn(&Node) -> f64,
+    {
+        self.weighted(&weight, heuristic)
+    }
+
+    fn weighted<H>(&self, weight: &Weight<'_>, heuristic: H) -> GraphResult<Path<'g, G>>
+    where
+        H: Fn(&Node) -> f64,
+    {
+        let graph = self.graph;
+        let estimate = |id: &NodeIndex| {
+            let mut left = 0.0;
+            graph.with_node(id, &mut |n| left = heuristic(n))?;
+            Ok::<_, Error>(left)
+        };
+        let start = estimate(&self.from)?;
+        require_node(graph, &self.to)?;
+        let mut best = HashMap::from([(self.from, 0.0)]);
+        let mut reached_by = HashMap::new();
+        let mut queue = BinaryHeap::from([Queued {
+            estimate: start,
+            cost: 0.0,
+            seq: 0,
+            node: self.from,
+        }]);
+        let mut seq = 1;
+        while let Some(Queued { cost, node, .. }) = queue.pop() {
+            if node == self.to {
+                return Ok(self.path(&reached_by, cost));
+            }
+            //a cheaper way here was queued after this entry
+            if cost > best[&node] {
+                continue;
+            }
+            for (edge, next) in self.walk.steps(graph, node) {
+                let cost = cost + weight.of(graph, edge)?;
+                if best.get(&next).is_some_and(|known| *known <= cost) {
+                    continue;
+                }
+                best.insert(next, cost);
+                reached_by.insert(next, edge);
+                queue.push(Queued {
+                    estimate: cost + estimate(&next)?,
+                    cost,
+                    seq,
+                    node: next,
+                });
+                seq += 1;
+            }
+        }
+        Err(self.no_path())
+    }
+    /// Follows `reached_by` back from the target
+    fn path(&self, reached_by: &HashMap<NodeIndex, EdgeIndex>, cost: f64) -> Path<'g, G> {
+        let mut edges = Vec::new();
+        let mut at = self.to;
+        while at != self.from {
+            let id = reached_by[&at];
+            let (from, to) = self
+                .graph
+                .edge_ends(&id)
+                .expect("Search only records live edges");
+            at = if to == at { from } else { to };
+            edges.push(id);
+        }
+        edges.reverse();
+        Path::new(self.graph, self.from, &edges, cost)
+    }
+    #[inline]
+    fn no_path(&self) -> Error {
+        Error::NoPath {
+            from: self.from,
+            to: self.to,
+        }
+    }
+}
+
+impl Graph {
+    /// Shortest path from `from` to `to`, see [`ShortestPath`]
+    #[inline]
+    pub fn shortest_path(&self, from: NodeIndex, to: NodeIndex) -> ShortestPath<'_> {
+        ShortestPath::new(self, from, to)
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::store::{require_node, GraphStore};
+use crate::vec_graph::{EdgeIndex, Graph, GraphResult, NodeIndex};
+
+/// Which edges of a node a traversal follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    #[default]
+    Outgoing,
+    Incoming,
+    /// Outgoing edges first, then incoming ones
+    Both,
+}
+
+/// One node reached by a traversal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Visit {
+    pub node: NodeIndex,
+    /// Hops from the start node along the path that reached it, 0 for the start itself
+    pub depth: usize,
+    /// Position in the visit sequence, 0 for the start node
+    pub order: usize,
+    /// Edge the node was reached through, None for the start node
+    pub edge: Option<EdgeIndex>,
+}
+
+/// Options shared by [`Bfs`] and [`Dfs`]
+#[derive(Debug, Clone, Default)]
+pub(super) struct Walk {
+    pub(super) direction: Direction,
+    //empty follows every relation
+    pub(super) relations: Vec<String>,
+    pub(super) max_depth: Option<usize>,
+}
+
+impl Walk {
+    /// Edges leaving `node` the walk follows, with the node each one leads to
+    pub(super) fn steps<G: GraphStore + ?Sized>(
+        &self,
+        graph: &G,
+        node: NodeIndex,
+    ) -> Vec<(EdgeIndex, NodeIndex)> {
+        graph
+            .adjacent(&node, self.direction, &self.relations)
+            .unwrap_or_default()
+    }
+    #[inline]
+    fn expands(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max| depth < max)
+    }
+}
+
+/// Breadth-first traversal, every node is visited once at its smallest depth
+///
+/// Lazy: each `next` expands a single node, so stopping early skips the rest of the graph.
+/// ```
+/// # use graph_db::{algo::{Bfs, Direction}, vec_graph::Graph};
+/// let mut graph = Graph::new();
+/// graph
+///     .add_node("a")?.add_node("b")?.add_node("c")?
+///     .add_edges_by_aliases("r", "a", "b")?
+///     .add_edges_by_aliases("r", "b", "c")?;
+/// let a = graph.get_ids_by_alias("a").unwrap()[0];
+/// let depths: Vec<usize> = Bfs::new(&graph, a)?.max_depth(1).map(|v| v.depth).collect();
+/// assert_eq!(depths, vec![0, 1]);
+/// # Ok::<(), graph_db::vec_graph::Error>(())
+/// ```
+pub struct Bfs<'g, G: GraphStore + ?Sized = Graph> {
+    graph: &'g G,
+    pub(super) walk: Walk,
+    queue: VecDeque<(NodeIndex, usize, Option<EdgeIndex>)>,
+    seen: HashSet<NodeIndex>,
+    order: usize,
+}
+
+impl<'g, G: GraphStore + ?Sized> Bfs<'g, G> {
+    /// Fails with `NodeNotFound` if `start` isn't in the graph
+    pub fn new(graph: &'g G, start: NodeIndex) -> GraphResult<Self> {
+        require_node(graph, &start)?;
+        Ok(Bfs {
+            graph,
+            walk: Walk::default(),
+            queue: VecDeque::from([(start, 0, None)]),
+            seen: HashSet::from([start]),
+            order: 0,
+        })
+    }
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.walk.direction = direction;
+        self
+    }
+    /// Only follow edges with one of `relations`
+    pub fn relations<I, S>(mut self, relations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.walk.relations = relations.into_iter().map(Into::into).collect();
+        self
+    }
+    /// Don't go further than `depth` hops from the start
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.walk.max_depth = Some(depth);
+        self
+    }
+}
+
+impl<G: GraphStore + ?Sized> Iterator for Bfs<'_, G> {
+    type Item = Visit;
+    fn next(&mut self) -> Option<Visit> {
+        let (node, depth, edge) = self.queue.pop_front()?;
+        if self.walk.expands(depth) {
+            for (via, next) in self.walk.steps(self.graph, node) {
+                if self.seen.insert(next) {
+                    self.queue.push_back((next, depth + 1, Some(via)));
+                }
+            }
+        }
+        self.order += 1;
+        Some(Visit {
+            node,
+            depth,
+            order: self.order - 1,
+            edge,
+        })
+    }
+}
+
+/// Depth-first traversal in preorder, following each node's edges in adjacency order
+///
+/// A node is visited once, at the depth of the first path that reaches it.
+/// With a max depth a node first reached deep is expanded again when a shorter path reaches it,
+/// so everything [`Bfs`] reaches within the same max depth is visited too
+pub struct Dfs<'g, G: GraphStore + ?Sized = Graph> {
+    graph: &'g G,
+    walk: Walk,
+    stack: Vec<(NodeIndex, usize, Option<EdgeIndex>)>,
+    //smallest depth each node was expanded at
+    depths: HashMap<NodeIndex, usize>,
+    order: usize,
+}
+
+impl<'g, G: GraphStore + ?Sized> Dfs<'g, G> {
+    /// Fails with `NodeNotFound` if `start` isn't in the graph
+    pub fn new(graph: &'g G, start: NodeIndex) -> GraphResult<Self> {
+        require_node(graph, &start)?;
+        Ok(Dfs {
+            graph,
+            walk: Walk::default(),
+            stack: vec![(start, 0, None)],
+            depths: HashMap::new(),
+            order: 0,
+        })
+    }
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.walk.direction = direction;
+        self
+    }
+    /// Only follow edges with one of `relations`
+    pub fn relations<I, S>(mut self, relations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.walk.relations = relations.into_iter().map(Into::into).collect();
+        self
+    }
+    /// Don't go further than `depth` hops from the start
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.walk.max_depth = Some(depth);
+        self
+    }
+}
+
+impl<G: GraphStore + ?Sized> Iterator for Dfs<'_, G> {
+    type Item = Visit;
+    fn next(&mut self) -> Option<Visit> {
+        loop {
+            //nodes can be pushed more than once, copies that wouldn't get any closer are skipped
+            let (node, depth, edge) = self.stack.pop()?;
+            if !self.closer(node, depth) {
+                continue;
+            }
+            let first = self.depths.insert(node, depth).is_none();
+            if self.walk.expands(depth) {
+                let mark = self.stack.len();
+                for (via, next) in self.walk.steps(self.graph, node) {
+                    if self.closer(next, depth + 1) {
+                        self.stack.push((next, depth + 1, Some(via)));
+                    }
+                }
+                //reversed so the first edge is popped first
+                self.stack[mark..].reverse();
+            }
+            //a shorter path to a visited node only expands it again
+            if first {
+                self.order += 1;
+                return Some(Visit {
+                    node,
+                    depth,
+                    order: self.order - 1,
+                    edge,
+                });
+            }
+        }
+    }
+}
+
+impl<G: GraphStore + ?Sized> Dfs<'_, G> {
+    //without a max depth the first expansion already reaches everything below a node
+    fn closer(&self, node: NodeIndex, depth: usize) -> bool {
+        match self.depths.get(&node) {
+            None => true,
+            Some(best) => self.walk.max_depth.is_some() && depth < *best,
+        }
+    }
+}
+
+impl Graph {
+    /// Breadth-first traversal from `start`, see [`Bfs`]
+    #[inline]
+    pub fn bfs(&self, start: NodeIndex) -> GraphResult<Bfs<'_>> {
+        Bfs::new(self, start)
+    }
+    /// Depth-first traversal from `start`, see [`Dfs`]
+    #[inline]
+    pub fn dfs(&self, start: NodeIndex) -> GraphResult<Dfs<'_>> {
+        Dfs::new(self, start)
+    }
+}
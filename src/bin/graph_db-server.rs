@@ -0,0 +1,55 @@
+use std::{env, path::PathBuf, process::ExitCode};
+
+use graph_db::{
+    server::Server,
+    vec_graph::{Graph, GraphResult, SharedGraph},
+};
+
+const USAGE: &str = "usage: graph_db-server [--addr HOST:PORT] [DB_FILE]
+  serves DB_FILE (creating it if missing) as newline-delimited JSON over TCP,
+  without DB_FILE the graph lives in memory only, the default address is 127.0.0.1:7687";
+
+const DEFAULT_ADDR: &str = "127.0.0.1:7687";
+
+fn main() -> ExitCode {
+    let mut addr = DEFAULT_ADDR.to_owned();
+    let mut path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            "--addr" => match args.next() {
+                Some(value) => addr = value,
+                None => return usage_error(),
+            },
+            _ if path.is_none() && !arg.starts_with('-') => path = Some(PathBuf::from(arg)),
+            _ => return usage_error(),
+        }
+    }
+    match run(&addr, path) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn usage_error() -> ExitCode {
+    eprintln!("{}", USAGE);
+    ExitCode::FAILURE
+}
+
+fn run(addr: &str, path: Option<PathBuf>) -> GraphResult<()> {
+    let graph = match path {
+        Some(path) => Graph::open(path)?,
+        None => Graph::new(),
+    };
+    let server = Server::bind(addr, SharedGraph::new(graph))?;
+    //tests and scripts started with port 0 read the real address from here
+    println!("listening on {}", server.local_addr()?);
+    server.serve()
+}
@@ -0,0 +1,217 @@
+//! Exporters for looking at a [`Graph`], or any other [`GraphStore`], in Graphviz and Gephi
+//!
+//! - [`Format::Dot`]: Graphviz `digraph`, nodes labelled with their alias and labels
+//! - [`Format::GraphMl`]: GraphML with typed `<key>`s for every prop
+//! - [`Format::Gexf`]: GEXF 1.3, the format Gephi opens natively
+//!
+//! Every format keeps the alias, labels and props of nodes and the relation, labels and props of edges.
+//! Props that GraphML and GEXF have no type for (bytes, lists, maps, null) are written as their `Display` form,
+//! and a key holding different types on different elements is exported as a string
+//!
+//! ```
+//! # use graph_db::{export::Format, vec_graph::Graph};
+//! let mut graph = Graph::new();
+//! graph.add_node("sisli")?.add_node("merkez")?;
+//! graph.add_edges_by_aliases("includes", "sisli", "merkez")?;
+//! let dot = graph.export().relations(["includes"]).render(Format::Dot);
+//! assert!(dot.contains(r#""0" -> "1""#));
+//! # Ok::<(), graph_db::vec_graph::Error>(())
+//! ```
+
+mod dot;
+mod gexf;
+mod graphml;
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::store::{EdgeData, GraphStore, NodeData};
+use crate::vec_graph::{Error, Graph, GraphResult, NodeIndex, PropValue};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Dot,
+    GraphMl,
+    Gexf,
+}
+
+/// Subgraph to export, see [`Graph::export`]
+pub struct Export<'g, G: GraphStore + ?Sized = Graph> {
+    graph: &'g G,
+    labels: Vec<String>,
+    relations: Vec<String>,
+}
+
+impl<'g, G: GraphStore + ?Sized> Export<'g, G> {
+    /// Every node and edge of `graph` until filtered
+    pub fn new(graph: &'g G) -> Self {
+        Export {
+            graph,
+            labels: Vec::new(),
+            relations: Vec::new(),
+        }
+    }
+    /// Only exports nodes with any of `labels`, and the edges between them
+    pub fn labels<I, S>(mut self, labels: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.labels = labels.into_iter().map(Into::into).collect();
+        self
+    }
+    /// Only exports edges with any of `relations`, nodes are still filtered by [`Export::labels`] only
+    pub fn relations<I, S>(mut self, relations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.relations = relations.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn write<W: Write>(&self, format: Format, w: &mut W) -> GraphResult<()> {
+        let written = match format {
+            Format::Dot => dot::write(self, w),
+            Format::GraphMl => graphml::write(self, w),
+            Format::Gexf => gexf::write(self, w),
+        };
+        written.map_err(Error::io("writing export"))
+    }
+    pub fn render(&self, format: Format) -> String {
+        let mut out = Vec::new();
+        self.write(format, &mut out)
+            .expect("Writing to a Vec can't fail");
+        String::from_utf8(out).expect("Exporters write UTF-8")
+    }
+    pub fn save<P: AsRef<Path>>(&self, format: Format, path: P) -> GraphResult<()> {
+        let path = path.as_ref();
+        let context = || format!("writing {}", path.display());
+        let file = File::create(path).map_err(Error::io(context()))?;
+        let mut w = BufWriter::new(file);
+        self.write(format, &mut w)?;
+        w.flush().map_err(Error::io(context()))
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = NodeData> + '_ {
+        self.graph
+            .node_ids()
+            .into_iter()
+            .filter(|id| self.keeps_node(id))
+            .filter_map(|id| self.graph.node(&id).ok())
+    }
+    fn edges(&self) -> impl Iterator<Item = EdgeData> + '_ {
+        self.graph
+            .edge_ids()
+            .into_iter()
+            .filter_map(|id| self.graph.edge(&id).ok())
+            .filter(|e| {
+                (self.relations.is_empty() || self.relations.iter().any(|r| r == e.relation()))
+                    && [e.from(), e.to()]
+                        .into_iter()
+                        .all(|id| self.graph.has_node(id) && self.keeps_node(id))
+            })
+    }
+    fn keeps_node(&self, id: &NodeIndex) -> bool {
+        self.labels.is_empty()
+            || self
+                .labels
+                .iter()
+                .any(|l| self.graph.node_has_label(id, l).unwrap_or(false))
+    }
+}
+
+impl Graph {
+    /// Exporter for this graph, see [`export`](crate::export)
+    pub fn export(&self) -> Export<'_> {
+        Export::new(self)
+    }
+}
+
+/// Type a GraphML/GEXF attribute is declared with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttrType {
+    Bool,
+    Long,
+    Double,
+    Str,
+}
+
+impl AttrType {
+    fn of(value: &PropValue) -> Self {
+        match value {
+            PropValue::Bool(_) => AttrType::Bool,
+            PropValue::Int(_) => AttrType::Long,
+            PropValue::Float(_) => AttrType::Double,
+            _ => AttrType::Str,
+        }
+    }
+    /// Same in GraphML and GEXF
+    fn name(self) -> &'static str {
+        match self {
+            AttrType::Bool => "boolean",
+            AttrType::Long => "long",
+            AttrType::Double => "double",
+            AttrType::Str => "string",
+        }
+    }
+    /// Ints and floats under one key widen to double, anything else mixed to string
+    fn merge(self, other: AttrType) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (AttrType::Long, AttrType::Double) | (AttrType::Double, AttrType::Long) => {
+                AttrType::Double
+            }
+            _ => AttrType::Str,
+        }
+    }
+}
+
+/// Prop keys with their types, sorted so exports are stable
+fn prop_types<'p, I>(props: I) -> BTreeMap<&'p str, AttrType>
+where
+    I: Iterator<Item = (&'p String, &'p PropValue)>,
+{
+    let mut types: BTreeMap<&str, AttrType> = BTreeMap::new();
+    for (key, value) in props {
+        let ty = AttrType::of(value);
+        types
+            .entry(key)
+            .and_modify(|t| *t = t.merge(ty))
+            .or_insert(ty);
+    }
+    types
+}
+
+/// Props of one element sorted by key, HashMap order would make every export diff
+fn sorted_props<'p, I>(props: I) -> Vec<(&'p String, &'p PropValue)>
+where
+    I: IntoIterator<Item = (&'p String, &'p PropValue)>,
+{
+    let mut props: Vec<_> = props.into_iter().collect();
+    props.sort_by(|a, b| a.0.cmp(b.0));
+    props
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn xml_header<W: Write>(w: &mut W) -> io::Result<()> {
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)
+}
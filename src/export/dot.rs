@@ -0,0 +1,90 @@
+//! Graphviz DOT
+//!
+//! ```text
+//! digraph graph_db {
+//!   "0" ["tur"="ilce", label="sisli\n:sehir", alias="sisli", labels="sehir"];
+//!   "0" -> "1" ["km"="2.5", label="includes", relation="includes"];
+//! }
+//! ```
+//!
+//! Ids are the node ids, `label` is what Graphviz draws. Props come first because Graphviz keeps
+//! the last of repeated attributes, so a prop named `label` or `alias` can't hide the real ones,
+//! while props named like Graphviz attributes (`color`, `shape`) style the drawing
+
+use std::io::{self, Write};
+
+use super::{sorted_props, Export};
+use crate::store::GraphStore;
+use crate::vec_graph::PropValue;
+
+pub(super) fn write<G, W>(export: &Export<'_, G>, w: &mut W) -> io::Result<()>
+where
+    G: GraphStore + ?Sized,
+    W: Write,
+{
+    writeln!(w, "digraph graph_db {{")?;
+    for node in export.nodes() {
+        let mut shown = node.alias.clone();
+        if !node.labels().is_empty() {
+            shown.push_str("\n:");
+            shown.push_str(&node.labels().join(":"));
+        }
+        write!(w, "  {} [", quote(&node.id.to_string()))?;
+        write_props(w, node.props())?;
+        writeln!(
+            w,
+            "label={}, alias={}, labels={}];",
+            quote(&shown),
+            quote(&node.alias),
+            quote(&node.labels().join(":"))
+        )?;
+    }
+    for edge in export.edges() {
+        write!(
+            w,
+            "  {} -> {} [",
+            quote(&edge.from().to_string()),
+            quote(&edge.to().to_string())
+        )?;
+        write_props(w, edge.props())?;
+        write!(
+            w,
+            "label={}, relation={}",
+            quote(edge.relation()),
+            quote(edge.relation())
+        )?;
+        if !edge.labels().is_empty() {
+            write!(w, ", labels={}", quote(&edge.labels().join(":")))?;
+        }
+        writeln!(w, "];")?;
+    }
+    writeln!(w, "}}")
+}
+
+fn write_props<'p, W, I>(w: &mut W, props: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = (&'p String, &'p PropValue)>,
+{
+    for (key, value) in sorted_props(props) {
+        write!(w, "{}={}, ", quote(key), quote(&value.to_string()))?;
+    }
+    Ok(())
+}
+
+/// DOT string literal, newlines become `\n` so Graphviz breaks the line there
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
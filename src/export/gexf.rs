@@ -0,0 +1,136 @@
+//! GEXF 1.3
+//!
+//! ```text
+//! <gexf xmlns="http://gexf.net/1.3" version="1.3">
+//!   <graph defaultedgetype="directed" mode="static">
+//!     <attributes class="node">
+//!       <attribute id="alias" title="alias" type="string"/>
+//!       <attribute id="labels" title="labels" type="string"/>
+//!       <attribute id="p0" title="tur" type="string"/>
+//!     </attributes>
+//!     <attributes class="edge">...</attributes>
+//!     <nodes>
+//!       <node id="0" label="sisli"><attvalues><attvalue for="alias" value="sisli"/>...</attvalues></node>
+//!     </nodes>
+//!     <edges>
+//!       <edge id="0" source="0" target="1" label="includes"><attvalues>...</attvalues></edge>
+//!     </edges>
+//!   </graph>
+//! </gexf>
+//! ```
+//!
+//! Gephi shows `label`, so nodes get their alias there and edges their relation.
+//! Labels are joined with `:`, props get attributes `p0`, `p1`, ... per class
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use super::{prop_types, sorted_props, xml_escape, xml_header, AttrType, Export};
+use crate::store::GraphStore;
+use crate::vec_graph::PropValue;
+
+pub(super) fn write<G, W>(export: &Export<'_, G>, w: &mut W) -> io::Result<()>
+where
+    G: GraphStore + ?Sized,
+    W: Write,
+{
+    let nodes: Vec<_> = export.nodes().collect();
+    let edges: Vec<_> = export.edges().collect();
+    let node_props = prop_types(nodes.iter().flat_map(|n| n.props().iter()));
+    let edge_props = prop_types(edges.iter().flat_map(|e| e.props().iter()));
+    let node_keys = attr_ids(&node_props);
+    let edge_keys = attr_ids(&edge_props);
+
+    xml_header(w)?;
+    writeln!(w, r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#)?;
+    writeln!(w, r#"  <graph defaultedgetype="directed" mode="static">"#)?;
+    writeln!(w, r#"    <attributes class="node">"#)?;
+    write_attribute(w, "alias", "alias", AttrType::Str)?;
+    write_attribute(w, "labels", "labels", AttrType::Str)?;
+    for (key, ty) in node_props.iter() {
+        write_attribute(w, &node_keys[*key], key, *ty)?;
+    }
+    writeln!(w, "    </attributes>")?;
+    writeln!(w, r#"    <attributes class="edge">"#)?;
+    write_attribute(w, "relation", "relation", AttrType::Str)?;
+    write_attribute(w, "labels", "labels", AttrType::Str)?;
+    for (key, ty) in edge_props.iter() {
+        write_attribute(w, &edge_keys[*key], key, *ty)?;
+    }
+    writeln!(w, "    </attributes>")?;
+
+    writeln!(w, "    <nodes>")?;
+    for node in nodes {
+        write!(
+            w,
+            r#"      <node id="{}" label="{}"><attvalues>"#,
+            node.id,
+            xml_escape(&node.alias)
+        )?;
+        write_value(w, "alias", &node.alias)?;
+        write_value(w, "labels", &node.labels().join(":"))?;
+        write_props(w, &node_keys, node.props())?;
+        writeln!(w, "</attvalues></node>")?;
+    }
+    writeln!(w, "    </nodes>")?;
+    writeln!(w, "    <edges>")?;
+    for edge in edges {
+        write!(
+            w,
+            r#"      <edge id="{}" source="{}" target="{}" label="{}"><attvalues>"#,
+            edge.id(),
+            edge.from(),
+            edge.to(),
+            xml_escape(edge.relation())
+        )?;
+        write_value(w, "relation", edge.relation())?;
+        if !edge.labels().is_empty() {
+            write_value(w, "labels", &edge.labels().join(":"))?;
+        }
+        write_props(w, &edge_keys, edge.props())?;
+        writeln!(w, "</attvalues></edge>")?;
+    }
+    writeln!(w, "    </edges>")?;
+    writeln!(w, "  </graph>")?;
+    writeln!(w, "</gexf>")
+}
+
+fn attr_ids(types: &BTreeMap<&str, AttrType>) -> BTreeMap<String, String> {
+    types
+        .keys()
+        .enumerate()
+        .map(|(i, key)| (key.to_string(), format!("p{}", i)))
+        .collect()
+}
+
+fn write_attribute<W: Write>(w: &mut W, id: &str, title: &str, ty: AttrType) -> io::Result<()> {
+    writeln!(
+        w,
+        r#"      <attribute id="{}" title="{}" type="{}"/>"#,
+        id,
+        xml_escape(title),
+        ty.name()
+    )
+}
+
+fn write_value<W: Write>(w: &mut W, id: &str, value: &str) -> io::Result<()> {
+    write!(
+        w,
+        r#"<attvalue for="{}" value="{}"/>"#,
+        id,
+        xml_escape(value)
+    )
+}
+
+fn write_props<'p, W, I>(w: &mut W, ids: &BTreeMap<String, String>, props: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = (&'p String, &'p PropValue)>,
+{
+    for (key, value) in sorted_props(props) {
+        write_value(w, &ids[key], &value.to_string())?;
+    }
+    Ok(())
+}
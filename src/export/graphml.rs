@@ -0,0 +1,118 @@
+//! GraphML
+//!
+//! ```text
+//! <graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+//!   <key id="d0" for="node" attr.name="alias" attr.type="string"/>
+//!   ...
+//!   <key id="d4" for="node" attr.name="tur" attr.type="string"/>
+//!   <graph id="graph_db" edgedefault="directed">
+//!     <node id="n0"><data key="d0">sisli</data><data key="d1">sehir</data><data key="d4">ilce</data></node>
+//!     <edge id="e0" source="n0" target="n1"><data key="d2">includes</data></edge>
+//!   </graph>
+//! </graphml>
+//! ```
+//!
+//! `d0`..`d3` are the alias, labels (joined with `:`), relation and edge labels, props get the keys after them
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use super::{prop_types, sorted_props, xml_escape, xml_header, AttrType, Export};
+use crate::store::GraphStore;
+use crate::vec_graph::PropValue;
+
+const ALIAS: &str = "d0";
+const LABELS: &str = "d1";
+const RELATION: &str = "d2";
+const EDGE_LABELS: &str = "d3";
+
+pub(super) fn write<G, W>(export: &Export<'_, G>, w: &mut W) -> io::Result<()>
+where
+    G: GraphStore + ?Sized,
+    W: Write,
+{
+    let nodes: Vec<_> = export.nodes().collect();
+    let edges: Vec<_> = export.edges().collect();
+    let node_props = prop_types(nodes.iter().flat_map(|n| n.props().iter()));
+    let edge_props = prop_types(edges.iter().flat_map(|e| e.props().iter()));
+    //prop key ids continue after the fixed ones
+    let node_keys = key_ids(&node_props, 4);
+    let edge_keys = key_ids(&edge_props, 4 + node_keys.len());
+
+    xml_header(w)?;
+    writeln!(
+        w,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    write_key(w, ALIAS, "node", "alias", AttrType::Str)?;
+    write_key(w, LABELS, "node", "labels", AttrType::Str)?;
+    write_key(w, RELATION, "edge", "relation", AttrType::Str)?;
+    write_key(w, EDGE_LABELS, "edge", "labels", AttrType::Str)?;
+    for (key, ty) in node_props.iter() {
+        write_key(w, &node_keys[*key], "node", key, *ty)?;
+    }
+    for (key, ty) in edge_props.iter() {
+        write_key(w, &edge_keys[*key], "edge", key, *ty)?;
+    }
+    writeln!(w, r#"  <graph id="graph_db" edgedefault="directed">"#)?;
+    for node in nodes {
+        write!(w, r#"    <node id="n{}">"#, node.id)?;
+        write_data(w, ALIAS, &node.alias)?;
+        write_data(w, LABELS, &node.labels().join(":"))?;
+        write_props(w, &node_keys, node.props())?;
+        writeln!(w, "</node>")?;
+    }
+    for edge in edges {
+        write!(
+            w,
+            r#"    <edge id="e{}" source="n{}" target="n{}">"#,
+            edge.id(),
+            edge.from(),
+            edge.to()
+        )?;
+        write_data(w, RELATION, edge.relation())?;
+        if !edge.labels().is_empty() {
+            write_data(w, EDGE_LABELS, &edge.labels().join(":"))?;
+        }
+        write_props(w, &edge_keys, edge.props())?;
+        writeln!(w, "</edge>")?;
+    }
+    writeln!(w, "  </graph>")?;
+    writeln!(w, "</graphml>")
+}
+
+fn key_ids(types: &BTreeMap<&str, AttrType>, first: usize) -> BTreeMap<String, String> {
+    types
+        .keys()
+        .enumerate()
+        .map(|(i, key)| (key.to_string(), format!("d{}", first + i)))
+        .collect()
+}
+
+fn write_key<W: Write>(w: &mut W, id: &str, of: &str, name: &str, ty: AttrType) -> io::Result<()> {
+    writeln!(
+        w,
+        r#"  <key id="{}" for="{}" attr.name="{}" attr.type="{}"/>"#,
+        id,
+        of,
+        xml_escape(name),
+        ty.name()
+    )
+}
+
+fn write_data<W: Write>(w: &mut W, key: &str, value: &str) -> io::Result<()> {
+    write!(w, r#"<data key="{}">{}</data>"#, key, xml_escape(value))
+}
+
+fn write_props<'p, W, I>(w: &mut W, keys: &BTreeMap<String, String>, props: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = (&'p String, &'p PropValue)>,
+{
+    for (key, value) in sorted_props(props) {
+        write_data(w, &keys[key], &value.to_string())?;
+    }
+    Ok(())
+}
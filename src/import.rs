@@ -0,0 +1,516 @@
+//! Bulk import of nodes and edges from CSV
+//!
+//! ```text
+//! nodes.csv                              edges.csv
+//! id,alias,labels,nufus:int,tur          from,to,relation,km:float
+//! 1,sisli,sehir;ilce,274420,ilce         1,2,includes,2.5
+//! 2,merkez,mahalle,,                     3,2,komsu,
+//! 3,mcdkoy,mahalle,,
+//! ```
+//!
+//! Columns are picked by name:
+//! - nodes: `id` (required, the file's own id), `alias` (defaults to the id) and `labels`
+//! - edges: `from` and `to` (ids from the node files), `relation` (all required) and `labels`
+//! - every other column is a prop, `key:int`, `key:float`, `key:bool` or `key:string` (the default) give its type,
+//!   `key:int[]` and so on make a list. Labels and lists are split on the list separator, `;` by default
+//!
+//! Empty cells leave the prop out. Without a header row the columns default to `id,alias,labels`
+//! and `from,to,relation`, [`CsvImport::node_columns`] and [`CsvImport::edge_columns`] name them otherwise.
+//!
+//! Every file is imported as one transaction, so a graph opened with [`Graph::open`] logs it as one record.
+//! Rows that can't be imported, malformed ones and ones breaking the graph's [constraints](Graph::add_constraint),
+//! are skipped and listed in the [`ImportReport`] with their line numbers, the rest of the file still goes in. Wrap the import in [`Graph::transaction`] and fail on
+//! [`ImportReport::errors`] to get all or nothing instead
+
+mod csv;
+
+use core::fmt;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use crate::vec_graph::{Error, Graph, GraphResult, NodeIndex, PropValue};
+use csv::Records;
+
+/// Importer keeping the id mapping and the report across files, see the [module docs](self)
+///
+/// ```
+/// # use graph_db::{import::CsvImport, vec_graph::Graph};
+/// let mut graph = Graph::new();
+/// let mut import = CsvImport::new().delimiter('\t');
+/// import.nodes(&mut graph, "nodes", "id\talias\n1\tsisli\n2\tmerkez\n".as_bytes())?;
+/// import.edges(&mut graph, "edges", "from\tto\trelation\n1\t2\tincludes\n1\t9\tincludes\n".as_bytes())?;
+/// assert_eq!(graph.edge_count(), 1);
+/// assert_eq!(import.report().errors[0].to_string(), "edges:3: unknown node id `9`");
+/// # Ok::<(), graph_db::vec_graph::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct CsvImport {
+    delimiter: char,
+    list_separator: char,
+    headers: bool,
+    node_columns: Option<Vec<String>>,
+    edge_columns: Option<Vec<String>>,
+    ids: HashMap<String, NodeIndex>,
+    report: ImportReport,
+}
+
+/// What an import added and which rows it skipped
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub nodes: usize,
+    pub edges: usize,
+    pub errors: Vec<RowError>,
+}
+
+/// A skipped row, displayed as `source:line: message`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    /// File name or the name given to [`CsvImport::nodes`]/[`CsvImport::edges`]
+    pub source: String,
+    /// Line the row starts on, counting from 1 and including the header
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Nodes,
+    Edges,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Column {
+    Id,
+    Alias,
+    Labels,
+    From,
+    To,
+    Relation,
+    Prop {
+        key: String,
+        ty: PropType,
+        list: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PropType {
+    Str,
+    Int,
+    Float,
+    Bool,
+}
+
+/// Parsed row, checked completely before anything is added so a bad row adds nothing
+struct Row {
+    //id and alias for nodes, from and to for edges
+    first: String,
+    second: String,
+    relation: String,
+    labels: Vec<String>,
+    props: Vec<(String, PropValue)>,
+}
+
+impl Default for CsvImport {
+    fn default() -> Self {
+        CsvImport::new()
+    }
+}
+
+impl CsvImport {
+    /// Comma separated files with a header row
+    pub fn new() -> Self {
+        CsvImport {
+            delimiter: ',',
+            list_separator: ';',
+            headers: true,
+            node_columns: None,
+            edge_columns: None,
+            ids: HashMap::new(),
+            report: ImportReport::default(),
+        }
+    }
+    /// Field delimiter, `,` by default
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        assert!(
+            !matches!(delimiter, '"' | '\n' | '\r'),
+            "CSV delimiter can't be a quote or a line break"
+        );
+        self.delimiter = delimiter;
+        self
+    }
+    /// Separator of labels and list values inside a field, `;` by default
+    pub fn list_separator(mut self, separator: char) -> Self {
+        self.list_separator = separator;
+        self
+    }
+    /// Whether files start with a header row naming the columns, true by default
+    pub fn headers(mut self, headers: bool) -> Self {
+        self.headers = headers;
+        self
+    }
+    /// Column names of node files, used instead of the header row
+    pub fn node_columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.node_columns = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+    /// Column names of edge files, used instead of the header row
+    pub fn edge_columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.edge_columns = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Imports the nodes in `reader`, `source` names it in the report
+    ///
+    /// Fails without importing anything if the input can't be read or its columns are unusable
+    pub fn nodes<R: Read>(
+        &mut self,
+        graph: &mut Graph,
+        source: &str,
+        reader: R,
+    ) -> GraphResult<()> {
+        self.import(Kind::Nodes, graph, source, reader)
+    }
+    /// Imports the edges in `reader`, their ends are looked up among the ids of the nodes imported so far
+    pub fn edges<R: Read>(
+        &mut self,
+        graph: &mut Graph,
+        source: &str,
+        reader: R,
+    ) -> GraphResult<()> {
+        self.import(Kind::Edges, graph, source, reader)
+    }
+    pub fn nodes_file<P: AsRef<Path>>(&mut self, graph: &mut Graph, path: P) -> GraphResult<()> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(Error::io(format!("opening {}", path.display())))?;
+        self.nodes(graph, &path.display().to_string(), file)
+    }
+    pub fn edges_file<P: AsRef<Path>>(&mut self, graph: &mut Graph, path: P) -> GraphResult<()> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(Error::io(format!("opening {}", path.display())))?;
+        self.edges(graph, &path.display().to_string(), file)
+    }
+
+    /// File ids of the imported nodes
+    #[inline]
+    pub fn ids(&self) -> &HashMap<String, NodeIndex> {
+        &self.ids
+    }
+    #[inline]
+    pub fn report(&self) -> &ImportReport {
+        &self.report
+    }
+    #[inline]
+    pub fn into_report(self) -> ImportReport {
+        self.report
+    }
+
+    fn import<R: Read>(
+        &mut self,
+        kind: Kind,
+        graph: &mut Graph,
+        source: &str,
+        reader: R,
+    ) -> GraphResult<()> {
+        let context = || format!("reading {}", source);
+        let mut records = Records::new(BufReader::new(reader), self.delimiter);
+        let header = if self.headers {
+            match records.next_record().map_err(Error::io(context()))? {
+                Some((_, Ok(names))) => Some(names),
+                Some((line, Err(e))) => return Err(file_error(source, line, &e)),
+                None => return Ok(()),
+            }
+        } else {
+            None
+        };
+        let configured = match kind {
+            Kind::Nodes => self.node_columns.clone(),
+            Kind::Edges => self.edge_columns.clone(),
+        };
+        let names = configured.or(header).unwrap_or_else(|| {
+            let defaults = match kind {
+                Kind::Nodes => ["id", "alias", "labels"],
+                Kind::Edges => ["from", "to", "relation"],
+            };
+            defaults.map(str::to_owned).to_vec()
+        });
+        let columns = columns(kind, &names).map_err(|e| file_error(source, 1, &e))?;
+
+        //ids and counts only become visible once the transaction commits
+        let mut ids = HashMap::new();
+        let mut added = 0;
+        let mut errors = Vec::new();
+        graph.transaction(|tx| {
+            while let Some((line, record)) = records.next_record().map_err(Error::io(context()))? {
+                let row = record.and_then(|fields| self.row(kind, &columns, fields, &ids));
+                let row = match row {
+                    Ok(row) => row,
+                    Err(message) => {
+                        errors.push(RowError {
+                            source: source.to_owned(),
+                            line,
+                            message,
+                        });
+                        continue;
+                    }
+                };
+                let savepoint = tx.savepoint();
+                let added_row = match kind {
+                    Kind::Nodes => add_node(tx, &row).map(|id| {
+                        ids.insert(row.first, id);
+                    }),
+                    Kind::Edges => add_edge(tx, &row, &self.ids),
+                };
+                match added_row {
+                    Ok(()) => added += 1,
+                    //rows breaking the graph's constraints are skipped like any other bad row
+                    Err(e @ Error::ConstraintViolation { .. }) => {
+                        tx.rollback_to(savepoint)?;
+                        errors.push(RowError {
+                            source: source.to_owned(),
+                            line,
+                            message: e.to_string(),
+                        });
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(())
+        })?;
+        self.ids.extend(ids);
+        match kind {
+            Kind::Nodes => self.report.nodes += added,
+            Kind::Edges => self.report.edges += added,
+        }
+        self.report.errors.extend(errors);
+        Ok(())
+    }
+
+    /// `new_ids` are the nodes of the file being imported, ids repeated within it are rejected too
+    fn row(
+        &self,
+        kind: Kind,
+        columns: &[Column],
+        fields: Vec<String>,
+        new_ids: &HashMap<String, NodeIndex>,
+    ) -> Result<Row, String> {
+        if fields.len() != columns.len() {
+            return Err(format!(
+                "expected {} fields, found {}",
+                columns.len(),
+                fields.len()
+            ));
+        }
+        let mut row = Row {
+            first: String::new(),
+            second: String::new(),
+            relation: String::new(),
+            labels: Vec::new(),
+            props: Vec::new(),
+        };
+        for (column, field) in columns.iter().zip(fields) {
+            match column {
+                Column::Id | Column::From => row.first = field,
+                Column::Alias | Column::To => row.second = field,
+                Column::Relation => row.relation = field,
+                Column::Labels => row.labels = self.split(&field),
+                Column::Prop { .. } if field.is_empty() => {}
+                Column::Prop { key, ty, list } => {
+                    let value = if *list {
+                        let values = self.split(&field);
+                        let values = values.iter().map(|v| parse_value(key, *ty, v));
+                        PropValue::List(values.collect::<Result<_, _>>()?)
+                    } else {
+                        parse_value(key, *ty, &field)?
+                    };
+                    row.props.push((key.clone(), value));
+                }
+            }
+        }
+        match kind {
+            Kind::Nodes => {
+                if row.first.is_empty() {
+                    return Err("empty id".to_owned());
+                }
+                if self.ids.contains_key(&row.first) || new_ids.contains_key(&row.first) {
+                    return Err(format!("duplicate id `{}`", row.first));
+                }
+                if row.second.is_empty() {
+                    row.second = row.first.clone();
+                }
+            }
+            Kind::Edges => {
+                for end in [&row.first, &row.second] {
+                    if !self.ids.contains_key(end) {
+                        return Err(format!("unknown node id `{}`", end));
+                    }
+                }
+                if row.relation.is_empty() {
+                    return Err("empty relation".to_owned());
+                }
+            }
+        }
+        Ok(row)
+    }
+
+    fn split(&self, field: &str) -> Vec<String> {
+        field
+            .split(self.list_separator)
+            .filter(|s| !s.is_empty())
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+fn add_node(graph: &mut Graph, row: &Row) -> GraphResult<NodeIndex> {
+    let id = graph
+        .add_node(&row.second)?
+        .get_last_node()
+        .expect("Node was just added")
+        .id;
+    //props first, constraints of the labels may require them
+    for (key, value) in row.props.iter() {
+        graph.add_prop(&id, key, value.clone())?;
+    }
+    for label in row.labels.iter() {
+        graph.add_label(&id, label)?;
+    }
+    Ok(id)
+}
+
+fn add_edge(graph: &mut Graph, row: &Row, ids: &HashMap<String, NodeIndex>) -> GraphResult<()> {
+    let id = graph
+        .add_edge(&row.relation, ids[&row.first], ids[&row.second])?
+        .get_last_edge()
+        .expect("Edge was just added")
+        .id();
+    for label in row.labels.iter() {
+        graph.add_edge_label(&id, label)?;
+    }
+    for (key, value) in row.props.iter() {
+        graph.add_edge_prop(&id, key, value.clone())?;
+    }
+    Ok(())
+}
+
+fn columns(kind: Kind, names: &[String]) -> Result<Vec<Column>, String> {
+    let mut columns = Vec::with_capacity(names.len());
+    for name in names {
+        let column = match (kind, name.trim()) {
+            (Kind::Nodes, "id") => Column::Id,
+            (Kind::Nodes, "alias") => Column::Alias,
+            (Kind::Edges, "from") => Column::From,
+            (Kind::Edges, "to") => Column::To,
+            (Kind::Edges, "relation") => Column::Relation,
+            (_, "labels") => Column::Labels,
+            (_, prop) => {
+                let (key, ty) = prop.split_once(':').unwrap_or((prop, "string"));
+                let (ty, list) = match ty.strip_suffix("[]") {
+                    Some(ty) => (ty, true),
+                    None => (ty, false),
+                };
+                let ty = match ty {
+                    "string" => PropType::Str,
+                    "int" => PropType::Int,
+                    "float" => PropType::Float,
+                    "bool" => PropType::Bool,
+                    other => return Err(format!("column `{}` has unknown type `{}`", key, other)),
+                };
+                if key.is_empty() {
+                    return Err("unnamed column".to_owned());
+                }
+                Column::Prop {
+                    key: key.to_owned(),
+                    ty,
+                    list,
+                }
+            }
+        };
+        if columns.contains(&column) {
+            return Err(format!("column `{}` appears twice", name));
+        }
+        columns.push(column);
+    }
+    let required: &[(Column, &str)] = match kind {
+        Kind::Nodes => &[(Column::Id, "id")],
+        Kind::Edges => &[
+            (Column::From, "from"),
+            (Column::To, "to"),
+            (Column::Relation, "relation"),
+        ],
+    };
+    for (column, name) in required {
+        if !columns.contains(column) {
+            return Err(format!("missing `{}` column", name));
+        }
+    }
+    Ok(columns)
+}
+
+fn parse_value(key: &str, ty: PropType, field: &str) -> Result<PropValue, String> {
+    let invalid = |ty: &str| format!("column `{}`: `{}` is not a valid {}", key, field, ty);
+    Ok(match ty {
+        PropType::Str => PropValue::Str(field.to_owned()),
+        PropType::Int => PropValue::Int(field.trim().parse().map_err(|_| invalid("int"))?),
+        PropType::Float => PropValue::Float(field.trim().parse().map_err(|_| invalid("float"))?),
+        PropType::Bool => match field.trim() {
+            "true" => PropValue::Bool(true),
+            "false" => PropValue::Bool(false),
+            _ => return Err(invalid("bool")),
+        },
+    })
+}
+
+fn file_error(source: &str, line: usize, message: &str) -> Error {
+    Error::Parse {
+        pos: None,
+        message: format!("{}:{}: {}", source, line, message),
+    }
+}
+
+impl ImportReport {
+    /// True if no row was skipped
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Summary line, then one line per skipped row
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural =
+            |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+        write!(
+            f,
+            "{}, {} imported",
+            plural(self.nodes, "node"),
+            plural(self.edges, "edge")
+        )?;
+        if !self.errors.is_empty() {
+            write!(f, ", {} skipped", plural(self.errors.len(), "row"))?;
+        }
+        for error in self.errors.iter() {
+            write!(f, "\n{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.source, self.line, self.message)
+    }
+}
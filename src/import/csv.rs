@@ -0,0 +1,118 @@
+//! Streaming CSV record reader, RFC 4180 with a configurable delimiter
+//!
+//! Fields may be quoted, `""` inside quotes is a quote, and quoted fields may span lines.
+//! Blank lines are skipped and `\r\n` line endings are accepted. A line that isn't UTF-8 fails only its own record
+
+use std::io::{self, BufRead};
+
+/// Fields of one record, or why they couldn't be read
+pub(super) type Record = Result<Vec<String>, String>;
+
+pub(super) struct Records<R> {
+    reader: R,
+    delimiter: char,
+    line: usize,
+    buf: String,
+    bytes: Vec<u8>,
+}
+
+impl<R: BufRead> Records<R> {
+    pub(super) fn new(reader: R, delimiter: char) -> Self {
+        Records {
+            reader,
+            delimiter,
+            line: 0,
+            buf: String::new(),
+            bytes: Vec::new(),
+        }
+    }
+
+    /// Next record with the line it starts on, None at the end of input
+    pub(super) fn next_record(&mut self) -> io::Result<Option<(usize, Record)>> {
+        loop {
+            match self.read_line()? {
+                None => return Ok(None),
+                Some(Err(e)) => return Ok(Some((self.line, Err(e)))),
+                Some(Ok(())) if !self.buf.is_empty() => break,
+                Some(Ok(())) => {}
+            }
+        }
+        let start = self.line;
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        //at the start of a field, inside quotes, or right after a closing quote
+        let (mut fresh, mut quoted, mut closed) = (true, false, false);
+        loop {
+            let mut chars = self.buf.chars().peekable();
+            while let Some(c) = chars.next() {
+                if quoted {
+                    match c {
+                        '"' if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        '"' => {
+                            quoted = false;
+                            closed = true;
+                        }
+                        c => field.push(c),
+                    }
+                } else if c == self.delimiter {
+                    fields.push(std::mem::take(&mut field));
+                    (fresh, closed) = (true, false);
+                } else if closed {
+                    return Ok(Some((
+                        start,
+                        Err(format!("unexpected `{}` after a closing quote", c)),
+                    )));
+                } else if c == '"' && fresh {
+                    quoted = true;
+                    fresh = false;
+                } else {
+                    field.push(c);
+                    fresh = false;
+                }
+            }
+            if !quoted {
+                break;
+            }
+            //the quoted field goes on in the next line
+            field.push('\n');
+            match self.read_line()? {
+                None => return Ok(Some((start, Err("unterminated quoted field".to_owned())))),
+                Some(Err(e)) => return Ok(Some((start, Err(e)))),
+                Some(Ok(())) => {}
+            }
+        }
+        fields.push(field);
+        Ok(Some((start, Ok(fields))))
+    }
+
+    /// Reads the next line into `buf` without its line ending, None at the end of input
+    /// and an error message if the line isn't UTF-8
+    fn read_line(&mut self) -> io::Result<Option<Result<(), String>>> {
+        self.buf.clear();
+        self.bytes.clear();
+        if self.reader.read_until(b'\n', &mut self.bytes)? == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        if self.bytes.ends_with(b"\n") {
+            self.bytes.pop();
+            if self.bytes.ends_with(b"\r") {
+                self.bytes.pop();
+            }
+        }
+        match std::str::from_utf8(&self.bytes) {
+            Ok(line) => self.buf.push_str(line),
+            Err(e) => {
+                return Ok(Some(Err(format!(
+                    "invalid UTF-8 at byte {} of line {}",
+                    e.valid_up_to() + 1,
+                    self.line
+                ))))
+            }
+        }
+        Ok(Some(Ok(())))
+    }
+}
@@ -0,0 +1,466 @@
+//! Minimal JSON value, parser and writer, used by the server protocol
+//!
+//! Numbers keep the int/float distinction of the text: `1` parses as [`Json::Int`], `1.0` and `1e3`
+//! as [`Json::Float`], and floats are always written with a dot or exponent so they read back as floats.
+//!
+//! [`PropValue`]s map onto JSON losslessly: null, bools, ints, floats, strings and lists as themselves,
+//! maps as objects, and the values JSON has no literal for as single key objects:
+//! `{"$bytes": "00ff"}` (hex), `{"$float": "NaN"}` (also `"inf"` and `"-inf"`).
+//! A map whose only key is one of these tags or `$map` is wrapped as `{"$map": {...}}`.
+
+use core::fmt;
+use std::collections::BTreeMap;
+
+use crate::vec_graph::{Error, GraphResult, PropValue};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Json {
+    #[default]
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+const TAGS: [&str; 3] = ["$bytes", "$float", "$map"];
+
+impl Json {
+    /// Parses one JSON value, surrounding whitespace allowed
+    pub fn parse(src: &str) -> GraphResult<Json> {
+        let mut parser = Parser {
+            src: src.as_bytes(),
+            pos: 0,
+        };
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        if parser.pos != src.len() {
+            return Err(parser.error("trailing characters after JSON value"));
+        }
+        Ok(value)
+    }
+    /// Object with the given entries
+    pub fn object<I, S>(entries: I) -> Json
+    where
+        I: IntoIterator<Item = (S, Json)>,
+        S: Into<String>,
+    {
+        Json::Object(entries.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+    /// Field of an object, None for other values and missing keys
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.get(key),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Json::Null)
+    }
+    #[inline]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+    /// Ints and floats as f64
+    #[inline]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Int(i) => Some(*i as f64),
+            Json::Float(x) => Some(*x),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn as_object(&self) -> Option<&BTreeMap<String, Json>> {
+        match self {
+            Json::Object(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
+
+impl From<&PropValue> for Json {
+    fn from(value: &PropValue) -> Json {
+        match value {
+            PropValue::Null => Json::Null,
+            PropValue::Bool(b) => Json::Bool(*b),
+            PropValue::Int(i) => Json::Int(*i),
+            PropValue::Float(x) if x.is_finite() => Json::Float(*x),
+            PropValue::Float(x) => Json::object([("$float", Json::Str(x.to_string()))]),
+            PropValue::Str(s) => Json::Str(s.clone()),
+            PropValue::Bytes(bytes) => {
+                let hex = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                Json::object([("$bytes", Json::Str(hex))])
+            }
+            PropValue::List(items) => Json::Array(items.iter().map(Json::from).collect()),
+            PropValue::Map(entries) => {
+                let object = Json::Object(
+                    entries
+                        .iter()
+                        .map(|(k, v)| (k.clone(), Json::from(v)))
+                        .collect(),
+                );
+                let ambiguous =
+                    entries.len() == 1 && entries.keys().all(|k| TAGS.contains(&k.as_str()));
+                if ambiguous {
+                    Json::object([("$map", object)])
+                } else {
+                    object
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<&Json> for PropValue {
+    type Error = Error;
+    fn try_from(json: &Json) -> GraphResult<PropValue> {
+        Ok(match json {
+            Json::Null => PropValue::Null,
+            Json::Bool(b) => PropValue::Bool(*b),
+            Json::Int(i) => PropValue::Int(*i),
+            Json::Float(x) => PropValue::Float(*x),
+            Json::Str(s) => PropValue::Str(s.clone()),
+            Json::Array(items) => PropValue::List(
+                items
+                    .iter()
+                    .map(PropValue::try_from)
+                    .collect::<GraphResult<_>>()?,
+            ),
+            Json::Object(entries) => match entries.iter().next() {
+                Some((tag, inner)) if entries.len() == 1 && TAGS.contains(&tag.as_str()) => {
+                    tagged(tag, inner)?
+                }
+                _ => PropValue::Map(
+                    entries
+                        .iter()
+                        .map(|(k, v)| Ok((k.clone(), PropValue::try_from(v)?)))
+                        .collect::<GraphResult<_>>()?,
+                ),
+            },
+        })
+    }
+}
+
+/// Value of a `{"$tag": inner}` object
+fn tagged(tag: &str, inner: &Json) -> GraphResult<PropValue> {
+    let invalid = || Error::query(format!("invalid `{}` value {}", tag, inner));
+    match tag {
+        "$bytes" => {
+            let hex = inner.as_str().ok_or_else(invalid)?;
+            if hex.len() % 2 != 0 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(invalid());
+            }
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid()))
+                .collect::<GraphResult<_>>()?;
+            Ok(PropValue::Bytes(bytes))
+        }
+        "$float" => match inner.as_str() {
+            Some("NaN") => Ok(PropValue::Float(f64::NAN)),
+            Some("inf") => Ok(PropValue::Float(f64::INFINITY)),
+            Some("-inf") => Ok(PropValue::Float(f64::NEG_INFINITY)),
+            _ => Err(invalid()),
+        },
+        _ => match inner {
+            Json::Object(entries) => Ok(PropValue::Map(
+                entries
+                    .iter()
+                    .map(|(k, v)| Ok((k.clone(), PropValue::try_from(v)?)))
+                    .collect::<GraphResult<_>>()?,
+            )),
+            _ => Err(invalid()),
+        },
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Int(i) => write!(f, "{}", i),
+            //debug formatting keeps a `.0` or an exponent, so floats read back as floats
+            Json::Float(x) if x.is_finite() => write!(f, "{:?}", x),
+            Json::Float(_) => write!(f, "null"),
+            Json::Str(s) => write_str(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_str(f, k)?;
+                    write!(f, ":{}", v)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_str(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+//deep enough for any real document, low enough that nesting can't overflow the stack
+pub(crate) const MAX_DEPTH: usize = 128;
+
+struct Parser<'s> {
+    src: &'s [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error::parse(self.pos, format!("invalid JSON: {}", message))
+    }
+    fn skip_whitespace(&mut self) {
+        while self
+            .src
+            .get(self.pos)
+            .is_some_and(|c| matches!(c, b' ' | b'\t' | b'\n' | b'\r'))
+        {
+            self.pos += 1;
+        }
+    }
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        if self.src.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+    fn expect(&mut self, c: u8) -> GraphResult<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", c as char)))
+        }
+    }
+    fn literal(&mut self, word: &str, value: Json) -> GraphResult<Json> {
+        if self.src[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> GraphResult<Json> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deep"));
+        }
+        self.skip_whitespace();
+        match self.src.get(self.pos) {
+            None => Err(self.error("unexpected end")),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::Str),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if self.eat(b']') {
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    if !self.eat(b',') {
+                        break;
+                    }
+                }
+                self.expect(b']')?;
+                Ok(Json::Array(items))
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut entries = BTreeMap::new();
+                if self.eat(b'}') {
+                    return Ok(Json::Object(entries));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    entries.insert(key, self.value(depth + 1)?);
+                    if !self.eat(b',') {
+                        break;
+                    }
+                }
+                self.expect(b'}')?;
+                Ok(Json::Object(entries))
+            }
+            Some(c) if *c == b'-' || c.is_ascii_digit() => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn number(&mut self) -> GraphResult<Json> {
+        let start = self.pos;
+        let digits = |p: &mut Self| {
+            let from = p.pos;
+            while p.src.get(p.pos).is_some_and(u8::is_ascii_digit) {
+                p.pos += 1;
+            }
+            p.pos > from
+        };
+        if self.src[self.pos] == b'-' {
+            self.pos += 1;
+        }
+        if !digits(self) {
+            return Err(self.error("expected digits"));
+        }
+        let mut float = false;
+        if self.src.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            float = true;
+            if !digits(self) {
+                return Err(self.error("expected digits after `.`"));
+            }
+        }
+        if matches!(self.src.get(self.pos), Some(b'e' | b'E')) {
+            self.pos += 1;
+            float = true;
+            if matches!(self.src.get(self.pos), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                return Err(self.error("expected exponent digits"));
+            }
+        }
+        let text = std::str::from_utf8(&self.src[start..self.pos]).expect("Digits are ascii");
+        //ints too big for i64 degrade to floats, like most JSON readers
+        match text.parse::<i64>() {
+            Ok(i) if !float => Ok(Json::Int(i)),
+            _ => text
+                .parse::<f64>()
+                .map(Json::Float)
+                .map_err(|_| self.error("invalid number")),
+        }
+    }
+
+    fn string(&mut self) -> GraphResult<String> {
+        if self.src.get(self.pos) != Some(&b'"') {
+            return Err(self.error("expected string"));
+        }
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            match self.src.get(self.pos) {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    //input is a &str and escapes push whole chars, so this can't fail
+                    return Ok(String::from_utf8(out).expect("Valid utf-8"));
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let c = match self.src.get(self.pos) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    self.pos += 1;
+                    let mut buf = [0; 4];
+                    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                Some(c) if *c < 0x20 => return Err(self.error("control character in string")),
+                Some(c) => {
+                    out.push(*c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// `\uXXXX` with `pos` on the `u`, surrogate pairs included. Leaves `pos` on the last hex digit
+    fn unicode_escape(&mut self) -> GraphResult<char> {
+        let first = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&first) {
+            if self.src.get(self.pos + 1..self.pos + 3) != Some(b"\\u") {
+                return Err(self.error("unpaired surrogate"));
+            }
+            self.pos += 2;
+            let second = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&second) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((first - 0xd800) << 10) + (second - 0xdc00)
+        } else {
+            first
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+    fn hex4(&mut self) -> GraphResult<u32> {
+        let hex = self
+            .src
+            .get(self.pos + 1..self.pos + 5)
+            .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u32::from_str_radix(h, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(hex)
+    }
+}
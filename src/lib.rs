@@ -1 +1,12 @@
+pub mod algo;
+pub mod export;
+pub mod import;
+pub mod json;
+pub mod query;
+pub mod rc_graph;
+pub mod server;
+pub mod shell;
+pub mod store;
+pub mod unsafe_graph;
 pub mod vec_graph;
+pub mod vgraph;
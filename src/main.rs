@@ -1,94 +1,253 @@
-mod unsafe_graph;
-pub mod vec_graph;
-///MATCH (you:Person {name:"elma"})-[:KNOWS]->(n)-[:KNOWS]->(friend:Person {name:"armut"})
-//RETURN n
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, BufRead, IsTerminal, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
 
-//QNode(
-//   alias="you",
-//    labels=["Person"],
-//    props=dict(name="elma"),
-//    rels=dict(
-//        knows=[QNode(
-//            alias="n",
-//            props=dict(name="armut"),
-//            rels=dict(
-//                knows=[QNode(alias="friend")])
-//        )],
-//    )
-//),
+use graph_db::{
+    import::CsvImport,
+    shell,
+    vec_graph::{Error, Graph, GraphResult},
+};
 
-//mod rc_graph;
-mod vgraph;
+const USAGE: &str = "usage: graph_db [DB_FILE]
+       graph_db import DB_FILE [--nodes FILE]... [--edges FILE]... [OPTIONS]
+  opens DB_FILE (creating it if missing) and reads commands from stdin,
+  without DB_FILE the graph lives in memory only
 
-fn main() -> vec_graph::GraphResult<()> {
-    {
-        use vec_graph::Graph;
+  import adds the nodes and then the edges of the CSV files to DB_FILE,
+  listing rows it had to skip, options:
+    --delimiter C           field delimiter, `tab` for tabs, default `,`
+    --list-separator C      separator of labels and lists, default `;`
+    --no-headers            files have no header row
+    --node-columns A,B,...  names of node columns instead of the header
+    --edge-columns A,B,...  names of edge columns instead of the header";
 
-        let mut graph = Graph::new();
-        graph
-            .add_node("sisli")?
-            .get_last_node_mut()
-            .and_then(|sisli| sisli.add_label("sehir").ok()?.add_prop("tur", "ilce").ok())
-            .ok_or("err")?;
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "import") {
+        let options = match ImportOptions::parse(&args[1..]) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("error: {}\n{}", message, USAGE);
+                return ExitCode::FAILURE;
+            }
+        };
+        return match options.run() {
+            Ok(code) => code,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    let path = match args.as_slice() {
+        [] => None,
+        [flag] if flag == "-h" || flag == "--help" => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        [path] => Some(PathBuf::from(path)),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    match Repl::new(path).and_then(|mut repl| repl.run()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Same as the library's io error wrapping, for `map_err`
+fn io_error<S: Into<String>>(context: S) -> impl FnOnce(io::Error) -> Error {
+    move |source| Error::Io {
+        context: context.into(),
+        source,
+    }
+}
+
+/// Arguments of `graph_db import`
+struct ImportOptions {
+    path: PathBuf,
+    nodes: Vec<PathBuf>,
+    edges: Vec<PathBuf>,
+    csv: CsvImport,
+}
 
-        println!("{}", graph);
+impl ImportOptions {
+    /// Err is the usage problem to print above the usage text
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut args = args.iter();
+        let path = args.next().ok_or("import needs a DB_FILE")?.into();
+        let (mut nodes, mut edges) = (Vec::new(), Vec::new());
+        let mut csv = CsvImport::new();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            let char_value = |value: &str| match value {
+                "tab" | "\\t" => Ok('\t'),
+                _ => {
+                    let mut chars = value.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Ok(c),
+                        _ => Err(format!("{} takes one character", arg)),
+                    }
+                }
+            };
+            match arg.as_str() {
+                "--nodes" => nodes.push(value()?.into()),
+                "--edges" => edges.push(value()?.into()),
+                "--delimiter" => match char_value(value()?)? {
+                    '"' | '\r' | '\n' => return Err("the delimiter can't be a quote".to_owned()),
+                    c => csv = csv.delimiter(c),
+                },
+                "--list-separator" => csv = csv.list_separator(char_value(value()?)?),
+                "--no-headers" => csv = csv.headers(false),
+                "--node-columns" => csv = csv.node_columns(value()?.split(',')),
+                "--edge-columns" => csv = csv.edge_columns(value()?.split(',')),
+                other => return Err(format!("unknown option `{}`", other)),
+            }
+        }
+        Ok(ImportOptions {
+            path,
+            nodes,
+            edges,
+            csv,
+        })
+    }
+
+    /// Fails if any row was skipped so scripts notice
+    fn run(mut self) -> GraphResult<ExitCode> {
+        let mut graph = Graph::open(&self.path)?;
+        for file in &self.nodes {
+            self.csv.nodes_file(&mut graph, file)?;
+        }
+        for file in &self.edges {
+            self.csv.edges_file(&mut graph, file)?;
+        }
+        let report = self.csv.into_report();
+        println!("{}", report);
+        Ok(if report.is_clean() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        })
+    }
+}
 
-        graph
-            .add_node("mcdkoy")?
-            .mut_last_node(|mcd| {
-                mcd.add_label("mahalle")?
-                    .add_label("bolge")?
-                    .add_prop("tur", "mahalle")
-            })?
-            .add_node("merkez")?
-            .mut_last_node(|merkez| {
-                merkez
-                    .add_label("mahalle")?
-                    .add_label("bolge")?
-                    .add_prop("tur", "mahalle")
-            })?
-            .add_edges_by_aliases("includes", "sisli", "merkez")?
-            .add_edges_by_aliases("includes", "sisli", "mcdkoy")?
-            .add_node("merkez")?
-            .mut_last_node(|merkez| {
-                merkez
-                    .add_label("mahalle")?
-                    .add_label("diger")?
-                    .add_prop("tur", "mahalle")?
-                    .add_prop("test", "alternatif")?
-                    .add_prop("test", "alt")
-            })?
-            .add_edges_by_aliases("ayni", "merkez", "merkez")?
-            .add_edges_by_aliases("komsu", "merkez", "mcdkoy")?
-            .add_edges_by_aliases("komsu", "mcdkoy", "merkez")?;
+struct Repl {
+    graph: Graph,
+    history: Vec<String>,
+    //`DB_FILE-history`, next to the write-ahead log
+    history_file: Option<PathBuf>,
+}
 
-        println!("{}", graph);
+impl Repl {
+    fn new(path: Option<PathBuf>) -> GraphResult<Self> {
+        let Some(path) = path else {
+            return Ok(Repl {
+                graph: Graph::new(),
+                history: Vec::new(),
+                history_file: None,
+            });
+        };
+        let graph = Graph::open(&path)?;
+        let history_file = PathBuf::from(format!("{}-history", path.display()));
+        let history = match fs::read_to_string(&history_file) {
+            Ok(text) => text.lines().map(str::to_owned).collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(io_error(format!("reading {}", history_file.display()))(e)),
+        };
+        Ok(Repl {
+            graph,
+            history,
+            history_file: Some(history_file),
+        })
+    }
 
-        let target_alias = "sisli";
-        println!(
-            "outgoings neighbors of {} ->\n{:#?}",
-            target_alias,
-            graph.get_outgoing_neighbors(
-                graph
-                    .get_nodes_by_alias(target_alias)
-                    .expect(format!("cant find {}?", target_alias).as_str())
-                    .first()
-                    .expect("no first in vec?")
-            )
-        );
+    fn run(&mut self) -> GraphResult<()> {
+        let stdin = io::stdin();
+        //prompts only make sense for a person typing, piped scripts get bare output
+        let interactive = stdin.is_terminal();
+        if interactive {
+            println!("graph_db, :help for commands");
+        }
+        let mut lines = stdin.lock().lines();
+        loop {
+            if interactive {
+                print!("graph_db> ");
+                io::stdout().flush().map_err(io_error("writing prompt"))?;
+            }
+            let Some(line) = lines.next() else {
+                return Ok(());
+            };
+            let line = line.map_err(io_error("reading stdin"))?;
+            let line = match self.recall(line.trim()) {
+                Ok(line) => line,
+                Err(e) => {
+                    println!("error: {}", e);
+                    continue;
+                }
+            };
+            match line.as_str() {
+                "" => continue,
+                ":quit" | ":q" | ":exit" => return Ok(()),
+                ":help" => println!("{}", shell::HELP),
+                ":history" => {
+                    for (i, entry) in self.history.iter().enumerate() {
+                        println!("{:>4}  {}", i + 1, entry);
+                    }
+                }
+                command => match shell::execute(&mut self.graph, command) {
+                    Ok(output) => println!("{}", output),
+                    Err(e) => println!("error: {}", e),
+                },
+            }
+            self.remember(line)?;
+        }
+    }
 
-        println!(
-            "incoming neighbors of {} ->\n{:#?}",
-            target_alias,
-            graph.get_outgoing_neighbors(
-                graph
-                    .get_nodes_by_alias(target_alias)
-                    .expect(format!("cant find {}?", target_alias).as_str())
-                    .first()
-                    .expect("no first in vec?")
-            )
-        );
+    /// Expands `!n` and `!!` to history entries, echoing what will run
+    fn recall(&self, line: &str) -> Result<String, String> {
+        let Some(which) = line.strip_prefix('!') else {
+            return Ok(line.to_owned());
+        };
+        let entry = match which {
+            "!" => self.history.last(),
+            n => n
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|i| self.history.get(i)),
+        };
+        match entry {
+            Some(entry) => {
+                println!("{}", entry);
+                Ok(entry.clone())
+            }
+            None => Err(format!("no history entry `{}`, see :history", line)),
+        }
+    }
 
+    fn remember(&mut self, line: String) -> GraphResult<()> {
+        if self.history.last() == Some(&line) || line == ":history" {
+            return Ok(());
+        }
+        if let Some(path) = self.history_file.as_ref() {
+            let context = || format!("writing {}", path.display());
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(io_error(context()))?;
+            writeln!(file, "{}", line).map_err(io_error(context()))?;
+        }
+        self.history.push(line);
         Ok(())
     }
 }
@@ -0,0 +1,106 @@
+//! Cypher subset over [`vec_graph::Graph`](crate::vec_graph::Graph), or any [`GraphStore`] through [`execute`]
+//!
+//! ```text
+//! MATCH (you:Person {name:"elma"})-[:KNOWS]->(n)-[:KNOWS]->(friend:Person {name:"armut"})
+//! RETURN n
+//! ```
+//!
+//! Supported:
+//! - node patterns with an optional variable, any number of labels and a property map
+//! - directed relationships `-[:R]->`, `<-[:R]-`, alternatives `-[:R1|R2]->` and untyped `-->`
+//! - variable length relationships `-[:R*1..3]->`, also `*`, `*2`, `*2..` and `*..3`
+//! - path variables `p = (a)-[:R*]->(b)` returned as [`MatchedPath`]s
+//! - comma separated paths sharing variables: `MATCH (a)-->(b), (b)-->(c)`
+//! - `WHERE` filters over props: `n.age > 30 AND (n.city = "ist" OR n.city IS NULL)`,
+//!   comparisons follow [`PropValue::compare`](crate::vec_graph::PropValue::compare) so null and mismatched types filter the row out
+//! - `RETURN` of node variables, every row holds their bound [`NodeIndex`]es
+//!
+//! A relationship is walked at most once per match, like Cypher's relationship isomorphism.
+//! `MATCH UNIQUE NODES ...` additionally keeps variable length relationships from passing a node twice.
+
+mod ast;
+mod exec;
+mod lexer;
+mod parser;
+
+pub use ast::*;
+
+use crate::algo::Path;
+use crate::store::GraphStore;
+use crate::vec_graph::{EdgeIndex, Graph, GraphResult, NodeIndex};
+
+/// Returned node variables in `columns`/`rows` and path variables in `path_columns`/`path_rows`,
+/// `path_rows[i]` belongs to the same match as `rows[i]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<NodeIndex>>,
+    pub path_columns: Vec<String>,
+    pub path_rows: Vec<Vec<MatchedPath>>,
+}
+
+/// Path bound to a path variable, `nodes` has one more entry than `edges`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedPath {
+    pub nodes: Vec<NodeIndex>,
+    pub edges: Vec<EdgeIndex>,
+}
+
+impl MatchedPath {
+    /// Looks the ids up in `graph`, fails if any was removed since the query ran
+    pub fn resolve<'g>(&self, graph: &'g Graph) -> GraphResult<Path<'g>> {
+        for id in self.nodes.iter() {
+            graph.get_node(id)?;
+        }
+        for id in self.edges.iter() {
+            graph.get_edge(id)?;
+        }
+        Ok(Path::new(
+            graph,
+            self.nodes[0],
+            &self.edges,
+            self.edges.len() as f64,
+        ))
+    }
+}
+
+impl QueryResult {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+    /// Values of a single returned variable, in row order
+    pub fn column(&self, name: &str) -> Option<Vec<NodeIndex>> {
+        let idx = self.columns.iter().position(|c| c == name)?;
+        Some(self.rows.iter().map(|row| row[idx]).collect())
+    }
+    /// Values of a single returned path variable, in row order
+    pub fn path_column(&self, name: &str) -> Option<Vec<MatchedPath>> {
+        let idx = self.path_columns.iter().position(|c| c == name)?;
+        Some(self.path_rows.iter().map(|row| row[idx].clone()).collect())
+    }
+}
+
+#[inline]
+pub fn parse(query: &str) -> GraphResult<Query> {
+    parser::parse(query)
+}
+
+/// Parses one shell line: a query or an `ADD`/`SET`/`LINK`/`UNLINK`/`DELETE`/`SHOW` command
+#[inline]
+pub fn parse_command(line: &str) -> GraphResult<Command> {
+    parser::parse_command(line)
+}
+
+/// Runs a parsed query against any backend, label and prop lookups use its indexes where it has them
+#[inline]
+pub fn execute<G: GraphStore + ?Sized>(graph: &G, query: &Query) -> GraphResult<QueryResult> {
+    exec::execute(graph, query)
+}
+
+impl Graph {
+    /// Parses and runs a `MATCH ... RETURN ...` query
+    pub fn query(&self, query: &str) -> GraphResult<QueryResult> {
+        execute(self, &parse(query)?)
+    }
+}
@@ -0,0 +1,101 @@
+use crate::algo::Uniqueness;
+use crate::vec_graph::PropValue;
+
+/// One line of the shell language, see [`parse_command`](super::parse_command)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// `MATCH ...`
+    Match(Query),
+    /// `ADD (alias:Label {key: value}), ...` adds one node per pattern
+    Add(Vec<NodePattern>),
+    /// `SET (alias:Label {key: value}), ...` adds labels and props to every node with the alias
+    Set(Vec<NodePattern>),
+    /// `LINK (a)-[:R]->(b)<-[:S]-(c)` adds the edges between every node with those aliases
+    Link(PathPattern),
+    /// `UNLINK (a)-[:R]->(b)` removes them
+    Unlink(PathPattern),
+    /// `DELETE alias, ...` removes every node with the aliases and their edges
+    Delete(Vec<String>),
+    /// `SHOW [alias, ...]` lists the nodes with the aliases, or all nodes
+    Show(Vec<String>),
+}
+
+/// `MATCH [UNIQUE NODES|EDGES] <patterns> [WHERE <filter>] RETURN <items>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// What variable length relationships may not repeat, edges unless `UNIQUE NODES` is given
+    pub uniqueness: Uniqueness,
+    pub patterns: Vec<PathPattern>,
+    pub filter: Option<Expr>,
+    pub returns: Vec<String>,
+}
+
+/// `p = (a)-[:R]->(b)<-[:S]-(c)`: `start` followed by (relationship, node) hops
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathPattern {
+    /// `p` in `p = (a)-->(b)`, bound to the whole matched path
+    pub variable: Option<String>,
+    pub start: NodePattern,
+    pub hops: Vec<(RelPattern, NodePattern)>,
+}
+
+/// `(alias:Label1:Label2 {key: "value"})`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NodePattern {
+    pub variable: Option<String>,
+    pub labels: Vec<String>,
+    pub props: Vec<(String, PropValue)>,
+}
+
+/// `-[:R1|R2]->` or `<-[:R*1..3]-`, an empty type list matches any relation
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelPattern {
+    pub variable: Option<String>,
+    pub relations: Vec<String>,
+    pub direction: Direction,
+    /// Hop bounds of a variable length relationship, None for a single hop
+    pub length: Option<HopRange>,
+}
+
+/// `*min..max`, `*` alone is `1..` and `*n` is exactly `n` hops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HopRange {
+    pub min: usize,
+    /// None for no upper bound
+    pub max: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// `-[]->`
+    Outgoing,
+    /// `<-[]-`
+    Incoming,
+}
+
+/// `WHERE` expression, evaluated with null propagating three valued logic
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// `n.key`, null when the node has no such prop
+    Prop {
+        variable: String,
+        key: String,
+    },
+    Value(PropValue),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+    /// `x IS NULL`, or `x IS NOT NULL` when the flag is set
+    IsNull(Box<Expr>, bool),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
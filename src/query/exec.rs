@@ -0,0 +1,369 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use super::ast::*;
+use super::{MatchedPath, QueryResult};
+use crate::algo;
+use crate::store::GraphStore;
+use crate::vec_graph::{EdgeIndex, Error, GraphResult, NodeIndex, PropValue};
+
+/// Pattern flattened into the order it is matched in, every step binds one node slot
+enum Step<'q> {
+    /// Start of the `n`th comma separated path, matched against every node unless already bound
+    Start(&'q NodePattern, usize, usize),
+    /// Relationship from the slot bound by the previous step to the next node
+    Hop(&'q RelPattern, &'q NodePattern, usize),
+}
+
+struct Matcher<'g, 'q, G: GraphStore + ?Sized> {
+    graph: &'g G,
+    uniqueness: algo::Uniqueness,
+    steps: Vec<Step<'q>>,
+    slots: HashMap<&'q str, usize>,
+    filter: Option<&'q Expr>,
+    returns: Vec<usize>,
+    //(path pattern, its start slot) for every returned path variable
+    path_returns: Vec<(usize, usize)>,
+    bindings: Vec<Option<NodeIndex>>,
+    //edges already walked in the current match in walk order, relationships can't be reused within one match
+    used_edges: Vec<EdgeIndex>,
+    //where each path pattern's edges start in `used_edges`
+    path_offsets: Vec<usize>,
+    rows: Vec<Vec<NodeIndex>>,
+    path_rows: Vec<Vec<MatchedPath>>,
+}
+
+pub fn execute<G: GraphStore + ?Sized>(graph: &G, query: &Query) -> GraphResult<QueryResult> {
+    let mut slots: HashMap<&str, usize> = HashMap::new();
+    let mut slot_count = 0;
+    let mut steps = Vec::new();
+    let mut path_starts = Vec::new();
+    for (i, pattern) in query.patterns.iter().enumerate() {
+        let start = slot_for(&mut slots, &mut slot_count, &pattern.start.variable);
+        path_starts.push(start);
+        steps.push(Step::Start(&pattern.start, start, i));
+        for (rel, node) in pattern.hops.iter() {
+            steps.push(Step::Hop(
+                rel,
+                node,
+                slot_for(&mut slots, &mut slot_count, &node.variable),
+            ));
+        }
+    }
+    let rel_vars: Vec<&str> = query
+        .patterns
+        .iter()
+        .flat_map(|p| p.hops.iter().filter_map(|(r, _)| r.variable.as_deref()))
+        .collect();
+    if let Some(var) = rel_vars.iter().find(|v| slots.contains_key(*v)) {
+        return Err(Error::query(format!(
+            "variable `{}` is used for both a node and a relationship",
+            var
+        )));
+    }
+    //path variable -> its pattern
+    let mut paths: HashMap<&str, usize> = HashMap::new();
+    for (i, pattern) in query.patterns.iter().enumerate() {
+        let Some(var) = pattern.variable.as_deref() else {
+            continue;
+        };
+        if slots.contains_key(var) || rel_vars.contains(&var) || paths.insert(var, i).is_some() {
+            return Err(Error::query(format!(
+                "path variable `{}` is already used for something else",
+                var
+            )));
+        }
+    }
+    let mut columns = Vec::new();
+    let mut returns = Vec::new();
+    let mut path_columns = Vec::new();
+    let mut path_returns = Vec::new();
+    for name in query.returns.iter() {
+        if let Some(&slot) = slots.get(name.as_str()) {
+            columns.push(name.clone());
+            returns.push(slot);
+        } else if let Some(&pattern) = paths.get(name.as_str()) {
+            path_columns.push(name.clone());
+            path_returns.push((pattern, path_starts[pattern]));
+        } else if rel_vars.contains(&name.as_str()) {
+            return Err(Error::query(format!(
+                "can't return relationship variable `{}`, only nodes and paths",
+                name
+            )));
+        } else {
+            return Err(Error::query(format!("variable `{}` not defined", name)));
+        }
+    }
+    if let Some(filter) = query.filter.as_ref() {
+        check_variables(filter, &slots, &paths)?;
+    }
+
+    let mut matcher = Matcher {
+        graph,
+        uniqueness: query.uniqueness,
+        steps,
+        slots,
+        filter: query.filter.as_ref(),
+        returns,
+        path_returns,
+        bindings: vec![None; slot_count],
+        used_edges: Vec::new(),
+        path_offsets: vec![0; query.patterns.len()],
+        rows: Vec::new(),
+        path_rows: Vec::new(),
+    };
+    matcher.walk(0, None);
+    Ok(QueryResult {
+        columns,
+        rows: matcher.rows,
+        path_columns,
+        path_rows: matcher.path_rows,
+    })
+}
+
+fn check_variables(
+    expr: &Expr,
+    slots: &HashMap<&str, usize>,
+    paths: &HashMap<&str, usize>,
+) -> GraphResult<()> {
+    match expr {
+        Expr::Prop { variable, .. } if paths.contains_key(variable.as_str()) => Err(Error::query(
+            format!("path variable `{}` has no props", variable),
+        )),
+        Expr::Prop { variable, .. } if !slots.contains_key(variable.as_str()) => {
+            Err(Error::query(format!("variable `{}` not defined", variable)))
+        }
+        Expr::Prop { .. } | Expr::Value(_) => Ok(()),
+        Expr::Compare(lhs, _, rhs) | Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+            check_variables(lhs, slots, paths)?;
+            check_variables(rhs, slots, paths)
+        }
+        Expr::IsNull(inner, _) | Expr::Not(inner) => check_variables(inner, slots, paths),
+    }
+}
+
+/// Slot of a named variable, allocating it on first use. Anonymous nodes always get a fresh slot
+fn slot_for<'q>(
+    slots: &mut HashMap<&'q str, usize>,
+    count: &mut usize,
+    var: &'q Option<String>,
+) -> usize {
+    let fresh = *count;
+    let slot = match var {
+        Some(name) => *slots.entry(name.as_str()).or_insert(fresh),
+        None => fresh,
+    };
+    if slot == fresh {
+        *count += 1;
+    }
+    slot
+}
+
+impl<'g, 'q, G: GraphStore + ?Sized> Matcher<'g, 'q, G> {
+    fn walk(&mut self, step: usize, current: Option<NodeIndex>) {
+        let Some(next) = self.steps.get(step) else {
+            if let Some(filter) = self.filter {
+                if truth(&self.eval(filter)) != Some(true) {
+                    return;
+                }
+            }
+            let row = self
+                .returns
+                .iter()
+                .map(|&s| self.bindings[s].expect("Returned slot left unbound"))
+                .collect();
+            self.rows.push(row);
+            let paths = self
+                .path_returns
+                .iter()
+                .map(|&(pattern, start)| self.matched_path(pattern, start))
+                .collect();
+            self.path_rows.push(paths);
+            return;
+        };
+        match *next {
+            Step::Start(pattern, slot, path) => {
+                self.path_offsets[path] = self.used_edges.len();
+                let candidates: Vec<NodeIndex> = match self.bindings[slot] {
+                    Some(bound) => vec![bound],
+                    None => start_candidates(self.graph, pattern),
+                };
+                for id in candidates {
+                    self.try_bind(step, slot, pattern, id, None);
+                }
+            }
+            Step::Hop(rel, pattern, slot) if rel.length.is_some() => {
+                let current = current.expect("Hop without a bound source node");
+                self.var_length_hop(step, rel, pattern, slot, current);
+            }
+            Step::Hop(rel, pattern, slot) => {
+                let current = current.expect("Hop without a bound source node");
+                let direction = match rel.direction {
+                    Direction::Outgoing => algo::Direction::Outgoing,
+                    Direction::Incoming => algo::Direction::Incoming,
+                };
+                let candidates: Vec<(EdgeIndex, NodeIndex)> = self
+                    .graph
+                    .adjacent(&current, direction, &rel.relations)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|(e, _)| !self.used_edges.contains(e))
+                    .collect();
+                for (edge, id) in candidates {
+                    self.try_bind(step, slot, pattern, id, Some(edge));
+                }
+            }
+        }
+    }
+
+    /// Binds every path end `rel` reaches from `current`, its edges count as walked for the rest of the match
+    fn var_length_hop(
+        &mut self,
+        step: usize,
+        rel: &RelPattern,
+        pattern: &NodePattern,
+        slot: usize,
+        current: NodeIndex,
+    ) {
+        let range = rel.length.expect("Checked variable length");
+        let direction = match rel.direction {
+            Direction::Outgoing => algo::Direction::Outgoing,
+            Direction::Incoming => algo::Direction::Incoming,
+        };
+        let Ok(paths) = algo::VarLengthPaths::new(self.graph, current) else {
+            return;
+        };
+        let paths = match range.max {
+            Some(max) => paths.hops(range.min..=max),
+            None => paths.hops(range.min..),
+        };
+        //a path through an edge used earlier in the match is dropped, and so are all its extensions
+        let candidates: Vec<(Vec<EdgeIndex>, NodeIndex)> = paths
+            .direction(direction)
+            .relations(rel.relations.iter().cloned())
+            .uniqueness(self.uniqueness)
+            .map(|p| {
+                let end = *p.node_ids().last().expect("Paths end with a node");
+                (p.edge_ids().to_vec(), end)
+            })
+            .filter(|(edges, _)| edges.iter().all(|e| !self.used_edges.contains(e)))
+            .collect();
+        for (edges, id) in candidates {
+            let mark = self.used_edges.len();
+            self.used_edges.extend(edges);
+            self.try_bind(step, slot, pattern, id, None);
+            self.used_edges.truncate(mark);
+        }
+    }
+
+    /// Nodes and edges the `pattern`th path walked in the current match
+    fn matched_path(&self, pattern: usize, start: usize) -> MatchedPath {
+        let end = self
+            .path_offsets
+            .get(pattern + 1)
+            .copied()
+            .unwrap_or(self.used_edges.len());
+        let edges = self.used_edges[self.path_offsets[pattern]..end].to_vec();
+        let mut at = self.bindings[start].expect("Path start left unbound");
+        let mut nodes = vec![at];
+        for id in edges.iter() {
+            let (from, to) = self.graph.edge_ends(id).expect("Matched edges are live");
+            at = if from == at { to } else { from };
+            nodes.push(at);
+        }
+        MatchedPath { nodes, edges }
+    }
+
+    fn eval(&self, expr: &Expr) -> PropValue {
+        match expr {
+            Expr::Prop { variable, key } => self.bindings[self.slots[variable.as_str()]]
+                .and_then(|id| self.graph.node_prop(&id, key).ok().flatten())
+                .unwrap_or_default(),
+            Expr::Value(val) => val.clone(),
+            Expr::Compare(lhs, op, rhs) => {
+                let (lhs, rhs) = (self.eval(lhs), self.eval(rhs));
+                let result = match op {
+                    CompareOp::Eq => lhs.equals(&rhs),
+                    CompareOp::Ne => lhs.equals(&rhs).map(|eq| !eq),
+                    CompareOp::Lt => lhs.compare(&rhs).map(|o| o == Ordering::Less),
+                    CompareOp::Le => lhs.compare(&rhs).map(|o| o != Ordering::Greater),
+                    CompareOp::Gt => lhs.compare(&rhs).map(|o| o == Ordering::Greater),
+                    CompareOp::Ge => lhs.compare(&rhs).map(|o| o != Ordering::Less),
+                };
+                result.into()
+            }
+            Expr::IsNull(inner, negated) => PropValue::Bool(self.eval(inner).is_null() != *negated),
+            Expr::And(lhs, rhs) => match (truth(&self.eval(lhs)), truth(&self.eval(rhs))) {
+                (Some(false), _) | (_, Some(false)) => PropValue::Bool(false),
+                (Some(true), Some(true)) => PropValue::Bool(true),
+                _ => PropValue::Null,
+            },
+            Expr::Or(lhs, rhs) => match (truth(&self.eval(lhs)), truth(&self.eval(rhs))) {
+                (Some(true), _) | (_, Some(true)) => PropValue::Bool(true),
+                (Some(false), Some(false)) => PropValue::Bool(false),
+                _ => PropValue::Null,
+            },
+            Expr::Not(inner) => truth(&self.eval(inner)).map(|b| !b).into(),
+        }
+    }
+
+    fn try_bind(
+        &mut self,
+        step: usize,
+        slot: usize,
+        pattern: &NodePattern,
+        id: NodeIndex,
+        edge: Option<EdgeIndex>,
+    ) {
+        let previous = self.bindings[slot];
+        if previous.is_some_and(|bound| bound != id) {
+            return;
+        }
+        if !node_matches(self.graph, id, pattern) {
+            return;
+        }
+        self.bindings[slot] = Some(id);
+        if let Some(edge) = edge {
+            self.used_edges.push(edge);
+        }
+        self.walk(step + 1, Some(id));
+        if edge.is_some() {
+            self.used_edges.pop();
+        }
+        self.bindings[slot] = previous;
+    }
+}
+
+/// Smallest candidate set the label and prop indexes can give for an unbound node, every node otherwise
+fn start_candidates<G: GraphStore + ?Sized>(graph: &G, pattern: &NodePattern) -> Vec<NodeIndex> {
+    let by_label = pattern.labels.iter().map(|l| graph.ids_with_label(l));
+    let by_prop = pattern.props.iter().filter_map(|(key, val)| match val {
+        PropValue::Null => Some(Vec::new()),
+        val => graph.ids_with_prop(key, val),
+    });
+    by_label
+        .chain(by_prop)
+        .min_by_key(|ids| ids.len())
+        .unwrap_or_else(|| graph.node_ids())
+}
+
+/// Boolean value of a filter result, None for null and non booleans
+fn truth(val: &PropValue) -> Option<bool> {
+    val.as_bool()
+}
+
+fn node_matches<G: GraphStore + ?Sized>(graph: &G, id: NodeIndex, pattern: &NodePattern) -> bool {
+    graph.has_node(&id)
+        && pattern
+            .labels
+            .iter()
+            .all(|l| graph.node_has_label(&id, l).unwrap_or(false))
+        && pattern.props.iter().all(|(key, val)| {
+            //null never equals anything, so `{key: null}` matches no node
+            graph
+                .node_prop(&id, key)
+                .ok()
+                .flatten()
+                .and_then(|prop| prop.equals(val))
+                .unwrap_or(false)
+        })
+}
@@ -0,0 +1,189 @@
+use crate::vec_graph::{Error, GraphResult};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Ident(String),
+    Str(String),
+    Int(i64),
+    Float(f64),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Colon,
+    Comma,
+    Dot,
+    Pipe,
+    Dash,
+    Lt,
+    Gt,
+    Eq,
+    Bang,
+    Star,
+    Semicolon,
+    Eof,
+}
+
+impl Token {
+    pub fn describe(&self) -> String {
+        match self {
+            Token::Ident(s) => format!("identifier `{}`", s),
+            Token::Str(s) => format!("string \"{}\"", s),
+            Token::Int(i) => format!("number {}", i),
+            Token::Float(x) => format!("number {}", x),
+            Token::LParen => "`(`".to_owned(),
+            Token::RParen => "`)`".to_owned(),
+            Token::LBracket => "`[`".to_owned(),
+            Token::RBracket => "`]`".to_owned(),
+            Token::LBrace => "`{`".to_owned(),
+            Token::RBrace => "`}`".to_owned(),
+            Token::Colon => "`:`".to_owned(),
+            Token::Comma => "`,`".to_owned(),
+            Token::Dot => "`.`".to_owned(),
+            Token::Pipe => "`|`".to_owned(),
+            Token::Dash => "`-`".to_owned(),
+            Token::Lt => "`<`".to_owned(),
+            Token::Gt => "`>`".to_owned(),
+            Token::Eq => "`=`".to_owned(),
+            Token::Bang => "`!`".to_owned(),
+            Token::Star => "`*`".to_owned(),
+            Token::Semicolon => "`;`".to_owned(),
+            Token::Eof => "end of query".to_owned(),
+        }
+    }
+}
+
+/// Token plus the byte offset it started at, used for error messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned {
+    pub token: Token,
+    pub pos: usize,
+}
+
+pub fn tokenize(src: &str) -> GraphResult<Vec<Spanned>> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        let single = match c {
+            b'(' => Some(Token::LParen),
+            b')' => Some(Token::RParen),
+            b'[' => Some(Token::LBracket),
+            b']' => Some(Token::RBracket),
+            b'{' => Some(Token::LBrace),
+            b'}' => Some(Token::RBrace),
+            b':' => Some(Token::Colon),
+            b',' => Some(Token::Comma),
+            b'.' => Some(Token::Dot),
+            b'|' => Some(Token::Pipe),
+            b'-' => Some(Token::Dash),
+            b'<' => Some(Token::Lt),
+            b'>' => Some(Token::Gt),
+            b'=' => Some(Token::Eq),
+            b'!' => Some(Token::Bang),
+            b'*' => Some(Token::Star),
+            b';' => Some(Token::Semicolon),
+            _ => None,
+        };
+        if let Some(token) = single {
+            tokens.push(Spanned { token, pos: start });
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if c == b'"' || c == b'\'' {
+            let (s, end) = read_string(src, i)?;
+            tokens.push(Spanned {
+                token: Token::Str(s),
+                pos: start,
+            });
+            i = end;
+        } else if c.is_ascii_digit() {
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            //a dot followed by a digit continues the number, `1..3` stays an int
+            let is_float = i + 1 < bytes.len() && bytes[i] == b'.' && bytes[i + 1].is_ascii_digit();
+            if is_float {
+                i += 1;
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let x = src[start..i]
+                    .parse::<f64>()
+                    .map_err(|e| Error::parse(start, format!("invalid number: {}", e)))?;
+                tokens.push(Spanned {
+                    token: Token::Float(x),
+                    pos: start,
+                });
+            } else {
+                let n = src[start..i]
+                    .parse::<i64>()
+                    .map_err(|e| Error::parse(start, format!("invalid number: {}", e)))?;
+                tokens.push(Spanned {
+                    token: Token::Int(n),
+                    pos: start,
+                });
+            }
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            tokens.push(Spanned {
+                token: Token::Ident(src[start..i].to_owned()),
+                pos: start,
+            });
+        } else if c == b'`' {
+            //backticked identifiers allow spaces and unicode: (n:`Şehir`)
+            let end = src[i + 1..]
+                .find('`')
+                .ok_or_else(|| Error::parse(start, "unterminated identifier"))?;
+            tokens.push(Spanned {
+                token: Token::Ident(src[i + 1..i + 1 + end].to_owned()),
+                pos: start,
+            });
+            i += end + 2;
+        } else {
+            let ch = src[i..].chars().next().unwrap_or('?');
+            return Err(Error::parse(
+                start,
+                format!("unexpected character '{}'", ch),
+            ));
+        }
+    }
+    tokens.push(Spanned {
+        token: Token::Eof,
+        pos: src.len(),
+    });
+    Ok(tokens)
+}
+
+/// Reads a quoted string starting at `start`, returns it unescaped with the index after the closing quote
+fn read_string(src: &str, start: usize) -> GraphResult<(String, usize)> {
+    let quote = src.as_bytes()[start] as char;
+    let mut out = String::new();
+    let mut chars = src[start + 1..].char_indices();
+    while let Some((off, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                let (_, esc) = chars
+                    .next()
+                    .ok_or_else(|| Error::parse(start, "unterminated string"))?;
+                out.push(match esc {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    other => other,
+                });
+            }
+            c if c == quote => return Ok((out, start + 1 + off + 1)),
+            c => out.push(c),
+        }
+    }
+    Err(Error::parse(start, "unterminated string"))
+}
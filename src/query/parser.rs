@@ -0,0 +1,491 @@
+use super::ast::*;
+use super::lexer::{tokenize, Spanned, Token};
+use crate::algo::Uniqueness;
+use crate::vec_graph::{Error, GraphResult, PropValue};
+
+pub fn parse(src: &str) -> GraphResult<Query> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+        depth: 0,
+    };
+    let query = parser.query()?;
+    parser.eat(&Token::Semicolon);
+    parser.expect(&Token::Eof)?;
+    Ok(query)
+}
+
+pub fn parse_command(src: &str) -> GraphResult<Command> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+        depth: 0,
+    };
+    let command = parser.command()?;
+    parser.eat(&Token::Semicolon);
+    parser.expect(&Token::Eof)?;
+    Ok(command)
+}
+
+//deep enough for any real query, low enough that nesting can't overflow the stack
+const MAX_DEPTH: usize = 256;
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    //open parentheses, lists, NOTs and AND/OR chain links around the current token
+    depth: usize,
+}
+
+impl Parser {
+    #[inline]
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].token
+    }
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].token.clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == token {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+    fn error<T>(&self, expected: &str) -> GraphResult<T> {
+        let found = &self.tokens[self.pos];
+        Err(Error::parse(
+            found.pos,
+            format!("expected {}, found {}", expected, found.token.describe()),
+        ))
+    }
+    fn expect(&mut self, token: &Token) -> GraphResult<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            self.error(&token.describe())
+        }
+    }
+    /// One level deeper, a parse error past `MAX_DEPTH`. Errors end the parse, so only
+    /// successful levels need a matching `leave`
+    fn enter(&mut self) -> GraphResult<()> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::parse(
+                self.tokens[self.pos].pos,
+                format!("nested deeper than {} levels", MAX_DEPTH),
+            ));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+    #[inline]
+    fn leave(&mut self, levels: usize) {
+        self.depth -= levels;
+    }
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident(s) if s.eq_ignore_ascii_case(keyword))
+    }
+    fn keyword(&mut self, keyword: &str) -> GraphResult<()> {
+        if self.is_keyword(keyword) {
+            self.next();
+            Ok(())
+        } else {
+            self.error(keyword)
+        }
+    }
+    fn ident(&mut self) -> GraphResult<String> {
+        match self.peek() {
+            Token::Ident(_) => match self.next() {
+                Token::Ident(s) => Ok(s),
+                _ => unreachable!(),
+            },
+            _ => self.error("identifier"),
+        }
+    }
+
+    fn command(&mut self) -> GraphResult<Command> {
+        if self.is_keyword("MATCH") {
+            return Ok(Command::Match(self.query()?));
+        }
+        let verb = match self.peek() {
+            Token::Ident(verb) => verb.to_ascii_uppercase(),
+            _ => return self.error("command"),
+        };
+        let pos = self.tokens[self.pos].pos;
+        self.next();
+        Ok(match verb.as_str() {
+            "ADD" => Command::Add(self.aliased_nodes()?),
+            "SET" => Command::Set(self.aliased_nodes()?),
+            "LINK" => Command::Link(self.alias_path()?),
+            "UNLINK" => Command::Unlink(self.alias_path()?),
+            "DELETE" => Command::Delete(self.aliases()?),
+            "SHOW" if *self.peek() == Token::Eof || *self.peek() == Token::Semicolon => {
+                Command::Show(Vec::new())
+            }
+            "SHOW" => Command::Show(self.aliases()?),
+            _ => {
+                return Err(Error::parse(
+                    pos,
+                    format!(
+                    "unknown command `{}`, expected MATCH, ADD, SET, LINK, UNLINK, DELETE or SHOW",
+                    verb
+                ),
+                ))
+            }
+        })
+    }
+
+    /// `(alias:Label {key: value}), ...` where every node names its alias
+    fn aliased_nodes(&mut self) -> GraphResult<Vec<NodePattern>> {
+        let mut nodes = Vec::new();
+        loop {
+            let pos = self.tokens[self.pos].pos;
+            let node = self.node()?;
+            if node.variable.is_none() {
+                return Err(Error::parse(pos, "node needs an alias: `(alias:Label)`"));
+            }
+            nodes.push(node);
+            if !self.eat(&Token::Comma) {
+                return Ok(nodes);
+            }
+        }
+    }
+
+    /// Path of bare aliases and single relation types: `(a)-[:R]->(b)`
+    fn alias_path(&mut self) -> GraphResult<PathPattern> {
+        let pos = self.tokens[self.pos].pos;
+        let path = self.path()?;
+        let bare =
+            |n: &NodePattern| n.variable.is_some() && n.labels.is_empty() && n.props.is_empty();
+        if path.variable.is_some() || path.hops.is_empty() || !bare(&path.start) {
+            return Err(Error::parse(pos, "expected `(alias)-[:relation]->(alias)`"));
+        }
+        for (rel, node) in path.hops.iter() {
+            if rel.relations.len() != 1 || rel.length.is_some() || !bare(node) {
+                return Err(Error::parse(pos, "expected `(alias)-[:relation]->(alias)`"));
+            }
+        }
+        Ok(path)
+    }
+
+    fn aliases(&mut self) -> GraphResult<Vec<String>> {
+        let mut aliases = vec![self.alias()?];
+        while self.eat(&Token::Comma) {
+            aliases.push(self.alias()?);
+        }
+        Ok(aliases)
+    }
+
+    /// Identifier or quoted string, for aliases that aren't valid identifiers
+    fn alias(&mut self) -> GraphResult<String> {
+        match self.peek() {
+            Token::Str(_) => match self.next() {
+                Token::Str(s) => Ok(s),
+                _ => unreachable!(),
+            },
+            _ => self.ident(),
+        }
+    }
+
+    fn query(&mut self) -> GraphResult<Query> {
+        self.keyword("MATCH")?;
+        let uniqueness = if self.is_keyword("UNIQUE") {
+            self.next();
+            if self.is_keyword("NODES") {
+                self.next();
+                Uniqueness::Nodes
+            } else {
+                self.keyword("EDGES")?;
+                Uniqueness::Edges
+            }
+        } else {
+            Uniqueness::default()
+        };
+        let mut patterns = vec![self.path()?];
+        while self.eat(&Token::Comma) {
+            patterns.push(self.path()?);
+        }
+        let filter = if self.is_keyword("WHERE") {
+            self.next();
+            Some(self.or_expr()?)
+        } else {
+            None
+        };
+        self.keyword("RETURN")?;
+        let mut returns = vec![self.ident()?];
+        while self.eat(&Token::Comma) {
+            returns.push(self.ident()?);
+        }
+        Ok(Query {
+            uniqueness,
+            patterns,
+            filter,
+            returns,
+        })
+    }
+
+    fn path(&mut self) -> GraphResult<PathPattern> {
+        let variable = if matches!(self.peek(), Token::Ident(_)) {
+            let variable = self.ident()?;
+            self.expect(&Token::Eq)?;
+            Some(variable)
+        } else {
+            None
+        };
+        let start = self.node()?;
+        let mut hops = Vec::new();
+        while matches!(self.peek(), Token::Dash | Token::Lt) {
+            let rel = self.rel()?;
+            hops.push((rel, self.node()?));
+        }
+        Ok(PathPattern {
+            variable,
+            start,
+            hops,
+        })
+    }
+
+    fn node(&mut self) -> GraphResult<NodePattern> {
+        self.expect(&Token::LParen)?;
+        let mut node = NodePattern::default();
+        if let Token::Ident(_) = self.peek() {
+            node.variable = Some(self.ident()?);
+        }
+        while self.eat(&Token::Colon) {
+            node.labels.push(self.ident()?);
+        }
+        if *self.peek() == Token::LBrace {
+            node.props = self.props()?;
+        }
+        self.expect(&Token::RParen)?;
+        Ok(node)
+    }
+
+    fn props(&mut self) -> GraphResult<Vec<(String, PropValue)>> {
+        self.expect(&Token::LBrace)?;
+        let mut props = Vec::new();
+        if self.eat(&Token::RBrace) {
+            return Ok(props);
+        }
+        loop {
+            let key = self.ident()?;
+            self.expect(&Token::Colon)?;
+            props.push((key, self.literal()?));
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        Ok(props)
+    }
+
+    fn literal(&mut self) -> GraphResult<PropValue> {
+        if self.eat(&Token::LBracket) {
+            self.enter()?;
+            let mut items = Vec::new();
+            if !self.eat(&Token::RBracket) {
+                loop {
+                    items.push(self.literal()?);
+                    if !self.eat(&Token::Comma) {
+                        break;
+                    }
+                }
+                self.expect(&Token::RBracket)?;
+            }
+            self.leave(1);
+            return Ok(PropValue::List(items));
+        }
+        let negative = self.eat(&Token::Dash);
+        let lit = match self.peek().clone() {
+            Token::Str(s) if !negative => PropValue::Str(s),
+            Token::Int(i) => PropValue::Int(if negative { -i } else { i }),
+            Token::Float(x) => PropValue::Float(if negative { -x } else { x }),
+            Token::Ident(s) if !negative && s.eq_ignore_ascii_case("true") => PropValue::Bool(true),
+            Token::Ident(s) if !negative && s.eq_ignore_ascii_case("false") => {
+                PropValue::Bool(false)
+            }
+            Token::Ident(s) if !negative && s.eq_ignore_ascii_case("null") => PropValue::Null,
+            _ => return self.error("literal"),
+        };
+        self.next();
+        Ok(lit)
+    }
+
+    fn or_expr(&mut self) -> GraphResult<Expr> {
+        let mut lhs = self.and_expr()?;
+        //each link nests the chain so far one level deeper
+        let mut links = 0;
+        while self.is_keyword("OR") {
+            self.next();
+            self.enter()?;
+            links += 1;
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and_expr()?));
+        }
+        self.leave(links);
+        Ok(lhs)
+    }
+
+    fn and_expr(&mut self) -> GraphResult<Expr> {
+        let mut lhs = self.not_expr()?;
+        let mut links = 0;
+        while self.is_keyword("AND") {
+            self.next();
+            self.enter()?;
+            links += 1;
+            lhs = Expr::And(Box::new(lhs), Box::new(self.not_expr()?));
+        }
+        self.leave(links);
+        Ok(lhs)
+    }
+
+    fn not_expr(&mut self) -> GraphResult<Expr> {
+        if self.is_keyword("NOT") {
+            self.next();
+            self.enter()?;
+            let expr = Expr::Not(Box::new(self.not_expr()?));
+            self.leave(1);
+            return Ok(expr);
+        }
+        let lhs = self.operand()?;
+        if self.is_keyword("IS") {
+            self.next();
+            let negated = self.is_keyword("NOT");
+            if negated {
+                self.next();
+            }
+            self.keyword("NULL")?;
+            return Ok(Expr::IsNull(Box::new(lhs), negated));
+        }
+        let op = match self.peek().clone() {
+            Token::Eq => CompareOp::Eq,
+            Token::Bang => {
+                self.next();
+                if *self.peek() != Token::Eq {
+                    return self.error("`=` after `!`");
+                }
+                CompareOp::Ne
+            }
+            Token::Lt if self.followed_by(&Token::Gt) => CompareOp::Ne,
+            Token::Lt if self.followed_by(&Token::Eq) => CompareOp::Le,
+            Token::Lt => CompareOp::Lt,
+            Token::Gt if self.followed_by(&Token::Eq) => CompareOp::Ge,
+            Token::Gt => CompareOp::Gt,
+            _ => return Ok(lhs),
+        };
+        self.next();
+        Ok(Expr::Compare(Box::new(lhs), op, Box::new(self.operand()?)))
+    }
+
+    /// For two token operators like `<=`: skips the current token if `token` comes right after it
+    fn followed_by(&mut self, token: &Token) -> bool {
+        let after = &self.tokens[(self.pos + 1).min(self.tokens.len() - 1)].token;
+        if after == token {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn operand(&mut self) -> GraphResult<Expr> {
+        if self.eat(&Token::LParen) {
+            self.enter()?;
+            let expr = self.or_expr()?;
+            self.expect(&Token::RParen)?;
+            self.leave(1);
+            return Ok(expr);
+        }
+        let is_literal = ["true", "false", "null"].iter().any(|k| self.is_keyword(k));
+        if matches!(self.peek(), Token::Ident(_)) && !is_literal {
+            let variable = self.ident()?;
+            self.expect(&Token::Dot)?;
+            let key = self.ident()?;
+            return Ok(Expr::Prop { variable, key });
+        }
+        Ok(Expr::Value(self.literal()?))
+    }
+
+    /// `-[...]->`, `<-[...]-`, `-->` and `<--`
+    fn rel(&mut self) -> GraphResult<RelPattern> {
+        let incoming = self.eat(&Token::Lt);
+        self.expect(&Token::Dash)?;
+        let mut rel = RelPattern {
+            variable: None,
+            relations: Vec::new(),
+            direction: if incoming {
+                Direction::Incoming
+            } else {
+                Direction::Outgoing
+            },
+            length: None,
+        };
+        if self.eat(&Token::LBracket) {
+            if let Token::Ident(_) = self.peek() {
+                rel.variable = Some(self.ident()?);
+            }
+            if self.eat(&Token::Colon) {
+                rel.relations.push(self.ident()?);
+                while self.eat(&Token::Pipe) {
+                    //both `:A|B` and `:A|:B` are accepted
+                    self.eat(&Token::Colon);
+                    rel.relations.push(self.ident()?);
+                }
+            }
+            if self.eat(&Token::Star) {
+                rel.length = Some(self.hop_range()?);
+            }
+            self.expect(&Token::RBracket)?;
+        }
+        self.expect(&Token::Dash)?;
+        if incoming {
+            if *self.peek() == Token::Gt {
+                return self.error("`(` after `<-[]-`, relationships can't point both ways");
+            }
+        } else if !self.eat(&Token::Gt) {
+            return self.error("`>`, undirected relationships are not supported");
+        }
+        Ok(rel)
+    }
+
+    /// After the `*`: nothing, `n`, `n..`, `..m` or `n..m`
+    fn hop_range(&mut self) -> GraphResult<HopRange> {
+        let pos = self.tokens[self.pos].pos;
+        let min = self.hop_count()?;
+        let range = if self.eat(&Token::Dot) {
+            self.expect(&Token::Dot)?;
+            HopRange {
+                min: min.unwrap_or(1),
+                max: self.hop_count()?,
+            }
+        } else {
+            match min {
+                Some(n) => HopRange {
+                    min: n,
+                    max: Some(n),
+                },
+                None => HopRange { min: 1, max: None },
+            }
+        };
+        if range.max.is_some_and(|max| max < range.min) {
+            return Err(Error::parse(pos, "hop range minimum is above its maximum"));
+        }
+        Ok(range)
+    }
+
+    fn hop_count(&mut self) -> GraphResult<Option<usize>> {
+        match *self.peek() {
+            Token::Int(n) => {
+                self.next();
+                usize::try_from(n)
+                    .map(Some)
+                    .map_err(|_| Error::parse(self.tokens[self.pos - 1].pos, "hop count too large"))
+            }
+            _ => Ok(None),
+        }
+    }
+}
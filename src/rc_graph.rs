@@ -1,94 +1,494 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+//! Graph whose nodes are shared `Rc<RefCell<_>>` handles that link to each other themselves
+//!
+//! The graph holds the only strong `Rc` of every node, links point at their target with a `Weak`,
+//! so there are no reference cycles and a node is freed once the graph and the callers let go of it.
+//! A [`NodeRef`] adds and removes its own links, no ids have to go through the graph
+//! ```
+//! # use graph_db::rc_graph::Graph;
+//! let mut graph = Graph::new();
+//! let ali = graph.add_node("ali")?;
+//! graph.add_node("veli")?;
+//! graph.get_node("ali")?.add_link("knows", &graph.get_node("veli")?)?;
+//! assert_eq!(ali.borrow().links()[0].target().unwrap().alias(), "veli");
+//!
+//! graph.remove_node("veli")?;
+//! assert!(ali.borrow().links().is_empty());
+//! # Ok::<(), graph_db::vec_graph::Error>(())
+//! ```
+//! Link methods borrow the nodes involved, calling them while holding `borrow_mut` of one panics
 
-type Link<T> = Rc<RefCell<T>>;
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    collections::{BTreeMap, HashMap},
+    rc::{Rc, Weak},
+};
 
-struct Edge {
+use crate::algo::Direction;
+use crate::store::{sorted, GraphStore};
+use crate::vec_graph::{Arena, EdgeIndex, Error, GraphResult, NodeIndex, PropValue};
+
+//source node of every link, shared by a graph and its nodes so nodes can hand out link ids
+type Edges = Rc<RefCell<Arena<NodeIndex>>>;
+
+/// Outgoing edge, stored in its source node
+#[derive(Debug, Clone)]
+pub struct Link {
+    id: EdgeIndex,
     relation: String,
-    link: Link<Node>,
+    to: NodeIndex,
+    target: Weak<RefCell<Node>>,
+    labels: Vec<String>,
+    props: HashMap<String, PropValue>,
 }
 
-impl Edge {
-    fn new(name: String, node: Node) -> Self {
-        Edge {
-            relation: name,
-            link: Rc::new(RefCell::new(node)),
-        }
+impl Link {
+    #[inline]
+    pub fn id(&self) -> EdgeIndex {
+        self.id
     }
-
-    fn new_to(name: String, node: Link<Node>) -> Self {
-        Edge {
-            relation: name,
-            link: node,
-        }
+    #[inline]
+    pub fn relation(&self) -> &str {
+        &self.relation
     }
-}
-
-impl PartialEq for Edge {
-    fn eq(&self, other: &Self) -> bool {
-        (self.relation == other.relation) && (self.link.borrow().id == other.link.borrow().id)
+    #[inline]
+    pub fn to(&self) -> &NodeIndex {
+        &self.to
+    }
+    /// None once the graph and every caller dropped the target
+    #[inline]
+    pub fn target(&self) -> Option<NodeRef> {
+        self.target.upgrade().map(NodeRef)
+    }
+    #[inline]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+    #[inline]
+    pub fn props(&self) -> &HashMap<String, PropValue> {
+        &self.props
+    }
+    #[inline]
+    pub fn get_prop(&self, key: &str) -> Option<&PropValue> {
+        self.props.get(key)
     }
 }
 
-struct Node {
-    id: usize,
+#[derive(Debug)]
+pub struct Node {
+    id: NodeIndex,
     alias: String,
     labels: Vec<String>,
-    props: HashMap<String, String>,
-    rels: Vec<Edge>,
+    props: HashMap<String, PropValue>,
+    links: Vec<Link>,
+    //(link, its source) for every link pointing here
+    incoming: Vec<(EdgeIndex, NodeIndex)>,
+    //dangles once the node is removed from its graph
+    edges: Weak<RefCell<Arena<NodeIndex>>>,
 }
 
 impl Node {
-    fn new(id: usize, alias: String) -> Self {
-        Node {
+    pub fn add_label<S: AsRef<str>>(&mut self, label: S) -> &mut Self {
+        self.labels.push(label.as_ref().to_owned());
+        self
+    }
+    pub fn remove_label<S: AsRef<str>>(&mut self, label: S) -> &mut Self {
+        self.labels.retain(|x| x != label.as_ref());
+        self
+    }
+    pub fn add_prop<S: AsRef<str>, V: Into<PropValue>>(&mut self, key: S, val: V) -> &mut Self {
+        self.props.insert(key.as_ref().to_owned(), val.into());
+        self
+    }
+    pub fn remove_prop<S: AsRef<str>>(&mut self, key: S) -> &mut Self {
+        self.props.remove(key.as_ref());
+        self
+    }
+    #[inline]
+    pub fn id(&self) -> NodeIndex {
+        self.id
+    }
+    #[inline]
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+    #[inline]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+    #[inline]
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|x| x == label)
+    }
+    #[inline]
+    pub fn props(&self) -> &HashMap<String, PropValue> {
+        &self.props
+    }
+    #[inline]
+    pub fn get_prop(&self, key: &str) -> Option<&PropValue> {
+        self.props.get(key)
+    }
+    /// Outgoing links, oldest first
+    #[inline]
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+    /// (link, source node) of every link to this node, oldest first
+    #[inline]
+    pub fn incoming(&self) -> &[(EdgeIndex, NodeIndex)] {
+        &self.incoming
+    }
+}
+
+/// Shared handle to a node, cloning it clones the `Rc`
+#[derive(Debug, Clone)]
+pub struct NodeRef(Rc<RefCell<Node>>);
+
+impl NodeRef {
+    #[inline]
+    pub fn borrow(&self) -> Ref<'_, Node> {
+        self.0.borrow()
+    }
+    #[inline]
+    pub fn borrow_mut(&self) -> RefMut<'_, Node> {
+        self.0.borrow_mut()
+    }
+    #[inline]
+    pub fn id(&self) -> NodeIndex {
+        self.0.borrow().id
+    }
+    #[inline]
+    pub fn alias(&self) -> String {
+        self.0.borrow().alias.clone()
+    }
+    /// Whether both handles are the same node
+    #[inline]
+    pub fn ptr_eq(&self, other: &NodeRef) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+    /// Whether the node is still in its graph
+    #[inline]
+    pub fn is_attached(&self) -> bool {
+        self.0.borrow().edges.strong_count() > 0
+    }
+    fn edges(&self) -> GraphResult<Edges> {
+        let node = self.0.borrow();
+        node.edges
+            .upgrade()
+            .ok_or(Error::NodeNotFound { id: node.id })
+    }
+    /// Adds a `relation` link to `to`, which must be in the same graph
+    pub fn add_link(&self, relation: &str, to: &NodeRef) -> GraphResult<EdgeIndex> {
+        let edges = self.edges()?;
+        match to.edges() {
+            Ok(other) if Rc::ptr_eq(&edges, &other) => {}
+            _ => return Err(Error::NodeNotFound { id: to.id() }),
+        }
+        let (from, target) = (self.id(), to.id());
+        let id = edges.borrow_mut().insert(from);
+        self.0.borrow_mut().links.push(Link {
             id,
-            alias,
+            relation: relation.to_owned(),
+            to: target,
+            target: Rc::downgrade(&to.0),
             labels: Vec::new(),
             props: HashMap::new(),
-            rels: Vec::new(),
+        });
+        to.0.borrow_mut().incoming.push((id, from));
+        Ok(id)
+    }
+    /// Removes one of this node's outgoing links
+    pub fn remove_link(&self, id: &EdgeIndex) -> GraphResult<()> {
+        let edges = self.edges()?;
+        let link = {
+            let mut node = self.0.borrow_mut();
+            let at = node
+                .links
+                .iter()
+                .position(|l| l.id == *id)
+                .ok_or(Error::EdgeNotFound { id: *id })?;
+            node.links.remove(at)
+        };
+        edges.borrow_mut().remove(*id);
+        if let Some(target) = link.target.upgrade() {
+            target.borrow_mut().incoming.retain(|(edge, _)| edge != id);
+        }
+        Ok(())
+    }
+    /// Removes every `relation` link to `to`
+    pub fn unlink(&self, relation: &str, to: &NodeRef) -> GraphResult<()> {
+        let links: Vec<EdgeIndex> = self
+            .0
+            .borrow()
+            .links
+            .iter()
+            .filter(|l| l.relation == relation && l.target.ptr_eq(&Rc::downgrade(&to.0)))
+            .map(|l| l.id)
+            .collect();
+        for id in links {
+            self.remove_link(&id)?;
         }
+        Ok(())
     }
+}
 
-    fn add_label(&mut self, label: String) {
-        self.labels.push(label);
+#[derive(Debug, Default)]
+pub struct Graph {
+    nodes: Arena<NodeRef>,
+    names: HashMap<String, NodeIndex>,
+    edges: Edges,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Graph::default()
     }
-    fn remove_label(&mut self, label: &String) {
-        self.labels.retain(|x| x != label)
+    /// Fails with `ConstraintViolation` if a node already has `alias`
+    pub fn add_node(&mut self, alias: &str) -> GraphResult<NodeRef> {
+        if let Some(id) = self.names.get(alias) {
+            return Err(Error::ConstraintViolation {
+                constraint: "unique aliases".to_owned(),
+                detail: format!("node {} is already `{}`", id, alias),
+            });
+        }
+        let id: NodeIndex = self.nodes.next_key();
+        let node = NodeRef(Rc::new(RefCell::new(Node {
+            id,
+            alias: alias.to_owned(),
+            labels: Vec::new(),
+            props: HashMap::new(),
+            links: Vec::new(),
+            incoming: Vec::new(),
+            edges: Rc::downgrade(&self.edges),
+        })));
+        self.nodes.insert::<NodeIndex>(node.clone());
+        self.names.insert(alias.to_owned(), id);
+        Ok(node)
+    }
+    /// Removes the node and every link from or to it, handles to it stay usable but detached
+    pub fn remove_node(&mut self, alias: &str) -> GraphResult<()> {
+        let id = self.id_of(alias)?;
+        self.delete_node(&id)
+    }
+    #[inline]
+    pub fn id_of(&self, alias: &str) -> GraphResult<NodeIndex> {
+        self.names
+            .get(alias)
+            .copied()
+            .ok_or_else(|| Error::AliasNotFound {
+                alias: alias.to_owned(),
+            })
     }
-    fn add_prop(&mut self, key: String, value: String) {
-        self.props.insert(key, value);
+    pub fn get_node(&self, alias: &str) -> GraphResult<NodeRef> {
+        self.get_node_by_id(&self.id_of(alias)?)
     }
-    fn remove_prop(&mut self, key: &String) {
-        self.props.remove(key);
+    #[inline]
+    pub fn get_node_by_id(&self, id: &NodeIndex) -> GraphResult<NodeRef> {
+        self.node_ref(id).cloned()
     }
-    fn add_relation(&mut self, relation: String, node: &Node) {
-        self.rels.push(Edge::new(relation, node))
+    #[inline]
+    fn node_ref(&self, id: &NodeIndex) -> GraphResult<&NodeRef> {
+        self.nodes.get(*id).ok_or(Error::NodeNotFound { id: *id })
     }
-    fn remove_relation(&mut self, relation: &Edge) {
-        self.rels.retain(|x| x != relation)
+    pub fn iter_nodes(&self) -> impl Iterator<Item = &NodeRef> {
+        self.nodes.iter::<NodeIndex>().map(|(_, node)| node)
+    }
+    /// The link behind `id`, borrowed from its source node
+    pub fn get_link(&self, id: &EdgeIndex) -> GraphResult<Ref<'_, Link>> {
+        let from = self.source(id)?;
+        Ok(Ref::map(self.node_ref(&from)?.borrow(), |node| {
+            node.links
+                .iter()
+                .find(|l| l.id == *id)
+                .expect("Indexed link must be in its source")
+        }))
+    }
+    fn get_link_mut(&self, id: &EdgeIndex) -> GraphResult<RefMut<'_, Link>> {
+        let from = self.source(id)?;
+        Ok(RefMut::map(self.node_ref(&from)?.borrow_mut(), |node| {
+            node.links
+                .iter_mut()
+                .find(|l| l.id == *id)
+                .expect("Indexed link must be in its source")
+        }))
+    }
+    fn source(&self, id: &EdgeIndex) -> GraphResult<NodeIndex> {
+        self.edges
+            .borrow()
+            .get(*id)
+            .copied()
+            .ok_or(Error::EdgeNotFound { id: *id })
     }
 }
 
-struct Graph {
-    nodes: Vec<Node>,
-}
+impl GraphStore for Graph {
+    fn create_node(&mut self, alias: &str) -> GraphResult<NodeIndex> {
+        Ok(self.add_node(alias)?.id())
+    }
+    fn delete_node(&mut self, id: &NodeIndex) -> GraphResult<()> {
+        let node = self
+            .nodes
+            .remove(*id)
+            .ok_or(Error::NodeNotFound { id: *id })?;
+        let (links, incoming) = {
+            let mut node = node.borrow_mut();
+            self.names.remove(&node.alias);
+            node.edges = Weak::new();
+            (
+                std::mem::take(&mut node.links),
+                std::mem::take(&mut node.incoming),
+            )
+        };
+        let mut edges = self.edges.borrow_mut();
+        //self links are in both lists, the target is gone from `nodes` by now
+        for link in links {
+            edges.remove(link.id);
+            if let Some(target) = self.nodes.get(link.to) {
+                target
+                    .borrow_mut()
+                    .incoming
+                    .retain(|(edge, _)| *edge != link.id);
+            }
+        }
+        for (edge, from) in incoming {
+            edges.remove(edge);
+            if let Some(source) = self.nodes.get(from) {
+                source.borrow_mut().links.retain(|l| l.id != edge);
+            }
+        }
+        Ok(())
+    }
+    #[inline]
+    fn has_node(&self, id: &NodeIndex) -> bool {
+        self.nodes.get(*id).is_some()
+    }
+    fn node_ids(&self) -> Vec<NodeIndex> {
+        self.nodes.iter::<NodeIndex>().map(|(id, _)| id).collect()
+    }
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+    fn node_alias(&self, id: &NodeIndex) -> GraphResult<String> {
+        Ok(self.node_ref(id)?.alias())
+    }
+    fn ids_with_alias(&self, alias: &str) -> Vec<NodeIndex> {
+        self.names.get(alias).copied().into_iter().collect()
+    }
 
-impl Graph {
-    fn new() -> Self {
-        Graph { nodes: Vec::new() }
+    fn node_labels(&self, id: &NodeIndex) -> GraphResult<Vec<String>> {
+        Ok(self.node_ref(id)?.borrow().labels.clone())
+    }
+    fn node_has_label(&self, id: &NodeIndex, label: &str) -> GraphResult<bool> {
+        Ok(self.node_ref(id)?.borrow().has_label(label))
     }
-    fn add_node(&mut self, alias: String) {
-        self.nodes.push(Node::new(self.nodes.len(), alias))
+    fn label_node(&mut self, id: &NodeIndex, label: &str) -> GraphResult<()> {
+        self.node_ref(id)?.borrow_mut().add_label(label);
+        Ok(())
     }
-    fn remove_node_by_alias(&mut self, alias: &String) {
-        self.nodes.retain(|x| &x.alias != alias)
+    fn unlabel_node(&mut self, id: &NodeIndex, label: &str) -> GraphResult<()> {
+        self.node_ref(id)?.borrow_mut().remove_label(label);
+        Ok(())
     }
-    fn remove_node_by_id(&mut self, id: &usize) {
-        self.nodes.retain(|x| &x.id != id)
+    fn node_prop(&self, id: &NodeIndex, key: &str) -> GraphResult<Option<PropValue>> {
+        Ok(self.node_ref(id)?.borrow().get_prop(key).cloned())
     }
-    fn get_node_by_id(&self, id: &usize) -> Option<&Node> {
-        self.nodes.iter().find(|x| &x.id == id)
+    fn node_props(&self, id: &NodeIndex) -> GraphResult<BTreeMap<String, PropValue>> {
+        Ok(sorted(&self.node_ref(id)?.borrow().props))
     }
-    fn get_node_by_alias(&self, alias: &String) -> Option<&Node> {
-        self.nodes.iter().find(|x| &x.alias == alias)
+    fn set_node_prop(&mut self, id: &NodeIndex, key: &str, val: PropValue) -> GraphResult<()> {
+        self.node_ref(id)?.borrow_mut().add_prop(key, val);
+        Ok(())
+    }
+    fn unset_node_prop(&mut self, id: &NodeIndex, key: &str) -> GraphResult<()> {
+        self.node_ref(id)?.borrow_mut().remove_prop(key);
+        Ok(())
+    }
+
+    fn create_edge(
+        &mut self,
+        relation: &str,
+        from: &NodeIndex,
+        to: &NodeIndex,
+    ) -> GraphResult<EdgeIndex> {
+        let to = self.node_ref(to)?;
+        self.node_ref(from)?.add_link(relation, to)
+    }
+    fn delete_edge(&mut self, id: &EdgeIndex) -> GraphResult<()> {
+        let from = self.source(id)?;
+        self.node_ref(&from)?.remove_link(id)
+    }
+    #[inline]
+    fn has_edge(&self, id: &EdgeIndex) -> bool {
+        self.edges.borrow().get(*id).is_some()
+    }
+    fn edge_ids(&self) -> Vec<EdgeIndex> {
+        self.edges
+            .borrow()
+            .iter::<EdgeIndex>()
+            .map(|(id, _)| id)
+            .collect()
+    }
+    #[inline]
+    fn edge_count(&self) -> usize {
+        self.edges.borrow().len()
+    }
+    fn edge_relation(&self, id: &EdgeIndex) -> GraphResult<String> {
+        Ok(self.get_link(id)?.relation.clone())
+    }
+    fn edge_ends(&self, id: &EdgeIndex) -> GraphResult<(NodeIndex, NodeIndex)> {
+        let to = self.get_link(id)?.to;
+        Ok((self.source(id)?, to))
+    }
+    fn edge_labels(&self, id: &EdgeIndex) -> GraphResult<Vec<String>> {
+        Ok(self.get_link(id)?.labels.clone())
+    }
+    fn label_edge(&mut self, id: &EdgeIndex, label: &str) -> GraphResult<()> {
+        self.get_link_mut(id)?.labels.push(label.to_owned());
+        Ok(())
+    }
+    fn unlabel_edge(&mut self, id: &EdgeIndex, label: &str) -> GraphResult<()> {
+        self.get_link_mut(id)?.labels.retain(|x| x != label);
+        Ok(())
+    }
+    fn edge_prop(&self, id: &EdgeIndex, key: &str) -> GraphResult<Option<PropValue>> {
+        Ok(self.get_link(id)?.get_prop(key).cloned())
+    }
+    fn edge_props(&self, id: &EdgeIndex) -> GraphResult<BTreeMap<String, PropValue>> {
+        Ok(sorted(&self.get_link(id)?.props))
+    }
+    fn set_edge_prop(&mut self, id: &EdgeIndex, key: &str, val: PropValue) -> GraphResult<()> {
+        self.get_link_mut(id)?.props.insert(key.to_owned(), val);
+        Ok(())
+    }
+    fn unset_edge_prop(&mut self, id: &EdgeIndex, key: &str) -> GraphResult<()> {
+        self.get_link_mut(id)?.props.remove(key);
+        Ok(())
+    }
+
+    fn adjacent(
+        &self,
+        id: &NodeIndex,
+        direction: Direction,
+        relations: &[String],
+    ) -> GraphResult<Vec<(EdgeIndex, NodeIndex)>> {
+        let node = self.node_ref(id)?.borrow();
+        let follows = |relation: &String| relations.is_empty() || relations.contains(relation);
+        let mut adjacent = Vec::new();
+        if matches!(direction, Direction::Outgoing | Direction::Both) {
+            adjacent.extend(
+                node.links
+                    .iter()
+                    .filter(|l| follows(&l.relation))
+                    .map(|l| (l.id, l.to)),
+            );
+        }
+        if matches!(direction, Direction::Incoming | Direction::Both) {
+            for (edge, from) in node.incoming.iter() {
+                if follows(&self.get_link(edge)?.relation) {
+                    adjacent.push((*edge, *from));
+                }
+            }
+        }
+        Ok(adjacent)
     }
 }
@@ -0,0 +1,218 @@
+//! TCP server sharing one [`Graph`] between processes, see [`protocol`] for the wire format
+//!
+//! Every connection gets a thread. Read only requests run on a snapshot of the [`SharedGraph`]
+//! without waiting for anyone, mutations run one at a time as transactions, so each request is atomic for other clients.
+//! A line that isn't a valid request, or is longer than [`protocol::MAX_REQUEST_LEN`], gets an error response
+//! and the connection stays open.
+
+pub mod protocol;
+
+use std::{
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::json::Json;
+use crate::shell;
+use crate::vec_graph::{Error, Graph, GraphResult, SharedGraph};
+use protocol::Request;
+
+pub struct Server {
+    listener: TcpListener,
+    graph: SharedGraph,
+}
+
+/// Server running on a background thread, see [`Server::spawn`]
+pub struct ServerHandle {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Server {
+    /// Listens on `addr`, port 0 picks a free one, see [`Server::local_addr`]
+    pub fn bind<A: ToSocketAddrs>(addr: A, graph: SharedGraph) -> GraphResult<Self> {
+        let listener = TcpListener::bind(addr).map_err(Error::io("binding server socket"))?;
+        Ok(Server { listener, graph })
+    }
+    pub fn local_addr(&self) -> GraphResult<SocketAddr> {
+        self.listener
+            .local_addr()
+            .map_err(Error::io("reading server address"))
+    }
+    /// Accepts connections until the process ends
+    pub fn serve(self) -> GraphResult<()> {
+        self.accept_until(&AtomicBool::new(false))
+    }
+    /// Serves from a background thread until [`ServerHandle::shutdown`]
+    pub fn spawn(self) -> GraphResult<ServerHandle> {
+        let addr = self.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let thread = thread::spawn(move || {
+            //accept errors end the thread, the handle's owner finds out through a dead server
+            let _ = self.accept_until(&flag);
+        });
+        Ok(ServerHandle { addr, stop, thread })
+    }
+
+    fn accept_until(&self, stop: &AtomicBool) -> GraphResult<()> {
+        for stream in self.listener.incoming() {
+            if stop.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            let stream = stream.map_err(Error::io("accepting connection"))?;
+            let graph = self.graph.clone();
+            thread::spawn(move || {
+                //a dropped client ends its own connection only
+                let _ = serve_connection(stream, &graph);
+            });
+        }
+        Ok(())
+    }
+}
+
+impl ServerHandle {
+    #[inline]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+    /// Stops accepting new connections, open ones are served until their clients disconnect
+    pub fn shutdown(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        //wakes the blocking accept so it sees the flag
+        let _ = TcpStream::connect(self.addr);
+        let _ = self.thread.join();
+    }
+}
+
+fn serve_connection(stream: TcpStream, graph: &SharedGraph) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        //one byte past the cap tells an oversize line from one that just fits
+        let cap = protocol::MAX_REQUEST_LEN as u64 + 2;
+        if (&mut reader).take(cap).read_until(b'\n', &mut buf)? == 0 {
+            return Ok(());
+        }
+        let (id, result) = match parse_line(&mut reader, &buf)? {
+            None => continue,
+            Some(Ok(message)) => {
+                let id = message.get("id").cloned().unwrap_or_default();
+                let result = Request::from_json(&message).and_then(|r| handle(graph, r));
+                (id, result)
+            }
+            Some(Err(e)) => (Json::Null, Err(e)),
+        };
+        writeln!(writer, "{}", protocol::response(id, &result))?;
+        writer.flush()?;
+    }
+}
+
+/// Message on one line read by [`serve_connection`], None for blank lines.
+/// An oversize line is skipped up to its newline so the next request starts clean
+fn parse_line(
+    reader: &mut impl BufRead,
+    line: &[u8],
+) -> std::io::Result<Option<GraphResult<Json>>> {
+    let (line, complete) = match line.strip_suffix(b"\n") {
+        Some(line) => (line.strip_suffix(b"\r").unwrap_or(line), true),
+        None => (line, false),
+    };
+    if line.len() > protocol::MAX_REQUEST_LEN {
+        if !complete {
+            reader.skip_until(b'\n')?;
+        }
+        let message = format!("request over {} bytes", protocol::MAX_REQUEST_LEN);
+        return Ok(Some(Err(protocol::invalid(&message))));
+    }
+    let line = match std::str::from_utf8(line) {
+        Ok(line) => line,
+        Err(_) => return Ok(Some(Err(protocol::invalid("request is not UTF-8")))),
+    };
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Json::parse(line)))
+}
+
+fn handle(graph: &SharedGraph, request: Request) -> GraphResult<Json> {
+    if request.is_read_only() {
+        return read(&graph.snapshot(), request);
+    }
+    graph.write(|tx| write(tx, request))
+}
+
+fn read(graph: &Graph, request: Request) -> GraphResult<Json> {
+    Ok(match request {
+        Request::Ping => Json::Str("pong".to_owned()),
+        Request::Query { text } => protocol::result_to_json(&graph.query(&text)?),
+        Request::GetNode { node } => protocol::node_to_json(graph.get_node(&node)?),
+        Request::NodesByAlias { alias } => {
+            protocol::ids_to_json(graph.get_ids_by_alias(&alias).map_or(&[][..], |ids| ids))
+        }
+        Request::NodesByLabel { label } => protocol::ids_to_json(&graph.find_ids_by_label(&label)),
+        Request::Stats => Json::object([
+            ("nodes", Json::Int(graph.node_count() as i64)),
+            ("edges", Json::Int(graph.edge_count() as i64)),
+        ]),
+        _ => unreachable!("Mutations go through write"),
+    })
+}
+
+fn write(graph: &mut Graph, request: Request) -> GraphResult<Json> {
+    Ok(match request {
+        Request::Execute { line } => protocol::output_to_json(&shell::execute(graph, &line)?),
+        Request::AddNode { alias } => {
+            let node = graph
+                .add_node(&alias)?
+                .get_last_node()
+                .expect("Node was just added");
+            protocol::id(&node.id)
+        }
+        Request::RemoveNode { node } => {
+            graph.remove_node_by_id(&node)?;
+            Json::Null
+        }
+        Request::AddLabel { node, label } => {
+            graph.add_label(&node, label)?;
+            Json::Null
+        }
+        Request::RemoveLabel { node, label } => {
+            graph.remove_label(&node, label)?;
+            Json::Null
+        }
+        Request::AddProp { node, key, value } => {
+            graph.add_prop(&node, key, value)?;
+            Json::Null
+        }
+        Request::RemoveProp { node, key } => {
+            graph.remove_prop(&node, key)?;
+            Json::Null
+        }
+        Request::AddEdge { relation, from, to } => {
+            let edge = graph
+                .add_edge(&relation, from, to)?
+                .get_last_edge()
+                .expect("Edge was just added");
+            protocol::id(&edge.id())
+        }
+        Request::Link { relation, from, to } => {
+            let before = graph.edge_count();
+            graph.add_edges_by_aliases(&relation, &from, &to)?;
+            Json::Int((graph.edge_count() - before) as i64)
+        }
+        Request::RemoveEdge { edge } => {
+            graph.remove_edge(&edge)?;
+            Json::Null
+        }
+        read_only => read(graph, read_only)?,
+    })
+}
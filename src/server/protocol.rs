@@ -0,0 +1,531 @@
+//! Messages of the server protocol and their JSON form, shared with the client crate
+//!
+//! Every request and response is one JSON object on its own line:
+//!
+//! ```text
+//! > {"id":1,"op":"add_node","alias":"sisli"}
+//! < {"id":1,"ok":"0"}
+//! > {"id":2,"op":"query","text":"MATCH (n) RETURN n"}
+//! < {"id":2,"ok":{"columns":["n"],"path_columns":[],"path_rows":[[]],"rows":[["0"]]}}
+//! > {"id":3,"op":"remove_node","node":"7"}
+//! < {"id":3,"error":{"kind":"NodeNotFound","message":"Node 7 not found","node":"7"}}
+//! ```
+//!
+//! `id` is optional and echoed back as is. Node and edge ids travel as their `Display` form (`3` or `3v1`),
+//! prop values in the lossless encoding described in [`json`](crate::json).
+//! Request lines longer than [`MAX_REQUEST_LEN`] bytes are skipped with an error response.
+
+use std::{io, str::FromStr};
+
+use crate::json::Json;
+use crate::query::{MatchedPath, QueryResult};
+use crate::shell::{Output, Table};
+use crate::vec_graph::{EdgeIndex, Error, GraphResult, Node, NodeIndex, PropValue};
+
+/// Longest request line the server reads, newline excluded
+pub const MAX_REQUEST_LEN: usize = 16 << 20;
+
+/// Everything a client can ask for, named after the `Graph` methods they run
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    /// Answers `"pong"`
+    Ping,
+    /// `MATCH ...` query, answers a [`QueryResult`]
+    Query {
+        text: String,
+    },
+    /// Shell line as typed in the REPL, answers a [`shell::Output`](crate::shell::Output)
+    Execute {
+        line: String,
+    },
+    /// Answers the new node's id
+    AddNode {
+        alias: String,
+    },
+    RemoveNode {
+        node: NodeIndex,
+    },
+    AddLabel {
+        node: NodeIndex,
+        label: String,
+    },
+    RemoveLabel {
+        node: NodeIndex,
+        label: String,
+    },
+    AddProp {
+        node: NodeIndex,
+        key: String,
+        value: PropValue,
+    },
+    RemoveProp {
+        node: NodeIndex,
+        key: String,
+    },
+    /// Answers the new edge's id
+    AddEdge {
+        relation: String,
+        from: NodeIndex,
+        to: NodeIndex,
+    },
+    /// Links every node with alias `from` to every node with alias `to`, answers how many edges were added
+    Link {
+        relation: String,
+        from: String,
+        to: String,
+    },
+    RemoveEdge {
+        edge: EdgeIndex,
+    },
+    /// Answers the node with its labels and props
+    GetNode {
+        node: NodeIndex,
+    },
+    /// Answers the ids of the nodes with `alias`, empty if there are none
+    NodesByAlias {
+        alias: String,
+    },
+    NodesByLabel {
+        label: String,
+    },
+    /// Answers `{"nodes": n, "edges": m}`
+    Stats,
+}
+
+impl Request {
+    /// Requests that only read, the server runs them concurrently
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Request::Ping
+                | Request::Query { .. }
+                | Request::GetNode { .. }
+                | Request::NodesByAlias { .. }
+                | Request::NodesByLabel { .. }
+                | Request::Stats
+        )
+    }
+
+    pub fn to_json(&self) -> Json {
+        let str = |s: &str| Json::Str(s.to_owned());
+        let (op, mut fields) = match self {
+            Request::Ping => ("ping", vec![]),
+            Request::Query { text } => ("query", vec![("text", str(text))]),
+            Request::Execute { line } => ("execute", vec![("line", str(line))]),
+            Request::AddNode { alias } => ("add_node", vec![("alias", str(alias))]),
+            Request::RemoveNode { node } => ("remove_node", vec![("node", id(node))]),
+            Request::AddLabel { node, label } => {
+                ("add_label", vec![("node", id(node)), ("label", str(label))])
+            }
+            Request::RemoveLabel { node, label } => (
+                "remove_label",
+                vec![("node", id(node)), ("label", str(label))],
+            ),
+            Request::AddProp { node, key, value } => (
+                "add_prop",
+                vec![
+                    ("node", id(node)),
+                    ("key", str(key)),
+                    ("value", Json::from(value)),
+                ],
+            ),
+            Request::RemoveProp { node, key } => {
+                ("remove_prop", vec![("node", id(node)), ("key", str(key))])
+            }
+            Request::AddEdge { relation, from, to } => (
+                "add_edge",
+                vec![
+                    ("relation", str(relation)),
+                    ("from", id(from)),
+                    ("to", id(to)),
+                ],
+            ),
+            Request::Link { relation, from, to } => (
+                "link",
+                vec![
+                    ("relation", str(relation)),
+                    ("from", str(from)),
+                    ("to", str(to)),
+                ],
+            ),
+            Request::RemoveEdge { edge } => ("remove_edge", vec![("edge", id(edge))]),
+            Request::GetNode { node } => ("get_node", vec![("node", id(node))]),
+            Request::NodesByAlias { alias } => ("nodes_by_alias", vec![("alias", str(alias))]),
+            Request::NodesByLabel { label } => ("nodes_by_label", vec![("label", str(label))]),
+            Request::Stats => ("stats", vec![]),
+        };
+        fields.push(("op", str(op)));
+        Json::object(fields)
+    }
+
+    pub fn from_json(json: &Json) -> GraphResult<Request> {
+        let op = field(json, "op")?
+            .as_str()
+            .ok_or_else(|| invalid("`op` must be a string"))?;
+        let text = |key: &str| -> GraphResult<String> {
+            field(json, key)?
+                .as_str()
+                .map(str::to_owned)
+                .ok_or_else(|| invalid(&format!("`{}` must be a string", key)))
+        };
+        Ok(match op {
+            "ping" => Request::Ping,
+            "query" => Request::Query {
+                text: text("text")?,
+            },
+            "execute" => Request::Execute {
+                line: text("line")?,
+            },
+            "add_node" => Request::AddNode {
+                alias: text("alias")?,
+            },
+            "remove_node" => Request::RemoveNode {
+                node: parse_id(field(json, "node")?)?,
+            },
+            "add_label" => Request::AddLabel {
+                node: parse_id(field(json, "node")?)?,
+                label: text("label")?,
+            },
+            "remove_label" => Request::RemoveLabel {
+                node: parse_id(field(json, "node")?)?,
+                label: text("label")?,
+            },
+            "add_prop" => Request::AddProp {
+                node: parse_id(field(json, "node")?)?,
+                key: text("key")?,
+                value: PropValue::try_from(field(json, "value")?)?,
+            },
+            "remove_prop" => Request::RemoveProp {
+                node: parse_id(field(json, "node")?)?,
+                key: text("key")?,
+            },
+            "add_edge" => Request::AddEdge {
+                relation: text("relation")?,
+                from: parse_id(field(json, "from")?)?,
+                to: parse_id(field(json, "to")?)?,
+            },
+            "link" => Request::Link {
+                relation: text("relation")?,
+                from: text("from")?,
+                to: text("to")?,
+            },
+            "remove_edge" => Request::RemoveEdge {
+                edge: parse_id(field(json, "edge")?)?,
+            },
+            "get_node" => Request::GetNode {
+                node: parse_id(field(json, "node")?)?,
+            },
+            "nodes_by_alias" => Request::NodesByAlias {
+                alias: text("alias")?,
+            },
+            "nodes_by_label" => Request::NodesByLabel {
+                label: text("label")?,
+            },
+            "stats" => Request::Stats,
+            other => return Err(invalid(&format!("unknown op `{}`", other))),
+        })
+    }
+}
+
+/// `{"id": id, "ok": value}` or `{"id": id, "error": {...}}`, `id` left out when null
+pub fn response(id: Json, result: &GraphResult<Json>) -> Json {
+    let mut fields = match result {
+        Ok(value) => vec![("ok", value.clone())],
+        Err(e) => vec![("error", error_to_json(e))],
+    };
+    if !id.is_null() {
+        fields.push(("id", id));
+    }
+    Json::object(fields)
+}
+
+/// Splits a response into its id and result
+pub fn parse_response(json: &Json) -> GraphResult<(Json, GraphResult<Json>)> {
+    let id = json.get("id").cloned().unwrap_or_default();
+    match (json.get("ok"), json.get("error")) {
+        (Some(value), None) => Ok((id, Ok(value.clone()))),
+        (None, Some(error)) => Ok((id, Err(error_from_json(error)))),
+        _ => Err(invalid("response needs exactly one of `ok` and `error`")),
+    }
+}
+
+#[inline]
+pub(crate) fn invalid(message: &str) -> Error {
+    Error::query(format!("invalid message: {}", message))
+}
+
+fn field<'j>(json: &'j Json, key: &str) -> GraphResult<&'j Json> {
+    json.get(key)
+        .ok_or_else(|| invalid(&format!("missing `{}`", key)))
+}
+
+#[inline]
+pub fn id<T: ToString>(id: &T) -> Json {
+    Json::Str(id.to_string())
+}
+
+pub fn parse_id<T: FromStr<Err = Error>>(json: &Json) -> GraphResult<T> {
+    json.as_str()
+        .ok_or_else(|| invalid("ids are strings like \"3\" or \"3v1\""))?
+        .parse()
+}
+
+pub fn ids_to_json<T: ToString>(ids: &[T]) -> Json {
+    Json::Array(ids.iter().map(id).collect())
+}
+
+pub fn ids_from_json<T: FromStr<Err = Error>>(json: &Json) -> GraphResult<Vec<T>> {
+    json.as_array()
+        .ok_or_else(|| invalid("expected an array of ids"))?
+        .iter()
+        .map(parse_id)
+        .collect()
+}
+
+fn strings_to_json(strings: &[String]) -> Json {
+    Json::Array(strings.iter().map(|s| Json::Str(s.clone())).collect())
+}
+
+fn strings_from_json(json: &Json) -> GraphResult<Vec<String>> {
+    json.as_array()
+        .ok_or_else(|| invalid("expected an array of strings"))?
+        .iter()
+        .map(|s| {
+            s.as_str()
+                .map(str::to_owned)
+                .ok_or_else(|| invalid("expected an array of strings"))
+        })
+        .collect()
+}
+
+pub fn node_to_json(node: &Node) -> Json {
+    Json::object([
+        ("id", id(&node.id)),
+        ("alias", Json::Str(node.alias.clone())),
+        ("labels", strings_to_json(node.labels())),
+        (
+            "props",
+            Json::object(node.props().iter().map(|(k, v)| (k.clone(), Json::from(v)))),
+        ),
+    ])
+}
+
+/// Copy of a node as sent by the server, without its edges
+pub fn node_from_json(json: &Json) -> GraphResult<Node> {
+    let alias = field(json, "alias")?
+        .as_str()
+        .ok_or_else(|| invalid("`alias` must be a string"))?;
+    let mut node = Node::new(parse_id(field(json, "id")?)?, alias.to_owned());
+    for label in strings_from_json(field(json, "labels")?)? {
+        node.add_label(label)?;
+    }
+    let props = field(json, "props")?
+        .as_object()
+        .ok_or_else(|| invalid("`props` must be an object"))?;
+    for (key, value) in props.iter() {
+        node.add_prop(key, PropValue::try_from(value)?)?;
+    }
+    Ok(node)
+}
+
+pub fn result_to_json(result: &QueryResult) -> Json {
+    let path = |p: &MatchedPath| {
+        Json::object([
+            ("nodes", ids_to_json(&p.nodes)),
+            ("edges", ids_to_json(&p.edges)),
+        ])
+    };
+    Json::object([
+        ("columns", strings_to_json(&result.columns)),
+        (
+            "rows",
+            Json::Array(result.rows.iter().map(|r| ids_to_json(r)).collect()),
+        ),
+        ("path_columns", strings_to_json(&result.path_columns)),
+        (
+            "path_rows",
+            Json::Array(
+                result
+                    .path_rows
+                    .iter()
+                    .map(|r| Json::Array(r.iter().map(path).collect()))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+pub fn result_from_json(json: &Json) -> GraphResult<QueryResult> {
+    let rows = |key: &str| {
+        field(json, key)?
+            .as_array()
+            .ok_or_else(|| invalid(&format!("`{}` must be an array", key)))
+    };
+    let path = |p: &Json| {
+        Ok(MatchedPath {
+            nodes: ids_from_json(field(p, "nodes")?)?,
+            edges: ids_from_json(field(p, "edges")?)?,
+        })
+    };
+    Ok(QueryResult {
+        columns: strings_from_json(field(json, "columns")?)?,
+        rows: rows("rows")?
+            .iter()
+            .map(ids_from_json)
+            .collect::<GraphResult<_>>()?,
+        path_columns: strings_from_json(field(json, "path_columns")?)?,
+        path_rows: rows("path_rows")?
+            .iter()
+            .map(|r| {
+                r.as_array()
+                    .ok_or_else(|| invalid("path rows must be arrays"))?
+                    .iter()
+                    .map(path)
+                    .collect()
+            })
+            .collect::<GraphResult<_>>()?,
+    })
+}
+
+/// `{"text": "..."}` or `{"headers": [...], "rows": [[...]]}`
+pub fn output_to_json(output: &Output) -> Json {
+    match output {
+        Output::Text(text) => Json::object([("text", Json::Str(text.clone()))]),
+        Output::Table(table) => Json::object([
+            ("headers", strings_to_json(&table.headers)),
+            (
+                "rows",
+                Json::Array(table.rows.iter().map(|r| strings_to_json(r)).collect()),
+            ),
+        ]),
+    }
+}
+
+pub fn output_from_json(json: &Json) -> GraphResult<Output> {
+    if let Some(text) = json.get("text") {
+        return text
+            .as_str()
+            .map(|t| Output::Text(t.to_owned()))
+            .ok_or_else(|| invalid("`text` must be a string"));
+    }
+    Ok(Output::Table(Table {
+        headers: strings_from_json(field(json, "headers")?)?,
+        rows: field(json, "rows")?
+            .as_array()
+            .ok_or_else(|| invalid("`rows` must be an array"))?
+            .iter()
+            .map(strings_from_json)
+            .collect::<GraphResult<_>>()?,
+    }))
+}
+
+/// Variant name, `Display` message and the variant's fields
+pub fn error_to_json(error: &Error) -> Json {
+    let str = |s: &str| Json::Str(s.to_owned());
+    let (kind, fields) = match error {
+        Error::NodeNotFound { id: node } => ("NodeNotFound", vec![("node", id(node))]),
+        Error::EdgeNotFound { id: edge } => ("EdgeNotFound", vec![("edge", id(edge))]),
+        Error::AliasNotFound { alias } => ("AliasNotFound", vec![("alias", str(alias))]),
+        Error::InvalidIndex { index, reason } => (
+            "InvalidIndex",
+            vec![("index", str(index)), ("reason", str(reason))],
+        ),
+        Error::ConstraintViolation { constraint, detail } => (
+            "ConstraintViolation",
+            vec![("constraint", str(constraint)), ("detail", str(detail))],
+        ),
+        Error::InvalidOperation { operation, reason } => (
+            "InvalidOperation",
+            vec![("operation", str(operation)), ("reason", str(reason))],
+        ),
+        Error::Io { context, source } => (
+            "Io",
+            vec![
+                ("context", str(context)),
+                ("source", str(&source.to_string())),
+            ],
+        ),
+        Error::Parse { pos, message } => (
+            "Parse",
+            vec![
+                ("pos", pos.map_or(Json::Null, |p| Json::Int(p as i64))),
+                ("detail", str(message)),
+            ],
+        ),
+        Error::Corrupt { context } => ("Corrupt", vec![("context", str(context))]),
+        Error::UnsupportedVersion { found, supported } => (
+            "UnsupportedVersion",
+            vec![
+                ("found", Json::Int(*found as i64)),
+                ("supported", Json::Int(*supported as i64)),
+            ],
+        ),
+        Error::NoPath { from, to } => ("NoPath", vec![("from", id(from)), ("to", id(to))]),
+        Error::InvalidWeight { edge, reason } => (
+            "InvalidWeight",
+            vec![("edge", id(edge)), ("reason", str(reason))],
+        ),
+    };
+    let mut fields = fields;
+    fields.push(("kind", str(kind)));
+    fields.push(("message", str(&error.to_string())));
+    Json::object(fields)
+}
+
+/// Rebuilds the typed error, anything unrecognized becomes a query error with the message
+pub fn error_from_json(json: &Json) -> Error {
+    let text = |key: &str| json.get(key).and_then(Json::as_str).map(str::to_owned);
+    let number = |key: &str| json.get(key).and_then(Json::as_i64);
+    let message = text("message").unwrap_or_else(|| json.to_string());
+    let rebuilt = match json.get("kind").and_then(Json::as_str) {
+        Some("NodeNotFound") => json
+            .get("node")
+            .and_then(|n| parse_id(n).ok())
+            .map(|id| Error::NodeNotFound { id }),
+        Some("EdgeNotFound") => json
+            .get("edge")
+            .and_then(|e| parse_id(e).ok())
+            .map(|id| Error::EdgeNotFound { id }),
+        Some("AliasNotFound") => text("alias").map(|alias| Error::AliasNotFound { alias }),
+        Some("InvalidIndex") => text("index")
+            .zip(text("reason"))
+            .map(|(index, reason)| Error::InvalidIndex { index, reason }),
+        Some("ConstraintViolation") => text("constraint")
+            .zip(text("detail"))
+            .map(|(constraint, detail)| Error::ConstraintViolation { constraint, detail }),
+        Some("InvalidOperation") => text("operation")
+            .zip(text("reason"))
+            .map(|(operation, reason)| Error::InvalidOperation { operation, reason }),
+        Some("Io") => text("context")
+            .zip(text("source"))
+            .map(|(context, source)| Error::Io {
+                context,
+                source: io::Error::other(source),
+            }),
+        Some("Parse") => text("detail").map(|message| Error::Parse {
+            pos: number("pos").map(|p| p as usize),
+            message,
+        }),
+        Some("Corrupt") => text("context").map(|context| Error::Corrupt { context }),
+        Some("UnsupportedVersion") => {
+            number("found")
+                .zip(number("supported"))
+                .map(|(found, supported)| Error::UnsupportedVersion {
+                    found: found as u32,
+                    supported: supported as u32,
+                })
+        }
+        Some("NoPath") => json
+            .get("from")
+            .and_then(|f| parse_id(f).ok())
+            .zip(json.get("to").and_then(|t| parse_id(t).ok()))
+            .map(|(from, to)| Error::NoPath { from, to }),
+        Some("InvalidWeight") => json
+            .get("edge")
+            .and_then(|e| parse_id(e).ok())
+            .zip(text("reason"))
+            .map(|(edge, reason)| Error::InvalidWeight { edge, reason }),
+        _ => None,
+    };
+    rebuilt.unwrap_or_else(|| Error::query(message))
+}
@@ -0,0 +1,381 @@
+//! Line commands over a [`Graph`], what the `graph_db` REPL runs
+//!
+//! ```text
+//! add (sisli:sehir {tur: "ilce"}), (merkez:mahalle)
+//! link (sisli)-[:includes]->(merkez)
+//! MATCH (s:sehir)-[:includes]->(m) RETURN s, m
+//! ```
+//!
+//! Every line runs in its own transaction, so a failing command leaves the graph untouched
+//! and a graph opened with [`Graph::open`] logs it as one record.
+
+use core::fmt;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::query::{self, Command, Direction, MatchedPath, NodePattern, PathPattern, QueryResult};
+use crate::vec_graph::{Error, Graph, GraphResult, NodeIndex};
+
+pub const HELP: &str = r#"Commands, keywords are case insensitive:
+  add (alias:Label {key: value}), ...    add nodes
+  set (alias:Label {key: value}), ...    add labels and props to every node with the alias
+  link (a)-[:rel]->(b)<-[:rel]-(c)       add edges between every node with those aliases
+  unlink (a)-[:rel]->(b)                 remove them
+  delete alias, ...                      remove nodes and their edges
+  show [alias, ...]                      list nodes
+  MATCH (a:Label)-[:rel*1..3]->(b) WHERE a.key > 1 RETURN a, b
+Meta commands:
+  :schema     labels, relations and prop keys with counts
+  :stats      node, edge and index counts
+  :history    numbered history, `!n` runs entry n again
+  :help       this text
+  :quit       exit"#;
+
+/// What a command printed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    Table(Table),
+    Text(String),
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::Table(table) => write!(f, "{}", table),
+            Output::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// Rows of cells printed with every column padded to its widest cell
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<I, S>(headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Table {
+            headers: headers.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+    /// Adds a row of anything `Display`, missing cells print empty
+    pub fn push<I, D>(&mut self, row: I)
+    where
+        I: IntoIterator<Item = D>,
+        D: fmt::Display,
+    {
+        self.rows
+            .push(row.into_iter().map(|cell| cell.to_string()).collect());
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = |i: usize| {
+            self.rows
+                .iter()
+                .filter_map(|row| row.get(i))
+                .chain(std::iter::once(&self.headers[i]))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        };
+        let widths: Vec<usize> = (0..self.headers.len()).map(width).collect();
+        let line = |f: &mut fmt::Formatter<'_>, cells: &[String]| {
+            let padded: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(i, w)| format!("{:<w$}", cells.get(i).map_or("", |c| c.as_str()), w = w))
+                .collect();
+            writeln!(f, "{}", padded.join(" | ").trim_end())
+        };
+        line(f, &self.headers)?;
+        let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        writeln!(f, "{}", rule.join("-+-"))?;
+        for row in self.rows.iter() {
+            line(f, row)?;
+        }
+        match self.rows.len() {
+            1 => write!(f, "(1 row)"),
+            n => write!(f, "({} rows)", n),
+        }
+    }
+}
+
+/// Runs one line, `:schema` and `:stats` included
+pub fn execute(graph: &mut Graph, line: &str) -> GraphResult<Output> {
+    let line = line.trim();
+    match line {
+        "" => return Ok(Output::Text(String::new())),
+        ":schema" => return Ok(Output::Table(schema(graph))),
+        ":stats" => return Ok(Output::Table(stats(graph))),
+        meta if meta.starts_with(':') => {
+            return Err(Error::query(format!(
+                "unknown meta command `{}`, see :help",
+                meta
+            )))
+        }
+        _ => {}
+    }
+    match query::parse_command(line)? {
+        Command::Match(query) => Ok(Output::Table(result_table(
+            graph,
+            &query::execute(graph, &query)?,
+        ))),
+        Command::Show(aliases) => show(graph, &aliases).map(Output::Table),
+        command => graph.transaction(|tx| run(tx, &command).map(Output::Text)),
+    }
+}
+
+/// Mutating commands, returns what was done
+fn run(graph: &mut Graph, command: &Command) -> GraphResult<String> {
+    match command {
+        Command::Add(nodes) => {
+            for node in nodes.iter() {
+                let id = graph
+                    .add_node(alias_of(node))?
+                    .get_last_node()
+                    .expect("Node was just added")
+                    .id;
+                decorate(graph, &id, node)?;
+            }
+            Ok(plural(nodes.len(), "node", "added"))
+        }
+        Command::Set(nodes) => {
+            let mut count = 0;
+            for node in nodes.iter() {
+                for id in ids_of(graph, alias_of(node))? {
+                    decorate(graph, &id, node)?;
+                    count += 1;
+                }
+            }
+            Ok(plural(count, "node", "updated"))
+        }
+        Command::Link(path) => {
+            let before = graph.edge_count();
+            for (from, relation, to) in hops(path) {
+                graph.add_edges_by_aliases(relation, from, to)?;
+            }
+            Ok(plural(graph.edge_count() - before, "edge", "added"))
+        }
+        Command::Unlink(path) => {
+            let mut removed = Vec::new();
+            for (from, relation, to) in hops(path) {
+                for f in ids_of(graph, from)? {
+                    for t in ids_of(graph, to)? {
+                        removed.extend(
+                            graph
+                                .get_edges_between(&f, &t)
+                                .into_iter()
+                                .filter(|e| e.relation() == relation)
+                                .map(|e| e.id()),
+                        );
+                    }
+                }
+            }
+            //one hop can name the same edge twice when aliases repeat
+            removed.sort();
+            removed.dedup();
+            for id in removed.iter() {
+                graph.remove_edge(id)?;
+            }
+            Ok(plural(removed.len(), "edge", "removed"))
+        }
+        Command::Delete(aliases) => {
+            let mut count = 0;
+            for alias in aliases.iter() {
+                for id in ids_of(graph, alias)? {
+                    graph.remove_node_by_id(&id)?;
+                    count += 1;
+                }
+            }
+            Ok(plural(count, "node", "deleted"))
+        }
+        Command::Match(_) | Command::Show(_) => unreachable!("Read only commands don't mutate"),
+    }
+}
+
+#[inline]
+fn alias_of(node: &NodePattern) -> &str {
+    node.variable.as_deref().expect("Parser requires aliases")
+}
+
+/// Adds the pattern's props and labels to node `id`, props first so constraints of the labels see them
+fn decorate(graph: &mut Graph, id: &NodeIndex, pattern: &NodePattern) -> GraphResult<()> {
+    for (key, val) in pattern.props.iter() {
+        graph.add_prop(id, key, val.clone())?;
+    }
+    for label in pattern.labels.iter() {
+        graph.add_label(id, label)?;
+    }
+    Ok(())
+}
+
+/// Live nodes with `alias`, failing when there are none
+fn ids_of(graph: &Graph, alias: &str) -> GraphResult<Vec<NodeIndex>> {
+    match graph.get_ids_by_alias(alias) {
+        Some(ids) if !ids.is_empty() => Ok(ids.clone()),
+        _ => Err(Error::alias_not_found(alias)),
+    }
+}
+
+/// `(from alias, relation, to alias)` for every hop, incoming hops flipped
+fn hops(path: &PathPattern) -> Vec<(&str, &str, &str)> {
+    let mut from = alias_of(&path.start);
+    path.hops
+        .iter()
+        .map(|(rel, node)| {
+            let to = alias_of(node);
+            let hop = match rel.direction {
+                Direction::Outgoing => (from, rel.relations[0].as_str(), to),
+                Direction::Incoming => (to, rel.relations[0].as_str(), from),
+            };
+            from = to;
+            hop
+        })
+        .collect()
+}
+
+fn plural(count: usize, noun: &str, verb: &str) -> String {
+    match count {
+        1 => format!("1 {} {}", noun, verb),
+        n => format!("{} {}s {}", n, noun, verb),
+    }
+}
+
+/// `alias (id)` for every returned node, `a -[:R]-> b` for every returned path
+pub fn result_table(graph: &Graph, result: &QueryResult) -> Table {
+    let mut table = Table::new(result.columns.iter().chain(result.path_columns.iter()));
+    for (nodes, paths) in result.rows.iter().zip(result.path_rows.iter()) {
+        let cells = nodes
+            .iter()
+            .map(|id| node_cell(graph, id))
+            .chain(paths.iter().map(|p| path_cell(graph, p)));
+        table.push(cells);
+    }
+    table
+}
+
+fn node_cell(graph: &Graph, id: &NodeIndex) -> String {
+    format!("{} ({})", graph.get_alias_by_id(id).unwrap_or("?"), id)
+}
+
+fn path_cell(graph: &Graph, path: &MatchedPath) -> String {
+    let mut cell = node_cell(graph, &path.nodes[0]);
+    for (edge, next) in path.edges.iter().zip(path.nodes[1..].iter()) {
+        let (relation, forward) = match graph.get_edge_by_idx(edge) {
+            Some(e) => (e.relation(), e.to() == next),
+            None => ("?", true),
+        };
+        let arrow = if forward {
+            format!(" -[:{}]-> ", relation)
+        } else {
+            format!(" <-[:{}]- ", relation)
+        };
+        cell.push_str(&arrow);
+        cell.push_str(&node_cell(graph, next));
+    }
+    cell
+}
+
+/// Nodes with any of `aliases`, every node if empty
+fn show(graph: &Graph, aliases: &[String]) -> GraphResult<Table> {
+    let ids: Vec<NodeIndex> = if aliases.is_empty() {
+        graph.iter_nodes().map(|n| n.id).collect()
+    } else {
+        let mut ids = Vec::new();
+        for alias in aliases.iter() {
+            ids.extend(ids_of(graph, alias)?);
+        }
+        ids
+    };
+    let mut table = Table::new(["id", "alias", "labels", "props"]);
+    for id in ids.iter() {
+        let node = graph.get_node(id)?;
+        let mut props: Vec<String> = node
+            .props()
+            .iter()
+            .map(|(k, v)| format!("{}: {}", k, v))
+            .collect();
+        props.sort();
+        table.push([
+            node.id.to_string(),
+            node.alias.clone(),
+            node.labels().join(":"),
+            props.join(", "),
+        ]);
+    }
+    Ok(table)
+}
+
+/// Labels, relations and prop keys in use, with how many nodes or edges have them
+pub fn schema(graph: &Graph) -> Table {
+    let mut labels: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut node_props: BTreeMap<&str, usize> = BTreeMap::new();
+    for node in graph.iter_nodes() {
+        let mut seen: Vec<&str> = node.labels().iter().map(|l| l.as_str()).collect();
+        //a label added twice still counts the node once
+        seen.sort();
+        seen.dedup();
+        for label in seen {
+            *labels.entry(label).or_default() += 1;
+        }
+        for key in node.props().keys() {
+            *node_props.entry(key).or_default() += 1;
+        }
+    }
+    let mut relations: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut edge_props: BTreeMap<&str, usize> = BTreeMap::new();
+    for edge in graph.iter_edges() {
+        *relations.entry(edge.relation()).or_default() += 1;
+        for key in edge.props().keys() {
+            *edge_props.entry(key).or_default() += 1;
+        }
+    }
+    let mut table = Table::new(["kind", "name", "count"]);
+    for (name, count) in labels {
+        table.push(["label", name, &count.to_string()]);
+    }
+    for (name, count) in relations {
+        table.push(["relation", name, &count.to_string()]);
+    }
+    for (name, count) in node_props {
+        let kind = if graph.has_prop_index(name) {
+            "node prop (indexed)"
+        } else {
+            "node prop"
+        };
+        table.push([kind, name, &count.to_string()]);
+    }
+    for (name, count) in edge_props {
+        table.push(["edge prop", name, &count.to_string()]);
+    }
+    table
+}
+
+pub fn stats(graph: &Graph) -> Table {
+    let aliases: HashSet<&str> = graph.iter_nodes().map(|n| n.alias.as_str()).collect();
+    let schema = schema(graph);
+    let kinds = |kind: &str| schema.rows.iter().filter(|row| row[0] == kind).count();
+    let mut table = Table::new(["stat", "value"]);
+    table.push(["nodes".to_owned(), graph.node_count().to_string()]);
+    table.push(["edges".to_owned(), graph.edge_count().to_string()]);
+    table.push(["aliases".to_owned(), aliases.len().to_string()]);
+    table.push(["labels".to_owned(), kinds("label").to_string()]);
+    table.push(["relations".to_owned(), kinds("relation").to_string()]);
+    table.push([
+        "prop indexes".to_owned(),
+        kinds("node prop (indexed)").to_string(),
+    ]);
+    table.push([
+        "in transaction".to_owned(),
+        graph.in_transaction().to_string(),
+    ]);
+    table
+}
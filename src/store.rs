@@ -0,0 +1,296 @@
+//! [`GraphStore`]: the operations every storage backend offers, so code can be written once for all of them
+//!
+//! - node and edge CRUD, labels and props on both, by the same [`NodeIndex`]/[`EdgeIndex`] handles
+//! - neighbor iteration by [`Direction`] and relation
+//! - owned [`NodeData`]/[`EdgeData`] records for callers that want everything about one element,
+//!   or a borrowed `vec_graph` [`Node`]/[`Edge`] lent to a closure where the backend holds those
+//! - [`copy`] between any two stores, which is how the other backends reach `vec_graph`'s snapshots and JSON
+//!
+//! [`algo`](crate::algo), [`query`](crate::query) and [`export`](crate::export) take any store,
+//! [`vec_graph::Graph`](crate::vec_graph::Graph) being the default.
+//! ```
+//! # use graph_db::{store::GraphStore, vec_graph::Graph};
+//! fn link<G: GraphStore + ?Sized>(store: &mut G) -> graph_db::vec_graph::GraphResult<usize> {
+//!     let a = store.create_node("a")?;
+//!     let b = store.create_node("b")?;
+//!     store.create_edge("r", &a, &b)?;
+//!     Ok(store.edge_count())
+//! }
+//! assert_eq!(link(&mut Graph::new())?, 1);
+//! # Ok::<(), graph_db::vec_graph::Error>(())
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::algo::Direction;
+use crate::vec_graph::{Edge, EdgeIndex, Error, GraphResult, Node, NodeIndex, PropValue};
+
+/// Storage backend behind the algorithms, queries and exporters
+///
+/// Handles follow `vec_graph`'s rules: a removed node or edge stops resolving, lookups through it
+/// fail with `NodeNotFound`/`EdgeNotFound` and the id isn't handed out again in the same form.
+/// Removing a node removes its edges. Backends may refuse a change with `ConstraintViolation`,
+/// like a second node with the same alias where aliases are unique.
+/// Adjacency order is the backend's own, callers that care sort
+pub trait GraphStore {
+    fn create_node(&mut self, alias: &str) -> GraphResult<NodeIndex>;
+    fn delete_node(&mut self, id: &NodeIndex) -> GraphResult<()>;
+    fn has_node(&self, id: &NodeIndex) -> bool;
+    /// Every live node, in the backend's order
+    fn node_ids(&self) -> Vec<NodeIndex>;
+    fn node_count(&self) -> usize;
+    fn node_alias(&self, id: &NodeIndex) -> GraphResult<String>;
+    fn ids_with_alias(&self, alias: &str) -> Vec<NodeIndex>;
+    /// Scans every node unless the backend indexes labels
+    fn ids_with_label(&self, label: &str) -> Vec<NodeIndex> {
+        self.node_ids()
+            .into_iter()
+            .filter(|id| self.node_has_label(id, label).unwrap_or(false))
+            .collect()
+    }
+    /// Nodes whose `key` equals `val`, None when the backend has no index for `key`
+    fn ids_with_prop(&self, _key: &str, _val: &PropValue) -> Option<Vec<NodeIndex>> {
+        None
+    }
+
+    fn node_labels(&self, id: &NodeIndex) -> GraphResult<Vec<String>>;
+    fn node_has_label(&self, id: &NodeIndex, label: &str) -> GraphResult<bool> {
+        Ok(self.node_labels(id)?.iter().any(|l| l == label))
+    }
+    fn label_node(&mut self, id: &NodeIndex, label: &str) -> GraphResult<()>;
+    fn unlabel_node(&mut self, id: &NodeIndex, label: &str) -> GraphResult<()>;
+    fn node_prop(&self, id: &NodeIndex, key: &str) -> GraphResult<Option<PropValue>>;
+    fn node_props(&self, id: &NodeIndex) -> GraphResult<BTreeMap<String, PropValue>>;
+    /// Adds `key` or replaces its value
+    fn set_node_prop(&mut self, id: &NodeIndex, key: &str, val: PropValue) -> GraphResult<()>;
+    fn unset_node_prop(&mut self, id: &NodeIndex, key: &str) -> GraphResult<()>;
+
+    /// Both nodes must exist
+    fn create_edge(
+        &mut self,
+        relation: &str,
+        from: &NodeIndex,
+        to: &NodeIndex,
+    ) -> GraphResult<EdgeIndex>;
+    fn delete_edge(&mut self, id: &EdgeIndex) -> GraphResult<()>;
+    fn has_edge(&self, id: &EdgeIndex) -> bool;
+    fn edge_ids(&self) -> Vec<EdgeIndex>;
+    fn edge_count(&self) -> usize;
+    fn edge_relation(&self, id: &EdgeIndex) -> GraphResult<String>;
+    /// (from, to)
+    fn edge_ends(&self, id: &EdgeIndex) -> GraphResult<(NodeIndex, NodeIndex)>;
+    fn edge_labels(&self, id: &EdgeIndex) -> GraphResult<Vec<String>>;
+    fn label_edge(&mut self, id: &EdgeIndex, label: &str) -> GraphResult<()>;
+    fn unlabel_edge(&mut self, id: &EdgeIndex, label: &str) -> GraphResult<()>;
+    fn edge_prop(&self, id: &EdgeIndex, key: &str) -> GraphResult<Option<PropValue>>;
+    fn edge_props(&self, id: &EdgeIndex) -> GraphResult<BTreeMap<String, PropValue>>;
+    fn set_edge_prop(&mut self, id: &EdgeIndex, key: &str, val: PropValue) -> GraphResult<()>;
+    fn unset_edge_prop(&mut self, id: &EdgeIndex, key: &str) -> GraphResult<()>;
+
+    /// Edges of `id` in `direction` with any of `relations` (all of them when empty),
+    /// each with the node at its other end. `Direction::Both` lists outgoing edges first
+    fn adjacent(
+        &self,
+        id: &NodeIndex,
+        direction: Direction,
+        relations: &[String],
+    ) -> GraphResult<Vec<(EdgeIndex, NodeIndex)>>;
+
+    /// Nodes one edge away, once per edge
+    fn neighbors(&self, id: &NodeIndex, direction: Direction) -> GraphResult<Vec<NodeIndex>> {
+        Ok(self
+            .adjacent(id, direction, &[])?
+            .into_iter()
+            .map(|(_, node)| node)
+            .collect())
+    }
+    fn node(&self, id: &NodeIndex) -> GraphResult<NodeData> {
+        Ok(NodeData {
+            id: *id,
+            alias: self.node_alias(id)?,
+            labels: self.node_labels(id)?,
+            props: self.node_props(id)?,
+        })
+    }
+    fn edge(&self, id: &EdgeIndex) -> GraphResult<EdgeData> {
+        let (from, to) = self.edge_ends(id)?;
+        Ok(EdgeData {
+            id: *id,
+            relation: self.edge_relation(id)?,
+            from,
+            to,
+            labels: self.edge_labels(id)?,
+            props: self.edge_props(id)?,
+        })
+    }
+    /// Calls `f` with node `id` as a `vec_graph` [`Node`], lent by `vec_graph` and copied by the
+    /// other backends, so callers that read a few fields don't pay for an owned record
+    fn with_node(&self, id: &NodeIndex, f: &mut dyn FnMut(&Node)) -> GraphResult<()> {
+        let (alias, labels, props) = (
+            self.node_alias(id)?,
+            self.node_labels(id)?,
+            self.node_props(id)?,
+        );
+        f(&Node::detached(*id, alias, labels, props));
+        Ok(())
+    }
+    /// Calls `f` with edge `id` as a `vec_graph` [`Edge`], see [`GraphStore::with_node`]
+    fn with_edge(&self, id: &EdgeIndex, f: &mut dyn FnMut(&Edge)) -> GraphResult<()> {
+        let (relation, ends, labels, props) = (
+            self.edge_relation(id)?,
+            self.edge_ends(id)?,
+            self.edge_labels(id)?,
+            self.edge_props(id)?,
+        );
+        f(&Edge::detached(*id, relation, ends, labels, props));
+        Ok(())
+    }
+}
+
+/// Copy of everything a store holds about one node
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeData {
+    pub id: NodeIndex,
+    pub alias: String,
+    labels: Vec<String>,
+    props: BTreeMap<String, PropValue>,
+}
+
+impl NodeData {
+    #[inline]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+    #[inline]
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|x| x == label)
+    }
+    /// Sorted by key
+    #[inline]
+    pub fn props(&self) -> &BTreeMap<String, PropValue> {
+        &self.props
+    }
+    #[inline]
+    pub fn get_prop(&self, key: &str) -> Option<&PropValue> {
+        self.props.get(key)
+    }
+}
+
+impl From<&Node> for NodeData {
+    fn from(node: &Node) -> Self {
+        NodeData {
+            id: node.id,
+            alias: node.alias.clone(),
+            labels: node.labels().to_vec(),
+            props: sorted(node.props()),
+        }
+    }
+}
+
+/// Copy of everything a store holds about one edge
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeData {
+    id: EdgeIndex,
+    relation: String,
+    from: NodeIndex,
+    to: NodeIndex,
+    labels: Vec<String>,
+    props: BTreeMap<String, PropValue>,
+}
+
+impl EdgeData {
+    #[inline]
+    pub fn id(&self) -> EdgeIndex {
+        self.id
+    }
+    #[inline]
+    pub fn relation(&self) -> &str {
+        &self.relation
+    }
+    #[inline]
+    pub fn from(&self) -> &NodeIndex {
+        &self.from
+    }
+    #[inline]
+    pub fn to(&self) -> &NodeIndex {
+        &self.to
+    }
+    #[inline]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+    #[inline]
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|x| x == label)
+    }
+    /// Sorted by key
+    #[inline]
+    pub fn props(&self) -> &BTreeMap<String, PropValue> {
+        &self.props
+    }
+    #[inline]
+    pub fn get_prop(&self, key: &str) -> Option<&PropValue> {
+        self.props.get(key)
+    }
+}
+
+impl From<&Edge> for EdgeData {
+    fn from(edge: &Edge) -> Self {
+        EdgeData {
+            id: edge.id(),
+            relation: edge.relation().to_owned(),
+            from: *edge.from(),
+            to: *edge.to(),
+            labels: edge.labels().to_vec(),
+            props: sorted(edge.props()),
+        }
+    }
+}
+
+/// `NodeNotFound` unless `id` is live in `graph`
+#[inline]
+pub(crate) fn require_node<G: GraphStore + ?Sized>(graph: &G, id: &NodeIndex) -> GraphResult<()> {
+    match graph.has_node(id) {
+        true => Ok(()),
+        false => Err(Error::NodeNotFound { id: *id }),
+    }
+}
+
+#[inline]
+pub(crate) fn sorted(props: &HashMap<String, PropValue>) -> BTreeMap<String, PropValue> {
+    props.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+}
+
+/// Adds every node and edge of `from` to `into`, returns where each node of `from` ended up
+///
+/// Props go on before labels so label rules like required props hold as soon as a label lands.
+/// Stops at the first change `into` refuses, leaving what was copied so far
+pub fn copy<S, D>(from: &S, into: &mut D) -> GraphResult<HashMap<NodeIndex, NodeIndex>>
+where
+    S: GraphStore + ?Sized,
+    D: GraphStore + ?Sized,
+{
+    let mut ids = HashMap::new();
+    for id in from.node_ids() {
+        let node = from.node(&id)?;
+        let copied = into.create_node(&node.alias)?;
+        for (key, val) in node.props {
+            into.set_node_prop(&copied, &key, val)?;
+        }
+        for label in node.labels.iter() {
+            into.label_node(&copied, label)?;
+        }
+        ids.insert(id, copied);
+    }
+    for id in from.edge_ids() {
+        let edge = from.edge(&id)?;
+        let copied = into.create_edge(&edge.relation, &ids[&edge.from], &ids[&edge.to])?;
+        for (key, val) in edge.props {
+            into.set_edge_prop(&copied, &key, val)?;
+        }
+        for label in edge.labels.iter() {
+            into.label_edge(&copied, label)?;
+        }
+    }
+    Ok(ids)
+}
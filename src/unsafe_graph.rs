@@ -1,20 +1,523 @@
-use std::collections::HashMap;
+//! Graph whose links are raw pointers to their target node
+//!
+//! Nodes live in a pinned arena: slots are allocated in fixed chunks that never move, so a
+//! node's address is stable for as long as it is in the graph and following a link is a single
+//! pointer dereference, no id lookup. The raw pointers stay private, links and nodes are only
+//! reachable through borrows of the graph and a node's links are removed at both ends before
+//! the node leaves, so every reference handed out points at a live node.
+//! ```
+//! # use graph_db::unsafe_graph::Graph;
+//! let mut graph = Graph::new();
+//! graph
+//!     .add_node("istanbul")?
+//!     .add_node("sisli")?
+//!     .link("includes", "istanbul", "sisli")?;
+//! let link = &graph.get_node("istanbul")?.links()[0];
+//! assert_eq!(link.target().alias(), "sisli");
+//! # Ok::<(), graph_db::vec_graph::Error>(())
+//! ```
+//! `cargo +nightly miri test --test unsafe_graph_tests` checks the pointer handling
+//! and `cargo run --release --example adjacency` compares traversal speed with the index based backends
 
-type Link = *mut Node;
+mod pinned;
 
-struct Node {
-    id: usize,
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    marker::PhantomData,
+    ops::Deref,
+    ptr::NonNull,
+};
+
+use crate::algo::Direction;
+use crate::store::{sorted, GraphStore};
+use crate::vec_graph::{Arena, EdgeIndex, Error, GraphResult, NodeIndex, PropValue};
+use pinned::Pinned;
+
+/// Outgoing edge, stored in its source node
+#[derive(Debug)]
+pub struct Link {
+    id: EdgeIndex,
+    relation: String,
+    to: NonNull<Node>,
+    labels: Vec<String>,
+    props: HashMap<String, PropValue>,
+}
+
+impl Link {
+    #[inline]
+    pub fn id(&self) -> EdgeIndex {
+        self.id
+    }
+    #[inline]
+    pub fn relation(&self) -> &str {
+        &self.relation
+    }
+    /// The node this link points at
+    #[inline]
+    pub fn target(&self) -> &Node {
+        // SAFETY: links are only reachable through a borrow of their graph, which removes a
+        // link before its target and never moves nodes
+        unsafe { self.to.as_ref() }
+    }
+    #[inline]
+    pub fn to(&self) -> &NodeIndex {
+        &self.target().id
+    }
+    #[inline]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+    #[inline]
+    pub fn props(&self) -> &HashMap<String, PropValue> {
+        &self.props
+    }
+    #[inline]
+    pub fn get_prop(&self, key: &str) -> Option<&PropValue> {
+        self.props.get(key)
+    }
+}
+
+#[derive(Debug)]
+pub struct Node {
+    id: NodeIndex,
     alias: String,
     labels: Vec<String>,
-    props: HashMap<String, String>,
-    rels: Vec<Edge>,
+    props: HashMap<String, PropValue>,
+    links: Vec<Link>,
+    //(link, its source) for every link pointing here
+    incoming: Vec<(EdgeIndex, NonNull<Node>)>,
 }
 
-struct Edge {
-    to: Link,
-    relation: String,
+impl Node {
+    #[inline]
+    pub fn id(&self) -> NodeIndex {
+        self.id
+    }
+    #[inline]
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+    #[inline]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+    #[inline]
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|x| x == label)
+    }
+    #[inline]
+    pub fn props(&self) -> &HashMap<String, PropValue> {
+        &self.props
+    }
+    #[inline]
+    pub fn get_prop(&self, key: &str) -> Option<&PropValue> {
+        self.props.get(key)
+    }
+    /// Outgoing links, oldest first
+    #[inline]
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+    /// (link, source node) of every link to this node, oldest first
+    pub fn incoming(&self) -> impl Iterator<Item = (EdgeIndex, &Node)> {
+        self.incoming
+            .iter()
+            // SAFETY: sources outlive their links, as for `Link::target`
+            .map(|(edge, from)| (*edge, unsafe { from.as_ref() }))
+    }
+}
+
+/// Mutable access to a node's labels and props
+///
+/// Links change through the [`Graph`] only, and a bare `&mut Node` is never handed out so
+/// nodes can't be swapped between graphs behind their links' back.
+/// Holds a pointer rather than a `&mut Node`: on a cycle the node is also reachable through
+/// its own links, as a `&Node`, which a unique reference would invalidate
+pub struct NodeMut<'g> {
+    node: NonNull<Node>,
+    graph: PhantomData<&'g mut Graph>,
+}
+
+impl NodeMut<'_> {
+    #[inline]
+    fn new(node: NonNull<Node>) -> Self {
+        NodeMut {
+            node,
+            graph: PhantomData,
+        }
+    }
+    /// Borrows just the fields this type changes, for the length of one call
+    #[inline]
+    fn fields(&mut self) -> (&mut Vec<String>, &mut HashMap<String, PropValue>) {
+        let node = self.node.as_ptr();
+        // SAFETY: the graph is borrowed mutably for 'g and `&mut self` ends every `&Node`
+        // handed out by `deref`, so nothing else reads the labels or props meanwhile
+        unsafe { (&mut (*node).labels, &mut (*node).props) }
+    }
+    pub fn add_label<S: AsRef<str>>(&mut self, label: S) -> &mut Self {
+        self.fields().0.push(label.as_ref().to_owned());
+        self
+    }
+    pub fn remove_label<S: AsRef<str>>(&mut self, label: S) -> &mut Self {
+        self.fields().0.retain(|x| x != label.as_ref());
+        self
+    }
+    pub fn add_prop<S: AsRef<str>, V: Into<PropValue>>(&mut self, key: S, val: V) -> &mut Self {
+        self.fields().1.insert(key.as_ref().to_owned(), val.into());
+        self
+    }
+    pub fn remove_prop<S: AsRef<str>>(&mut self, key: S) -> &mut Self {
+        self.fields().1.remove(key.as_ref());
+        self
+    }
 }
 
-struct Graph {
-    nodes: Vec<Node>,
+impl Deref for NodeMut<'_> {
+    type Target = Node;
+    fn deref(&self) -> &Node {
+        // SAFETY: live for 'g, and only shared while `self` is
+        unsafe { self.node.as_ref() }
+    }
+}
+
+/// Not `Send`/`Sync`, the links are plain pointers
+#[derive(Default)]
+pub struct Graph {
+    nodes: Pinned<Node>,
+    //source node of every link, links live in their source's `links`
+    edges: Arena<NonNull<Node>>,
+    names: HashMap<String, NodeIndex>,
+}
+
+impl fmt::Debug for Graph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter_nodes()).finish()
+    }
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Graph::default()
+    }
+    /// Fails with `ConstraintViolation` if a node already has `alias`
+    pub fn add_node(&mut self, alias: &str) -> GraphResult<&mut Self> {
+        self.insert_node(alias)?;
+        Ok(self)
+    }
+    /// Removes the node and every link from or to it
+    pub fn remove_node(&mut self, alias: &str) -> GraphResult<&mut Self> {
+        let id = self.id_of(alias)?;
+        self.delete_node(&id)?;
+        Ok(self)
+    }
+    #[inline]
+    pub fn id_of(&self, alias: &str) -> GraphResult<NodeIndex> {
+        self.names
+            .get(alias)
+            .copied()
+            .ok_or_else(|| Error::AliasNotFound {
+                alias: alias.to_owned(),
+            })
+    }
+    pub fn get_node(&self, alias: &str) -> GraphResult<&Node> {
+        self.get_node_by_id(&self.id_of(alias)?)
+    }
+    pub fn get_node_mut(&mut self, alias: &str) -> GraphResult<NodeMut<'_>> {
+        let id = self.id_of(alias)?;
+        self.node_mut(&id)
+    }
+    #[inline]
+    pub fn get_node_by_id(&self, id: &NodeIndex) -> GraphResult<&Node> {
+        // SAFETY: live slots hold initialized nodes, shared for as long as the graph is
+        Ok(unsafe { self.node_ptr(id)?.as_ref() })
+    }
+    #[inline]
+    fn node_mut(&mut self, id: &NodeIndex) -> GraphResult<NodeMut<'_>> {
+        self.node_ptr(id).map(NodeMut::new)
+    }
+    #[inline]
+    fn node_ptr(&self, id: &NodeIndex) -> GraphResult<NonNull<Node>> {
+        self.nodes.get(*id).ok_or(Error::NodeNotFound { id: *id })
+    }
+    pub fn mut_node<F>(&mut self, alias: &str, f: F) -> GraphResult<&mut Self>
+    where
+        F: FnOnce(&mut NodeMut),
+    {
+        f(&mut self.get_node_mut(alias)?);
+        Ok(self)
+    }
+    pub fn iter_nodes(&self) -> impl Iterator<Item = &Node> {
+        // SAFETY: as in `get_node_by_id`
+        self.nodes
+            .iter::<NodeIndex>()
+            .map(|(_, node)| unsafe { node.as_ref() })
+    }
+    /// Adds a `relation` link from `from` to `to`
+    pub fn link(&mut self, relation: &str, from: &str, to: &str) -> GraphResult<&mut Self> {
+        let (from, to) = (self.id_of(from)?, self.id_of(to)?);
+        self.insert_link(relation, &from, &to)?;
+        Ok(self)
+    }
+    /// Removes every `relation` link from `from` to `to`
+    pub fn unlink(&mut self, relation: &str, from: &str, to: &str) -> GraphResult<&mut Self> {
+        let (from, to) = (self.id_of(from)?, self.id_of(to)?);
+        let links: Vec<EdgeIndex> = self
+            .get_node_by_id(&from)?
+            .links
+            .iter()
+            .filter(|l| l.relation == relation && *l.to() == to)
+            .map(|l| l.id)
+            .collect();
+        for id in links {
+            self.delete_edge(&id)?;
+        }
+        Ok(self)
+    }
+    /// The link behind `id` with its source node
+    pub fn get_link(&self, id: &EdgeIndex) -> GraphResult<(&Link, &Node)> {
+        let from = self.edges.get(*id).ok_or(Error::EdgeNotFound { id: *id })?;
+        // SAFETY: indexed links have live sources
+        let from = unsafe { from.as_ref() };
+        let link = from
+            .links
+            .iter()
+            .find(|l| l.id == *id)
+            .expect("Indexed link must be in its source");
+        Ok((link, from))
+    }
+    fn get_link_mut(&mut self, id: &EdgeIndex) -> GraphResult<&mut Link> {
+        let mut from = *self.edges.get(*id).ok_or(Error::EdgeNotFound { id: *id })?;
+        // SAFETY: indexed links have live sources, the graph is borrowed mutably
+        Ok(unsafe { from.as_mut() }
+            .links
+            .iter_mut()
+            .find(|l| l.id == *id)
+            .expect("Indexed link must be in its source"))
+    }
+
+    fn insert_node(&mut self, alias: &str) -> GraphResult<NodeIndex> {
+        if let Some(id) = self.names.get(alias) {
+            return Err(Error::ConstraintViolation {
+                constraint: "unique aliases".to_owned(),
+                detail: format!("node {} is already `{}`", id, alias),
+            });
+        }
+        let (id, _) = self.nodes.insert_with(|id| Node {
+            id,
+            alias: alias.to_owned(),
+            labels: Vec::new(),
+            props: HashMap::new(),
+            links: Vec::new(),
+            incoming: Vec::new(),
+        });
+        self.names.insert(alias.to_owned(), id);
+        Ok(id)
+    }
+    fn insert_link(
+        &mut self,
+        relation: &str,
+        from: &NodeIndex,
+        to: &NodeIndex,
+    ) -> GraphResult<EdgeIndex> {
+        let (source, target) = (self.node_ptr(from)?, self.node_ptr(to)?);
+        let id = self.edges.insert(source);
+        // SAFETY: both nodes are live and unborrowed, each is touched through its own
+        // pointer one statement at a time so a self link never aliases
+        unsafe {
+            (*source.as_ptr()).links.push(Link {
+                id,
+                relation: relation.to_owned(),
+                to: target,
+                labels: Vec::new(),
+                props: HashMap::new(),
+            });
+            (*target.as_ptr()).incoming.push((id, source));
+        }
+        Ok(id)
+    }
+}
+
+impl GraphStore for Graph {
+    #[inline]
+    fn create_node(&mut self, alias: &str) -> GraphResult<NodeIndex> {
+        self.insert_node(alias)
+    }
+    fn delete_node(&mut self, id: &NodeIndex) -> GraphResult<()> {
+        let node = self.node_ptr(id)?;
+        // SAFETY: the node and its neighbors are live and unborrowed; every pointer to the
+        // node is dropped before `remove` moves it out
+        unsafe {
+            let (links, incoming) = {
+                let node = &mut *node.as_ptr();
+                self.names.remove(&node.alias);
+                (
+                    std::mem::take(&mut node.links),
+                    std::mem::take(&mut node.incoming),
+                )
+            };
+            //self links are in both lists, their second removal finds nothing
+            for link in links {
+                self.edges.remove(link.id);
+                (*link.to.as_ptr())
+                    .incoming
+                    .retain(|(edge, _)| *edge != link.id);
+            }
+            for (edge, from) in incoming {
+                self.edges.remove(edge);
+                (*from.as_ptr()).links.retain(|l| l.id != edge);
+            }
+        }
+        self.nodes.remove(*id);
+        Ok(())
+    }
+    #[inline]
+    fn has_node(&self, id: &NodeIndex) -> bool {
+        self.nodes.get(*id).is_some()
+    }
+    fn node_ids(&self) -> Vec<NodeIndex> {
+        self.nodes.iter::<NodeIndex>().map(|(id, _)| id).collect()
+    }
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+    fn node_alias(&self, id: &NodeIndex) -> GraphResult<String> {
+        Ok(self.get_node_by_id(id)?.alias.clone())
+    }
+    fn ids_with_alias(&self, alias: &str) -> Vec<NodeIndex> {
+        self.names.get(alias).copied().into_iter().collect()
+    }
+
+    fn node_labels(&self, id: &NodeIndex) -> GraphResult<Vec<String>> {
+        Ok(self.get_node_by_id(id)?.labels.clone())
+    }
+    fn node_has_label(&self, id: &NodeIndex, label: &str) -> GraphResult<bool> {
+        Ok(self.get_node_by_id(id)?.has_label(label))
+    }
+    fn label_node(&mut self, id: &NodeIndex, label: &str) -> GraphResult<()> {
+        self.node_mut(id)?.add_label(label);
+        Ok(())
+    }
+    fn unlabel_node(&mut self, id: &NodeIndex, label: &str) -> GraphResult<()> {
+        self.node_mut(id)?.remove_label(label);
+        Ok(())
+    }
+    fn node_prop(&self, id: &NodeIndex, key: &str) -> GraphResult<Option<PropValue>> {
+        Ok(self.get_node_by_id(id)?.get_prop(key).cloned())
+    }
+    fn node_props(&self, id: &NodeIndex) -> GraphResult<BTreeMap<String, PropValue>> {
+        Ok(sorted(&self.get_node_by_id(id)?.props))
+    }
+    fn set_node_prop(&mut self, id: &NodeIndex, key: &str, val: PropValue) -> GraphResult<()> {
+        self.node_mut(id)?.add_prop(key, val);
+        Ok(())
+    }
+    fn unset_node_prop(&mut self, id: &NodeIndex, key: &str) -> GraphResult<()> {
+        self.node_mut(id)?.remove_prop(key);
+        Ok(())
+    }
+
+    #[inline]
+    fn create_edge(
+        &mut self,
+        relation: &str,
+        from: &NodeIndex,
+        to: &NodeIndex,
+    ) -> GraphResult<EdgeIndex> {
+        self.insert_link(relation, from, to)
+    }
+    fn delete_edge(&mut self, id: &EdgeIndex) -> GraphResult<()> {
+        let from = self
+            .edges
+            .remove(*id)
+            .ok_or(Error::EdgeNotFound { id: *id })?;
+        // SAFETY: indexed links have live, unborrowed ends; the link is moved out of its
+        // source before its target is touched
+        unsafe {
+            let links = &mut (*from.as_ptr()).links;
+            let at = links
+                .iter()
+                .position(|l| l.id == *id)
+                .expect("Indexed link must be in its source");
+            let link = links.remove(at);
+            (*link.to.as_ptr()).incoming.retain(|(edge, _)| edge != id);
+        }
+        Ok(())
+    }
+    #[inline]
+    fn has_edge(&self, id: &EdgeIndex) -> bool {
+        self.edges.get(*id).is_some()
+    }
+    fn edge_ids(&self) -> Vec<EdgeIndex> {
+        self.edges.iter::<EdgeIndex>().map(|(id, _)| id).collect()
+    }
+    #[inline]
+    fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+    fn edge_relation(&self, id: &EdgeIndex) -> GraphResult<String> {
+        Ok(self.get_link(id)?.0.relation.clone())
+    }
+    fn edge_ends(&self, id: &EdgeIndex) -> GraphResult<(NodeIndex, NodeIndex)> {
+        let (link, from) = self.get_link(id)?;
+        Ok((from.id, *link.to()))
+    }
+    fn edge_labels(&self, id: &EdgeIndex) -> GraphResult<Vec<String>> {
+        Ok(self.get_link(id)?.0.labels.clone())
+    }
+    fn label_edge(&mut self, id: &EdgeIndex, label: &str) -> GraphResult<()> {
+        self.get_link_mut(id)?.labels.push(label.to_owned());
+        Ok(())
+    }
+    fn unlabel_edge(&mut self, id: &EdgeIndex, label: &str) -> GraphResult<()> {
+        self.get_link_mut(id)?.labels.retain(|x| x != label);
+        Ok(())
+    }
+    fn edge_prop(&self, id: &EdgeIndex, key: &str) -> GraphResult<Option<PropValue>> {
+        Ok(self.get_link(id)?.0.get_prop(key).cloned())
+    }
+    fn edge_props(&self, id: &EdgeIndex) -> GraphResult<BTreeMap<String, PropValue>> {
+        Ok(sorted(&self.get_link(id)?.0.props))
+    }
+    fn set_edge_prop(&mut self, id: &EdgeIndex, key: &str, val: PropValue) -> GraphResult<()> {
+        self.get_link_mut(id)?.props.insert(key.to_owned(), val);
+        Ok(())
+    }
+    fn unset_edge_prop(&mut self, id: &EdgeIndex, key: &str) -> GraphResult<()> {
+        self.get_link_mut(id)?.props.remove(key);
+        Ok(())
+    }
+
+    fn adjacent(
+        &self,
+        id: &NodeIndex,
+        direction: Direction,
+        relations: &[String],
+    ) -> GraphResult<Vec<(EdgeIndex, NodeIndex)>> {
+        let node = self.get_node_by_id(id)?;
+        let follows = |relation: &String| relations.is_empty() || relations.contains(relation);
+        let mut adjacent = Vec::new();
+        if matches!(direction, Direction::Outgoing | Direction::Both) {
+            adjacent.extend(
+                node.links
+                    .iter()
+                    .filter(|l| follows(&l.relation))
+                    .map(|l| (l.id, *l.to())),
+            );
+        }
+        if matches!(direction, Direction::Incoming | Direction::Both) {
+            for (edge, from) in node.incoming() {
+                let link = from
+                    .links
+                    .iter()
+                    .find(|l| l.id == edge)
+                    .expect("Incoming link must be in its source");
+                if follows(&link.relation) {
+                    adjacent.push((edge, from.id));
+                }
+            }
+        }
+        Ok(adjacent)
+    }
 }
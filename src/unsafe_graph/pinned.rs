@@ -0,0 +1,146 @@
+use std::{
+    mem::MaybeUninit,
+    ptr::{self, NonNull},
+};
+
+use crate::vec_graph::ArenaKey;
+
+//slots per allocation
+const CHUNK: usize = 64;
+
+struct Slot<T> {
+    generation: u32,
+    occupied: bool,
+    value: MaybeUninit<T>,
+}
+
+/// Slot map whose values never move: slots are allocated `CHUNK` at a time and a chunk is only
+/// freed on drop, so a value keeps its address from insert to remove
+///
+/// Every pointer handed out is derived from its chunk's raw allocation, never from a reference,
+/// so any number of them stay valid side by side
+pub(crate) struct Pinned<T> {
+    chunks: Vec<NonNull<Slot<T>>>,
+    //slots handed out at least once, the rest of the last chunk is untouched
+    used: usize,
+    //freed slot indices, reused last in first out
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl<T> Default for Pinned<T> {
+    fn default() -> Self {
+        Pinned {
+            chunks: Vec::new(),
+            used: 0,
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> Pinned<T> {
+    #[inline]
+    fn slot(&self, index: usize) -> *mut Slot<T> {
+        debug_assert!(index < self.used);
+        // SAFETY: index < used <= chunks.len() * CHUNK, so the offset is inside its chunk
+        unsafe { self.chunks[index / CHUNK].as_ptr().add(index % CHUNK) }
+    }
+    /// Stores `make(key)` and returns the key with the value's address
+    pub(crate) fn insert_with<K: ArenaKey>(
+        &mut self,
+        make: impl FnOnce(K) -> T,
+    ) -> (K, NonNull<T>) {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                if self.used == self.chunks.len() * CHUNK {
+                    let chunk: Box<[Slot<T>]> = (0..CHUNK)
+                        .map(|_| Slot {
+                            generation: 0,
+                            occupied: false,
+                            value: MaybeUninit::uninit(),
+                        })
+                        .collect();
+                    let chunk = Box::into_raw(chunk).cast::<Slot<T>>();
+                    self.chunks
+                        .push(NonNull::new(chunk).expect("Box pointers are never null"));
+                }
+                self.used += 1;
+                self.used - 1
+            }
+        };
+        let slot = self.slot(index);
+        // SAFETY: the slot is in bounds and vacant, nothing else points at its value
+        unsafe {
+            let key = K::new(index, (*slot).generation);
+            let value = ptr::addr_of_mut!((*slot).value).cast::<T>();
+            value.write(make(key));
+            (*slot).occupied = true;
+            self.len += 1;
+            (key, NonNull::new_unchecked(value))
+        }
+    }
+    /// Address of the value behind `key`, None once it's removed
+    #[inline]
+    pub(crate) fn get<K: ArenaKey>(&self, key: K) -> Option<NonNull<T>> {
+        if key.index() >= self.used {
+            return None;
+        }
+        let slot = self.slot(key.index());
+        // SAFETY: in bounds, the value is only read as a pointer here
+        unsafe {
+            match (*slot).occupied && (*slot).generation == key.generation() {
+                true => Some(NonNull::new_unchecked(
+                    ptr::addr_of_mut!((*slot).value).cast::<T>(),
+                )),
+                false => None,
+            }
+        }
+    }
+    /// Moves the value out, callers must have dropped every pointer to it
+    pub(crate) fn remove<K: ArenaKey>(&mut self, key: K) -> Option<T> {
+        let value = self.get(key)?;
+        let slot = self.slot(key.index());
+        // SAFETY: the slot is occupied, marking it vacant first means it's never read twice
+        unsafe {
+            (*slot).occupied = false;
+            (*slot).generation = (*slot).generation.wrapping_add(1);
+            self.free.push(key.index());
+            self.len -= 1;
+            Some(value.as_ptr().read())
+        }
+    }
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+    /// Live values with their keys, in slot order
+    pub(crate) fn iter<K: ArenaKey>(&self) -> impl Iterator<Item = (K, NonNull<T>)> + '_ {
+        (0..self.used).filter_map(|index| {
+            let slot = self.slot(index);
+            // SAFETY: in bounds, only the slot header is read
+            let generation = unsafe { (*slot).generation };
+            self.get(K::new(index, generation))
+                .map(|value| (K::new(index, generation), value))
+        })
+    }
+}
+
+impl<T> Drop for Pinned<T> {
+    fn drop(&mut self) {
+        for index in 0..self.used {
+            let slot = self.slot(index);
+            // SAFETY: occupied slots hold an initialized value that nothing uses anymore
+            unsafe {
+                if (*slot).occupied {
+                    ptr::drop_in_place(ptr::addr_of_mut!((*slot).value).cast::<T>());
+                }
+            }
+        }
+        for chunk in self.chunks.drain(..) {
+            // SAFETY: every chunk came from `Box::into_raw` of a CHUNK long slice
+            drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(chunk.as_ptr(), CHUNK)) });
+        }
+    }
+}
@@ -1,16 +1,84 @@
 use core::fmt;
-use std::{
-    borrow::{Borrow, BorrowMut},
-    collections::HashMap,
-    iter::zip,
-};
+use std::{collections::HashMap, ops::Deref, str::FromStr};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, PartialOrd, Ord)]
-pub struct NodeIndex(usize);
+use crate::algo::Direction;
+use crate::store::GraphStore;
 
+mod arena;
+mod codec;
+mod error;
+mod index;
+mod json_io;
+mod op;
+mod schema;
+mod shared;
+mod snapshot;
+mod store;
+mod tx;
+mod value;
+mod wal;
+
+pub(crate) use arena::{Arena, ArenaKey};
+pub use error::{Error, GraphResult};
+use index::Indexes;
+use op::Op;
+pub use schema::Constraint;
+use schema::Uniques;
+pub use shared::SharedGraph;
+use tx::{positions, TxLog, Undo};
+pub use tx::{Savepoint, Transaction};
+pub use value::PropValue;
+use wal::Journal;
+pub use wal::WalOptions;
+
+/// Slot of a node plus the slot's generation
+///
+/// Removing a node bumps its slot's generation, so handles to it stop resolving
+/// instead of pointing at whichever node reuses the slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct NodeIndex {
+    index: usize,
+    generation: u32,
+}
+
+impl NodeIndex {
+    /// Handle to slot `index` at `generation`, as printed by `Display` or sent over the wire
+    #[inline]
+    pub fn from_parts(index: usize, generation: u32) -> Self {
+        NodeIndex { index, generation }
+    }
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl ArenaKey for NodeIndex {
+    #[inline]
+    fn new(index: usize, generation: u32) -> Self {
+        NodeIndex { index, generation }
+    }
+    #[inline]
+    fn index(&self) -> usize {
+        self.index
+    }
+    #[inline]
+    fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// First generation of slot `value`
 impl From<usize> for NodeIndex {
     fn from(value: usize) -> Self {
-        NodeIndex(value)
+        NodeIndex {
+            index: value,
+            generation: 0,
+        }
     }
 }
 
@@ -20,40 +88,208 @@ impl From<i32> for NodeIndex {
             value >= 0,
             "NodeIndex can't be initialized with a negative integer"
         );
-        NodeIndex(value as usize)
+        NodeIndex::from(value as usize)
     }
 }
 
 impl fmt::Display for NodeIndex {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        match self.generation {
+            0 => write!(f, "{}", self.index),
+            generation => write!(f, "{}v{}", self.index, generation),
+        }
+    }
+}
+
+/// Parses the `Display` form back, `3` or `3v1`
+impl FromStr for NodeIndex {
+    type Err = Error;
+    fn from_str(s: &str) -> GraphResult<Self> {
+        parse_key(s).map(|(index, generation)| NodeIndex { index, generation })
+    }
+}
+
+fn parse_key(s: &str) -> GraphResult<(usize, u32)> {
+    let invalid = || Error::InvalidIndex {
+        index: format!("`{}`", s),
+        reason: "expected a slot number, optionally followed by v and a generation".to_owned(),
+    };
+    let (index, generation) = s.split_once('v').unwrap_or((s, "0"));
+    Ok((
+        index.parse().map_err(|_| invalid())?,
+        generation.parse().map_err(|_| invalid())?,
+    ))
+}
+
+/// Slot of an edge plus the slot's generation, see [`NodeIndex`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct EdgeIndex {
+    index: usize,
+    generation: u32,
+}
+
+impl EdgeIndex {
+    /// Handle to slot `index` at `generation`, as printed by `Display` or sent over the wire
+    #[inline]
+    pub fn from_parts(index: usize, generation: u32) -> Self {
+        EdgeIndex { index, generation }
+    }
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl ArenaKey for EdgeIndex {
+    #[inline]
+    fn new(index: usize, generation: u32) -> Self {
+        EdgeIndex { index, generation }
+    }
+    #[inline]
+    fn index(&self) -> usize {
+        self.index
+    }
+    #[inline]
+    fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// First generation of slot `value`
+impl From<usize> for EdgeIndex {
+    fn from(value: usize) -> Self {
+        EdgeIndex {
+            index: value,
+            generation: 0,
+        }
+    }
+}
+
+impl fmt::Display for EdgeIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.generation {
+            0 => write!(f, "{}", self.index),
+            generation => write!(f, "{}v{}", self.index, generation),
+        }
+    }
+}
+
+/// Parses the `Display` form back, `3` or `3v1`
+impl FromStr for EdgeIndex {
+    type Err = Error;
+    fn from_str(s: &str) -> GraphResult<Self> {
+        parse_key(s).map(|(index, generation)| EdgeIndex { index, generation })
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Edge {
+    id: EdgeIndex,
     relation: String,
     from: NodeIndex,
     to: NodeIndex,
+    labels: Vec<String>,
+    props: HashMap<String, PropValue>,
+    //next edge in `from`'s outgoing list and in `to`'s incoming list
+    next_outgoing: Option<EdgeIndex>,
+    next_incoming: Option<EdgeIndex>,
 }
 
 impl Edge {
-    fn new(relation: &str, from: NodeIndex, to: NodeIndex) -> Self {
+    fn new(id: EdgeIndex, relation: &str, from: NodeIndex, to: NodeIndex) -> Self {
         Edge {
+            id,
             relation: relation.to_owned(),
             from,
             to,
+            labels: Vec::new(),
+            props: HashMap::new(),
+            next_outgoing: None,
+            next_incoming: None,
         }
     }
+    /// Copy of another store's edge, in no graph's edge lists
+    pub(crate) fn detached(
+        id: EdgeIndex,
+        relation: String,
+        (from, to): (NodeIndex, NodeIndex),
+        labels: Vec<String>,
+        props: impl IntoIterator<Item = (String, PropValue)>,
+    ) -> Self {
+        Edge {
+            labels,
+            props: props.into_iter().collect(),
+            relation,
+            ..Edge::new(id, "", from, to)
+        }
+    }
+    pub fn add_label<S: AsRef<str>>(&mut self, label: S) -> GraphResult<&mut Self> {
+        self.labels.push(label.as_ref().to_owned());
+        Ok(self)
+    }
+    pub fn remove_label<S: AsRef<str>>(&mut self, label: S) -> GraphResult<&mut Self> {
+        self.labels.retain(|x| x != label.as_ref());
+        Ok(self)
+    }
+    pub fn add_prop<S: AsRef<str>, V: Into<PropValue>>(
+        &mut self,
+        key: S,
+        val: V,
+    ) -> GraphResult<&mut Self> {
+        self.props.insert(key.as_ref().to_owned(), val.into());
+        Ok(self)
+    }
+    pub fn remove_prop<S: AsRef<str>>(&mut self, key: S) -> GraphResult<&mut Self> {
+        self.props.remove(key.as_ref());
+        Ok(self)
+    }
+    #[inline]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+    #[inline]
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|x| x == label)
+    }
+    #[inline]
+    pub fn props(&self) -> &HashMap<String, PropValue> {
+        &self.props
+    }
+    #[inline]
+    pub fn get_prop(&self, key: &str) -> Option<&PropValue> {
+        self.props.get(key)
+    }
+    #[inline]
+    pub fn id(&self) -> EdgeIndex {
+        self.id
+    }
+    #[inline]
+    pub fn relation(&self) -> &str {
+        &self.relation
+    }
+    #[inline]
+    pub fn from(&self) -> &NodeIndex {
+        &self.from
+    }
+    #[inline]
+    pub fn to(&self) -> &NodeIndex {
+        &self.to
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Node {
     pub id: NodeIndex,
     pub alias: String,
     labels: Vec<String>,
-    props: HashMap<String, String>,
-    //    rels: Vec<Edge>
+    props: HashMap<String, PropValue>,
+    //heads of the edge lists threaded through Edge::next_outgoing/next_incoming
+    first_outgoing: Option<EdgeIndex>,
+    first_incoming: Option<EdgeIndex>,
 }
 
 impl Node {
@@ -63,6 +299,21 @@ impl Node {
             alias,
             labels: Vec::new(),
             props: HashMap::new(),
+            first_outgoing: None,
+            first_incoming: None,
+        }
+    }
+    /// Copy of another store's node, in no graph
+    pub(crate) fn detached(
+        id: NodeIndex,
+        alias: String,
+        labels: Vec<String>,
+        props: impl IntoIterator<Item = (String, PropValue)>,
+    ) -> Self {
+        Node {
+            labels,
+            props: props.into_iter().collect(),
+            ..Node::new(id, alias)
         }
     }
     pub fn add_label<S: AsRef<str>>(&mut self, label: S) -> GraphResult<&mut Self> {
@@ -73,15 +324,34 @@ impl Node {
         self.labels.retain(|x| x != label.as_ref());
         Ok(self)
     }
-    pub fn add_prop<S: AsRef<str>>(&mut self, key: S, val: S) -> GraphResult<&mut Self> {
-        self.props
-            .insert(key.as_ref().to_owned(), val.as_ref().to_owned());
+    pub fn add_prop<S: AsRef<str>, V: Into<PropValue>>(
+        &mut self,
+        key: S,
+        val: V,
+    ) -> GraphResult<&mut Self> {
+        self.props.insert(key.as_ref().to_owned(), val.into());
         Ok(self)
     }
     pub fn remove_prop<S: AsRef<str>>(&mut self, key: S) -> GraphResult<&mut Self> {
         self.props.remove(key.as_ref());
         Ok(self)
     }
+    #[inline]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+    #[inline]
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|x| x == label)
+    }
+    #[inline]
+    pub fn props(&self) -> &HashMap<String, PropValue> {
+        &self.props
+    }
+    #[inline]
+    pub fn get_prop(&self, key: &str) -> Option<&PropValue> {
+        self.props.get(key)
+    }
 }
 
 impl fmt::Display for Node {
@@ -92,7 +362,7 @@ impl fmt::Display for Node {
             self.id, self.alias, self.labels
         )?;
         for (k, v) in self.props.iter() {
-            write!(f, "\t\t{}:{}\n", k, v)?;
+            writeln!(f, "\t\t{}:{}", k, v)?;
         }
         Ok(())
     }
@@ -100,7 +370,7 @@ impl fmt::Display for Node {
 
 //struct AliasMap(HashMap<String, NodeIndex>);
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AliasMap {
     inner: HashMap<String, Vec<NodeIndex>>,
 }
@@ -126,154 +396,520 @@ impl AliasMap {
         self.inner.get(key)
     }
     #[inline]
-    fn remove_all(&mut self, key: &str) -> Option<Vec<NodeIndex>> {
-        self.inner.remove(key)
-    }
-    #[inline]
     fn remove_id_at(&mut self, key: &str, idx: &NodeIndex) -> Option<()> {
-        self.inner.get_mut(key)?.retain(|x| x.0 != idx.0);
-        Some(())
-    }
-    fn change_id_at(
-        &mut self,
-        key: &str,
-        old_idx: &NodeIndex,
-        mut new_idx: NodeIndex,
-    ) -> Option<()> {
-        self.inner
-            .get_mut(key)?
-            .iter_mut()
-            .filter(|idx| *idx == old_idx)
-            .for_each(|idx| *idx = new_idx);
-        Some(())
-    }
-    #[inline]
-    fn retain_at<F>(&mut self, key: &str, mut f: F) -> Option<()>
-    where
-        F: FnMut(&NodeIndex) -> bool,
-    {
-        self.inner.get_mut(key)?.retain(f);
+        self.inner.get_mut(key)?.retain(|x| x != idx);
         Some(())
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Graph {
     pub aliases: AliasMap,
-    nodes: Vec<Node>,
+    nodes: Arena<Node>,
     //nodes: HashMap<String, Node>
-    edges: Vec<Edge>,
+    edges: Arena<Edge>,
+    index: Indexes,
+    constraints: Vec<Constraint>,
+    uniques: Uniques,
+    session: Session,
+}
+
+/// State of this handle on the graph rather than of its contents: where `mut_last_node`/`mut_last_edge`
+/// point, the write-ahead log and the running transaction
+///
+/// Every session compares equal, so graphs are equal when their contents are
+#[derive(Debug, Default)]
+struct Session {
+    last_node: Option<NodeIndex>,
+    last_edge: Option<EdgeIndex>,
+    //write-ahead log of a graph opened with `Graph::open`, None for in-memory graphs
+    journal: Option<Journal>,
+    //ops and undo entries of the running transaction
+    tx: Option<TxLog>,
+}
+
+impl Session {
+    fn new(last_node: Option<NodeIndex>, last_edge: Option<EdgeIndex>) -> Self {
+        Session {
+            last_node,
+            last_edge,
+            ..Session::default()
+        }
+    }
+}
+
+impl PartialEq for Session {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Session {}
+
+/// The clone is an in-memory copy, it doesn't write to the original's log
+impl Clone for Session {
+    fn clone(&self) -> Self {
+        Session::new(self.last_node, self.last_edge)
+    }
 }
 
 impl Graph {
     pub fn new() -> Self {
         Graph {
             aliases: HashMap::new().into(),
-            nodes: Vec::new(),
-            edges: Vec::new(),
+            nodes: Arena::default(),
+            edges: Arena::default(),
+            index: Indexes::default(),
+            constraints: Vec::new(),
+            uniques: Uniques::default(),
+            session: Session::default(),
         }
     }
-    pub fn add_node(&mut self, alias: &str) -> GraphResult<&mut Self> {
-        self.aliases.insert(alias, self.nodes.len().into());
-        self.nodes
-            .push(Node::new(self.nodes.len().into(), alias.to_owned()));
-        Ok(self)
+    /// Fails if `op` can't be applied to the current state, so [`Graph::apply`] never has to
+    fn check(&self, op: &Op) -> GraphResult<()> {
+        if let Op::AddProp { key, val, .. } | Op::AddEdgeProp { key, val, .. } = op {
+            if val.nested_deeper_than(PropValue::MAX_DEPTH) {
+                return Err(Error::InvalidOperation {
+                    operation: format!("set property `{}`", key),
+                    reason: format!(
+                        "the value nests deeper than {} levels",
+                        PropValue::MAX_DEPTH
+                    ),
+                });
+            }
+        }
+        match op {
+            Op::AddNode { .. } => {}
+            Op::RemoveNode { id }
+            | Op::AddLabel { id, .. }
+            | Op::RemoveLabel { id, .. }
+            | Op::AddProp { id, .. }
+            | Op::RemoveProp { id, .. } => {
+                self.get_node(id)?;
+            }
+            Op::CreatePropIndex { key } if self.index.has_prop_key(key) => {
+                return Err(Error::InvalidIndex {
+                    index: format!("prop index `{}`", key),
+                    reason: "already exists".to_owned(),
+                });
+            }
+            Op::DropPropIndex { key } if !self.index.has_prop_key(key) => {
+                return Err(Error::not_indexed(key));
+            }
+            Op::CreatePropIndex { .. }
+            | Op::DropPropIndex { .. }
+            | Op::AddConstraint { .. }
+            | Op::DropConstraint { .. } => {}
+            Op::AddEdge { from, to, .. } => {
+                self.get_node(from)?;
+                self.get_node(to)?;
+            }
+            Op::RemoveEdge { id }
+            | Op::AddEdgeLabel { id, .. }
+            | Op::RemoveEdgeLabel { id, .. }
+            | Op::AddEdgeProp { id, .. }
+            | Op::RemoveEdgeProp { id, .. } => {
+                self.get_edge(id)?;
+            }
+        }
+        self.check_constraints(op)
     }
-    /// TODO:BUG:
-    pub fn remove_node_by_id(&mut self, id: &NodeIndex) -> GraphResult<&mut Self> {
-        if self.nodes.len() < id.0 {
-            Err(format!(
-                "There are {} nodes but tried to index at {}",
-                self.nodes.len(),
-                id.0
-            )
-            .into())
-        } else {
-            // let node = self.nodes.remove(id.0);
-            //remove shifts over remaining elements so id assigning should be ok
-            let node = self.nodes.swap_remove(id.0);
-            //swaps id with last, removes and gets id, last is now id
-            //remove node from other records
-            self.aliases.remove_id_at(&node.alias, id);
-            self.remove_all_edges_from(id)?;
-            self.remove_all_edges_to(id)?;
-            //swap lasts new id
-            if let Some(last) = self.nodes.get_mut(id.0) {
-                //last's alias will point to its old id
-                self.aliases
-                    .change_id_at(&last.alias, &last.id, id.to_owned());
-                //swap all edges from and to last's old id to its new id
-                self.edges.iter_mut().for_each(|edge| {
-                    if edge.from == last.id {
-                        edge.from = last.id
-                    }
-                    if edge.to == last.id {
-                        edge.to = last.id
-                    }
+    /// Applies an op that passed [`Graph::check`], inside a transaction also records how to undo it
+    fn apply(&mut self, op: Op) {
+        match op {
+            Op::AddNode { alias } => {
+                let id: NodeIndex = self.nodes.next_key();
+                self.record(|g| Undo::NodeInserted {
+                    id,
+                    reused: g.nodes.has_free(),
+                    alias_existed: g.aliases.get(&alias).is_some(),
+                    last_node: g.session.last_node,
                 });
-                //change its inner id to its new id
-                last.id = id.to_owned();
-            } else {
-                // no last node -> there was only 1 node?
-            };
-
-            Ok(self)
+                self.aliases.insert(&alias, id);
+                self.nodes.insert::<NodeIndex>(Node::new(id, alias));
+                self.session.last_node = Some(id);
+            }
+            Op::RemoveNode { id } => {
+                //edges first, unlinking them needs the node's list heads
+                while let Some(edge) = self.nodes.get(id).and_then(|n| n.first_outgoing) {
+                    self.apply(Op::RemoveEdge { id: edge });
+                }
+                while let Some(edge) = self.nodes.get(id).and_then(|n| n.first_incoming) {
+                    self.apply(Op::RemoveEdge { id: edge });
+                }
+                let node = self.nodes.remove(id).expect("Checked node must exist");
+                let alias_pos = self
+                    .aliases
+                    .get(&node.alias)
+                    .and_then(|ids| ids.iter().position(|x| *x == id));
+                //remove node from other records
+                self.aliases.remove_id_at(&node.alias, &id);
+                self.index.remove_node(&node);
+                self.uniques.remove_node(&node);
+                let last_node = self.session.last_node;
+                if self.session.last_node == Some(id) {
+                    self.session.last_node = None;
+                }
+                self.record(move |_| Undo::NodeRemoved {
+                    node,
+                    alias_pos,
+                    last_node,
+                });
+            }
+            Op::AddLabel { id, label } => {
+                self.record(|_| Undo::NodeLabelAdded {
+                    id,
+                    label: label.clone(),
+                });
+                self.index.insert_label(&label, id);
+                self.change_node(id, |node| node.labels.push(label));
+            }
+            Op::RemoveLabel { id, label } => {
+                self.record(|g| Undo::NodeLabelRemoved {
+                    id,
+                    positions: positions(
+                        &g.nodes.get(id).expect("Checked node must exist").labels,
+                        &label,
+                    ),
+                    label: label.clone(),
+                });
+                self.index.remove_label(&label, &id);
+                self.change_node(id, |node| node.labels.retain(|x| *x != label));
+            }
+            Op::AddProp { id, key, val } => {
+                let old = self.change_node(id, |node| node.props.insert(key.clone(), val.clone()));
+                if let Some(old) = old.as_ref() {
+                    self.index.remove_prop(&key, old, &id);
+                }
+                self.index.insert_prop(&key, &val, id);
+                self.record(move |_| Undo::NodeProp { id, key, old });
+            }
+            Op::RemoveProp { id, key } => {
+                if let Some(old) = self.change_node(id, |node| node.props.remove(&key)) {
+                    self.index.remove_prop(&key, &old, &id);
+                    self.record(move |_| Undo::NodeProp {
+                        id,
+                        key,
+                        old: Some(old),
+                    });
+                }
+            }
+            Op::CreatePropIndex { key } => {
+                self.index
+                    .create_prop_index(&key, self.nodes.iter::<NodeIndex>().map(|(_, n)| n));
+                self.record(move |_| Undo::PropIndexCreated { key });
+            }
+            Op::DropPropIndex { key } => {
+                self.index.drop_prop_index(&key);
+                self.record(move |_| Undo::PropIndexDropped { key });
+            }
+            Op::AddConstraint { constraint } => {
+                self.uniques
+                    .add(&constraint, self.nodes.iter::<NodeIndex>().map(|(_, n)| n));
+                self.constraints.push(constraint);
+                self.record(|_| Undo::ConstraintAdded);
+            }
+            Op::DropConstraint { constraint } => {
+                let pos = self
+                    .constraints
+                    .iter()
+                    .position(|c| *c == constraint)
+                    .expect("Checked constraint must exist");
+                let constraint = self.constraints.remove(pos);
+                self.uniques.remove(&constraint);
+                self.record(move |_| Undo::ConstraintDropped { constraint, pos });
+            }
+            Op::AddEdge { relation, from, to } => {
+                self.insert_edge(&relation, from, to);
+            }
+            Op::RemoveEdge { id } => {
+                let edge = self.edges.remove(id).expect("Checked edge must exist");
+                self.index.remove_edge(&edge);
+                let (prev_outgoing, prev_incoming) = self.unlink_edge(&edge);
+                let last_edge = self.session.last_edge;
+                if self.session.last_edge == Some(id) {
+                    self.session.last_edge = None;
+                }
+                self.record(move |_| Undo::EdgeRemoved {
+                    edge,
+                    prev_outgoing,
+                    prev_incoming,
+                    last_edge,
+                });
+            }
+            Op::AddEdgeLabel { id, label } => {
+                self.record(|_| Undo::EdgeLabelAdded { id });
+                self.edges
+                    .get_mut(id)
+                    .expect("Checked edge must exist")
+                    .labels
+                    .push(label);
+            }
+            Op::RemoveEdgeLabel { id, label } => {
+                self.record(|g| Undo::EdgeLabelRemoved {
+                    id,
+                    positions: positions(
+                        &g.edges.get(id).expect("Checked edge must exist").labels,
+                        &label,
+                    ),
+                    label: label.clone(),
+                });
+                self.edges
+                    .get_mut(id)
+                    .expect("Checked edge must exist")
+                    .labels
+                    .retain(|x| *x != label);
+            }
+            Op::AddEdgeProp { id, key, val } => {
+                let edge = self.edges.get_mut(id).expect("Checked edge must exist");
+                let old = edge.props.insert(key.clone(), val);
+                self.record(move |_| Undo::EdgeProp { id, key, old });
+            }
+            Op::RemoveEdgeProp { id, key } => {
+                let edge = self.edges.get_mut(id).expect("Checked edge must exist");
+                if let Some(old) = edge.props.remove(&key) {
+                    self.record(move |_| Undo::EdgeProp {
+                        id,
+                        key,
+                        old: Some(old),
+                    });
+                }
+            }
         }
     }
     #[inline]
-    pub fn get_node_mut_by_idx(&mut self, idx: &NodeIndex) -> Option<&mut Node> {
-        self.nodes.get_mut(idx.0)
+    fn submit(&mut self, op: Op) -> GraphResult<()> {
+        self.submit_all(vec![op])
     }
-    pub fn get_nodes_mut_by_alias(&mut self, alias: &str) -> Option<Vec<&mut Node>> {
-        let idxs = self.aliases.get(alias)?;
-        let mut mut_node_iter = self.nodes.iter_mut();
-        let mut nodes = Vec::with_capacity(idxs.len());
-
-        // idxs.iter().for_each(|id| nodes.push(self.nodes.get_mut(id.0)));
-        // idxs.iter().for_each(|id| nodes.push(self.nodes.get_mut(id.0).expect("Idx returned by alias was out of bounds?")));
-
-        //BUG!?: if idxs are not ordered: since nth() consumes the iterator for previous values result will be incorrect if encountered a higher and lower id
-        // + nth(0) == next(), nth(0).nth(0) == next().next(), nth(15) == [15], nth(15).nth(15) == [30], not [15],[15] so nth()'s after first id are also incorrect
-        for id in idxs {
-            nodes.push(mut_node_iter.nth(id.0)?)
+    /// Checks, journals and applies `ops` as one unit
+    ///
+    /// Every op is checked against the state before the first is applied,
+    /// so callers only batch ops that don't depend on each other.
+    /// Inside a transaction the ops are held back and journaled together on commit
+    fn submit_all(&mut self, ops: Vec<Op>) -> GraphResult<()> {
+        if ops.is_empty() {
+            return Ok(());
         }
-        return Some(nodes);
+        for op in ops.iter() {
+            self.check(op)?;
+        }
+        if let Some(tx) = self.session.tx.as_mut() {
+            tx.ops.extend(ops.iter().cloned());
+        } else {
+            if self
+                .session
+                .journal
+                .as_ref()
+                .is_some_and(Journal::wants_checkpoint)
+            {
+                self.checkpoint()?;
+            }
+            if let Some(journal) = self.session.journal.as_mut() {
+                journal.append(&ops)?;
+            }
+        }
+        for op in ops {
+            self.apply(op);
+        }
+        Ok(())
     }
+    pub fn add_node(&mut self, alias: &str) -> GraphResult<&mut Self> {
+        self.submit(Op::AddNode {
+            alias: alias.to_owned(),
+        })?;
+        Ok(self)
+    }
+    /// Frees the node's slot and drops its edges, other nodes keep their ids
+    pub fn remove_node_by_id(&mut self, id: &NodeIndex) -> GraphResult<&mut Self> {
+        self.submit(Op::RemoveNode { id: *id })?;
+        Ok(self)
+    }
+    #[inline]
+    pub fn get_node_mut_by_idx(&mut self, idx: &NodeIndex) -> Option<NodeMut<'_>> {
+        self.nodes.get(*idx)?;
+        Some(NodeMut {
+            graph: self,
+            id: *idx,
+        })
+    }
+    /// Most recently added node, None if it has been removed since
     #[inline]
     pub fn get_last_node(&mut self) -> Option<&Node> {
-        self.nodes.last()
+        self.nodes.get(self.session.last_node?)
     }
     #[inline]
-    pub fn get_last_node_mut(&mut self) -> Option<&mut Node> {
-        self.nodes.last_mut()
+    pub fn get_last_node_mut(&mut self) -> Option<NodeMut<'_>> {
+        let id = self.session.last_node?;
+        self.get_node_mut_by_idx(&id)
+    }
+    /// Runs `f` on the node at `id`, label and prop changes made through [`NodeMut`] keep the indexes updated
+    pub fn mut_node<F>(&mut self, id: &NodeIndex, mut f: F) -> GraphResult<&mut Self>
+    where
+        F: for<'a, 'g> FnMut(&'a mut NodeMut<'g>) -> GraphResult<&'a mut NodeMut<'g>>,
+    {
+        let mut node = self
+            .get_node_mut_by_idx(id)
+            .ok_or(Error::NodeNotFound { id: *id })?;
+        f(&mut node)?;
+        Ok(self)
     }
-    pub fn mut_last_node<F>(&mut self, mut f: F) -> GraphResult<&mut Self>
+    pub fn mut_last_node<F>(&mut self, f: F) -> GraphResult<&mut Self>
     where
-        F: FnMut(&mut Node) -> GraphResult<&mut Node>,
+        F: for<'a, 'g> FnMut(&'a mut NodeMut<'g>) -> GraphResult<&'a mut NodeMut<'g>>,
     {
-        f(self.nodes.last_mut().ok_or("Failed getting last node")?)?;
+        let id = self.session.last_node.ok_or_else(|| Error::InvalidIndex {
+            index: "last node".to_owned(),
+            reason: "no node was added or it was removed".to_owned(),
+        })?;
+        self.mut_node(&id, f)
+    }
+    /// Runs `f` on every node with `alias`
+    pub fn mut_nodes_by_alias<F>(&mut self, alias: &str, mut f: F) -> GraphResult<&mut Self>
+    where
+        F: for<'a, 'g> FnMut(&'a mut NodeMut<'g>) -> GraphResult<&'a mut NodeMut<'g>>,
+    {
+        let ids = self
+            .get_ids_by_alias(alias)
+            .ok_or_else(|| Error::alias_not_found(alias))?
+            .clone();
+        for id in ids.iter() {
+            self.mut_node(id, &mut f)?;
+        }
+        Ok(self)
+    }
+    pub fn add_label<S: AsRef<str>>(&mut self, id: &NodeIndex, label: S) -> GraphResult<&mut Self> {
+        self.submit(Op::AddLabel {
+            id: *id,
+            label: label.as_ref().to_owned(),
+        })?;
+        Ok(self)
+    }
+    pub fn remove_label<S: AsRef<str>>(
+        &mut self,
+        id: &NodeIndex,
+        label: S,
+    ) -> GraphResult<&mut Self> {
+        self.submit(Op::RemoveLabel {
+            id: *id,
+            label: label.as_ref().to_owned(),
+        })?;
+        Ok(self)
+    }
+    pub fn add_prop<S: AsRef<str>, V: Into<PropValue>>(
+        &mut self,
+        id: &NodeIndex,
+        key: S,
+        val: V,
+    ) -> GraphResult<&mut Self> {
+        self.submit(Op::AddProp {
+            id: *id,
+            key: key.as_ref().to_owned(),
+            val: val.into(),
+        })?;
+        Ok(self)
+    }
+    pub fn remove_prop<S: AsRef<str>>(&mut self, id: &NodeIndex, key: S) -> GraphResult<&mut Self> {
+        self.submit(Op::RemoveProp {
+            id: *id,
+            key: key.as_ref().to_owned(),
+        })?;
+        Ok(self)
+    }
+    /// Starts indexing `key` so [`Graph::find_nodes_by_prop`] can look it up
+    pub fn create_prop_index(&mut self, key: &str) -> GraphResult<&mut Self> {
+        self.submit(Op::CreatePropIndex {
+            key: key.to_owned(),
+        })?;
+        Ok(self)
+    }
+    pub fn drop_prop_index(&mut self, key: &str) -> GraphResult<&mut Self> {
+        self.submit(Op::DropPropIndex {
+            key: key.to_owned(),
+        })?;
         Ok(self)
     }
     #[inline]
+    pub fn has_prop_index(&self, key: &str) -> bool {
+        self.index.has_prop_key(key)
+    }
+    pub fn find_ids_by_label(&self, label: &str) -> Vec<NodeIndex> {
+        self.index
+            .label(label)
+            .map(|ids| ids.iter().copied().collect())
+            .unwrap_or_default()
+    }
+    pub fn find_nodes_by_label(&self, label: &str) -> Vec<&Node> {
+        self.index
+            .label(label)
+            .map(|ids| ids.iter().filter_map(|id| self.nodes.get(*id)).collect())
+            .unwrap_or_default()
+    }
+    /// Only works on props indexed with [`Graph::create_prop_index`]
+    pub fn find_ids_by_prop<V: Into<PropValue>>(
+        &self,
+        key: &str,
+        val: V,
+    ) -> GraphResult<Vec<NodeIndex>> {
+        let ids = self
+            .index
+            .prop(key, &val.into())
+            .ok_or_else(|| Error::not_indexed(key))?;
+        Ok(ids
+            .map(|ids| ids.iter().copied().collect())
+            .unwrap_or_default())
+    }
+    /// Only works on props indexed with [`Graph::create_prop_index`]
+    pub fn find_nodes_by_prop<V: Into<PropValue>>(
+        &self,
+        key: &str,
+        val: V,
+    ) -> GraphResult<Vec<&Node>> {
+        Ok(self
+            .find_ids_by_prop(key, val)?
+            .iter()
+            .filter_map(|id| self.nodes.get(*id))
+            .collect())
+    }
+    #[inline]
     pub fn get_node_by_idx(&self, idx: &NodeIndex) -> Option<&Node> {
-        self.nodes.get(idx.0)
+        self.nodes.get(*idx)
+    }
+    /// Like [`Graph::get_node_by_idx`] but a removed node is a [`Error::NodeNotFound`]
+    #[inline]
+    pub fn get_node(&self, idx: &NodeIndex) -> GraphResult<&Node> {
+        self.nodes.get(*idx).ok_or(Error::NodeNotFound { id: *idx })
     }
     pub fn get_nodes_by_alias(&self, alias: &str) -> Option<Vec<&Node>> {
         let id = self.aliases.get(alias)?;
-        if id.len() == 0 {
-            return None;
+        if id.is_empty() {
+            None
         } else {
             let mut nodes = Vec::new();
             for i in id.iter() {
-                nodes.push(self.nodes.get(i.0)?) //? dangerous?
+                nodes.push(self.nodes.get(*i)?)
             }
-            return Some(nodes);
+            Some(nodes)
         }
     }
     #[inline]
+    pub fn iter_nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter::<NodeIndex>().map(|(_, node)| node)
+    }
+    #[inline]
+    pub fn iter_edges(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.iter::<EdgeIndex>().map(|(_, edge)| edge)
+    }
+    #[inline]
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+    #[inline]
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+    #[inline]
     pub fn get_alias_by_id(&self, id: &NodeIndex) -> Option<&str> {
-        self.get_node_by_idx(id)
-            .and_then(|x| Some(x.alias.as_str()))
+        self.get_node_by_idx(id).map(|x| x.alias.as_str())
     }
     #[inline]
     pub fn get_ids_by_alias(&self, alias: &str) -> Option<&Vec<NodeIndex>> {
@@ -285,9 +921,69 @@ impl Graph {
         from: NodeIndex,
         to: NodeIndex,
     ) -> GraphResult<&mut Self> {
-        self.edges.push(Edge::new(relation, from, to));
+        self.submit(Op::AddEdge {
+            relation: relation.to_owned(),
+            from,
+            to,
+        })?;
         Ok(self)
     }
+    /// Pushes the edge to the front of `from`'s outgoing and `to`'s incoming lists, both nodes must exist
+    fn insert_edge(&mut self, relation: &str, from: NodeIndex, to: NodeIndex) -> EdgeIndex {
+        let id: EdgeIndex = self.edges.next_key();
+        self.record(|g| Undo::EdgeInserted {
+            id,
+            reused: g.edges.has_free(),
+            last_edge: g.session.last_edge,
+        });
+        let mut edge = Edge::new(id, relation, from, to);
+        let source = self.nodes.get_mut(from).expect("Edge source must exist");
+        edge.next_outgoing = source.first_outgoing.replace(id);
+        let target = self.nodes.get_mut(to).expect("Edge target must exist");
+        edge.next_incoming = target.first_incoming.replace(id);
+        self.index.insert_edge(&edge);
+        self.edges.insert::<EdgeIndex>(edge);
+        self.session.last_edge = Some(id);
+        id
+    }
+    /// Takes a removed edge out of its endpoints' lists, walking them to find the predecessor
+    ///
+    /// Returns the predecessors in the outgoing and incoming lists, None where the edge was the head
+    fn unlink_edge(&mut self, edge: &Edge) -> (Option<EdgeIndex>, Option<EdgeIndex>) {
+        let mut prev_outgoing = None;
+        if let Some(source) = self.nodes.get_mut(edge.from) {
+            if source.first_outgoing == Some(edge.id) {
+                source.first_outgoing = edge.next_outgoing;
+            } else {
+                let mut cursor = source.first_outgoing;
+                while let Some(prev) = cursor.and_then(|c| self.edges.get_mut(c)) {
+                    if prev.next_outgoing == Some(edge.id) {
+                        prev.next_outgoing = edge.next_outgoing;
+                        prev_outgoing = Some(prev.id);
+                        break;
+                    }
+                    cursor = prev.next_outgoing;
+                }
+            }
+        }
+        let mut prev_incoming = None;
+        if let Some(target) = self.nodes.get_mut(edge.to) {
+            if target.first_incoming == Some(edge.id) {
+                target.first_incoming = edge.next_incoming;
+            } else {
+                let mut cursor = target.first_incoming;
+                while let Some(prev) = cursor.and_then(|c| self.edges.get_mut(c)) {
+                    if prev.next_incoming == Some(edge.id) {
+                        prev.next_incoming = edge.next_incoming;
+                        prev_incoming = Some(prev.id);
+                        break;
+                    }
+                    cursor = prev.next_incoming;
+                }
+            }
+        }
+        (prev_outgoing, prev_incoming)
+    }
     pub fn add_edges_by_aliases(
         &mut self,
         relation: &str,
@@ -296,11 +992,11 @@ impl Graph {
     ) -> GraphResult<&mut Self> {
         let fid = self
             .get_ids_by_alias(from)
-            .ok_or(format!("Failed getting ids with {}", from))?
+            .ok_or_else(|| Error::alias_not_found(from))?
             .clone();
         let tid = self
             .get_ids_by_alias(to)
-            .ok_or(format!("Failed getting ids with {}", from))?
+            .ok_or_else(|| Error::alias_not_found(to))?
             .clone();
         //need product not zip?
         // fid are all the ids that matched from string [1,2,3]
@@ -312,26 +1008,195 @@ impl Graph {
         //     self.edges
         //         .push(Edge::new(relation, f, t))
         // }
-        fid.iter().for_each(|f| {
-            tid.iter().for_each(|t| 
-                self.edges.push(Edge::new(relation, *f, *t)))
-        });
-
+        let ops = fid
+            .iter()
+            .flat_map(|f| {
+                tid.iter().map(|t| Op::AddEdge {
+                    relation: relation.to_owned(),
+                    from: *f,
+                    to: *t,
+                })
+            })
+            .collect();
+        self.submit_all(ops)?;
         Ok(self)
     }
     pub fn remove_all_edges_from(&mut self, from: &NodeIndex) -> GraphResult<&mut Self> {
-        self.edges.retain(|x| x.from.borrow() != from);
+        let ops = self
+            .outgoing_edges(from)
+            .map(|e| Op::RemoveEdge { id: e.id })
+            .collect();
+        self.submit_all(ops)?;
         Ok(self)
     }
     pub fn remove_all_edges_to(&mut self, to: &NodeIndex) -> GraphResult<&mut Self> {
-        self.edges.retain(|x| x.to.borrow() != to);
+        let ops = self
+            .incoming_edges(to)
+            .map(|e| Op::RemoveEdge { id: e.id })
+            .collect();
+        self.submit_all(ops)?;
+        Ok(self)
+    }
+    pub fn remove_edge(&mut self, id: &EdgeIndex) -> GraphResult<&mut Self> {
+        self.submit(Op::RemoveEdge { id: *id })?;
+        Ok(self)
+    }
+    #[inline]
+    pub fn get_edge_by_idx(&self, idx: &EdgeIndex) -> Option<&Edge> {
+        self.edges.get(*idx)
+    }
+    /// Like [`Graph::get_edge_by_idx`] but a removed edge is a [`Error::EdgeNotFound`]
+    #[inline]
+    pub fn get_edge(&self, idx: &EdgeIndex) -> GraphResult<&Edge> {
+        self.edges.get(*idx).ok_or(Error::EdgeNotFound { id: *idx })
+    }
+    #[inline]
+    pub fn get_edge_mut_by_idx(&mut self, idx: &EdgeIndex) -> Option<EdgeMut<'_>> {
+        self.edges.get(*idx)?;
+        Some(EdgeMut {
+            graph: self,
+            id: *idx,
+        })
+    }
+    /// Most recently added edge, None if it has been removed since
+    #[inline]
+    pub fn get_last_edge(&self) -> Option<&Edge> {
+        self.edges.get(self.session.last_edge?)
+    }
+    pub fn mut_edge<F>(&mut self, id: &EdgeIndex, mut f: F) -> GraphResult<&mut Self>
+    where
+        F: for<'a, 'g> FnMut(&'a mut EdgeMut<'g>) -> GraphResult<&'a mut EdgeMut<'g>>,
+    {
+        let mut edge = self
+            .get_edge_mut_by_idx(id)
+            .ok_or(Error::EdgeNotFound { id: *id })?;
+        f(&mut edge)?;
+        Ok(self)
+    }
+    /// Runs `f` on the most recently added edge, for `add_edge(..)?.mut_last_edge(..)` chains
+    pub fn mut_last_edge<F>(&mut self, f: F) -> GraphResult<&mut Self>
+    where
+        F: for<'a, 'g> FnMut(&'a mut EdgeMut<'g>) -> GraphResult<&'a mut EdgeMut<'g>>,
+    {
+        let id = self.session.last_edge.ok_or_else(|| Error::InvalidIndex {
+            index: "last edge".to_owned(),
+            reason: "no edge was added or it was removed".to_owned(),
+        })?;
+        self.mut_edge(&id, f)
+    }
+    pub fn add_edge_label<S: AsRef<str>>(
+        &mut self,
+        id: &EdgeIndex,
+        label: S,
+    ) -> GraphResult<&mut Self> {
+        self.submit(Op::AddEdgeLabel {
+            id: *id,
+            label: label.as_ref().to_owned(),
+        })?;
+        Ok(self)
+    }
+    pub fn remove_edge_label<S: AsRef<str>>(
+        &mut self,
+        id: &EdgeIndex,
+        label: S,
+    ) -> GraphResult<&mut Self> {
+        self.submit(Op::RemoveEdgeLabel {
+            id: *id,
+            label: label.as_ref().to_owned(),
+        })?;
         Ok(self)
     }
+    pub fn add_edge_prop<S: AsRef<str>, V: Into<PropValue>>(
+        &mut self,
+        id: &EdgeIndex,
+        key: S,
+        val: V,
+    ) -> GraphResult<&mut Self> {
+        self.submit(Op::AddEdgeProp {
+            id: *id,
+            key: key.as_ref().to_owned(),
+            val: val.into(),
+        })?;
+        Ok(self)
+    }
+    pub fn remove_edge_prop<S: AsRef<str>>(
+        &mut self,
+        id: &EdgeIndex,
+        key: S,
+    ) -> GraphResult<&mut Self> {
+        self.submit(Op::RemoveEdgeProp {
+            id: *id,
+            key: key.as_ref().to_owned(),
+        })?;
+        Ok(self)
+    }
+    /// Edges leaving `from`, most recently added first. Walks the node's own list so costs O(out degree)
+    pub fn outgoing_edges(&self, from: &NodeIndex) -> AdjacentEdges<'_> {
+        AdjacentEdges {
+            graph: self,
+            next: self.nodes.get(*from).and_then(|n| n.first_outgoing),
+            outgoing: true,
+        }
+    }
+    /// Edges arriving at `to`, most recently added first. Costs O(in degree)
+    pub fn incoming_edges(&self, to: &NodeIndex) -> AdjacentEdges<'_> {
+        AdjacentEdges {
+            graph: self,
+            next: self.nodes.get(*to).and_then(|n| n.first_incoming),
+            outgoing: false,
+        }
+    }
+    pub fn get_outgoing_edges(&self, from: &NodeIndex) -> Vec<&Edge> {
+        self.outgoing_edges(from).collect()
+    }
+    pub fn get_incoming_edges(&self, to: &NodeIndex) -> Vec<&Edge> {
+        self.incoming_edges(to).collect()
+    }
+    pub fn get_edges_between(&self, from: &NodeIndex, to: &NodeIndex) -> Vec<&Edge> {
+        self.outgoing_edges(from).filter(|e| e.to == *to).collect()
+    }
+    /// Edges with `relation` in id order, looked up in the relation index
+    pub fn edges_by_relation(&self, relation: &str) -> Vec<&Edge> {
+        self.index
+            .relation(relation)
+            .map(|ids| ids.iter().filter_map(|id| self.edges.get(*id)).collect())
+            .unwrap_or_default()
+    }
+    #[inline]
+    pub fn count_by_relation(&self, relation: &str) -> usize {
+        self.index.relation(relation).map_or(0, |ids| ids.len())
+    }
+    /// Nodes one `relation` edge away from `node`, once per edge and most recently linked first
+    ///
+    /// `Direction::Both` lists the targets of outgoing edges before the sources of incoming ones.
+    /// Fails with `NodeNotFound` if `node` isn't in the graph
+    pub fn neighbors_via(
+        &self,
+        node: &NodeIndex,
+        relation: &str,
+        direction: Direction,
+    ) -> GraphResult<Vec<&Node>> {
+        self.get_node(node)?;
+        if self.count_by_relation(relation) == 0 {
+            return Ok(Vec::new());
+        }
+        Ok(
+            GraphStore::adjacent(self, node, direction, &[relation.to_owned()])?
+                .into_iter()
+                .map(|(_, id)| {
+                    self.get_node_by_idx(&id)
+                        .expect("Edge has invalid endpoint node index")
+                })
+                .collect(),
+        )
+    }
+    #[inline]
+    pub fn edge_ids(&self) -> impl Iterator<Item = EdgeIndex> + '_ {
+        self.iter_edges().map(|e| e.id)
+    }
     pub fn get_outgoing_neighbors(&self, node: &Node) -> GraphResult<Vec<&Node>> {
         Ok(self
-            .edges
-            .iter()
-            .filter(|e| e.from == node.id)
+            .outgoing_edges(&node.id)
             .map(|e| {
                 self.get_node_by_idx(&e.to)
                     .expect("Outgoing edge has invalid target(to) node index")
@@ -340,9 +1205,7 @@ impl Graph {
     }
     pub fn get_incoming_neighbors(&self, node: &Node) -> GraphResult<Vec<&Node>> {
         Ok(self
-            .edges
-            .iter()
-            .filter(|e| e.to == node.id)
+            .incoming_edges(&node.id)
             .map(|e| {
                 self.get_node_by_idx(&e.from)
                     .expect("Incoming edge has invalid source(from) node index")
@@ -351,43 +1214,136 @@ impl Graph {
     }
 }
 
+/// Walks one node's outgoing or incoming edge list, see [`Graph::outgoing_edges`]
+pub struct AdjacentEdges<'g> {
+    graph: &'g Graph,
+    next: Option<EdgeIndex>,
+    outgoing: bool,
+}
+
+impl<'g> Iterator for AdjacentEdges<'g> {
+    type Item = &'g Edge;
+    fn next(&mut self) -> Option<&'g Edge> {
+        let edge = self.graph.edges.get(self.next?)?;
+        self.next = if self.outgoing {
+            edge.next_outgoing
+        } else {
+            edge.next_incoming
+        };
+        Some(edge)
+    }
+}
+
+/// Mutable handle to a node inside a [`Graph`]
+///
+/// Label and prop changes go through the graph so its indexes stay correct,
+/// reads are available through `Deref<Target = Node>`
+pub struct NodeMut<'g> {
+    graph: &'g mut Graph,
+    id: NodeIndex,
+}
+
+impl NodeMut<'_> {
+    pub fn add_label<S: AsRef<str>>(&mut self, label: S) -> GraphResult<&mut Self> {
+        self.graph.add_label(&self.id, label)?;
+        Ok(self)
+    }
+    pub fn remove_label<S: AsRef<str>>(&mut self, label: S) -> GraphResult<&mut Self> {
+        self.graph.remove_label(&self.id, label)?;
+        Ok(self)
+    }
+    pub fn add_prop<S: AsRef<str>, V: Into<PropValue>>(
+        &mut self,
+        key: S,
+        val: V,
+    ) -> GraphResult<&mut Self> {
+        self.graph.add_prop(&self.id, key, val)?;
+        Ok(self)
+    }
+    pub fn remove_prop<S: AsRef<str>>(&mut self, key: S) -> GraphResult<&mut Self> {
+        self.graph.remove_prop(&self.id, key)?;
+        Ok(self)
+    }
+}
+
+impl Deref for NodeMut<'_> {
+    type Target = Node;
+    fn deref(&self) -> &Node {
+        self.graph
+            .get_node_by_idx(&self.id)
+            .expect("NodeMut points to a removed node")
+    }
+}
+
+/// Mutable handle to an edge inside a [`Graph`], see [`NodeMut`]
+pub struct EdgeMut<'g> {
+    graph: &'g mut Graph,
+    id: EdgeIndex,
+}
+
+impl EdgeMut<'_> {
+    pub fn add_label<S: AsRef<str>>(&mut self, label: S) -> GraphResult<&mut Self> {
+        self.graph.add_edge_label(&self.id, label)?;
+        Ok(self)
+    }
+    pub fn remove_label<S: AsRef<str>>(&mut self, label: S) -> GraphResult<&mut Self> {
+        self.graph.remove_edge_label(&self.id, label)?;
+        Ok(self)
+    }
+    pub fn add_prop<S: AsRef<str>, V: Into<PropValue>>(
+        &mut self,
+        key: S,
+        val: V,
+    ) -> GraphResult<&mut Self> {
+        self.graph.add_edge_prop(&self.id, key, val)?;
+        Ok(self)
+    }
+    pub fn remove_prop<S: AsRef<str>>(&mut self, key: S) -> GraphResult<&mut Self> {
+        self.graph.remove_edge_prop(&self.id, key)?;
+        Ok(self)
+    }
+}
+
+impl Deref for EdgeMut<'_> {
+    type Target = Edge;
+    fn deref(&self) -> &Edge {
+        self.graph
+            .get_edge_by_idx(&self.id)
+            .expect("EdgeMut points to a removed edge")
+    }
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl fmt::Display for Graph {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Nodes:\n")?;
-        for n in self.nodes.iter() {
+        writeln!(f, "Nodes:")?;
+        for n in self.iter_nodes() {
             write!(f, "{}", n)?;
         }
-        write!(f, "Edges:\n")?;
-        for e in self.edges.iter() {
+        writeln!(f, "Edges:")?;
+        for e in self.iter_edges() {
             write!(
                 f,
-                "{:>10}({:02}) {:-^16}> ({:02}){:<10}\n",
+                "{:>10}({:02}) {:-^16}> ({:02}){:<10}",
                 self.get_alias_by_id(&e.from).expect("Failed getting alias"),
                 e.from,
                 e.relation,
                 e.to,
                 self.get_alias_by_id(&e.to).expect("Failed getting alias"),
             )?;
+            if !e.labels.is_empty() {
+                write!(f, " labels: {:?}", e.labels)?;
+            }
+            for (k, v) in e.props.iter() {
+                write!(f, " {}:{}", k, v)?;
+            }
+            writeln!(f)?;
         }
         Ok(())
     }
 }
-
-#[derive(Debug)]
-pub enum Error {
-    Text(String),
-}
-
-impl From<String> for Error {
-    fn from(value: String) -> Self {
-        Error::Text(value)
-    }
-}
-
-impl From<&str> for Error {
-    fn from(value: &str) -> Self {
-        Error::Text(value.to_owned())
-    }
-}
-
-pub type GraphResult<T> = Result<T, Error>;
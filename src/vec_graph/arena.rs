@@ -0,0 +1,161 @@
+/// Handle into an [`Arena`]: slot position plus the generation the slot had when it was handed out
+pub(crate) trait ArenaKey: Copy {
+    fn new(index: usize, generation: u32) -> Self;
+    fn index(&self) -> usize;
+    fn generation(&self) -> u32;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Slot map: removing leaves a hole that is reused with a bumped generation,
+/// so handles never move and stale handles stop resolving instead of pointing at a newer value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    //freed slot indices, reused last in first out
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Arena {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> Arena<T> {
+    /// Key the next `insert` will hand out
+    pub(crate) fn next_key<K: ArenaKey>(&self) -> K {
+        match self.free.last() {
+            Some(&index) => K::new(index, self.slots[index].generation),
+            None => K::new(self.slots.len(), 0),
+        }
+    }
+    pub(crate) fn insert<K: ArenaKey>(&mut self, value: T) -> K {
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.value = Some(value);
+                K::new(index, slot.generation)
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                K::new(self.slots.len() - 1, 0)
+            }
+        }
+    }
+    pub(crate) fn remove<K: ArenaKey>(&mut self, key: K) -> Option<T> {
+        let slot = self.slots.get_mut(key.index())?;
+        if slot.generation != key.generation() {
+            return None;
+        }
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(key.index());
+        self.len -= 1;
+        Some(value)
+    }
+    /// Whether the next `insert` reuses a freed slot
+    #[inline]
+    pub(crate) fn has_free(&self) -> bool {
+        !self.free.is_empty()
+    }
+    /// Reverts the latest `insert` that returned `key`, `reused` as [`Arena::has_free`] said before it
+    ///
+    /// Undoing inserts and removes in reverse order restores slots, generations and the free list exactly
+    pub(crate) fn uninsert<K: ArenaKey>(&mut self, key: K, reused: bool) -> T {
+        let value = self.slots[key.index()]
+            .value
+            .take()
+            .expect("Uninserted slot must be occupied");
+        if reused {
+            self.free.push(key.index());
+        } else {
+            debug_assert_eq!(key.index() + 1, self.slots.len());
+            self.slots.pop();
+        }
+        self.len -= 1;
+        value
+    }
+    /// Reverts the latest `remove` of `key`, see [`Arena::uninsert`]
+    pub(crate) fn unremove<K: ArenaKey>(&mut self, key: K, value: T) {
+        let freed = self.free.pop();
+        debug_assert_eq!(freed, Some(key.index()));
+        let slot = &mut self.slots[key.index()];
+        slot.generation = key.generation();
+        slot.value = Some(value);
+        self.len += 1;
+    }
+    #[inline]
+    pub(crate) fn get<K: ArenaKey>(&self, key: K) -> Option<&T> {
+        self.slots
+            .get(key.index())
+            .filter(|slot| slot.generation == key.generation())?
+            .value
+            .as_ref()
+    }
+    #[inline]
+    pub(crate) fn get_mut<K: ArenaKey>(&mut self, key: K) -> Option<&mut T> {
+        self.slots
+            .get_mut(key.index())
+            .filter(|slot| slot.generation == key.generation())?
+            .value
+            .as_mut()
+    }
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+    /// Live values with their keys, in slot order
+    pub(crate) fn iter<K: ArenaKey>(&self) -> impl Iterator<Item = (K, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.value
+                .as_ref()
+                .map(|value| (K::new(index, slot.generation), value))
+        })
+    }
+    /// Every slot as (generation, value) plus the free list, what a snapshot needs to restore the exact same keys
+    pub(crate) fn raw_parts(&self) -> (impl Iterator<Item = (u32, Option<&T>)>, &[usize]) {
+        (
+            self.slots
+                .iter()
+                .map(|slot| (slot.generation, slot.value.as_ref())),
+            &self.free,
+        )
+    }
+    /// Inverse of [`Arena::raw_parts`], None if `free` doesn't list exactly the empty slots
+    pub(crate) fn from_raw_parts(slots: Vec<(u32, Option<T>)>, free: Vec<usize>) -> Option<Self> {
+        let slots: Vec<Slot<T>> = slots
+            .into_iter()
+            .map(|(generation, value)| Slot { generation, value })
+            .collect();
+        let empty = slots.iter().filter(|slot| slot.value.is_none()).count();
+        let mut seen = vec![false; slots.len()];
+        for &index in free.iter() {
+            match slots.get(index) {
+                Some(slot) if slot.value.is_none() && !seen[index] => seen[index] = true,
+                _ => return None,
+            }
+        }
+        if free.len() != empty {
+            return None;
+        }
+        Some(Arena {
+            len: slots.len() - empty,
+            slots,
+            free,
+        })
+    }
+}
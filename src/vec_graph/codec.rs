@@ -0,0 +1,279 @@
+//! Little endian binary encoding shared by the write-ahead log and snapshots
+
+use std::collections::BTreeMap;
+use std::io::{self, ErrorKind};
+
+use super::arena::ArenaKey;
+use super::{Error, GraphResult, PropValue};
+
+#[derive(Debug, Default)]
+pub(crate) struct Writer {
+    pub(crate) buf: Vec<u8>,
+    //first thing the format couldn't hold, `Writer::finish` turns it into the error
+    failed: Option<String>,
+}
+
+impl Writer {
+    /// The written bytes, an error if a length or a value didn't fit the format
+    pub(crate) fn finish(self) -> io::Result<Vec<u8>> {
+        match self.failed {
+            None => Ok(self.buf),
+            Some(reason) => Err(io::Error::new(ErrorKind::InvalidInput, reason)),
+        }
+    }
+    fn fail(&mut self, reason: impl FnOnce() -> String) {
+        if self.failed.is_none() {
+            self.failed = Some(reason());
+        }
+    }
+    //lengths are u32 on disk, a longer one is written as 0 and fails the writer
+    fn length(&mut self, len: usize, unit: &str) {
+        let written = u32::try_from(len).unwrap_or_else(|_| {
+            self.fail(|| {
+                format!(
+                    "{} {} is over the {} the format can hold",
+                    len,
+                    unit,
+                    u32::MAX
+                )
+            });
+            0
+        });
+        self.u32(written);
+    }
+    #[inline]
+    pub(crate) fn u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+    #[inline]
+    pub(crate) fn u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+    #[inline]
+    pub(crate) fn u64(&mut self, v: u64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+    #[inline]
+    pub(crate) fn i64(&mut self, v: i64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+    #[inline]
+    pub(crate) fn f64(&mut self, v: f64) {
+        self.buf.extend_from_slice(&v.to_bits().to_le_bytes());
+    }
+    pub(crate) fn bytes(&mut self, v: &[u8]) {
+        self.length(v.len(), "bytes");
+        self.buf.extend_from_slice(v);
+    }
+    #[inline]
+    pub(crate) fn str(&mut self, v: &str) {
+        self.bytes(v.as_bytes());
+    }
+    pub(crate) fn key<K: ArenaKey>(&mut self, k: K) {
+        self.u64(k.index() as u64);
+        self.u32(k.generation());
+    }
+    pub(crate) fn opt_key<K: ArenaKey>(&mut self, k: Option<K>) {
+        match k {
+            None => self.u8(0),
+            Some(k) => {
+                self.u8(1);
+                self.key(k);
+            }
+        }
+    }
+    pub(crate) fn value(&mut self, v: &PropValue) {
+        self.value_at(v, 0);
+    }
+    //`depth` lists and maps around `v`, the reader refuses more than `PropValue::MAX_DEPTH`
+    fn value_at(&mut self, v: &PropValue, depth: usize) {
+        if matches!(v, PropValue::List(_) | PropValue::Map(_)) && depth >= PropValue::MAX_DEPTH {
+            self.fail(|| format!("value nested deeper than {} levels", PropValue::MAX_DEPTH));
+            return self.u8(0);
+        }
+        match v {
+            PropValue::Null => self.u8(0),
+            PropValue::Bool(b) => {
+                self.u8(1);
+                self.u8(*b as u8);
+            }
+            PropValue::Int(i) => {
+                self.u8(2);
+                self.i64(*i);
+            }
+            PropValue::Float(x) => {
+                self.u8(3);
+                self.f64(*x);
+            }
+            PropValue::Str(s) => {
+                self.u8(4);
+                self.str(s);
+            }
+            PropValue::Bytes(b) => {
+                self.u8(5);
+                self.bytes(b);
+            }
+            PropValue::List(l) => {
+                self.u8(6);
+                self.length(l.len(), "items");
+                l.iter().for_each(|v| self.value_at(v, depth + 1));
+            }
+            PropValue::Map(m) => {
+                self.u8(7);
+                self.length(m.len(), "entries");
+                for (k, v) in m.iter() {
+                    self.str(k);
+                    self.value_at(v, depth + 1);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Reader { buf, pos: 0 }
+    }
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+    pub(crate) fn take(&mut self, n: usize) -> GraphResult<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.buf.len())
+            .ok_or_else(|| {
+                Error::corrupt(format!("Unexpected end of data at byte {}", self.pos))
+            })?;
+        let out = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+    #[inline]
+    pub(crate) fn u8(&mut self) -> GraphResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+    #[inline]
+    pub(crate) fn u32(&mut self) -> GraphResult<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    #[inline]
+    pub(crate) fn u64(&mut self) -> GraphResult<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+    #[inline]
+    pub(crate) fn i64(&mut self) -> GraphResult<i64> {
+        Ok(i64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+    #[inline]
+    pub(crate) fn f64(&mut self) -> GraphResult<f64> {
+        Ok(f64::from_bits(self.u64()?))
+    }
+    /// Element count written with `Writer::u64`, rejected if the remaining bytes can't hold that many elements
+    pub(crate) fn count(&mut self) -> GraphResult<usize> {
+        let count = self.u64()?;
+        self.bounded(count)
+    }
+    /// Like [`Reader::count`] for counts written with `Writer::u32`
+    pub(crate) fn count32(&mut self) -> GraphResult<usize> {
+        let count = self.u32()? as u64;
+        self.bounded(count)
+    }
+    //every element takes at least a byte, so a count past the end is corrupt and must not drive an allocation
+    fn bounded(&self, count: u64) -> GraphResult<usize> {
+        if count > (self.buf.len() - self.pos) as u64 {
+            return Err(Error::corrupt(format!(
+                "Count {} at byte {} is larger than the remaining data",
+                count, self.pos
+            )));
+        }
+        Ok(count as usize)
+    }
+    pub(crate) fn bytes(&mut self) -> GraphResult<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+    pub(crate) fn str(&mut self) -> GraphResult<String> {
+        let at = self.pos;
+        String::from_utf8(self.bytes()?.to_vec())
+            .map_err(|_| Error::corrupt(format!("Invalid utf-8 string at byte {}", at)))
+    }
+    pub(crate) fn key<K: ArenaKey>(&mut self) -> GraphResult<K> {
+        let index = self.u64()? as usize;
+        Ok(K::new(index, self.u32()?))
+    }
+    pub(crate) fn opt_key<K: ArenaKey>(&mut self) -> GraphResult<Option<K>> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.key()?)),
+            tag => Err(Error::corrupt(format!("Invalid option tag {}", tag))),
+        }
+    }
+    pub(crate) fn value(&mut self) -> GraphResult<PropValue> {
+        self.value_at(0)
+    }
+    //`depth` lists and maps around the value, recursing without a limit would let a crafted file overflow the stack
+    fn value_at(&mut self, depth: usize) -> GraphResult<PropValue> {
+        let tag = self.u8()?;
+        if matches!(tag, 6 | 7) && depth >= PropValue::MAX_DEPTH {
+            return Err(Error::corrupt("value nested too deep"));
+        }
+        Ok(match tag {
+            0 => PropValue::Null,
+            1 => PropValue::Bool(self.u8()? != 0),
+            2 => PropValue::Int(self.i64()?),
+            3 => PropValue::Float(self.f64()?),
+            4 => PropValue::Str(self.str()?),
+            5 => PropValue::Bytes(self.bytes()?.to_vec()),
+            6 => {
+                let len = self.count32()?;
+                (0..len)
+                    .map(|_| self.value_at(depth + 1))
+                    .collect::<GraphResult<Vec<_>>>()?
+                    .into()
+            }
+            7 => {
+                let len = self.count32()?;
+                let mut map = BTreeMap::new();
+                for _ in 0..len {
+                    let key = self.str()?;
+                    map.insert(key, self.value_at(depth + 1)?);
+                }
+                map.into()
+            }
+            tag => return Err(Error::corrupt(format!("Invalid value tag {}", tag))),
+        })
+    }
+}
+
+/// CRC-32 (IEEE), guards log records and snapshots against torn writes and bit rot
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut c = i as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 {
+                    0xEDB8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[i] = c;
+            i += 1;
+        }
+        table
+    };
+    !data.iter().fold(!0u32, |crc, &b| {
+        TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
@@ -0,0 +1,125 @@
+use core::fmt;
+use std::io;
+
+use super::{EdgeIndex, NodeIndex};
+
+/// Everything that can go wrong in `vec_graph` and the query layer
+///
+/// Variants carry the context needed to act on them, `Display` turns them into a readable message
+#[derive(Debug)]
+pub enum Error {
+    /// Handle to a node that doesn't exist or was removed
+    NodeNotFound { id: NodeIndex },
+    /// Handle to an edge that doesn't exist or was removed
+    EdgeNotFound { id: EdgeIndex },
+    /// No node has `alias`
+    AliasNotFound { alias: String },
+    /// `index` doesn't point anywhere usable: a prop lookup on a key without a prop index,
+    /// `mut_last_node` with no last node, a savepoint outside the running transaction
+    InvalidIndex { index: String, reason: String },
+    /// The change would break `constraint`, one of the graph's schema rules
+    ConstraintViolation { constraint: String, detail: String },
+    /// `operation` can't run in the graph's current state, like a checkpoint inside a transaction
+    /// or on a graph without a file
+    InvalidOperation { operation: String, reason: String },
+    /// Reading or writing the files behind a graph failed while doing `context`
+    Io { context: String, source: io::Error },
+    /// Query text that doesn't parse, `pos` is the byte offset when the problem has one
+    Parse { pos: Option<usize>, message: String },
+    /// A snapshot or log record failed its checksum or couldn't be decoded
+    Corrupt { context: String },
+    /// Snapshot written by a newer format version than this build reads
+    UnsupportedVersion { found: u32, supported: u32 },
+    /// No edges the search may follow lead from `from` to `to`
+    NoPath { from: NodeIndex, to: NodeIndex },
+    /// Weighted search got a missing, non numeric, negative or NaN weight for `edge`
+    InvalidWeight { edge: EdgeIndex, reason: String },
+}
+
+impl Error {
+    /// Wraps io errors with what was being done, for `map_err`
+    pub(crate) fn io<S: Into<String>>(context: S) -> impl FnOnce(io::Error) -> Error {
+        move |source| Error::Io {
+            context: context.into(),
+            source,
+        }
+    }
+    #[inline]
+    pub(crate) fn alias_not_found(alias: &str) -> Error {
+        Error::AliasNotFound {
+            alias: alias.to_owned(),
+        }
+    }
+    #[inline]
+    pub(crate) fn not_indexed(key: &str) -> Error {
+        Error::InvalidIndex {
+            index: format!("prop index `{}`", key),
+            reason: "prop is not indexed".to_owned(),
+        }
+    }
+    #[inline]
+    pub(crate) fn corrupt<S: Into<String>>(context: S) -> Error {
+        Error::Corrupt {
+            context: context.into(),
+        }
+    }
+    /// Query that parses but can't run, like an undefined variable
+    #[inline]
+    pub(crate) fn query<S: Into<String>>(message: S) -> Error {
+        Error::Parse {
+            pos: None,
+            message: message.into(),
+        }
+    }
+    #[inline]
+    pub(crate) fn parse<S: Into<String>>(pos: usize, message: S) -> Error {
+        Error::Parse {
+            pos: Some(pos),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NodeNotFound { id } => write!(f, "Node {} not found", id),
+            Error::EdgeNotFound { id } => write!(f, "Edge {} not found", id),
+            Error::AliasNotFound { alias } => write!(f, "No node with alias `{}`", alias),
+            Error::InvalidIndex { index, reason } => write!(f, "Invalid {}: {}", index, reason),
+            Error::ConstraintViolation { constraint, detail } => {
+                write!(f, "Constraint {} violated: {}", constraint, detail)
+            }
+            Error::InvalidOperation { operation, reason } => {
+                write!(f, "Can't {}: {}", operation, reason)
+            }
+            Error::Io { context, source } => write!(f, "Failed {}: {}", context, source),
+            Error::Parse {
+                pos: Some(pos),
+                message,
+            } => write!(f, "Parse error at {}: {}", pos, message),
+            Error::Parse { pos: None, message } => write!(f, "Query error: {}", message),
+            Error::Corrupt { context } => write!(f, "Corrupt data: {}", context),
+            Error::UnsupportedVersion { found, supported } => write!(
+                f,
+                "Snapshot format version {} is newer than the supported {}",
+                found, supported
+            ),
+            Error::NoPath { from, to } => write!(f, "No path from {} to {}", from, to),
+            Error::InvalidWeight { edge, reason } => {
+                write!(f, "Invalid weight on edge {}: {}", edge, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+pub type GraphResult<T> = Result<T, Error>;
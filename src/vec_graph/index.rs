@@ -0,0 +1,125 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::{Edge, EdgeIndex, Node, NodeIndex, PropValue};
+
+/// Secondary indexes kept in sync by every `Graph` mutation
+///
+/// Labels and edge relations are always indexed, props only for keys registered with
+/// `Graph::create_prop_index`. Prop values are stored under [`index_key`] so a lookup for `30` also finds `30.0`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Indexes {
+    labels: HashMap<String, BTreeSet<NodeIndex>>,
+    props: HashMap<String, HashMap<PropValue, BTreeSet<NodeIndex>>>,
+    relations: HashMap<String, BTreeSet<EdgeIndex>>,
+}
+
+impl Indexes {
+    #[inline]
+    pub(crate) fn label(&self, label: &str) -> Option<&BTreeSet<NodeIndex>> {
+        self.labels.get(label)
+    }
+    #[inline]
+    pub(crate) fn has_prop_key(&self, key: &str) -> bool {
+        self.props.contains_key(key)
+    }
+    #[inline]
+    pub(crate) fn prop_keys(&self) -> impl Iterator<Item = &String> {
+        self.props.keys()
+    }
+    #[inline]
+    pub(crate) fn relation(&self, relation: &str) -> Option<&BTreeSet<EdgeIndex>> {
+        self.relations.get(relation)
+    }
+    /// None if `key` isn't indexed, Some(None) if it is but no node has `val`
+    pub(crate) fn prop(&self, key: &str, val: &PropValue) -> Option<Option<&BTreeSet<NodeIndex>>> {
+        self.props.get(key).map(|vals| vals.get(&index_key(val)))
+    }
+
+    pub(crate) fn insert_label(&mut self, label: &str, id: NodeIndex) {
+        self.labels.entry(label.to_owned()).or_default().insert(id);
+    }
+    pub(crate) fn remove_label(&mut self, label: &str, id: &NodeIndex) {
+        if let Some(set) = self.labels.get_mut(label) {
+            set.remove(id);
+            if set.is_empty() {
+                self.labels.remove(label);
+            }
+        }
+    }
+    pub(crate) fn insert_prop(&mut self, key: &str, val: &PropValue, id: NodeIndex) {
+        if let Some(vals) = self.props.get_mut(key) {
+            vals.entry(index_key(val)).or_default().insert(id);
+        }
+    }
+    pub(crate) fn remove_prop(&mut self, key: &str, val: &PropValue, id: &NodeIndex) {
+        if let Some(vals) = self.props.get_mut(key) {
+            let val = index_key(val);
+            if let Some(set) = vals.get_mut(&val) {
+                set.remove(id);
+                if set.is_empty() {
+                    vals.remove(&val);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn insert_node(&mut self, node: &Node) {
+        for label in node.labels() {
+            self.insert_label(label, node.id);
+        }
+        for (key, val) in node.props() {
+            self.insert_prop(key, val, node.id);
+        }
+    }
+    pub(crate) fn remove_node(&mut self, node: &Node) {
+        for label in node.labels() {
+            self.remove_label(label, &node.id);
+        }
+        for (key, val) in node.props() {
+            self.remove_prop(key, val, &node.id);
+        }
+    }
+
+    pub(crate) fn insert_edge(&mut self, edge: &Edge) {
+        self.relations
+            .entry(edge.relation.clone())
+            .or_default()
+            .insert(edge.id);
+    }
+    pub(crate) fn remove_edge(&mut self, edge: &Edge) {
+        if let Some(set) = self.relations.get_mut(&edge.relation) {
+            set.remove(&edge.id);
+            if set.is_empty() {
+                self.relations.remove(&edge.relation);
+            }
+        }
+    }
+
+    /// Starts indexing `key`, filled from `nodes` since existing props aren't tracked yet
+    pub(crate) fn create_prop_index<'a, I>(&mut self, key: &str, nodes: I)
+    where
+        I: Iterator<Item = &'a Node>,
+    {
+        let mut vals: HashMap<PropValue, BTreeSet<NodeIndex>> = HashMap::new();
+        for node in nodes {
+            if let Some(val) = node.get_prop(key) {
+                vals.entry(index_key(val)).or_default().insert(node.id);
+            }
+        }
+        self.props.insert(key.to_owned(), vals);
+    }
+    #[inline]
+    pub(crate) fn drop_prop_index(&mut self, key: &str) -> bool {
+        self.props.remove(key).is_some()
+    }
+}
+
+/// Integral floats are indexed as ints, matching the `1 = 1.0` equality queries use
+pub(crate) fn index_key(val: &PropValue) -> PropValue {
+    match val {
+        PropValue::Float(x) if x.fract() == 0.0 && x.abs() < i64::MAX as f64 => {
+            PropValue::Int(*x as i64)
+        }
+        other => other.clone(),
+    }
+}
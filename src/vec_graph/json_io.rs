@@ -0,0 +1,630 @@
+//! Lossless JSON form of a whole `Graph`, as one document or as JSON Lines, for diffs and moving graphs around
+//!
+//! ```text
+//! {
+//!   "format": "graph_db",
+//!   "version": 1,
+//!   "prop_indexes": ["tur"],
+//!   "constraints": [{"kind":"unique_alias","label":"sehir"}],
+//!   "free_nodes": ["2v1"],
+//!   "free_edges": [],
+//!   "last_node": "1",
+//!   "last_edge": "0",
+//!   "nodes": [
+//!     {"id":"0","alias":"sisli","labels":["sehir"],"props":{"tur":"ilce"},"out":["0"],"in":[]},
+//!     {"id":"1","alias":"merkez","labels":[],"props":{},"out":[],"in":["0"]}
+//!   ],
+//!   "edges": [
+//!     {"id":"0","relation":"includes","from":"0","to":"1","labels":[],"props":{"km":2.5}}
+//!   ],
+//!   "aliases": {
+//!     "merkez": ["1"],
+//!     "sisli": ["0"]
+//!   }
+//! }
+//! ```
+//!
+//! Ids are written the way `NodeIndex`/`EdgeIndex` display them and loading hands every node and edge
+//! its old id back. `free_nodes`/`free_edges` are the removed slots in reuse order with the id each
+//! gives out next, `out`/`in` are a node's outgoing and incoming edges in iteration order.
+//! Props use the lossless value encoding of [`json`](crate::json) sorted by key, so equal graphs give equal text.
+//! Constraints are objects with a `kind` of `required_prop`, `unique_prop` (both with `label` and `key`),
+//! `unique_alias` (`label`) or `relation` (`relation`, `from` and `to`).
+//!
+//! JSON Lines starts with the header fields on a `{"type":"graph",...}` line, followed by one
+//! `{"type":"node",...}` or `{"type":"edge",...}` line per entry and `{"type":"alias","alias":"sisli","nodes":["0"]}`
+//! per alias. Every field is required, files that don't add up are an [`Error::Corrupt`]
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use super::{
+    snapshot::{validate, write_atomic},
+    AliasMap, Arena, ArenaKey, Constraint, Edge, EdgeIndex, Error, Graph, GraphResult, Indexes,
+    Node, NodeIndex, PropValue, Session, Uniques,
+};
+use crate::json::{Json, MAX_DEPTH};
+
+const FORMAT: &str = "graph_db";
+const VERSION: u32 = 1;
+
+//props sit four levels into a document, each list or map of a value takes at most two levels
+//(maps wrapped in `$map`) and a tagged leaf one more, so every value the graph accepts parses again
+const _: () = assert!(5 + 2 * PropValue::MAX_DEPTH <= MAX_DEPTH);
+
+impl Graph {
+    /// Writes the graph as one JSON document, an entry per line
+    pub fn write_json<W: Write>(&self, mut w: W) -> GraphResult<()> {
+        write_document(self, &mut w).map_err(Error::io("writing graph JSON"))
+    }
+    /// Reads a graph written by [`Graph::write_json`]
+    pub fn read_json<R: Read>(mut reader: R) -> GraphResult<Graph> {
+        let mut text = String::new();
+        reader
+            .read_to_string(&mut text)
+            .map_err(Error::io("reading graph JSON"))?;
+        let doc = Json::parse(&text)?;
+        let mut loader = Loader::default();
+        loader.header(&doc)?;
+        for node in array(&doc, "nodes")? {
+            loader.node(node)?;
+        }
+        for edge in array(&doc, "edges")? {
+            loader.edge(edge)?;
+        }
+        let aliases = field(&doc, "aliases")?
+            .as_object()
+            .ok_or_else(|| invalid("aliases"))?;
+        for (alias, ids) in aliases {
+            loader.alias(alias, ids)?;
+        }
+        loader.finish()
+    }
+    /// Writes the graph as JSON Lines, one line at a time
+    pub fn write_json_lines<W: Write>(&self, mut w: W) -> GraphResult<()> {
+        write_lines(self, &mut w).map_err(Error::io("writing graph JSON lines"))
+    }
+    /// Reads a graph written by [`Graph::write_json_lines`] line by line
+    ///
+    /// Syntax errors are [`Error::Parse`]s at their byte offset in the input, other problems name the line
+    pub fn read_json_lines<R: BufRead>(mut reader: R) -> GraphResult<Graph> {
+        let mut loader = Loader::default();
+        let (mut line, mut number, mut offset) = (String::new(), 0, 0);
+        loop {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .map_err(Error::io("reading graph JSON lines"))?;
+            if read == 0 {
+                break;
+            }
+            let start = offset;
+            (number, offset) = (number + 1, offset + read);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = Json::parse(&line).map_err(|e| match e {
+                Error::Parse {
+                    pos: Some(pos),
+                    message,
+                } => Error::parse(start + pos, message),
+                e => e,
+            })?;
+            loader.line(&entry).map_err(|e| match e {
+                Error::Corrupt { context } => {
+                    Error::corrupt(format!("line {}: {}", number, context))
+                }
+                e => e,
+            })?;
+        }
+        if !loader.started {
+            return Err(Error::corrupt("No graph line in JSON lines"));
+        }
+        loader.finish()
+    }
+    /// [`Graph::write_json`] to `path`, replacing the file atomically
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> GraphResult<()> {
+        let mut buf = Vec::new();
+        self.write_json(&mut buf)?;
+        write_atomic(path.as_ref(), &buf)
+    }
+    /// [`Graph::read_json`] from `path`
+    pub fn load_json<P: AsRef<Path>>(path: P) -> GraphResult<Graph> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(Error::io(format!("opening {}", path.display())))?;
+        Graph::read_json(file)
+    }
+    /// [`Graph::write_json_lines`] to `path`, replacing the file atomically
+    pub fn save_json_lines<P: AsRef<Path>>(&self, path: P) -> GraphResult<()> {
+        let mut buf = Vec::new();
+        self.write_json_lines(&mut buf)?;
+        write_atomic(path.as_ref(), &buf)
+    }
+    /// [`Graph::read_json_lines`] from `path`
+    pub fn load_json_lines<P: AsRef<Path>>(path: P) -> GraphResult<Graph> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(Error::io(format!("opening {}", path.display())))?;
+        Graph::read_json_lines(BufReader::new(file))
+    }
+}
+
+fn write_document<W: Write>(graph: &Graph, w: &mut W) -> io::Result<()> {
+    check_depth(graph)?;
+    writeln!(w, "{{")?;
+    for (key, value) in header(graph) {
+        writeln!(w, "  {}: {},", Json::Str(key.to_owned()), value)?;
+    }
+    let nodes: Vec<_> = graph.iter_nodes().map(|n| node_fields(graph, n)).collect();
+    let edges: Vec<_> = graph.iter_edges().map(edge_fields).collect();
+    for (key, entries) in [("nodes", nodes), ("edges", edges)] {
+        write!(w, "  \"{}\": [", key)?;
+        for (i, fields) in entries.iter().enumerate() {
+            write!(w, "{}\n    ", if i > 0 { "," } else { "" })?;
+            write_fields(w, fields)?;
+        }
+        writeln!(w, "{}],", if entries.is_empty() { "" } else { "\n  " })?;
+    }
+    write!(w, "  \"aliases\": {{")?;
+    let aliases = sorted_aliases(graph);
+    for (i, (alias, ids)) in aliases.iter().enumerate() {
+        let comma = if i > 0 { "," } else { "" };
+        write!(
+            w,
+            "{}\n    {}: {}",
+            comma,
+            Json::Str(alias.to_string()),
+            id_list(ids.iter())
+        )?;
+    }
+    writeln!(w, "{}}}", if aliases.is_empty() { "" } else { "\n  " })?;
+    writeln!(w, "}}")
+}
+
+fn write_lines<W: Write>(graph: &Graph, w: &mut W) -> io::Result<()> {
+    check_depth(graph)?;
+    let mut line = |kind: &str, mut fields: Vec<(&str, Json)>| {
+        fields.insert(0, ("type", Json::Str(kind.to_owned())));
+        write_fields(w, &fields)?;
+        writeln!(w)
+    };
+    line("graph", header(graph))?;
+    for node in graph.iter_nodes() {
+        line("node", node_fields(graph, node))?;
+    }
+    for edge in graph.iter_edges() {
+        line("edge", edge_fields(edge))?;
+    }
+    for (alias, ids) in sorted_aliases(graph) {
+        line(
+            "alias",
+            vec![
+                ("alias", Json::Str(alias.to_owned())),
+                ("nodes", id_list(ids.iter())),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Refuses values nested deeper than [`read_props`] accepts, before anything is written
+fn check_depth(graph: &Graph) -> io::Result<()> {
+    let props = graph
+        .iter_nodes()
+        .flat_map(|n| n.props.iter())
+        .chain(graph.iter_edges().flat_map(|e| e.props.iter()));
+    for (key, val) in props {
+        if val.nested_deeper_than(PropValue::MAX_DEPTH) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "prop `{}` nests deeper than {} levels",
+                    key,
+                    PropValue::MAX_DEPTH
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `{"key":value,...}` keeping the order of `fields`, where `Json` would sort them
+fn write_fields<W: Write>(w: &mut W, fields: &[(&str, Json)]) -> io::Result<()> {
+    write!(w, "{{")?;
+    for (i, (key, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(w, ",")?;
+        }
+        write!(w, "{}:{}", Json::Str((*key).to_owned()), value)?;
+    }
+    write!(w, "}}")
+}
+
+fn header(graph: &Graph) -> Vec<(&'static str, Json)> {
+    let mut keys: Vec<_> = graph.index.prop_keys().collect();
+    keys.sort();
+    vec![
+        ("format", Json::Str(FORMAT.to_owned())),
+        ("version", Json::Int(VERSION as i64)),
+        ("prop_indexes", strings(keys)),
+        (
+            "constraints",
+            Json::Array(graph.constraints.iter().map(constraint).collect()),
+        ),
+        ("free_nodes", free_list::<_, NodeIndex>(&graph.nodes)),
+        ("free_edges", free_list::<_, EdgeIndex>(&graph.edges)),
+        ("last_node", opt_id(graph.session.last_node)),
+        ("last_edge", opt_id(graph.session.last_edge)),
+    ]
+}
+
+fn node_fields(graph: &Graph, node: &Node) -> Vec<(&'static str, Json)> {
+    vec![
+        ("id", Json::Str(node.id.to_string())),
+        ("alias", Json::Str(node.alias.clone())),
+        ("labels", strings(&node.labels)),
+        ("props", props(&node.props)),
+        ("out", id_list(graph.outgoing_edges(&node.id).map(|e| e.id))),
+        ("in", id_list(graph.incoming_edges(&node.id).map(|e| e.id))),
+    ]
+}
+
+fn constraint(constraint: &Constraint) -> Json {
+    let s = |s: &str| Json::Str(s.to_owned());
+    match constraint {
+        Constraint::RequiredProp { label, key } => Json::object([
+            ("kind", s("required_prop")),
+            ("label", s(label)),
+            ("key", s(key)),
+        ]),
+        Constraint::UniqueProp { label, key } => Json::object([
+            ("kind", s("unique_prop")),
+            ("label", s(label)),
+            ("key", s(key)),
+        ]),
+        Constraint::UniqueAlias { label } => {
+            Json::object([("kind", s("unique_alias")), ("label", s(label))])
+        }
+        Constraint::RelationEndpoints { relation, from, to } => Json::object([
+            ("kind", s("relation")),
+            ("relation", s(relation)),
+            ("from", s(from)),
+            ("to", s(to)),
+        ]),
+    }
+}
+
+fn read_constraint(json: &Json) -> GraphResult<Constraint> {
+    Ok(match field(json, "kind")?.as_str() {
+        Some("required_prop") => Constraint::RequiredProp {
+            label: string(json, "label")?,
+            key: string(json, "key")?,
+        },
+        Some("unique_prop") => Constraint::UniqueProp {
+            label: string(json, "label")?,
+            key: string(json, "key")?,
+        },
+        Some("unique_alias") => Constraint::UniqueAlias {
+            label: string(json, "label")?,
+        },
+        Some("relation") => Constraint::RelationEndpoints {
+            relation: string(json, "relation")?,
+            from: string(json, "from")?,
+            to: string(json, "to")?,
+        },
+        _ => return Err(invalid("kind")),
+    })
+}
+
+fn edge_fields(edge: &Edge) -> Vec<(&'static str, Json)> {
+    vec![
+        ("id", Json::Str(edge.id.to_string())),
+        ("relation", Json::Str(edge.relation.clone())),
+        ("from", Json::Str(edge.from.to_string())),
+        ("to", Json::Str(edge.to.to_string())),
+        ("labels", strings(&edge.labels)),
+        ("props", props(&edge.props)),
+    ]
+}
+
+fn sorted_aliases(graph: &Graph) -> Vec<(&String, &Vec<NodeIndex>)> {
+    let mut aliases: Vec<_> = graph.aliases.inner.iter().collect();
+    aliases.sort_by(|a, b| a.0.cmp(b.0));
+    aliases
+}
+
+fn strings<I: IntoIterator<Item = S>, S: AsRef<str>>(strings: I) -> Json {
+    Json::Array(
+        strings
+            .into_iter()
+            .map(|s| Json::Str(s.as_ref().to_owned()))
+            .collect(),
+    )
+}
+
+fn props(props: &HashMap<String, PropValue>) -> Json {
+    Json::object(props.iter().map(|(k, v)| (k.clone(), Json::from(v))))
+}
+
+fn id_list<I: IntoIterator<Item = K>, K: ToString>(ids: I) -> Json {
+    Json::Array(
+        ids.into_iter()
+            .map(|id| Json::Str(id.to_string()))
+            .collect(),
+    )
+}
+
+fn opt_id<K: ToString>(id: Option<K>) -> Json {
+    id.map_or(Json::Null, |id| Json::Str(id.to_string()))
+}
+
+/// Freed slots in reuse order, each as the key it hands out next
+fn free_list<T, K: ArenaKey + ToString>(arena: &Arena<T>) -> Json {
+    let (slots, free) = arena.raw_parts();
+    let generations: Vec<_> = slots.map(|(generation, _)| generation).collect();
+    id_list(free.iter().map(|&index| K::new(index, generations[index])))
+}
+
+fn invalid(what: &str) -> Error {
+    Error::corrupt(format!("Invalid `{}`", what))
+}
+
+fn field<'j>(json: &'j Json, key: &str) -> GraphResult<&'j Json> {
+    json.get(key)
+        .ok_or_else(|| Error::corrupt(format!("Missing `{}`", key)))
+}
+
+fn array<'j>(json: &'j Json, key: &str) -> GraphResult<&'j [Json]> {
+    field(json, key)?.as_array().ok_or_else(|| invalid(key))
+}
+
+fn string(json: &Json, key: &str) -> GraphResult<String> {
+    Ok(field(json, key)?
+        .as_str()
+        .ok_or_else(|| invalid(key))?
+        .to_owned())
+}
+
+fn read_strings(json: &Json, key: &str) -> GraphResult<Vec<String>> {
+    array(json, key)?
+        .iter()
+        .map(|s| s.as_str().map(str::to_owned).ok_or_else(|| invalid(key)))
+        .collect()
+}
+
+fn read_id<K: FromStr>(json: &Json, what: &str) -> GraphResult<K> {
+    json.as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| Error::corrupt(format!("Invalid id {} in `{}`", json, what)))
+}
+
+fn read_ids<K: FromStr>(json: &Json, key: &str) -> GraphResult<Vec<K>> {
+    array(json, key)?
+        .iter()
+        .map(|id| read_id(id, key))
+        .collect()
+}
+
+fn read_opt_id<K: FromStr>(json: &Json, key: &str) -> GraphResult<Option<K>> {
+    match field(json, key)? {
+        Json::Null => Ok(None),
+        id => read_id(id, key).map(Some),
+    }
+}
+
+fn read_props(json: &Json) -> GraphResult<HashMap<String, PropValue>> {
+    field(json, "props")?
+        .as_object()
+        .ok_or_else(|| invalid("props"))?
+        .iter()
+        .map(|(k, v)| match PropValue::try_from(v)? {
+            v if v.nested_deeper_than(PropValue::MAX_DEPTH) => Err(Error::corrupt(format!(
+                "Prop `{}` nests deeper than {} levels",
+                k,
+                PropValue::MAX_DEPTH
+            ))),
+            v => Ok((k.clone(), v)),
+        })
+        .collect()
+}
+
+/// Collects the entries of either form, then puts the graph together and checks it
+#[derive(Default)]
+struct Loader {
+    started: bool,
+    prop_indexes: Vec<String>,
+    constraints: Vec<Constraint>,
+    free_nodes: Vec<NodeIndex>,
+    free_edges: Vec<EdgeIndex>,
+    last_node: Option<NodeIndex>,
+    last_edge: Option<EdgeIndex>,
+    //with their outgoing and incoming edge lists
+    nodes: Vec<(Node, Vec<EdgeIndex>, Vec<EdgeIndex>)>,
+    edges: Vec<Edge>,
+    aliases: HashMap<String, Vec<NodeIndex>>,
+}
+
+impl Loader {
+    /// One JSON Lines entry, the graph line has to come first
+    fn line(&mut self, entry: &Json) -> GraphResult<()> {
+        match (field(entry, "type")?.as_str(), self.started) {
+            (Some("graph"), false) => self.header(entry),
+            (Some("graph"), true) => Err(Error::corrupt("Second graph line")),
+            (_, false) => Err(Error::corrupt("Expected the graph line first")),
+            (Some("node"), _) => self.node(entry),
+            (Some("edge"), _) => self.edge(entry),
+            (Some("alias"), _) => self.alias(&string(entry, "alias")?, field(entry, "nodes")?),
+            _ => Err(invalid("type")),
+        }
+    }
+
+    fn header(&mut self, json: &Json) -> GraphResult<()> {
+        if field(json, "format")?.as_str() != Some(FORMAT) {
+            return Err(Error::corrupt("Not a graph_db JSON graph"));
+        }
+        let version = field(json, "version")?
+            .as_i64()
+            .filter(|v| (1..=u32::MAX as i64).contains(v))
+            .ok_or_else(|| invalid("version"))? as u32;
+        if version > VERSION {
+            return Err(Error::UnsupportedVersion {
+                found: version,
+                supported: VERSION,
+            });
+        }
+        self.prop_indexes = read_strings(json, "prop_indexes")?;
+        self.constraints = array(json, "constraints")?
+            .iter()
+            .map(read_constraint)
+            .collect::<GraphResult<_>>()?;
+        self.free_nodes = read_ids(json, "free_nodes")?;
+        self.free_edges = read_ids(json, "free_edges")?;
+        self.last_node = read_opt_id(json, "last_node")?;
+        self.last_edge = read_opt_id(json, "last_edge")?;
+        self.started = true;
+        Ok(())
+    }
+
+    fn node(&mut self, json: &Json) -> GraphResult<()> {
+        let mut node = Node::new(read_id(field(json, "id")?, "id")?, string(json, "alias")?);
+        node.labels = read_strings(json, "labels")?;
+        node.props = read_props(json)?;
+        let (out, incoming) = (read_ids(json, "out")?, read_ids(json, "in")?);
+        self.nodes.push((node, out, incoming));
+        Ok(())
+    }
+
+    fn edge(&mut self, json: &Json) -> GraphResult<()> {
+        let mut edge = Edge::new(
+            read_id(field(json, "id")?, "id")?,
+            &string(json, "relation")?,
+            read_id(field(json, "from")?, "from")?,
+            read_id(field(json, "to")?, "to")?,
+        );
+        edge.labels = read_strings(json, "labels")?;
+        edge.props = read_props(json)?;
+        self.edges.push(edge);
+        Ok(())
+    }
+
+    fn alias(&mut self, alias: &str, ids: &Json) -> GraphResult<()> {
+        let ids = ids
+            .as_array()
+            .ok_or_else(|| invalid("aliases"))?
+            .iter()
+            .map(|id| read_id(id, alias))
+            .collect::<GraphResult<_>>()?;
+        if self.aliases.insert(alias.to_owned(), ids).is_some() {
+            return Err(Error::corrupt(format!("Alias `{}` listed twice", alias)));
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> GraphResult<Graph> {
+        let mut edges = arena(
+            "edge",
+            self.edges.into_iter().map(|e| (e.id, e)).collect(),
+            self.free_edges,
+        )?;
+        //thread each node's lists back through the edges, every edge has to be on exactly one of each
+        let (mut outgoing, mut incoming) = (HashSet::new(), HashSet::new());
+        let mut nodes = Vec::new();
+        for (mut node, out, ins) in self.nodes {
+            node.first_outgoing = out.first().copied();
+            node.first_incoming = ins.first().copied();
+            for (i, id) in out.iter().enumerate() {
+                let edge = edges.get_mut(*id).filter(|e| e.from == node.id);
+                match edge {
+                    Some(edge) if outgoing.insert(*id) => {
+                        edge.next_outgoing = out.get(i + 1).copied()
+                    }
+                    _ => return Err(misplaced(*id, "out", &node)),
+                }
+            }
+            for (i, id) in ins.iter().enumerate() {
+                let edge = edges.get_mut(*id).filter(|e| e.to == node.id);
+                match edge {
+                    Some(edge) if incoming.insert(*id) => {
+                        edge.next_incoming = ins.get(i + 1).copied()
+                    }
+                    _ => return Err(misplaced(*id, "in", &node)),
+                }
+            }
+            nodes.push((node.id, node));
+        }
+        if outgoing.len() != edges.len() || incoming.len() != edges.len() {
+            return Err(Error::corrupt(
+                "Edges missing from their nodes' `out` or `in` lists",
+            ));
+        }
+        let nodes = arena("node", nodes, self.free_nodes)?;
+
+        let mut index = Indexes::default();
+        for key in self.prop_indexes.iter() {
+            index.create_prop_index(key, std::iter::empty());
+        }
+        let mut graph = Graph {
+            aliases: AliasMap::from(self.aliases),
+            nodes,
+            edges,
+            index,
+            constraints: self.constraints,
+            uniques: Uniques::default(),
+            session: Session::new(self.last_node, self.last_edge),
+        };
+        validate(&graph)?;
+        graph.index_uniques();
+        for (_, node) in graph.nodes.iter::<NodeIndex>() {
+            graph.index.insert_node(node);
+        }
+        for (_, edge) in graph.edges.iter::<EdgeIndex>() {
+            graph.index.insert_edge(edge);
+        }
+        Ok(graph)
+    }
+}
+
+fn misplaced(edge: EdgeIndex, list: &str, node: &Node) -> Error {
+    Error::corrupt(format!(
+        "Edge {} doesn't belong in the `{}` list of node {}",
+        edge, list, node.id
+    ))
+}
+
+/// Arena with `entries` at their ids and `free` as the free list, which has to cover the remaining slots
+fn arena<T, K: ArenaKey + ToString>(
+    what: &str,
+    entries: Vec<(K, T)>,
+    free: Vec<K>,
+) -> GraphResult<Arena<T>> {
+    let len = entries
+        .iter()
+        .map(|(id, _)| id)
+        .chain(free.iter())
+        .map(|id| id.index() + 1)
+        .max()
+        .unwrap_or(0);
+    let mut slots: Vec<(u32, Option<T>)> = (0..len).map(|_| (0, None)).collect();
+    for (id, value) in entries {
+        let slot = &mut slots[id.index()];
+        if slot.1.is_some() {
+            return Err(Error::corrupt(format!(
+                "Two {}s with id {}",
+                what,
+                id.to_string()
+            )));
+        }
+        *slot = (id.generation(), Some(value));
+    }
+    let mut indices = Vec::new();
+    for id in free {
+        slots[id.index()].0 = id.generation();
+        indices.push(id.index());
+    }
+    Arena::from_raw_parts(slots, indices)
+        .ok_or_else(|| Error::corrupt(format!("Free {} list doesn't match the empty slots", what)))
+}
@@ -0,0 +1,210 @@
+use super::codec::{Reader, Writer};
+use super::{Constraint, EdgeIndex, Error, GraphResult, NodeIndex, PropValue};
+
+/// One change to a `Graph`
+///
+/// Every public mutation is turned into ops that are checked, journaled and then applied,
+/// so replaying the write-ahead log goes through exactly the same code as the original call
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Op {
+    AddNode {
+        alias: String,
+    },
+    /// Also drops every edge touching the node
+    RemoveNode {
+        id: NodeIndex,
+    },
+    AddLabel {
+        id: NodeIndex,
+        label: String,
+    },
+    RemoveLabel {
+        id: NodeIndex,
+        label: String,
+    },
+    AddProp {
+        id: NodeIndex,
+        key: String,
+        val: PropValue,
+    },
+    RemoveProp {
+        id: NodeIndex,
+        key: String,
+    },
+    CreatePropIndex {
+        key: String,
+    },
+    DropPropIndex {
+        key: String,
+    },
+    AddEdge {
+        relation: String,
+        from: NodeIndex,
+        to: NodeIndex,
+    },
+    RemoveEdge {
+        id: EdgeIndex,
+    },
+    AddEdgeLabel {
+        id: EdgeIndex,
+        label: String,
+    },
+    RemoveEdgeLabel {
+        id: EdgeIndex,
+        label: String,
+    },
+    AddEdgeProp {
+        id: EdgeIndex,
+        key: String,
+        val: PropValue,
+    },
+    RemoveEdgeProp {
+        id: EdgeIndex,
+        key: String,
+    },
+    AddConstraint {
+        constraint: Constraint,
+    },
+    DropConstraint {
+        constraint: Constraint,
+    },
+}
+
+impl Op {
+    pub(crate) fn encode(&self, w: &mut Writer) {
+        match self {
+            Op::AddNode { alias } => {
+                w.u8(1);
+                w.str(alias);
+            }
+            Op::RemoveNode { id } => {
+                w.u8(2);
+                w.key(*id);
+            }
+            Op::AddLabel { id, label } => {
+                w.u8(3);
+                w.key(*id);
+                w.str(label);
+            }
+            Op::RemoveLabel { id, label } => {
+                w.u8(4);
+                w.key(*id);
+                w.str(label);
+            }
+            Op::AddProp { id, key, val } => {
+                w.u8(5);
+                w.key(*id);
+                w.str(key);
+                w.value(val);
+            }
+            Op::RemoveProp { id, key } => {
+                w.u8(6);
+                w.key(*id);
+                w.str(key);
+            }
+            Op::CreatePropIndex { key } => {
+                w.u8(7);
+                w.str(key);
+            }
+            Op::DropPropIndex { key } => {
+                w.u8(8);
+                w.str(key);
+            }
+            Op::AddEdge { relation, from, to } => {
+                w.u8(9);
+                w.str(relation);
+                w.key(*from);
+                w.key(*to);
+            }
+            Op::RemoveEdge { id } => {
+                w.u8(10);
+                w.key(*id);
+            }
+            Op::AddEdgeLabel { id, label } => {
+                w.u8(11);
+                w.key(*id);
+                w.str(label);
+            }
+            Op::RemoveEdgeLabel { id, label } => {
+                w.u8(12);
+                w.key(*id);
+                w.str(label);
+            }
+            Op::AddEdgeProp { id, key, val } => {
+                w.u8(13);
+                w.key(*id);
+                w.str(key);
+                w.value(val);
+            }
+            Op::RemoveEdgeProp { id, key } => {
+                w.u8(14);
+                w.key(*id);
+                w.str(key);
+            }
+            Op::AddConstraint { constraint } => {
+                w.u8(15);
+                constraint.encode(w);
+            }
+            Op::DropConstraint { constraint } => {
+                w.u8(16);
+                constraint.encode(w);
+            }
+        }
+    }
+
+    pub(crate) fn decode(r: &mut Reader) -> GraphResult<Op> {
+        Ok(match r.u8()? {
+            1 => Op::AddNode { alias: r.str()? },
+            2 => Op::RemoveNode { id: r.key()? },
+            3 => Op::AddLabel {
+                id: r.key()?,
+                label: r.str()?,
+            },
+            4 => Op::RemoveLabel {
+                id: r.key()?,
+                label: r.str()?,
+            },
+            5 => Op::AddProp {
+                id: r.key()?,
+                key: r.str()?,
+                val: r.value()?,
+            },
+            6 => Op::RemoveProp {
+                id: r.key()?,
+                key: r.str()?,
+            },
+            7 => Op::CreatePropIndex { key: r.str()? },
+            8 => Op::DropPropIndex { key: r.str()? },
+            9 => Op::AddEdge {
+                relation: r.str()?,
+                from: r.key()?,
+                to: r.key()?,
+            },
+            10 => Op::RemoveEdge { id: r.key()? },
+            11 => Op::AddEdgeLabel {
+                id: r.key()?,
+                label: r.str()?,
+            },
+            12 => Op::RemoveEdgeLabel {
+                id: r.key()?,
+                label: r.str()?,
+            },
+            13 => Op::AddEdgeProp {
+                id: r.key()?,
+                key: r.str()?,
+                val: r.value()?,
+            },
+            14 => Op::RemoveEdgeProp {
+                id: r.key()?,
+                key: r.str()?,
+            },
+            15 => Op::AddConstraint {
+                constraint: Constraint::decode(r)?,
+            },
+            16 => Op::DropConstraint {
+                constraint: Constraint::decode(r)?,
+            },
+            tag => return Err(Error::corrupt(format!("Invalid op tag {}", tag))),
+        })
+    }
+}
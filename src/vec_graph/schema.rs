@@ -0,0 +1,508 @@
+//! Schema constraints, checked with every mutation
+//!
+//! Constraints are graph state like prop indexes: adding or dropping one is an op, so it is journaled,
+//! rolled back with its transaction and kept by snapshots. Adding one fails if the graph already breaks it.
+//!
+//! New nodes have no labels, so label rules start to apply when a label is added:
+//! give a node the props its labels require before adding the labels
+//!
+//! Unique constraints are backed by a value to node map each, see [`Uniques`], so checking one
+//! doesn't scan every node with the label
+
+use core::fmt;
+use std::collections::HashMap;
+
+use super::codec::{Reader, Writer};
+use super::index::index_key;
+use super::op::Op;
+use super::{Edge, Error, Graph, GraphResult, Node, NodeIndex, PropValue};
+
+/// One rule of a graph's schema, see [`Graph::add_constraint`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Constraint {
+    /// Nodes with `label` must have prop `key`
+    RequiredProp { label: String, key: String },
+    /// No two nodes with `label` have equal `key` props, `1` and `1.0` count as equal
+    UniqueProp { label: String, key: String },
+    /// No two nodes with `label` share an alias
+    UniqueAlias { label: String },
+    /// `relation` edges go from a node with label `from` to a node with label `to`,
+    /// with several of these for one relation an edge has to match one of them
+    RelationEndpoints {
+        relation: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl Constraint {
+    pub fn required_prop(label: &str, key: &str) -> Self {
+        Constraint::RequiredProp {
+            label: label.to_owned(),
+            key: key.to_owned(),
+        }
+    }
+    pub fn unique_prop(label: &str, key: &str) -> Self {
+        Constraint::UniqueProp {
+            label: label.to_owned(),
+            key: key.to_owned(),
+        }
+    }
+    pub fn unique_alias(label: &str) -> Self {
+        Constraint::UniqueAlias {
+            label: label.to_owned(),
+        }
+    }
+    pub fn relation(relation: &str, from: &str, to: &str) -> Self {
+        Constraint::RelationEndpoints {
+            relation: relation.to_owned(),
+            from: from.to_owned(),
+            to: to.to_owned(),
+        }
+    }
+
+    pub(crate) fn encode(&self, w: &mut Writer) {
+        match self {
+            Constraint::RequiredProp { label, key } => {
+                w.u8(0);
+                w.str(label);
+                w.str(key);
+            }
+            Constraint::UniqueProp { label, key } => {
+                w.u8(1);
+                w.str(label);
+                w.str(key);
+            }
+            Constraint::UniqueAlias { label } => {
+                w.u8(2);
+                w.str(label);
+            }
+            Constraint::RelationEndpoints { relation, from, to } => {
+                w.u8(3);
+                w.str(relation);
+                w.str(from);
+                w.str(to);
+            }
+        }
+    }
+    pub(crate) fn decode(r: &mut Reader) -> GraphResult<Constraint> {
+        Ok(match r.u8()? {
+            0 => Constraint::RequiredProp {
+                label: r.str()?,
+                key: r.str()?,
+            },
+            1 => Constraint::UniqueProp {
+                label: r.str()?,
+                key: r.str()?,
+            },
+            2 => Constraint::UniqueAlias { label: r.str()? },
+            3 => Constraint::RelationEndpoints {
+                relation: r.str()?,
+                from: r.str()?,
+                to: r.str()?,
+            },
+            tag => return Err(Error::corrupt(format!("Invalid constraint tag {}", tag))),
+        })
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constraint::RequiredProp { label, key } => write!(f, "(:{}) requires `{}`", label, key),
+            Constraint::UniqueProp { label, key } => write!(f, "(:{}) has unique `{}`", label, key),
+            Constraint::UniqueAlias { label } => write!(f, "(:{}) has unique aliases", label),
+            Constraint::RelationEndpoints { relation, from, to } => {
+                write!(f, "[:{}] links (:{}) to (:{})", relation, from, to)
+            }
+        }
+    }
+}
+
+/// Value to node maps of the unique constraints, kept in sync by every `Graph` mutation like [`Indexes`]
+///
+/// A node is filed under a constraint while it has the label, and the prop for [`Constraint::UniqueProp`].
+/// Prop values are stored under [`index_key`] so `1` and `1.0` collide
+///
+/// [`Indexes`]: super::index::Indexes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct Uniques {
+    //(label, key) of each unique prop constraint
+    props: HashMap<(String, String), HashMap<PropValue, NodeIndex>>,
+    //label of each unique alias constraint
+    aliases: HashMap<String, HashMap<String, NodeIndex>>,
+}
+
+impl Uniques {
+    /// Starts indexing `constraint` if it's a unique one, `nodes` must keep it already
+    pub(super) fn add<'a, I>(&mut self, constraint: &Constraint, nodes: I)
+    where
+        I: Iterator<Item = &'a Node>,
+    {
+        match constraint {
+            Constraint::UniqueProp { label, key } => {
+                let vals = nodes
+                    .filter(|n| n.has_label(label))
+                    .filter_map(|n| n.get_prop(key).map(|v| (index_key(v), n.id)))
+                    .collect();
+                self.props.insert((label.clone(), key.clone()), vals);
+            }
+            Constraint::UniqueAlias { label } => {
+                let aliases = nodes
+                    .filter(|n| n.has_label(label))
+                    .map(|n| (n.alias.clone(), n.id))
+                    .collect();
+                self.aliases.insert(label.clone(), aliases);
+            }
+            _ => {}
+        }
+    }
+    pub(super) fn remove(&mut self, constraint: &Constraint) {
+        match constraint {
+            Constraint::UniqueProp { label, key } => {
+                self.props.remove(&(label.clone(), key.clone()));
+            }
+            Constraint::UniqueAlias { label } => {
+                self.aliases.remove(label);
+            }
+            _ => {}
+        }
+    }
+    pub(super) fn insert_node(&mut self, node: &Node) {
+        for ((label, key), vals) in self.props.iter_mut() {
+            if let Some(val) = node.get_prop(key).filter(|_| node.has_label(label)) {
+                vals.insert(index_key(val), node.id);
+            }
+        }
+        for (label, aliases) in self.aliases.iter_mut() {
+            if node.has_label(label) {
+                aliases.insert(node.alias.clone(), node.id);
+            }
+        }
+    }
+    pub(super) fn remove_node(&mut self, node: &Node) {
+        for ((label, key), vals) in self.props.iter_mut() {
+            if let Some(val) = node.get_prop(key).filter(|_| node.has_label(label)) {
+                let val = index_key(val);
+                if vals.get(&val) == Some(&node.id) {
+                    vals.remove(&val);
+                }
+            }
+        }
+        for (label, aliases) in self.aliases.iter_mut() {
+            if node.has_label(label) && aliases.get(&node.alias) == Some(&node.id) {
+                aliases.remove(&node.alias);
+            }
+        }
+    }
+    /// Node with `label` that has `val` under `key`
+    fn prop(&self, label: &str, key: &str, val: &PropValue) -> Option<NodeIndex> {
+        let vals = self.props.get(&(label.to_owned(), key.to_owned()))?;
+        vals.get(&index_key(val)).copied()
+    }
+    /// Node with `label` and `alias`
+    fn alias(&self, label: &str, alias: &str) -> Option<NodeIndex> {
+        self.aliases.get(label)?.get(alias).copied()
+    }
+}
+
+fn violation<C: ToString, S: Into<String>>(constraint: C, detail: S) -> Error {
+    Error::ConstraintViolation {
+        constraint: constraint.to_string(),
+        detail: detail.into(),
+    }
+}
+
+/// Whether one of `rules` matches an edge, `from`/`to` tell if its endpoints have a label.
+/// No rules allow any edge
+fn links<F, T>(rules: &[&Constraint], from: F, to: T) -> bool
+where
+    F: Fn(&str) -> bool,
+    T: Fn(&str) -> bool,
+{
+    rules.is_empty()
+        || rules.iter().any(|rule| {
+            matches!(rule, Constraint::RelationEndpoints { from: f, to: t, .. } if from(f) && to(t))
+        })
+}
+
+fn joined(rules: &[&Constraint]) -> String {
+    rules
+        .iter()
+        .map(|rule| rule.to_string())
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+impl Graph {
+    /// Adds a rule every later mutation has to keep, failing if the graph already breaks it
+    pub fn add_constraint(&mut self, constraint: Constraint) -> GraphResult<&mut Self> {
+        self.submit(Op::AddConstraint { constraint })?;
+        Ok(self)
+    }
+    pub fn drop_constraint(&mut self, constraint: &Constraint) -> GraphResult<&mut Self> {
+        self.submit(Op::DropConstraint {
+            constraint: constraint.clone(),
+        })?;
+        Ok(self)
+    }
+    /// Constraints in the order they were added
+    #[inline]
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    /// Fails if applying `op` would break a constraint, part of [`Graph::check`] after the ids were checked
+    pub(super) fn check_constraints(&self, op: &Op) -> GraphResult<()> {
+        match op {
+            Op::AddConstraint { constraint } => return self.check_new(constraint),
+            Op::DropConstraint { constraint } => return self.check_dropped(constraint),
+            _ if self.constraints.is_empty() => return Ok(()),
+            _ => {}
+        }
+        match op {
+            Op::AddLabel { id, label } => {
+                let node = self.get_node(id)?;
+                for constraint in self.constraints.iter() {
+                    match constraint {
+                        Constraint::RequiredProp { label: l, key }
+                            if l == label && node.get_prop(key).is_none() =>
+                        {
+                            return Err(violation(
+                                constraint,
+                                format!("node {} has no `{}`", id, key),
+                            ));
+                        }
+                        Constraint::UniqueProp { label: l, key } if l == label => {
+                            self.check_unique_prop(constraint, node, label, key)?;
+                        }
+                        Constraint::UniqueAlias { label: l } if l == label => {
+                            let twin = self.uniques.alias(label, &node.alias);
+                            if let Some(twin) = twin.filter(|twin| twin != id) {
+                                return Err(violation(
+                                    constraint,
+                                    format!("nodes {} and {} are both `{}`", twin, id, node.alias),
+                                ));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Op::RemoveLabel { id, label } => {
+                //edges of the node may only be allowed because of the label
+                let has =
+                    |node: &Node, l: &str| node.has_label(l) && !(node.id == *id && l == label);
+                for edge in self.outgoing_edges(id).chain(self.incoming_edges(id)) {
+                    let rules = self.endpoint_rules(&edge.relation);
+                    let (from, to) = (self.get_node(&edge.from)?, self.get_node(&edge.to)?);
+                    if !links(&rules, |l| has(from, l), |l| has(to, l)) {
+                        return Err(violation(
+                            joined(&rules),
+                            format!("edge {} needs node {} to keep :{}", edge.id, id, label),
+                        ));
+                    }
+                }
+            }
+            Op::AddProp { id, key, val } => {
+                let node = self.get_node(id)?;
+                for constraint in self.constraints.iter() {
+                    if let Constraint::UniqueProp { label, key: k } = constraint {
+                        if k == key && node.has_label(label) {
+                            self.check_unique_value(constraint, id, label, key, val)?;
+                        }
+                    }
+                }
+            }
+            Op::RemoveProp { id, key } => {
+                let node = self.get_node(id)?;
+                let required = self.constraints.iter().find(|c| {
+                    matches!(c, Constraint::RequiredProp { label, key: k } if k == key && node.has_label(label))
+                });
+                if let (Some(constraint), Some(_)) = (required, node.get_prop(key)) {
+                    return Err(violation(
+                        constraint,
+                        format!("node {} can't lose `{}`", id, key),
+                    ));
+                }
+            }
+            Op::AddEdge { relation, from, to } => {
+                let rules = self.endpoint_rules(relation);
+                let (source, target) = (self.get_node(from)?, self.get_node(to)?);
+                if !links(&rules, |l| source.has_label(l), |l| target.has_label(l)) {
+                    return Err(violation(
+                        joined(&rules),
+                        format!("edge from node {} to node {}", from, to),
+                    ));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Files every node under the unique constraints, for graphs that weren't built op by op
+    pub(super) fn index_uniques(&mut self) {
+        let mut uniques = Uniques::default();
+        for constraint in self.constraints.iter() {
+            uniques.add(constraint, self.nodes.iter::<NodeIndex>().map(|(_, n)| n));
+        }
+        self.uniques = uniques;
+    }
+    /// Runs `change` on node `id` with the node out of the unique indexes, and files it again after
+    pub(super) fn change_node<T>(
+        &mut self,
+        id: NodeIndex,
+        change: impl FnOnce(&mut Node) -> T,
+    ) -> T {
+        let node = self.nodes.get_mut(id).expect("Changed node must exist");
+        self.uniques.remove_node(node);
+        let result = change(node);
+        self.uniques.insert_node(node);
+        result
+    }
+
+    /// Nodes with `label`, through the label index
+    fn labeled<'g>(&'g self, label: &str) -> impl Iterator<Item = &'g Node> + 'g {
+        self.index
+            .label(label)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.get_node_by_idx(id))
+    }
+    /// Edges of `relation`, through the relation index
+    fn related<'g>(&'g self, relation: &str) -> impl Iterator<Item = &'g Edge> + 'g {
+        self.index
+            .relation(relation)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.get_edge_by_idx(id))
+    }
+    fn endpoint_rules(&self, relation: &str) -> Vec<&Constraint> {
+        self.constraints
+            .iter()
+            .filter(
+                |c| matches!(c, Constraint::RelationEndpoints { relation: r, .. } if r == relation),
+            )
+            .collect()
+    }
+    fn check_unique_prop(
+        &self,
+        constraint: &Constraint,
+        node: &Node,
+        label: &str,
+        key: &str,
+    ) -> GraphResult<()> {
+        match node.get_prop(key) {
+            Some(val) => self.check_unique_value(constraint, &node.id, label, key, val),
+            None => Ok(()),
+        }
+    }
+    /// Fails if a node other than `id` with `label` has `val` under `key`
+    fn check_unique_value(
+        &self,
+        constraint: &Constraint,
+        id: &NodeIndex,
+        label: &str,
+        key: &str,
+        val: &PropValue,
+    ) -> GraphResult<()> {
+        let twin = self.uniques.prop(label, key, val).filter(|twin| twin != id);
+        let twin = twin.and_then(|twin| Some((twin, self.get_node_by_idx(&twin)?.get_prop(key)?)));
+        match twin {
+            Some((twin, v)) => Err(violation(
+                constraint,
+                format!("node {} already has `{}` {}", twin, key, v),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// The graph has to keep `constraint` already
+    fn check_new(&self, constraint: &Constraint) -> GraphResult<()> {
+        if self.constraints.contains(constraint) {
+            return Err(violation(constraint, "already defined"));
+        }
+        match constraint {
+            Constraint::RequiredProp { label, key } => {
+                if let Some(node) = self.labeled(label).find(|n| n.get_prop(key).is_none()) {
+                    return Err(violation(
+                        constraint,
+                        format!("node {} has no `{}`", node.id, key),
+                    ));
+                }
+            }
+            Constraint::UniqueProp { label, key } => {
+                let mut seen = HashMap::new();
+                for node in self.labeled(label) {
+                    if let Some(val) = node.get_prop(key) {
+                        if let Some((twin, first)) = seen.insert(index_key(val), (node.id, val)) {
+                            return Err(violation(
+                                constraint,
+                                format!(
+                                    "nodes {} and {} both have `{}` {}",
+                                    twin, node.id, key, first
+                                ),
+                            ));
+                        }
+                    }
+                }
+            }
+            Constraint::UniqueAlias { label } => {
+                let mut seen = HashMap::new();
+                for node in self.labeled(label) {
+                    if let Some(twin) = seen.insert(node.alias.as_str(), node.id) {
+                        return Err(violation(
+                            constraint,
+                            format!("nodes {} and {} are both `{}`", twin, node.id, node.alias),
+                        ));
+                    }
+                }
+            }
+            //another rule for the relation only allows more, the first one has to fit every edge
+            Constraint::RelationEndpoints { relation, .. } => {
+                if self.endpoint_rules(relation).is_empty() {
+                    self.check_edges(relation, &[constraint])?;
+                }
+            }
+        }
+        Ok(())
+    }
+    fn check_dropped(&self, constraint: &Constraint) -> GraphResult<()> {
+        if !self.constraints.contains(constraint) {
+            return Err(violation(constraint, "not defined"));
+        }
+        //the remaining rules of the relation have to allow every edge it allowed
+        if let Constraint::RelationEndpoints { relation, .. } = constraint {
+            let rest: Vec<_> = self
+                .endpoint_rules(relation)
+                .into_iter()
+                .filter(|rule| *rule != constraint)
+                .collect();
+            if !rest.is_empty() {
+                self.check_edges(relation, &rest)?;
+            }
+        }
+        Ok(())
+    }
+    fn check_edges(&self, relation: &str, rules: &[&Constraint]) -> GraphResult<()> {
+        let edge = self.related(relation).find(|e| {
+            let (from, to) = (self.get_node_by_idx(&e.from), self.get_node_by_idx(&e.to));
+            !links(
+                rules,
+                |l| from.is_some_and(|n| n.has_label(l)),
+                |l| to.is_some_and(|n| n.has_label(l)),
+            )
+        });
+        match edge {
+            Some(edge) => Err(violation(
+                joined(rules),
+                format!(
+                    "edge {} from node {} to node {}",
+                    edge.id, edge.from, edge.to
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Graph handle shared between threads
+//!
+//! Writers take turns on the one [`Graph`] that owns the write-ahead log, each write is a transaction.
+//! Readers never touch it: they get an immutable snapshot (`Arc<Graph>`) of the last committed state,
+//! which stays valid and unchanged for as long as they hold it, however many writes commit meanwhile.
+//!
+//! After a commit the writer brings the published snapshot up to date by replaying the committed ops
+//! on it, in place when no reader holds it, otherwise it publishes a fresh copy and leaves
+//! the old one to its readers. Replay is deterministic, so both copies hand out the same ids
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use super::{op::Op, Graph, GraphResult, Transaction};
+
+/// Clonable handle, every clone sees the same graph
+///
+/// ```
+/// # use graph_db::vec_graph::{Graph, SharedGraph};
+/// let shared = SharedGraph::new(Graph::new());
+/// let before = shared.snapshot();
+/// let writer = shared.clone();
+/// std::thread::spawn(move || writer.write(|tx| tx.add_node("sisli").map(|_| ())))
+///     .join()
+///     .unwrap()?;
+/// assert_eq!(before.node_count(), 0);
+/// assert_eq!(shared.read(|graph| graph.node_count()), 1);
+/// # Ok::<(), graph_db::vec_graph::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct SharedGraph {
+    inner: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    writer: Mutex<Graph>,
+    published: RwLock<Published>,
+}
+
+#[derive(Debug)]
+struct Published {
+    graph: Arc<Graph>,
+    version: u64,
+}
+
+impl SharedGraph {
+    /// Takes over `graph`, a graph opened with [`Graph::open`] keeps logging every write
+    pub fn new(graph: Graph) -> Self {
+        let published = Published {
+            graph: Arc::new(graph.clone()),
+            version: 0,
+        };
+        SharedGraph {
+            inner: Arc::new(Shared {
+                writer: Mutex::new(graph),
+                published: RwLock::new(published),
+            }),
+        }
+    }
+
+    /// Consistent view of the last committed write, unaffected by later ones
+    pub fn snapshot(&self) -> Arc<Graph> {
+        self.published().graph.clone()
+    }
+    /// Runs `f` on a snapshot, without blocking writers
+    pub fn read<T, F: FnOnce(&Graph) -> T>(&self, f: F) -> T {
+        f(&self.snapshot())
+    }
+    /// Number of writes that changed the graph, readers use it to tell snapshots apart
+    pub fn version(&self) -> u64 {
+        self.published().version
+    }
+
+    /// Runs `f` as a transaction once the writes before it are done
+    ///
+    /// Readers see all of its mutations or, if it fails, none of them.
+    /// Snapshots taken while it runs show the state before it
+    pub fn write<T, F>(&self, f: F) -> GraphResult<T>
+    where
+        F: FnOnce(&mut Transaction<'_>) -> GraphResult<T>,
+    {
+        let mut graph = self.writer();
+        let (value, ops) = graph.transaction_ops(f)?;
+        if !ops.is_empty() {
+            self.publish(&graph, ops);
+        }
+        Ok(value)
+    }
+    /// Writes a snapshot of a graph opened with [`Graph::open`] and resets its log,
+    /// see [`Graph::checkpoint`]
+    pub fn checkpoint(&self) -> GraphResult<()> {
+        self.writer().checkpoint()
+    }
+
+    /// The graph, if this is the last handle
+    pub fn into_inner(self) -> Option<Graph> {
+        Arc::into_inner(self.inner).map(|shared| {
+            shared
+                .writer
+                .into_inner()
+                .unwrap_or_else(PoisonError::into_inner)
+        })
+    }
+
+    //a writer that panicked left the graph rolled back by its transaction, so poisoning is ignored
+    fn writer(&self) -> MutexGuard<'_, Graph> {
+        self.inner
+            .writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+    fn published(&self) -> RwLockReadGuard<'_, Published> {
+        self.inner
+            .published
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+    fn published_mut(&self) -> RwLockWriteGuard<'_, Published> {
+        self.inner
+            .published
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Called with the writer lock held, so publishes happen in commit order
+    fn publish(&self, graph: &Graph, ops: Vec<Op>) {
+        let mut published = self.published_mut();
+        if let Some(snapshot) = Arc::get_mut(&mut published.graph) {
+            for op in ops {
+                snapshot.apply(op);
+            }
+            published.version += 1;
+            return;
+        }
+        drop(published);
+        //readers hold the old state and keep it, the copy is made without blocking new snapshots
+        let fresh = Arc::new(graph.clone());
+        let mut published = self.published_mut();
+        published.graph = fresh;
+        published.version += 1;
+    }
+}
+
+impl From<Graph> for SharedGraph {
+    fn from(graph: Graph) -> Self {
+        SharedGraph::new(graph)
+    }
+}
@@ -0,0 +1,407 @@
+//! Binary snapshot of a whole `Graph`, written by [`Graph::save_snapshot`] and by checkpoints
+//!
+//! All integers are little endian. A file is a 36 byte header followed by the body:
+//!
+//! | offset | size | field                                                          |
+//! |--------|------|----------------------------------------------------------------|
+//! | 0      | 8    | magic `GDBSNAP\0`                                              |
+//! | 8      | 4    | format version, currently 2                                    |
+//! | 12     | 8    | log sequence number, 0 outside of `Graph::open` databases       |
+//! | 20     | 8    | body length in bytes                                           |
+//! | 28     | 4    | crc32 of the body                                              |
+//! | 32     | 4    | crc32 of header bytes 0..32                                    |
+//!
+//! The body, with `str`/`bytes` as a u32 length plus the bytes and `key` as u64 slot plus u32 generation:
+//! - node slots: u64 count, then per slot u32 generation and u8 present flag, a present slot continues with
+//!   alias, labels (u64 count + strs), props (u64 count + key str + value, sorted by key)
+//!   and the optional first outgoing/incoming edge keys (u8 flag + key)
+//! - node free list: u64 count + u64 slot indices
+//! - edge slots: like nodes but relation str, from and to keys, labels, props, next outgoing/incoming edge keys
+//! - edge free list
+//! - aliases: u64 count, then alias str plus u64 count of node keys, sorted by alias
+//! - indexed prop keys: u64 count + strs, the index contents are rebuilt on load
+//! - optional last added node and edge keys
+//! - since version 2, constraints: u64 count, then a u8 kind and its strs (0 required prop: label, key,
+//!   1 unique prop: label, key, 2 unique alias: label, 3 relation: relation, from label, to label)
+//!
+//! Values are a u8 tag followed by the payload: 0 null, 1 bool (u8), 2 int (i64), 3 float (f64 bits),
+//! 4 str, 5 bytes, 6 list (u32 count + values), 7 map (u32 count + key str and value),
+//! lists and maps nest at most [`PropValue::MAX_DEPTH`] levels.
+//! Keeping every slot with its generation plus the free lists means ids handed out before saving
+//! still resolve after loading
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+};
+
+use super::codec::{crc32, Reader, Writer};
+use super::{
+    AliasMap, Arena, ArenaKey, Constraint, Edge, EdgeIndex, Error, Graph, GraphResult, Indexes,
+    Node, NodeIndex, PropValue, Session, Uniques,
+};
+
+const MAGIC: &[u8; 8] = b"GDBSNAP\0";
+pub(crate) const VERSION: u32 = 2;
+const HEADER_LEN: usize = 36;
+
+impl Graph {
+    /// Writes the graph to `path` in the snapshot format, replacing the file atomically
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> GraphResult<()> {
+        let path = path.as_ref();
+        let bytes =
+            encode(self, 0).map_err(Error::io(format!("writing snapshot {}", path.display())))?;
+        write_atomic(path, &bytes)
+    }
+    /// Reads a graph written by [`Graph::save_snapshot`]
+    ///
+    /// A damaged file is an [`Error::Corrupt`], one written by a newer format is an [`Error::UnsupportedVersion`]
+    pub fn load_snapshot<P: AsRef<Path>>(path: P) -> GraphResult<Graph> {
+        let path = path.as_ref();
+        let bytes =
+            fs::read(path).map_err(Error::io(format!("reading snapshot {}", path.display())))?;
+        decode(&bytes).map(|(graph, _)| graph)
+    }
+}
+
+/// Writes next to `path` and renames over it, so a crash leaves either the old or the new file
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> GraphResult<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push("-tmp");
+    let context = format!("writing snapshot {}", path.display());
+    File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(Error::io(context))?;
+    //persist the rename itself, not possible on every platform so best effort
+    if let Some(dir) = path.parent() {
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+/// Snapshot bytes, an error if something in the graph is too large or too deeply nested for the format
+pub(crate) fn encode(graph: &Graph, seq: u64) -> io::Result<Vec<u8>> {
+    let mut body = Writer::default();
+
+    let (slots, free) = graph.nodes.raw_parts();
+    let slots: Vec<_> = slots.collect();
+    body.u64(slots.len() as u64);
+    for (generation, node) in slots {
+        body.u32(generation);
+        match node {
+            None => body.u8(0),
+            Some(node) => {
+                body.u8(1);
+                body.str(&node.alias);
+                labels(&mut body, &node.labels);
+                props(&mut body, &node.props);
+                body.opt_key(node.first_outgoing);
+                body.opt_key(node.first_incoming);
+            }
+        }
+    }
+    free_list(&mut body, free);
+
+    let (slots, free) = graph.edges.raw_parts();
+    let slots: Vec<_> = slots.collect();
+    body.u64(slots.len() as u64);
+    for (generation, edge) in slots {
+        body.u32(generation);
+        match edge {
+            None => body.u8(0),
+            Some(edge) => {
+                body.u8(1);
+                body.str(&edge.relation);
+                body.key(edge.from);
+                body.key(edge.to);
+                labels(&mut body, &edge.labels);
+                props(&mut body, &edge.props);
+                body.opt_key(edge.next_outgoing);
+                body.opt_key(edge.next_incoming);
+            }
+        }
+    }
+    free_list(&mut body, free);
+
+    let mut aliases: Vec<_> = graph.aliases.inner.iter().collect();
+    aliases.sort_by(|a, b| a.0.cmp(b.0));
+    body.u64(aliases.len() as u64);
+    for (alias, ids) in aliases {
+        body.str(alias);
+        body.u64(ids.len() as u64);
+        ids.iter().for_each(|id| body.key(*id));
+    }
+
+    let mut keys: Vec<_> = graph.index.prop_keys().collect();
+    keys.sort();
+    body.u64(keys.len() as u64);
+    keys.iter().for_each(|key| body.str(key));
+
+    body.opt_key(graph.session.last_node);
+    body.opt_key(graph.session.last_edge);
+
+    body.u64(graph.constraints.len() as u64);
+    graph.constraints.iter().for_each(|c| c.encode(&mut body));
+
+    let body = body.finish()?;
+    let mut out = Writer::default();
+    out.buf.extend_from_slice(MAGIC);
+    out.u32(VERSION);
+    out.u64(seq);
+    out.u64(body.len() as u64);
+    out.u32(crc32(&body));
+    out.u32(crc32(&out.buf));
+    out.buf.extend_from_slice(&body);
+    out.finish()
+}
+
+/// Graph and log sequence number stored in `bytes`
+pub(crate) fn decode(bytes: &[u8]) -> GraphResult<(Graph, u64)> {
+    if bytes.len() < 12 || &bytes[..8] != MAGIC {
+        return Err(Error::corrupt("Not a graph snapshot".to_owned()));
+    }
+    //version first, a newer format may lay out the rest of its header differently
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    if version > VERSION {
+        return Err(Error::UnsupportedVersion {
+            found: version,
+            supported: VERSION,
+        });
+    }
+    if version == 0 {
+        return Err(Error::corrupt("Invalid snapshot version 0".to_owned()));
+    }
+    if bytes.len() < HEADER_LEN {
+        return Err(Error::corrupt("Truncated snapshot header".to_owned()));
+    }
+    let mut header = Reader::new(&bytes[12..HEADER_LEN]);
+    let seq = header.u64()?;
+    let len = header.u64()?;
+    let body_crc = header.u32()?;
+    if crc32(&bytes[..32]) != header.u32()? {
+        return Err(Error::corrupt(
+            "Snapshot header checksum mismatch".to_owned(),
+        ));
+    }
+    let body = &bytes[HEADER_LEN..];
+    if body.len() as u64 != len {
+        return Err(Error::corrupt(format!(
+            "Snapshot body is {} bytes, header says {}",
+            body.len(),
+            len
+        )));
+    }
+    if crc32(body) != body_crc {
+        return Err(Error::corrupt("Snapshot checksum mismatch".to_owned()));
+    }
+    let mut r = Reader::new(body);
+
+    let count = r.count()?;
+    let mut slots = Vec::new();
+    for index in 0..count {
+        let generation = r.u32()?;
+        let node = match r.u8()? {
+            0 => None,
+            1 => {
+                let mut node = Node::new(NodeIndex::new(index, generation), r.str()?);
+                node.labels = read_labels(&mut r)?;
+                node.props = read_props(&mut r)?;
+                node.first_outgoing = r.opt_key()?;
+                node.first_incoming = r.opt_key()?;
+                Some(node)
+            }
+            tag => return Err(Error::corrupt(format!("Invalid slot tag {}", tag))),
+        };
+        slots.push((generation, node));
+    }
+    let free = read_free_list(&mut r)?;
+    let nodes = Arena::from_raw_parts(slots, free)
+        .ok_or_else(|| Error::corrupt("Node free list doesn't match empty slots".to_owned()))?;
+
+    let count = r.count()?;
+    let mut slots = Vec::new();
+    for index in 0..count {
+        let generation = r.u32()?;
+        let edge = match r.u8()? {
+            0 => None,
+            1 => {
+                let relation = r.str()?;
+                let (from, to) = (r.key()?, r.key()?);
+                let mut edge = Edge::new(EdgeIndex::new(index, generation), &relation, from, to);
+                edge.labels = read_labels(&mut r)?;
+                edge.props = read_props(&mut r)?;
+                edge.next_outgoing = r.opt_key()?;
+                edge.next_incoming = r.opt_key()?;
+                Some(edge)
+            }
+            tag => return Err(Error::corrupt(format!("Invalid slot tag {}", tag))),
+        };
+        slots.push((generation, edge));
+    }
+    let free = read_free_list(&mut r)?;
+    let edges = Arena::from_raw_parts(slots, free)
+        .ok_or_else(|| Error::corrupt("Edge free list doesn't match empty slots".to_owned()))?;
+
+    let mut aliases = HashMap::new();
+    for _ in 0..r.count()? {
+        let alias = r.str()?;
+        let ids = (0..r.count()?)
+            .map(|_| r.key())
+            .collect::<GraphResult<Vec<NodeIndex>>>()?;
+        aliases.insert(alias, ids);
+    }
+
+    let mut index = Indexes::default();
+    for _ in 0..r.count()? {
+        index.create_prop_index(&r.str()?, std::iter::empty());
+    }
+    let last_node = r.opt_key()?;
+    let last_edge = r.opt_key()?;
+    let constraints = match version {
+        1 => Vec::new(),
+        _ => (0..r.count()?)
+            .map(|_| Constraint::decode(&mut r))
+            .collect::<GraphResult<_>>()?,
+    };
+    if !r.is_empty() {
+        return Err(Error::corrupt(
+            "Trailing bytes after snapshot body".to_owned(),
+        ));
+    }
+
+    let mut graph = Graph {
+        aliases: AliasMap::from(aliases),
+        nodes,
+        edges,
+        index,
+        constraints,
+        uniques: Uniques::default(),
+        session: Session::new(last_node, last_edge),
+    };
+    validate(&graph)?;
+    graph.index_uniques();
+    for (_, node) in graph.nodes.iter::<NodeIndex>() {
+        graph.index.insert_node(node);
+    }
+    for (_, edge) in graph.edges.iter::<EdgeIndex>() {
+        graph.index.insert_edge(edge);
+    }
+    Ok((graph, seq))
+}
+
+/// Every handle stored in the snapshot must point at a live node or edge, and every edge
+/// must be in its source's outgoing list and its target's incoming list exactly once
+pub(super) fn validate(graph: &Graph) -> GraphResult<()> {
+    let dangling =
+        |what: &str, owner: String| Err(Error::corrupt(format!("Dangling {} in {}", what, owner)));
+    let live_edge = |id: &Option<EdgeIndex>| id.is_none_or(|id| graph.edges.get(id).is_some());
+    for node in graph.iter_nodes() {
+        if !live_edge(&node.first_outgoing) || !live_edge(&node.first_incoming) {
+            return dangling("edge list head", format!("node {}", node.id));
+        }
+    }
+    for edge in graph.iter_edges() {
+        if graph.nodes.get(edge.from).is_none() || graph.nodes.get(edge.to).is_none() {
+            return dangling("endpoint", format!("edge {}", edge.id));
+        }
+        if !live_edge(&edge.next_outgoing) || !live_edge(&edge.next_incoming) {
+            return dangling("edge list link", format!("edge {}", edge.id));
+        }
+    }
+    for (alias, ids) in graph.aliases.inner.iter() {
+        if ids.iter().any(|id| graph.nodes.get(*id).is_none()) {
+            return dangling("node id", format!("alias {}", alias));
+        }
+    }
+    //a cycle or a foreign edge would send adjacency walks around forever or off to other nodes
+    for outgoing in [true, false] {
+        let list = if outgoing { "outgoing" } else { "incoming" };
+        let mut seen = HashSet::new();
+        for node in graph.iter_nodes() {
+            let mut cursor = match outgoing {
+                true => node.first_outgoing,
+                false => node.first_incoming,
+            };
+            while let Some(id) = cursor {
+                let edge = graph.edges.get(id).expect("Checked live above");
+                let owner = if outgoing { edge.from } else { edge.to };
+                if owner != node.id {
+                    return Err(Error::corrupt(format!(
+                        "Edge {} in the {} list of node {}",
+                        id, list, node.id
+                    )));
+                }
+                if !seen.insert(id) {
+                    return Err(Error::corrupt(format!(
+                        "Cycle at edge {} in the {} list of node {}",
+                        id, list, node.id
+                    )));
+                }
+                cursor = match outgoing {
+                    true => edge.next_outgoing,
+                    false => edge.next_incoming,
+                };
+            }
+        }
+        if seen.len() != graph.edges.len() {
+            return Err(Error::corrupt(format!(
+                "{} of {} edges missing from their {} list",
+                graph.edges.len() - seen.len(),
+                graph.edges.len(),
+                list
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn labels(w: &mut Writer, labels: &[String]) {
+    w.u64(labels.len() as u64);
+    labels.iter().for_each(|label| w.str(label));
+}
+
+/// Sorted by key so equal graphs give equal bytes
+fn props(w: &mut Writer, props: &HashMap<String, PropValue>) {
+    let mut props: Vec<_> = props.iter().collect();
+    props.sort_by(|a, b| a.0.cmp(b.0));
+    w.u64(props.len() as u64);
+    for (key, val) in props {
+        w.str(key);
+        w.value(val);
+    }
+}
+
+fn free_list(w: &mut Writer, free: &[usize]) {
+    w.u64(free.len() as u64);
+    free.iter().for_each(|index| w.u64(*index as u64));
+}
+
+fn read_labels(r: &mut Reader) -> GraphResult<Vec<String>> {
+    (0..r.count()?).map(|_| r.str()).collect()
+}
+
+fn read_props(r: &mut Reader) -> GraphResult<HashMap<String, PropValue>> {
+    let mut props = HashMap::new();
+    for _ in 0..r.count()? {
+        let key = r.str()?;
+        props.insert(key, r.value()?);
+    }
+    Ok(props)
+}
+
+fn read_free_list(r: &mut Reader) -> GraphResult<Vec<usize>> {
+    (0..r.count()?).map(|_| Ok(r.u64()? as usize)).collect()
+}
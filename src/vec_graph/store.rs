@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use super::{Edge, EdgeIndex, Graph, GraphResult, Node, NodeIndex, PropValue};
+use crate::algo::Direction;
+use crate::store::{sorted, EdgeData, GraphStore, NodeData};
+
+/// Every change goes through `submit`, so the trait's mutations are journaled, indexed,
+/// constraint checked and rolled back with transactions like the inherent ones
+impl GraphStore for Graph {
+    fn create_node(&mut self, alias: &str) -> GraphResult<NodeIndex> {
+        self.add_node(alias)?;
+        Ok(self.session.last_node.expect("Added node is the last node"))
+    }
+    fn delete_node(&mut self, id: &NodeIndex) -> GraphResult<()> {
+        self.remove_node_by_id(id).map(|_| ())
+    }
+    #[inline]
+    fn has_node(&self, id: &NodeIndex) -> bool {
+        self.nodes.get(*id).is_some()
+    }
+    fn node_ids(&self) -> Vec<NodeIndex> {
+        self.iter_nodes().map(|n| n.id).collect()
+    }
+    #[inline]
+    fn node_count(&self) -> usize {
+        Graph::node_count(self)
+    }
+    fn node_alias(&self, id: &NodeIndex) -> GraphResult<String> {
+        Ok(self.get_node(id)?.alias.clone())
+    }
+    fn ids_with_alias(&self, alias: &str) -> Vec<NodeIndex> {
+        self.get_ids_by_alias(alias).cloned().unwrap_or_default()
+    }
+    #[inline]
+    fn ids_with_label(&self, label: &str) -> Vec<NodeIndex> {
+        self.find_ids_by_label(label)
+    }
+    fn ids_with_prop(&self, key: &str, val: &PropValue) -> Option<Vec<NodeIndex>> {
+        self.find_ids_by_prop(key, val.clone()).ok()
+    }
+
+    fn node_labels(&self, id: &NodeIndex) -> GraphResult<Vec<String>> {
+        Ok(self.get_node(id)?.labels().to_vec())
+    }
+    fn node_has_label(&self, id: &NodeIndex, label: &str) -> GraphResult<bool> {
+        Ok(self.get_node(id)?.has_label(label))
+    }
+    fn label_node(&mut self, id: &NodeIndex, label: &str) -> GraphResult<()> {
+        self.add_label(id, label).map(|_| ())
+    }
+    fn unlabel_node(&mut self, id: &NodeIndex, label: &str) -> GraphResult<()> {
+        self.remove_label(id, label).map(|_| ())
+    }
+    fn node_prop(&self, id: &NodeIndex, key: &str) -> GraphResult<Option<PropValue>> {
+        Ok(self.get_node(id)?.get_prop(key).cloned())
+    }
+    fn node_props(&self, id: &NodeIndex) -> GraphResult<BTreeMap<String, PropValue>> {
+        Ok(sorted(self.get_node(id)?.props()))
+    }
+    fn set_node_prop(&mut self, id: &NodeIndex, key: &str, val: PropValue) -> GraphResult<()> {
+        self.add_prop(id, key, val).map(|_| ())
+    }
+    fn unset_node_prop(&mut self, id: &NodeIndex, key: &str) -> GraphResult<()> {
+        self.remove_prop(id, key).map(|_| ())
+    }
+
+    fn create_edge(
+        &mut self,
+        relation: &str,
+        from: &NodeIndex,
+        to: &NodeIndex,
+    ) -> GraphResult<EdgeIndex> {
+        self.add_edge(relation, *from, *to)?;
+        Ok(self.session.last_edge.expect("Added edge is the last edge"))
+    }
+    fn delete_edge(&mut self, id: &EdgeIndex) -> GraphResult<()> {
+        self.remove_edge(id).map(|_| ())
+    }
+    #[inline]
+    fn has_edge(&self, id: &EdgeIndex) -> bool {
+        self.edges.get(*id).is_some()
+    }
+    fn edge_ids(&self) -> Vec<EdgeIndex> {
+        Graph::edge_ids(self).collect()
+    }
+    #[inline]
+    fn edge_count(&self) -> usize {
+        Graph::edge_count(self)
+    }
+    fn edge_relation(&self, id: &EdgeIndex) -> GraphResult<String> {
+        Ok(self.get_edge(id)?.relation().to_owned())
+    }
+    fn edge_ends(&self, id: &EdgeIndex) -> GraphResult<(NodeIndex, NodeIndex)> {
+        let edge = self.get_edge(id)?;
+        Ok((*edge.from(), *edge.to()))
+    }
+    fn edge_labels(&self, id: &EdgeIndex) -> GraphResult<Vec<String>> {
+        Ok(self.get_edge(id)?.labels().to_vec())
+    }
+    fn label_edge(&mut self, id: &EdgeIndex, label: &str) -> GraphResult<()> {
+        self.add_edge_label(id, label).map(|_| ())
+    }
+    fn unlabel_edge(&mut self, id: &EdgeIndex, label: &str) -> GraphResult<()> {
+        self.remove_edge_label(id, label).map(|_| ())
+    }
+    fn edge_prop(&self, id: &EdgeIndex, key: &str) -> GraphResult<Option<PropValue>> {
+        Ok(self.get_edge(id)?.get_prop(key).cloned())
+    }
+    fn edge_props(&self, id: &EdgeIndex) -> GraphResult<BTreeMap<String, PropValue>> {
+        Ok(sorted(self.get_edge(id)?.props()))
+    }
+    fn set_edge_prop(&mut self, id: &EdgeIndex, key: &str, val: PropValue) -> GraphResult<()> {
+        self.add_edge_prop(id, key, val).map(|_| ())
+    }
+    fn unset_edge_prop(&mut self, id: &EdgeIndex, key: &str) -> GraphResult<()> {
+        self.remove_edge_prop(id, key).map(|_| ())
+    }
+
+    fn adjacent(
+        &self,
+        id: &NodeIndex,
+        direction: Direction,
+        relations: &[String],
+    ) -> GraphResult<Vec<(EdgeIndex, NodeIndex)>> {
+        self.get_node(id)?;
+        let outgoing = matches!(direction, Direction::Outgoing | Direction::Both)
+            .then(|| self.outgoing_edges(id).map(|e| (e, e.to)));
+        let incoming = matches!(direction, Direction::Incoming | Direction::Both)
+            .then(|| self.incoming_edges(id).map(|e| (e, e.from)));
+        Ok(outgoing
+            .into_iter()
+            .flatten()
+            .chain(incoming.into_iter().flatten())
+            .filter(|(e, _)| relations.is_empty() || relations.contains(&e.relation))
+            .map(|(e, node)| (e.id, node))
+            .collect())
+    }
+
+    fn node(&self, id: &NodeIndex) -> GraphResult<NodeData> {
+        self.get_node(id).map(Into::into)
+    }
+    fn edge(&self, id: &EdgeIndex) -> GraphResult<EdgeData> {
+        self.get_edge(id).map(Into::into)
+    }
+    fn with_node(&self, id: &NodeIndex, f: &mut dyn FnMut(&Node)) -> GraphResult<()> {
+        f(self.get_node(id)?);
+        Ok(())
+    }
+    fn with_edge(&self, id: &EdgeIndex, f: &mut dyn FnMut(&Edge)) -> GraphResult<()> {
+        f(self.get_edge(id)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,492 @@
+//! All-or-nothing transactions over a `Graph`
+//!
+//! Mutations inside a transaction are applied right away so later calls see them,
+//! and every applied op leaves an [`Undo`] entry describing the exact state it replaced.
+//! Rolling back replays those entries in reverse, which restores slots, generations, free lists,
+//! adjacency order and indexes, so ids handed out afterwards are the same as if the rolled back
+//! mutations never happened. The write-ahead log only sees the ops on commit, as one record
+
+use std::{
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::op::Op;
+use super::{
+    Constraint, Edge, EdgeIndex, Error, Graph, GraphResult, Journal, Node, NodeIndex, PropValue,
+};
+
+/// Inverse of one applied op, see [`Graph::apply`]
+#[derive(Debug)]
+pub(crate) enum Undo {
+    NodeInserted {
+        id: NodeIndex,
+        reused: bool,
+        alias_existed: bool,
+        last_node: Option<NodeIndex>,
+    },
+    NodeRemoved {
+        node: Node,
+        alias_pos: Option<usize>,
+        last_node: Option<NodeIndex>,
+    },
+    NodeLabelAdded {
+        id: NodeIndex,
+        label: String,
+    },
+    NodeLabelRemoved {
+        id: NodeIndex,
+        label: String,
+        positions: Vec<usize>,
+    },
+    /// Prop `key` had `old` before the op, None if it was missing
+    NodeProp {
+        id: NodeIndex,
+        key: String,
+        old: Option<PropValue>,
+    },
+    PropIndexCreated {
+        key: String,
+    },
+    PropIndexDropped {
+        key: String,
+    },
+    /// Constraints are only ever pushed, popping is enough
+    ConstraintAdded,
+    ConstraintDropped {
+        constraint: Constraint,
+        pos: usize,
+    },
+    EdgeInserted {
+        id: EdgeIndex,
+        reused: bool,
+        last_edge: Option<EdgeIndex>,
+    },
+    /// `prev_*` are the edges before it in its endpoints' lists, None where it was the head
+    EdgeRemoved {
+        edge: Edge,
+        prev_outgoing: Option<EdgeIndex>,
+        prev_incoming: Option<EdgeIndex>,
+        last_edge: Option<EdgeIndex>,
+    },
+    /// Edge labels aren't indexed, popping the pushed label is enough
+    EdgeLabelAdded {
+        id: EdgeIndex,
+    },
+    EdgeLabelRemoved {
+        id: EdgeIndex,
+        label: String,
+        positions: Vec<usize>,
+    },
+    EdgeProp {
+        id: EdgeIndex,
+        key: String,
+        old: Option<PropValue>,
+    },
+}
+
+#[derive(Debug)]
+pub(crate) struct TxLog {
+    //tells a transaction's own log from one swapped in behind its back
+    id: u64,
+    pub(crate) ops: Vec<Op>,
+    undo: Vec<Undo>,
+}
+
+impl TxLog {
+    fn new() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        TxLog {
+            id: NEXT.fetch_add(1, Ordering::Relaxed),
+            ops: Vec::new(),
+            undo: Vec::new(),
+        }
+    }
+}
+
+/// Point inside a transaction to roll back to, see [`Transaction::savepoint`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Savepoint {
+    ops: usize,
+    undo: usize,
+}
+
+/// Graph inside [`Graph::transaction`], every `Graph` method is available through `DerefMut`
+///
+/// Dropping it without committing, including by a panic, rolls back.
+/// Replacing the graph itself (`*tx = Graph::new()`) leaves nothing to roll back or commit,
+/// committing then fails with `InvalidOperation`
+pub struct Transaction<'g> {
+    graph: &'g mut Graph,
+    log: u64,
+    start: Savepoint,
+    outermost: bool,
+    done: bool,
+}
+
+/// Indices of `label` in `labels`, so removing every copy of it can be undone in place
+pub(super) fn positions(labels: &[String], label: &str) -> Vec<usize> {
+    labels
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| *x == label)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+impl Graph {
+    /// Runs `f` and keeps its mutations only if it returns Ok
+    ///
+    /// ```
+    /// # use graph_db::vec_graph::Graph;
+    /// let mut graph = Graph::new();
+    /// let failed = graph.transaction(|tx| {
+    ///     tx.add_node("sisli")?.add_edges_by_aliases("includes", "sisli", "missing")?;
+    ///     Ok(())
+    /// });
+    /// assert!(failed.is_err());
+    /// assert_eq!(graph.node_count(), 0);
+    /// ```
+    ///
+    /// Nested calls behave like a savepoint: an error rolls back only the inner closure's changes.
+    /// On a graph opened with [`Graph::open`] the whole transaction is one log record,
+    /// so recovery sees all of it or none of it
+    pub fn transaction<T, F>(&mut self, f: F) -> GraphResult<T>
+    where
+        F: FnOnce(&mut Transaction<'_>) -> GraphResult<T>,
+    {
+        self.transaction_ops(f).map(|(value, _)| value)
+    }
+    /// [`Graph::transaction`] that also returns the committed ops, empty for nested calls
+    pub(crate) fn transaction_ops<T, F>(&mut self, f: F) -> GraphResult<(T, Vec<Op>)>
+    where
+        F: FnOnce(&mut Transaction<'_>) -> GraphResult<T>,
+    {
+        let outermost = self.session.tx.is_none();
+        if outermost {
+            self.session.tx = Some(TxLog::new());
+        }
+        let mut tx = Transaction {
+            log: self.session.tx.as_ref().expect("Started transaction").id,
+            start: self.tx_position(),
+            graph: self,
+            outermost,
+            done: false,
+        };
+        let value = f(&mut tx)?;
+        let ops = tx.commit()?;
+        Ok((value, ops))
+    }
+    #[inline]
+    pub fn in_transaction(&self) -> bool {
+        self.session.tx.is_some()
+    }
+    fn tx_position(&self) -> Savepoint {
+        let tx = self.session.tx.as_ref().expect("No transaction is running");
+        Savepoint {
+            ops: tx.ops.len(),
+            undo: tx.undo.len(),
+        }
+    }
+    /// Keeps the entry built by `undo` if a transaction is running
+    #[inline]
+    pub(super) fn record<F: FnOnce(&Graph) -> Undo>(&mut self, undo: F) {
+        if let Some(mut tx) = self.session.tx.take() {
+            tx.undo.push(undo(self));
+            self.session.tx = Some(tx);
+        }
+    }
+    fn rollback(&mut self, to: Savepoint) {
+        let mut tx = self.session.tx.take().expect("No transaction is running");
+        tx.ops.truncate(to.ops);
+        while tx.undo.len() > to.undo {
+            let entry = tx.undo.pop().expect("Checked length");
+            self.undo(entry);
+        }
+        self.session.tx = Some(tx);
+    }
+    fn undo(&mut self, entry: Undo) {
+        match entry {
+            Undo::NodeInserted {
+                id,
+                reused,
+                alias_existed,
+                last_node,
+            } => {
+                let node = self.nodes.uninsert(id, reused);
+                self.uniques.remove_node(&node);
+                if alias_existed {
+                    self.aliases.remove_id_at(&node.alias, &id);
+                } else {
+                    self.aliases.inner.remove(&node.alias);
+                }
+                self.session.last_node = last_node;
+            }
+            Undo::NodeRemoved {
+                node,
+                alias_pos,
+                last_node,
+            } => {
+                let id = node.id;
+                self.index.insert_node(&node);
+                self.uniques.insert_node(&node);
+                if let Some(pos) = alias_pos {
+                    self.aliases
+                        .inner
+                        .get_mut(&node.alias)
+                        .expect("Removed node's alias entry is kept")
+                        .insert(pos, id);
+                }
+                self.nodes.unremove(id, node);
+                self.session.last_node = last_node;
+            }
+            Undo::NodeLabelAdded { id, label } => {
+                let kept = self.change_node(id, |node| {
+                    node.labels.pop();
+                    node.has_label(&label)
+                });
+                if !kept {
+                    self.index.remove_label(&label, &id);
+                }
+            }
+            Undo::NodeLabelRemoved {
+                id,
+                label,
+                positions,
+            } => {
+                self.change_node(id, |node| {
+                    for pos in positions.iter() {
+                        node.labels.insert(*pos, label.clone());
+                    }
+                });
+                if !positions.is_empty() {
+                    self.index.insert_label(&label, id);
+                }
+            }
+            Undo::NodeProp { id, key, old } => {
+                let current = self.change_node(id, |node| match old.clone() {
+                    Some(old) => node.props.insert(key.clone(), old),
+                    None => node.props.remove(&key),
+                });
+                if let Some(current) = current {
+                    self.index.remove_prop(&key, &current, &id);
+                }
+                if let Some(old) = old {
+                    self.index.insert_prop(&key, &old, id);
+                }
+            }
+            Undo::PropIndexCreated { key } => {
+                self.index.drop_prop_index(&key);
+            }
+            Undo::PropIndexDropped { key } => {
+                self.index
+                    .create_prop_index(&key, self.nodes.iter::<NodeIndex>().map(|(_, n)| n));
+            }
+            Undo::ConstraintAdded => {
+                let constraint = self
+                    .constraints
+                    .pop()
+                    .expect("Undone constraint must exist");
+                self.uniques.remove(&constraint);
+            }
+            Undo::ConstraintDropped { constraint, pos } => {
+                self.uniques
+                    .add(&constraint, self.nodes.iter::<NodeIndex>().map(|(_, n)| n));
+                self.constraints.insert(pos, constraint);
+            }
+            Undo::EdgeInserted {
+                id,
+                reused,
+                last_edge,
+            } => {
+                //later edges are already undone, so this one is the head of both lists
+                let edge = self.edges.uninsert(id, reused);
+                self.index.remove_edge(&edge);
+                self.nodes
+                    .get_mut(edge.from)
+                    .expect("Undone edge source must exist")
+                    .first_outgoing = edge.next_outgoing;
+                self.nodes
+                    .get_mut(edge.to)
+                    .expect("Undone edge target must exist")
+                    .first_incoming = edge.next_incoming;
+                self.session.last_edge = last_edge;
+            }
+            Undo::EdgeRemoved {
+                edge,
+                prev_outgoing,
+                prev_incoming,
+                last_edge,
+            } => {
+                let id = edge.id;
+                match prev_outgoing {
+                    None => {
+                        self.nodes
+                            .get_mut(edge.from)
+                            .expect("Undone edge source must exist")
+                            .first_outgoing = Some(id)
+                    }
+                    Some(prev) => {
+                        self.edges
+                            .get_mut(prev)
+                            .expect("Undone edge predecessor must exist")
+                            .next_outgoing = Some(id)
+                    }
+                }
+                match prev_incoming {
+                    None => {
+                        self.nodes
+                            .get_mut(edge.to)
+                            .expect("Undone edge target must exist")
+                            .first_incoming = Some(id)
+                    }
+                    Some(prev) => {
+                        self.edges
+                            .get_mut(prev)
+                            .expect("Undone edge predecessor must exist")
+                            .next_incoming = Some(id)
+                    }
+                }
+                self.index.insert_edge(&edge);
+                self.edges.unremove(id, edge);
+                self.session.last_edge = last_edge;
+            }
+            Undo::EdgeLabelAdded { id } => {
+                self.edges
+                    .get_mut(id)
+                    .expect("Undone edge must exist")
+                    .labels
+                    .pop();
+            }
+            Undo::EdgeLabelRemoved {
+                id,
+                label,
+                positions,
+            } => {
+                let edge = self.edges.get_mut(id).expect("Undone edge must exist");
+                for pos in positions {
+                    edge.labels.insert(pos, label.clone());
+                }
+            }
+            Undo::EdgeProp { id, key, old } => {
+                let edge = self.edges.get_mut(id).expect("Undone edge must exist");
+                match old {
+                    Some(old) => edge.props.insert(key, old),
+                    None => edge.props.remove(&key),
+                };
+            }
+        }
+    }
+}
+
+impl Transaction<'_> {
+    /// Marks the current state, [`Transaction::rollback_to`] returns to it
+    pub fn savepoint(&self) -> Savepoint {
+        match self.owns_log() {
+            true => self.graph.tx_position(),
+            false => self.start,
+        }
+    }
+    /// Undoes every mutation made since `savepoint`, the transaction stays open
+    ///
+    /// Fails for savepoints taken before this transaction (or nested transaction) started
+    /// and for ones already rolled back past
+    pub fn rollback_to(&mut self, savepoint: Savepoint) -> GraphResult<()> {
+        if !self.owns_log() {
+            return Err(replaced("roll back"));
+        }
+        let current = self.graph.tx_position();
+        if savepoint.undo < self.start.undo
+            || savepoint.ops < self.start.ops
+            || savepoint.undo > current.undo
+            || savepoint.ops > current.ops
+        {
+            return Err(Error::InvalidIndex {
+                index: "savepoint".to_owned(),
+                reason: "not part of this transaction or already rolled back past".to_owned(),
+            });
+        }
+        self.graph.rollback(savepoint);
+        Ok(())
+    }
+    /// Journals the transaction's ops as one record, rolling everything back if that fails
+    fn commit(mut self) -> GraphResult<Vec<Op>> {
+        self.done = true;
+        if !self.owns_log() {
+            return Err(replaced("commit"));
+        }
+        if !self.outermost {
+            return Ok(Vec::new());
+        }
+        let ops = std::mem::take(
+            &mut self
+                .graph
+                .session
+                .tx
+                .as_mut()
+                .expect("Checked transaction")
+                .ops,
+        );
+        let logged = match self.graph.session.journal.as_mut() {
+            Some(journal) if !ops.is_empty() => journal.append(&ops),
+            _ => Ok(()),
+        };
+        if logged.is_err() {
+            self.graph.rollback(self.start);
+        }
+        self.graph.session.tx = None;
+        if logged.is_ok()
+            && self
+                .graph
+                .session
+                .journal
+                .as_ref()
+                .is_some_and(Journal::wants_checkpoint)
+        {
+            //the commit is in the log already, a failed checkpoint leaves the log as it was
+            //and the next write tries again
+            let _ = self.graph.checkpoint();
+        }
+        logged.map(|()| ops)
+    }
+    /// False once the graph behind the transaction was replaced
+    fn owns_log(&self) -> bool {
+        self.graph
+            .session
+            .tx
+            .as_ref()
+            .is_some_and(|tx| tx.id == self.log)
+    }
+}
+
+fn replaced(operation: &str) -> Error {
+    Error::InvalidOperation {
+        operation: operation.to_owned(),
+        reason: "the transaction's graph was replaced".to_owned(),
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        if self.done || !self.owns_log() {
+            return;
+        }
+        self.graph.rollback(self.start);
+        if self.outermost {
+            self.graph.session.tx = None;
+        }
+    }
+}
+
+impl Deref for Transaction<'_> {
+    type Target = Graph;
+    fn deref(&self) -> &Graph {
+        self.graph
+    }
+}
+
+impl DerefMut for Transaction<'_> {
+    fn deref_mut(&mut self) -> &mut Graph {
+        self.graph
+    }
+}
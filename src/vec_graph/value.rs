@@ -0,0 +1,328 @@
+use core::fmt;
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+};
+
+/// Value of a node or edge property
+///
+/// `Eq`, `Ord` and `Hash` are total so values can be sorted and used as index keys:
+/// floats compare with `f64::total_cmp`, values of different types are ordered
+/// `Null < Bool < Int/Float < Str < Bytes < List < Map`, and ints and floats compare
+/// numerically with the int first on a tie, so `Int(1) != Float(1.0)`.
+/// Queries want null-aware numeric comparisons instead, see [`PropValue::compare`] and [`PropValue::equals`].
+#[derive(Debug, Clone, Default)]
+pub enum PropValue {
+    #[default]
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    List(Vec<PropValue>),
+    Map(BTreeMap<String, PropValue>),
+}
+
+impl PropValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PropValue::Null => "null",
+            PropValue::Bool(_) => "bool",
+            PropValue::Int(_) => "int",
+            PropValue::Float(_) => "float",
+            PropValue::Str(_) => "string",
+            PropValue::Bytes(_) => "bytes",
+            PropValue::List(_) => "list",
+            PropValue::Map(_) => "map",
+        }
+    }
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        matches!(self, PropValue::Null)
+    }
+    #[inline]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            PropValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            PropValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            PropValue::Float(x) => Some(*x),
+            _ => None,
+        }
+    }
+    /// Ints and floats as f64
+    #[inline]
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            PropValue::Int(i) => Some(*i as f64),
+            PropValue::Float(x) => Some(*x),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PropValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            PropValue::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn as_list(&self) -> Option<&[PropValue]> {
+        match self {
+            PropValue::List(l) => Some(l),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn as_map(&self) -> Option<&BTreeMap<String, PropValue>> {
+        match self {
+            PropValue::Map(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    /// Query comparison: numbers compare across int and float, null or mismatched types give None
+    pub fn compare(&self, other: &PropValue) -> Option<Ordering> {
+        use PropValue::*;
+        match (self, other) {
+            (Null, _) | (_, Null) => None,
+            (Int(a), Int(b)) => Some(a.cmp(b)),
+            (Int(_) | Float(_), Int(_) | Float(_)) => {
+                self.as_number()?.partial_cmp(&other.as_number()?)
+            }
+            (Bool(a), Bool(b)) => Some(a.cmp(b)),
+            (Str(a), Str(b)) => Some(a.cmp(b)),
+            (Bytes(a), Bytes(b)) => Some(a.cmp(b)),
+            (List(_), List(_)) | (Map(_), Map(_)) => Some(self.cmp(other)),
+            _ => None,
+        }
+    }
+    /// Query equality: like [`PropValue::compare`], `1 = 1.0` holds and anything involving null is None
+    pub fn equals(&self, other: &PropValue) -> Option<bool> {
+        use PropValue::*;
+        match (self, other) {
+            (Null, _) | (_, Null) => None,
+            (Int(_) | Float(_), Int(_) | Float(_)) => {
+                Some(self.compare(other) == Some(Ordering::Equal))
+            }
+            _ => Some(self == other),
+        }
+    }
+
+    /// Deepest nesting of lists and maps a graph stores, deeper values are refused when set and when read
+    pub const MAX_DEPTH: usize = 32;
+
+    /// Whether lists and maps nest more than `depth` levels, looks no further than that
+    pub(crate) fn nested_deeper_than(&self, depth: usize) -> bool {
+        match self {
+            PropValue::List(items) => {
+                depth == 0 || items.iter().any(|v| v.nested_deeper_than(depth - 1))
+            }
+            PropValue::Map(entries) => {
+                depth == 0 || entries.values().any(|v| v.nested_deeper_than(depth - 1))
+            }
+            _ => false,
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            PropValue::Null => 0,
+            PropValue::Bool(_) => 1,
+            PropValue::Int(_) | PropValue::Float(_) => 2,
+            PropValue::Str(_) => 3,
+            PropValue::Bytes(_) => 4,
+            PropValue::List(_) => 5,
+            PropValue::Map(_) => 6,
+        }
+    }
+}
+
+impl PartialEq for PropValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PropValue {}
+
+impl PartialOrd for PropValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PropValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        use PropValue::*;
+        match (self, other) {
+            (Null, Null) => Ordering::Equal,
+            (Bool(a), Bool(b)) => a.cmp(b),
+            (Int(a), Int(b)) => a.cmp(b),
+            (Float(a), Float(b)) => a.total_cmp(b),
+            //numerically, ints before floats when equal so Int(1) and Float(1.0) stay distinct
+            (Int(a), Float(b)) => (*a as f64).total_cmp(b).then(Ordering::Less),
+            (Float(a), Int(b)) => a.total_cmp(&(*b as f64)).then(Ordering::Greater),
+            (Str(a), Str(b)) => a.cmp(b),
+            (Bytes(a), Bytes(b)) => a.cmp(b),
+            (List(a), List(b)) => a.cmp(b),
+            (Map(a), Map(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl Hash for PropValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            PropValue::Null => {}
+            PropValue::Bool(b) => b.hash(state),
+            PropValue::Int(i) => i.hash(state),
+            PropValue::Float(x) => x.to_bits().hash(state),
+            PropValue::Str(s) => s.hash(state),
+            PropValue::Bytes(b) => b.hash(state),
+            PropValue::List(l) => l.hash(state),
+            PropValue::Map(m) => m.hash(state),
+        }
+    }
+}
+
+impl fmt::Display for PropValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropValue::Null => write!(f, "null"),
+            PropValue::Bool(b) => write!(f, "{}", b),
+            PropValue::Int(i) => write!(f, "{}", i),
+            PropValue::Float(x) => write!(f, "{:?}", x),
+            PropValue::Str(s) => write!(f, "{}", s),
+            PropValue::Bytes(b) => {
+                write!(f, "0x")?;
+                b.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+            PropValue::List(l) => {
+                write!(f, "[")?;
+                for (i, v) in l.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    v.fmt_nested(f)?;
+                }
+                write!(f, "]")
+            }
+            PropValue::Map(m) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in m.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: ", k)?;
+                    v.fmt_nested(f)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+impl PropValue {
+    //strings are quoted inside lists and maps so `["a, b"]` stays readable
+    fn fmt_nested(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropValue::Str(s) => write!(f, "{:?}", s),
+            other => write!(f, "{}", other),
+        }
+    }
+}
+
+impl From<bool> for PropValue {
+    fn from(value: bool) -> Self {
+        PropValue::Bool(value)
+    }
+}
+
+impl From<i64> for PropValue {
+    fn from(value: i64) -> Self {
+        PropValue::Int(value)
+    }
+}
+
+impl From<i32> for PropValue {
+    fn from(value: i32) -> Self {
+        PropValue::Int(value as i64)
+    }
+}
+
+impl From<f64> for PropValue {
+    fn from(value: f64) -> Self {
+        PropValue::Float(value)
+    }
+}
+
+impl From<&str> for PropValue {
+    fn from(value: &str) -> Self {
+        PropValue::Str(value.to_owned())
+    }
+}
+
+impl From<String> for PropValue {
+    fn from(value: String) -> Self {
+        PropValue::Str(value)
+    }
+}
+
+impl From<&String> for PropValue {
+    fn from(value: &String) -> Self {
+        PropValue::Str(value.to_owned())
+    }
+}
+
+impl From<Vec<u8>> for PropValue {
+    fn from(value: Vec<u8>) -> Self {
+        PropValue::Bytes(value)
+    }
+}
+
+impl From<&[u8]> for PropValue {
+    fn from(value: &[u8]) -> Self {
+        PropValue::Bytes(value.to_owned())
+    }
+}
+
+impl From<Vec<PropValue>> for PropValue {
+    fn from(value: Vec<PropValue>) -> Self {
+        PropValue::List(value)
+    }
+}
+
+impl From<BTreeMap<String, PropValue>> for PropValue {
+    fn from(value: BTreeMap<String, PropValue>) -> Self {
+        PropValue::Map(value)
+    }
+}
+
+impl<T: Into<PropValue>> From<Option<T>> for PropValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(PropValue::Null, Into::into)
+    }
+}
@@ -0,0 +1,282 @@
+//! Write-ahead log behind [`Graph::open`]
+//!
+//! A database is two files: the snapshot at `path`, written by checkpoints, and the log at `path-wal`.
+//! The log starts with a header naming the snapshot it continues from, followed by records of
+//! `[payload length u32][crc32 u32][payload]`, one record per public mutation.
+//! A record is appended and synced before its change touches memory,
+//! recovery replays every whole record and cuts off a torn tail left by a crash.
+//! A record that doesn't fit the u32 length field is refused, a transaction that needs one rolls back
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use super::codec::{crc32, Reader, Writer};
+use super::op::Op;
+use super::{snapshot, Error, Graph, GraphResult};
+
+const MAGIC: &[u8; 8] = b"GDBWAL\0\0";
+const HEADER_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalOptions {
+    /// Fold the log into the snapshot once it holds this many records, None to only checkpoint manually
+    pub checkpoint_every: Option<usize>,
+    /// fsync after every record. Without it a crash of the machine (not just the process) can lose recent changes
+    pub sync: bool,
+}
+
+impl Default for WalOptions {
+    fn default() -> Self {
+        WalOptions {
+            checkpoint_every: Some(1024),
+            sync: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Journal {
+    path: PathBuf,
+    file: File,
+    //sequence number of the snapshot this log continues from
+    seq: u64,
+    //bytes of whole records in the file
+    len: u64,
+    records: usize,
+    options: WalOptions,
+}
+
+impl Journal {
+    pub(crate) fn wants_checkpoint(&self) -> bool {
+        self.options
+            .checkpoint_every
+            .is_some_and(|every| self.records >= every)
+    }
+
+    /// Writes `ops` as one record, a failed write is cut back off so the log stays replayable
+    pub(crate) fn append(&mut self, ops: &[Op]) -> GraphResult<()> {
+        let mut payload = Writer::default();
+        payload.u32(self.length(ops.len(), "ops")?);
+        ops.iter().for_each(|op| op.encode(&mut payload));
+        let payload = payload.finish().map_err(Error::io(format!(
+            "appending to {}",
+            wal_path(&self.path).display()
+        )))?;
+        let mut record = Writer::default();
+        record.u32(self.length(payload.len(), "bytes")?);
+        record.u32(crc32(&payload));
+        record.buf.extend_from_slice(&payload);
+
+        let written = self.file.write_all(&record.buf).and_then(|_| {
+            if self.options.sync {
+                self.file.sync_data()
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(err) = written {
+            let _ = self.file.set_len(self.len);
+            let _ = self.file.seek(SeekFrom::Start(self.len));
+            return Err(Error::io(format!(
+                "appending to {}",
+                wal_path(&self.path).display()
+            ))(err));
+        }
+        self.len += record.buf.len() as u64;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// `len` as a record's u32 length field, an error for records too large to frame
+    fn length(&self, len: usize, unit: &str) -> GraphResult<u32> {
+        u32::try_from(len).map_err(|_| {
+            Error::io(format!("appending to {}", wal_path(&self.path).display()))(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "record of {} {} is over the {} a log record can hold",
+                    len,
+                    unit,
+                    u32::MAX
+                ),
+            ))
+        })
+    }
+
+    /// Empties the log and points it at snapshot `seq`
+    fn reset(&mut self, seq: u64) -> GraphResult<()> {
+        self.file
+            .set_len(0)
+            .and_then(|_| self.file.seek(SeekFrom::Start(0)))
+            .and_then(|_| self.file.write_all(&header(seq)))
+            .and_then(|_| self.file.sync_all())
+            .map_err(Error::io(format!(
+                "resetting {}",
+                wal_path(&self.path).display()
+            )))?;
+        self.seq = seq;
+        self.len = HEADER_LEN as u64;
+        self.records = 0;
+        Ok(())
+    }
+}
+
+fn header(seq: u64) -> [u8; HEADER_LEN] {
+    let mut header = [0; HEADER_LEN];
+    header[..8].copy_from_slice(MAGIC);
+    header[8..].copy_from_slice(&seq.to_le_bytes());
+    header
+}
+
+/// Log file belonging to the snapshot at `path`
+pub(crate) fn wal_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push("-wal");
+    PathBuf::from(name)
+}
+
+/// Ops of the whole record at `pos` and where the next one starts, None at the end or at a torn record
+fn read_record(bytes: &[u8], pos: usize) -> GraphResult<Option<(Vec<Op>, usize)>> {
+    let Some(head) = bytes.get(pos..pos + 8) else {
+        return Ok(None);
+    };
+    let len = u32::from_le_bytes(head[..4].try_into().unwrap()) as usize;
+    let crc = u32::from_le_bytes(head[4..].try_into().unwrap());
+    let Some(payload) = bytes.get(pos + 8..pos + 8 + len) else {
+        return Ok(None);
+    };
+    if crc32(payload) != crc {
+        return Ok(None);
+    }
+    //a record that passed its checksum but doesn't decode wasn't torn, it's a bug or tampering
+    let mut r = Reader::new(payload);
+    let ops = (0..r.count32()?)
+        .map(|_| Op::decode(&mut r))
+        .collect::<GraphResult<Vec<Op>>>()?;
+    Ok(Some((ops, pos + 8 + len)))
+}
+
+impl Graph {
+    /// Opens the database at `path`, creating it if missing, see [`Graph::open_with`]
+    pub fn open<P: AsRef<Path>>(path: P) -> GraphResult<Graph> {
+        Self::open_with(path, WalOptions::default())
+    }
+    /// Opens the database at `path` and recovers it from the last checkpoint plus the log
+    ///
+    /// Changes are logged before they are applied, so after a crash the graph comes back
+    /// with every mutation whose call returned and none of a half written one
+    pub fn open_with<P: AsRef<Path>>(path: P, options: WalOptions) -> GraphResult<Graph> {
+        let path = path.as_ref().to_path_buf();
+        let (mut graph, seq) = match fs::read(&path) {
+            Ok(bytes) => snapshot::decode(&bytes)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => (Graph::new(), 0),
+            Err(err) => {
+                return Err(Error::io(format!("reading snapshot {}", path.display()))(
+                    err,
+                ))
+            }
+        };
+
+        let wal = wal_path(&path);
+        let mut bytes = Vec::new();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&wal)
+            .and_then(|mut file| file.read_to_end(&mut bytes).map(|_| file))
+            .map_err(Error::io(format!("reading {}", wal.display())))?;
+
+        let mut records = 0;
+        let mut len = 0;
+        //shorter than a header means the crash hit while the log was being reset
+        if bytes.len() >= HEADER_LEN {
+            if &bytes[..8] != MAGIC {
+                return Err(Error::corrupt(format!(
+                    "{} is not a graph log",
+                    wal.display()
+                )));
+            }
+            let wal_seq = u64::from_le_bytes(bytes[8..HEADER_LEN].try_into().unwrap());
+            if wal_seq > seq {
+                return Err(Error::corrupt(format!(
+                    "Log continues snapshot {} but snapshot {} was found",
+                    wal_seq, seq
+                )));
+            }
+            //an older log was already folded into the snapshot by a checkpoint that crashed before resetting it
+            if wal_seq == seq {
+                len = HEADER_LEN;
+                while let Some((ops, next)) = read_record(&bytes, len)? {
+                    for op in ops {
+                        graph.check(&op).map_err(|err| {
+                            Error::corrupt(format!("Log record at byte {} fails: {}", len, err))
+                        })?;
+                        graph.apply(op);
+                    }
+                    len = next;
+                    records += 1;
+                }
+            }
+        }
+
+        let mut journal = Journal {
+            path,
+            file,
+            seq,
+            len: len as u64,
+            records,
+            options,
+        };
+        if len == 0 {
+            journal.reset(seq)?;
+        } else if len < bytes.len() {
+            journal
+                .file
+                .set_len(len as u64)
+                .and_then(|_| journal.file.sync_all())
+                .map_err(Error::io(format!("cutting torn tail of {}", wal.display())))?;
+        }
+        journal
+            .file
+            .seek(SeekFrom::Start(journal.len))
+            .map_err(Error::io(format!("seeking in {}", wal.display())))?;
+        graph.session.journal = Some(journal);
+        Ok(graph)
+    }
+    /// Writes the whole graph to the snapshot file and empties the log
+    ///
+    /// The new snapshot is written next to the old one and renamed over it,
+    /// so a crash at any point leaves either the old snapshot and log or the new snapshot
+    pub fn checkpoint(&mut self) -> GraphResult<()> {
+        if self.in_transaction() {
+            return Err(Error::InvalidOperation {
+                operation: "checkpoint".to_owned(),
+                reason: "a transaction is running, its changes aren't committed yet".to_owned(),
+            });
+        }
+        let mut journal = self
+            .session
+            .journal
+            .take()
+            .ok_or_else(|| Error::InvalidOperation {
+                operation: "checkpoint".to_owned(),
+                reason: "only graphs opened with Graph::open have a file".to_owned(),
+            })?;
+        let result = self.write_checkpoint(&mut journal);
+        self.session.journal = Some(journal);
+        result
+    }
+    fn write_checkpoint(&self, journal: &mut Journal) -> GraphResult<()> {
+        let seq = journal.seq + 1;
+        let bytes = snapshot::encode(self, seq).map_err(Error::io(format!(
+            "writing snapshot {}",
+            journal.path.display()
+        )))?;
+        snapshot::write_atomic(&journal.path, &bytes)?;
+        journal.reset(seq)
+    }
+}
@@ -1,116 +1,454 @@
-use std::collections::HashMap;
+//! Graph that keeps each node's edges inside the node: outgoing [`Link`]s with their relation,
+//! labels and props, plus the source of every incoming link
+//!
+//! A node and everything a traversal needs from it sit together, so walking outgoing links
+//! doesn't jump around an edge table. Aliases are unique and the API is keyed by them
+//! ```
+//! # use graph_db::vgraph::Graph;
+//! let mut graph = Graph::new();
+//! graph
+//!     .add_node("istanbul")?
+//!     .add_node("sisli")?
+//!     .link("includes", "istanbul", "sisli")?
+//!     .mut_node("sisli", |n| {
+//!         n.add_label("ilce").add_prop("nufus", 274420);
+//!     })?;
+//! let istanbul = graph.get_node("istanbul")?;
+//! assert_eq!(istanbul.links()[0].relation(), "includes");
+//! assert_eq!(graph.get_node("sisli")?.incoming().len(), 1);
+//! # Ok::<(), graph_db::vec_graph::Error>(())
+//! ```
 
-struct NodeIndex(usize);
-impl NodeIndex {
-    fn new(index: usize) -> Self {
-        NodeIndex(index)
-    }
-}
-impl From<usize> for NodeIndex {
-    fn from(value: usize) -> Self {
-        NodeIndex(value)
-    }
-}
+use std::collections::{BTreeMap, HashMap};
+
+use crate::algo::Direction;
+use crate::store::{sorted, GraphStore};
+use crate::vec_graph::{Arena, EdgeIndex, Error, GraphResult, NodeIndex, PropValue};
 
-struct Link {
+/// Outgoing edge, stored in its source node
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    id: EdgeIndex,
     relation: String,
     to: NodeIndex,
+    labels: Vec<String>,
+    props: HashMap<String, PropValue>,
 }
+
 impl Link {
-    fn new(relation: &str, to: NodeIndex) -> Self {
+    fn new(id: EdgeIndex, relation: &str, to: NodeIndex) -> Self {
         Link {
+            id,
             relation: relation.to_owned(),
             to,
+            labels: Vec::new(),
+            props: HashMap::new(),
         }
     }
+    #[inline]
+    pub fn id(&self) -> EdgeIndex {
+        self.id
+    }
+    #[inline]
+    pub fn relation(&self) -> &str {
+        &self.relation
+    }
+    #[inline]
+    pub fn to(&self) -> &NodeIndex {
+        &self.to
+    }
+    #[inline]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+    #[inline]
+    pub fn props(&self) -> &HashMap<String, PropValue> {
+        &self.props
+    }
+    #[inline]
+    pub fn get_prop(&self, key: &str) -> Option<&PropValue> {
+        self.props.get(key)
+    }
 }
 
-struct Node {
-    pub alias: String,
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    id: NodeIndex,
+    //private so it can't drift from the graph's alias map
+    alias: String,
     labels: Vec<String>,
-    props: HashMap<String, String>,
-    rels: Vec<Link>, //outgoing node
+    props: HashMap<String, PropValue>,
+    links: Vec<Link>,
+    //(link, its source) for every link pointing here
+    incoming: Vec<(EdgeIndex, NodeIndex)>,
 }
+
 impl Node {
-    fn new(alias: String) -> Self {
+    fn new(id: NodeIndex, alias: &str) -> Self {
         Node {
-            alias: alias,
+            id,
+            alias: alias.to_owned(),
             labels: Vec::new(),
             props: HashMap::new(),
-            rels: Vec::new(),
+            links: Vec::new(),
+            incoming: Vec::new(),
         }
     }
-    fn add_label(&mut self, label: &str) -> &mut Self {
-        self.labels.push(label.to_owned());
+    pub fn add_label<S: AsRef<str>>(&mut self, label: S) -> &mut Self {
+        self.labels.push(label.as_ref().to_owned());
         self
     }
-    fn remove_label(&mut self, label: &str) -> &mut Self {
-        self.labels.retain(|x| x != label);
+    pub fn remove_label<S: AsRef<str>>(&mut self, label: S) -> &mut Self {
+        self.labels.retain(|x| x != label.as_ref());
         self
     }
-    fn add_prop(&mut self, key: &str, val: &str) -> &mut Self {
-        self.props.insert(key.to_owned(), val.to_owned());
+    pub fn add_prop<S: AsRef<str>, V: Into<PropValue>>(&mut self, key: S, val: V) -> &mut Self {
+        self.props.insert(key.as_ref().to_owned(), val.into());
         self
     }
-    fn remove_prop(&mut self, key: &str) -> &mut Self {
-        self.props.remove(key);
+    pub fn remove_prop<S: AsRef<str>>(&mut self, key: S) -> &mut Self {
+        self.props.remove(key.as_ref());
         self
     }
-    fn add_relation(&mut self, relation: &str, to: NodeIndex) -> &mut Self {
-        self.rels.push(Link::new(relation, to));
-        self
+    #[inline]
+    pub fn id(&self) -> NodeIndex {
+        self.id
     }
-    fn remove_relation(&mut self, relation: &str) -> &mut Self {
-        self.rels.retain(|x| x.relation != relation);
-        self
+    #[inline]
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+    #[inline]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+    #[inline]
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|x| x == label)
+    }
+    #[inline]
+    pub fn props(&self) -> &HashMap<String, PropValue> {
+        &self.props
+    }
+    #[inline]
+    pub fn get_prop(&self, key: &str) -> Option<&PropValue> {
+        self.props.get(key)
+    }
+    /// Outgoing links, oldest first
+    #[inline]
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+    /// (link, source node) of every link to this node, oldest first
+    #[inline]
+    pub fn incoming(&self) -> &[(EdgeIndex, NodeIndex)] {
+        &self.incoming
     }
 }
 
-struct Graph {
-    nodes: Vec<Node>,
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    nodes: Arena<Node>,
+    //source node of every link, links live in their source's `links`
+    edges: Arena<NodeIndex>,
     names: HashMap<String, NodeIndex>,
 }
 
 impl Graph {
-    fn new() -> Self {
-        Graph {
-            nodes: Vec::new(),
-            names: HashMap::new(),
+    pub fn new() -> Self {
+        Graph::default()
+    }
+    /// Fails with `ConstraintViolation` if a node already has `alias`
+    pub fn add_node(&mut self, alias: &str) -> GraphResult<&mut Self> {
+        self.insert_node(alias)?;
+        Ok(self)
+    }
+    /// Removes the node and every link from or to it
+    pub fn remove_node(&mut self, alias: &str) -> GraphResult<&mut Self> {
+        let id = self.id_of(alias)?;
+        self.delete_node(&id)?;
+        Ok(self)
+    }
+    #[inline]
+    pub fn id_of(&self, alias: &str) -> GraphResult<NodeIndex> {
+        self.names
+            .get(alias)
+            .copied()
+            .ok_or_else(|| Error::AliasNotFound {
+                alias: alias.to_owned(),
+            })
+    }
+    pub fn get_node(&self, alias: &str) -> GraphResult<&Node> {
+        self.get_node_by_id(&self.id_of(alias)?)
+    }
+    pub fn get_node_mut(&mut self, alias: &str) -> GraphResult<&mut Node> {
+        let id = self.id_of(alias)?;
+        self.get_node_mut_by_id(&id)
+    }
+    #[inline]
+    pub fn get_node_by_id(&self, id: &NodeIndex) -> GraphResult<&Node> {
+        self.nodes.get(*id).ok_or(Error::NodeNotFound { id: *id })
+    }
+    #[inline]
+    fn get_node_mut_by_id(&mut self, id: &NodeIndex) -> GraphResult<&mut Node> {
+        self.nodes
+            .get_mut(*id)
+            .ok_or(Error::NodeNotFound { id: *id })
+    }
+    pub fn mut_node<F>(&mut self, alias: &str, f: F) -> GraphResult<&mut Self>
+    where
+        F: FnOnce(&mut Node),
+    {
+        f(self.get_node_mut(alias)?);
+        Ok(self)
+    }
+    pub fn iter_nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter::<NodeIndex>().map(|(_, node)| node)
+    }
+    /// Adds a `relation` link from `from` to `to`
+    pub fn link(&mut self, relation: &str, from: &str, to: &str) -> GraphResult<&mut Self> {
+        let (from, to) = (self.id_of(from)?, self.id_of(to)?);
+        self.insert_link(relation, from, to)?;
+        Ok(self)
+    }
+    /// Removes every `relation` link from `from` to `to`
+    pub fn unlink(&mut self, relation: &str, from: &str, to: &str) -> GraphResult<&mut Self> {
+        let (from, to) = (self.id_of(from)?, self.id_of(to)?);
+        let links: Vec<EdgeIndex> = self
+            .get_node_by_id(&from)?
+            .links
+            .iter()
+            .filter(|l| l.relation == relation && l.to == to)
+            .map(|l| l.id)
+            .collect();
+        for id in links {
+            self.delete_edge(&id)?;
         }
+        Ok(self)
+    }
+    /// The link behind `id` with its source node
+    pub fn get_link(&self, id: &EdgeIndex) -> GraphResult<(&Link, NodeIndex)> {
+        let from = *self.edges.get(*id).ok_or(Error::EdgeNotFound { id: *id })?;
+        let link = self
+            .get_node_by_id(&from)
+            .expect("Link source must exist")
+            .links
+            .iter()
+            .find(|l| l.id == *id)
+            .expect("Indexed link must be in its source");
+        Ok((link, from))
     }
-    ///aliases are unique
-    fn add_node(&mut self, alias: &str) -> &mut Self {
-        if self.names.contains_key(alias) {
-            //skips silently?
-            self
-        } else {
-            self.nodes.push(Node::new(alias.to_owned()));
-            self.names.insert(alias.to_owned(), self.nodes.len().into());
-            self
+    fn get_link_mut(&mut self, id: &EdgeIndex) -> GraphResult<&mut Link> {
+        let from = *self.edges.get(*id).ok_or(Error::EdgeNotFound { id: *id })?;
+        Ok(self
+            .nodes
+            .get_mut(from)
+            .expect("Link source must exist")
+            .links
+            .iter_mut()
+            .find(|l| l.id == *id)
+            .expect("Indexed link must be in its source"))
+    }
+
+    fn insert_node(&mut self, alias: &str) -> GraphResult<NodeIndex> {
+        if let Some(id) = self.names.get(alias) {
+            return Err(Error::ConstraintViolation {
+                constraint: "unique aliases".to_owned(),
+                detail: format!("node {} is already `{}`", id, alias),
+            });
         }
+        let id: NodeIndex = self.nodes.next_key();
+        self.nodes.insert::<NodeIndex>(Node::new(id, alias));
+        self.names.insert(alias.to_owned(), id);
+        Ok(id)
     }
-    fn remove_node(&mut self, alias: &str) -> &mut Self {
-        self.names.remove(alias).map(|x| {
-            self.nodes.swap_remove(x.0);
-            self.nodes
-                .get(x.0)
-                .map(|n| self.names.insert(n.alias.to_owned(), x));
-        });
-        self
+    fn insert_link(
+        &mut self,
+        relation: &str,
+        from: NodeIndex,
+        to: NodeIndex,
+    ) -> GraphResult<EdgeIndex> {
+        self.get_node_by_id(&from)?;
+        self.get_node_by_id(&to)?;
+        let id = self.edges.insert(from);
+        self.get_node_mut_by_id(&from)?
+            .links
+            .push(Link::new(id, relation, to));
+        self.get_node_mut_by_id(&to)?.incoming.push((id, from));
+        Ok(id)
+    }
+}
+
+impl GraphStore for Graph {
+    #[inline]
+    fn create_node(&mut self, alias: &str) -> GraphResult<NodeIndex> {
+        self.insert_node(alias)
+    }
+    fn delete_node(&mut self, id: &NodeIndex) -> GraphResult<()> {
+        let node = self
+            .nodes
+            .remove(*id)
+            .ok_or(Error::NodeNotFound { id: *id })?;
+        self.names.remove(&node.alias);
+        //self links were in both lists and went with the node
+        for link in node.links.iter() {
+            self.edges.remove(link.id);
+            if let Some(target) = self.nodes.get_mut(link.to) {
+                target.incoming.retain(|(edge, _)| *edge != link.id);
+            }
+        }
+        for (edge, from) in node.incoming.iter() {
+            self.edges.remove(*edge);
+            if let Some(source) = self.nodes.get_mut(*from) {
+                source.links.retain(|l| l.id != *edge);
+            }
+        }
+        Ok(())
+    }
+    #[inline]
+    fn has_node(&self, id: &NodeIndex) -> bool {
+        self.nodes.get(*id).is_some()
+    }
+    fn node_ids(&self) -> Vec<NodeIndex> {
+        self.nodes.iter::<NodeIndex>().map(|(id, _)| id).collect()
+    }
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+    fn node_alias(&self, id: &NodeIndex) -> GraphResult<String> {
+        Ok(self.get_node_by_id(id)?.alias.clone())
+    }
+    fn ids_with_alias(&self, alias: &str) -> Vec<NodeIndex> {
+        self.names.get(alias).copied().into_iter().collect()
+    }
+
+    fn node_labels(&self, id: &NodeIndex) -> GraphResult<Vec<String>> {
+        Ok(self.get_node_by_id(id)?.labels.clone())
+    }
+    fn node_has_label(&self, id: &NodeIndex, label: &str) -> GraphResult<bool> {
+        Ok(self.get_node_by_id(id)?.has_label(label))
+    }
+    fn label_node(&mut self, id: &NodeIndex, label: &str) -> GraphResult<()> {
+        self.get_node_mut_by_id(id)?.add_label(label);
+        Ok(())
+    }
+    fn unlabel_node(&mut self, id: &NodeIndex, label: &str) -> GraphResult<()> {
+        self.get_node_mut_by_id(id)?.remove_label(label);
+        Ok(())
+    }
+    fn node_prop(&self, id: &NodeIndex, key: &str) -> GraphResult<Option<PropValue>> {
+        Ok(self.get_node_by_id(id)?.get_prop(key).cloned())
+    }
+    fn node_props(&self, id: &NodeIndex) -> GraphResult<BTreeMap<String, PropValue>> {
+        Ok(sorted(&self.get_node_by_id(id)?.props))
+    }
+    fn set_node_prop(&mut self, id: &NodeIndex, key: &str, val: PropValue) -> GraphResult<()> {
+        self.get_node_mut_by_id(id)?.add_prop(key, val);
+        Ok(())
     }
-    fn get_node_mut(&mut self, alias: &str) -> &mut Node {
+    fn unset_node_prop(&mut self, id: &NodeIndex, key: &str) -> GraphResult<()> {
+        self.get_node_mut_by_id(id)?.remove_prop(key);
+        Ok(())
+    }
+
+    #[inline]
+    fn create_edge(
+        &mut self,
+        relation: &str,
+        from: &NodeIndex,
+        to: &NodeIndex,
+    ) -> GraphResult<EdgeIndex> {
+        self.insert_link(relation, *from, *to)
+    }
+    fn delete_edge(&mut self, id: &EdgeIndex) -> GraphResult<()> {
+        let from = self
+            .edges
+            .remove(*id)
+            .ok_or(Error::EdgeNotFound { id: *id })?;
+        let source = self.nodes.get_mut(from).expect("Link source must exist");
+        let at = source
+            .links
+            .iter()
+            .position(|l| l.id == *id)
+            .expect("Indexed link must be in its source");
+        let link = source.links.remove(at);
         self.nodes
-            .get_mut(self.names.get(alias).unwrap().0)
-            .unwrap()
+            .get_mut(link.to)
+            .expect("Link target must exist")
+            .incoming
+            .retain(|(edge, _)| edge != id);
+        Ok(())
     }
-    fn get_node(&self, alias: &str) -> &Node {
-        self.nodes.get(self.names.get(alias).unwrap().0).unwrap()
+    #[inline]
+    fn has_edge(&self, id: &EdgeIndex) -> bool {
+        self.edges.get(*id).is_some()
     }
-    fn mut_node<F>(&mut self, alias: &str, mut f: F) -> &mut Graph
-    where
-        F: FnMut(&mut Node),
-    {
-        f(self.get_node_mut(alias));
-        self
+    fn edge_ids(&self) -> Vec<EdgeIndex> {
+        self.edges.iter::<EdgeIndex>().map(|(id, _)| id).collect()
+    }
+    #[inline]
+    fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+    fn edge_relation(&self, id: &EdgeIndex) -> GraphResult<String> {
+        Ok(self.get_link(id)?.0.relation.clone())
+    }
+    fn edge_ends(&self, id: &EdgeIndex) -> GraphResult<(NodeIndex, NodeIndex)> {
+        let (link, from) = self.get_link(id)?;
+        Ok((from, link.to))
+    }
+    fn edge_labels(&self, id: &EdgeIndex) -> GraphResult<Vec<String>> {
+        Ok(self.get_link(id)?.0.labels.clone())
+    }
+    fn label_edge(&mut self, id: &EdgeIndex, label: &str) -> GraphResult<()> {
+        self.get_link_mut(id)?.labels.push(label.to_owned());
+        Ok(())
+    }
+    fn unlabel_edge(&mut self, id: &EdgeIndex, label: &str) -> GraphResult<()> {
+        self.get_link_mut(id)?.labels.retain(|x| x != label);
+        Ok(())
+    }
+    fn edge_prop(&self, id: &EdgeIndex, key: &str) -> GraphResult<Option<PropValue>> {
+        Ok(self.get_link(id)?.0.get_prop(key).cloned())
+    }
+    fn edge_props(&self, id: &EdgeIndex) -> GraphResult<BTreeMap<String, PropValue>> {
+        Ok(sorted(&self.get_link(id)?.0.props))
+    }
+    fn set_edge_prop(&mut self, id: &EdgeIndex, key: &str, val: PropValue) -> GraphResult<()> {
+        self.get_link_mut(id)?.props.insert(key.to_owned(), val);
+        Ok(())
+    }
+    fn unset_edge_prop(&mut self, id: &EdgeIndex, key: &str) -> GraphResult<()> {
+        self.get_link_mut(id)?.props.remove(key);
+        Ok(())
+    }
+
+    fn adjacent(
+        &self,
+        id: &NodeIndex,
+        direction: Direction,
+        relations: &[String],
+    ) -> GraphResult<Vec<(EdgeIndex, NodeIndex)>> {
+        let node = self.get_node_by_id(id)?;
+        let follows = |relation: &String| relations.is_empty() || relations.contains(relation);
+        let mut adjacent = Vec::new();
+        if matches!(direction, Direction::Outgoing | Direction::Both) {
+            adjacent.extend(
+                node.links
+                    .iter()
+                    .filter(|l| follows(&l.relation))
+                    .map(|l| (l.id, l.to)),
+            );
+        }
+        if matches!(direction, Direction::Incoming | Direction::Both) {
+            for (edge, from) in node.incoming.iter() {
+                let (link, _) = self.get_link(edge)?;
+                if follows(&link.relation) {
+                    adjacent.push((*edge, *from));
+                }
+            }
+        }
+        Ok(adjacent)
     }
 }
@@ -0,0 +1,88 @@
+use graph_db::vec_graph::*;
+
+fn first_id(graph: &Graph, alias: &str) -> NodeIndex {
+    graph.get_ids_by_alias(alias).unwrap()[0]
+}
+
+fn roads() -> Graph {
+    let mut graph = Graph::new();
+    graph
+        .add_node("sisli")
+        .unwrap()
+        .add_node("besiktas")
+        .unwrap()
+        .add_node("kadikoy")
+        .unwrap()
+        .add_edges_by_aliases("road", "sisli", "besiktas")
+        .unwrap()
+        .mut_last_edge(|e| e.add_prop("km", 4)?.add_label("highway"))
+        .unwrap()
+        .add_edges_by_aliases("ferry", "besiktas", "kadikoy")
+        .unwrap()
+        .mut_last_edge(|e| e.add_prop("km", 6)?.add_prop("source", "ido"))
+        .unwrap();
+    graph
+}
+
+#[test]
+fn edge_props_and_labels() {
+    let graph = roads();
+    let sisli = first_id(&graph, "sisli");
+    let road = graph.get_outgoing_edges(&sisli)[0];
+    assert_eq!(road.relation(), "road");
+    assert_eq!(road.get_prop("km").and_then(PropValue::as_int), Some(4));
+    assert!(road.has_label("highway"));
+    let fetched = graph.get_edge(&road.id()).unwrap();
+    assert_eq!(fetched, road);
+}
+
+#[test]
+fn mutate_edge_by_id() {
+    let mut graph = roads();
+    let kadikoy = first_id(&graph, "kadikoy");
+    let ferry = graph.get_incoming_edges(&kadikoy)[0].id();
+    graph
+        .mut_edge(&ferry, |e| e.remove_prop("source")?.add_prop("km", 7.5))
+        .unwrap()
+        .add_edge_label(&ferry, "sea")
+        .unwrap();
+    let edge = graph.get_edge_by_idx(&ferry).unwrap();
+    assert_eq!(edge.get_prop("km"), Some(&PropValue::Float(7.5)));
+    assert_eq!(edge.get_prop("source"), None);
+    assert_eq!(edge.labels(), ["sea"]);
+}
+
+#[test]
+fn remove_single_edge() {
+    let mut graph = roads();
+    let sisli = first_id(&graph, "sisli");
+    let besiktas = first_id(&graph, "besiktas");
+    graph.add_edge("road", sisli, besiktas).unwrap();
+    let between: Vec<EdgeIndex> = graph
+        .get_edges_between(&sisli, &besiktas)
+        .iter()
+        .map(|e| e.id())
+        .collect();
+    assert_eq!(between.len(), 2);
+
+    graph.remove_edge(&between[0]).unwrap();
+    assert_eq!(graph.get_edges_between(&sisli, &besiktas).len(), 1);
+    assert_eq!(graph.edge_count(), 2);
+    assert!(matches!(
+        graph.remove_edge(&between[0]),
+        Err(Error::EdgeNotFound { id }) if id == between[0]
+    ));
+    assert!(graph.get_edge(&between[0]).is_err());
+    assert!(graph.edge_ids().all(|id| id != between[0]));
+}
+
+#[test]
+fn node_removal_drops_edges() {
+    let mut graph = roads();
+    let besiktas = first_id(&graph, "besiktas");
+    let ids: Vec<EdgeIndex> = graph.edge_ids().collect();
+    graph.remove_node_by_id(&besiktas).unwrap();
+    assert_eq!(graph.edge_count(), 0);
+    assert!(ids.iter().all(|id| graph.get_edge_by_idx(id).is_none()));
+    assert!(graph.get_last_edge().is_none());
+}
@@ -0,0 +1,65 @@
+use std::error::Error as _;
+
+use graph_db::vec_graph::*;
+
+#[test]
+fn missing_alias_and_node() {
+    let mut graph = Graph::new();
+    graph.add_node("sisli").unwrap();
+    match graph.add_edges_by_aliases("includes", "sisli", "merkez") {
+        Err(Error::AliasNotFound { alias }) => assert_eq!(alias, "merkez"),
+        other => panic!("unexpected {:?}", other.map(|_| ())),
+    }
+    let ghost = NodeIndex::from(7);
+    let err = graph.add_label(&ghost, "x").unwrap_err();
+    assert!(matches!(err, Error::NodeNotFound { id } if id == ghost));
+    assert_eq!(err.to_string(), "Node 7 not found");
+}
+
+#[test]
+fn invalid_indexes() {
+    let mut graph = Graph::new();
+    assert!(matches!(
+        graph.mut_last_node(|n| n.add_label("x")),
+        Err(Error::InvalidIndex { .. })
+    ));
+    match graph.find_ids_by_prop("yas", 3) {
+        Err(Error::InvalidIndex { index, .. }) => assert!(index.contains("yas")),
+        other => panic!("unexpected {:?}", other),
+    }
+    graph.create_prop_index("yas").unwrap();
+    assert!(matches!(
+        graph.create_prop_index("yas"),
+        Err(Error::InvalidIndex { .. })
+    ));
+}
+
+#[test]
+fn query_errors_carry_position() {
+    let graph = Graph::new();
+    match graph.query("MATCH (n RETURN n") {
+        Err(Error::Parse {
+            pos: Some(pos),
+            message,
+        }) => {
+            assert_eq!(pos, 9);
+            assert!(message.contains("RETURN"), "{}", message);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    //parses fine but can't run
+    assert!(matches!(
+        graph.query("MATCH (n) RETURN m"),
+        Err(Error::Parse { pos: None, .. })
+    ));
+}
+
+#[test]
+fn io_errors_keep_their_source() {
+    let dir = std::env::temp_dir().join(format!("graph_db_errors_{}", std::process::id()));
+    let err = Graph::load_snapshot(dir.join("missing.snap")).unwrap_err();
+    assert!(matches!(err, Error::Io { .. }));
+    assert!(err.to_string().contains("missing.snap"));
+    let source = err.source().expect("Io errors have a source");
+    assert!(source.downcast_ref::<std::io::Error>().is_some());
+}
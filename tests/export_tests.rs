@@ -0,0 +1,158 @@
+use std::fs;
+
+use graph_db::{export::Format, vec_graph::*};
+
+fn id(graph: &Graph, alias: &str) -> NodeIndex {
+    graph.get_ids_by_alias(alias).unwrap()[0]
+}
+
+/// sisli -includes-> merkez <-komsu- mcdkoy
+fn city() -> Graph {
+    let mut graph = Graph::new();
+    graph
+        .add_node("sisli")
+        .unwrap()
+        .add_node("merkez")
+        .unwrap()
+        .add_node("mcdkoy")
+        .unwrap();
+    let (sisli, merkez, mcdkoy) = (
+        id(&graph, "sisli"),
+        id(&graph, "merkez"),
+        id(&graph, "mcdkoy"),
+    );
+    graph.add_label(&sisli, "sehir").unwrap();
+    graph.add_label(&merkez, "mahalle").unwrap();
+    graph.add_label(&mcdkoy, "mahalle").unwrap();
+    graph.add_prop(&sisli, "tur", "ilce").unwrap();
+    graph.add_prop(&sisli, "nufus", 274420).unwrap();
+    graph.add_prop(&merkez, "nufus", 1.5e4).unwrap();
+    graph.add_prop(&mcdkoy, "nufus", "bilinmiyor").unwrap();
+    graph
+        .add_edges_by_aliases("includes", "sisli", "merkez")
+        .unwrap()
+        .mut_last_edge(|e| e.add_prop("km", 2.5))
+        .unwrap();
+    graph
+        .add_edges_by_aliases("komsu", "mcdkoy", "merkez")
+        .unwrap();
+    graph
+}
+
+#[test]
+fn dot() {
+    let graph = city();
+    let dot = graph.export().render(Format::Dot);
+    assert_eq!(
+        dot,
+        r#"digraph graph_db {
+  "0" ["nufus"="274420", "tur"="ilce", label="sisli\n:sehir", alias="sisli", labels="sehir"];
+  "1" ["nufus"="15000.0", label="merkez\n:mahalle", alias="merkez", labels="mahalle"];
+  "2" ["nufus"="bilinmiyor", label="mcdkoy\n:mahalle", alias="mcdkoy", labels="mahalle"];
+  "0" -> "1" ["km"="2.5", label="includes", relation="includes"];
+  "2" -> "1" [label="komsu", relation="komsu"];
+}
+"#
+    );
+}
+
+#[test]
+fn graphml_types_and_escaping() {
+    let mut graph = city();
+    let merkez = id(&graph, "merkez");
+    graph.add_prop(&merkez, "not", r#"<"a" & 'b'>"#).unwrap();
+    graph.add_prop(&merkez, "aktif", true).unwrap();
+    let xml = graph.export().render(Format::GraphMl);
+
+    assert!(xml.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+    assert!(xml.contains(r#"<key id="d4" for="node" attr.name="aktif" attr.type="boolean"/>"#));
+    assert!(xml.contains(r#"<key id="d5" for="node" attr.name="not" attr.type="string"/>"#));
+    //long, double and string under one key
+    assert!(xml.contains(r#"<key id="d6" for="node" attr.name="nufus" attr.type="string"/>"#));
+    assert!(xml.contains(r#"<key id="d8" for="edge" attr.name="km" attr.type="double"/>"#));
+    assert!(xml.contains(
+        r#"<node id="n1"><data key="d0">merkez</data><data key="d1">mahalle</data><data key="d4">true</data><data key="d5">&lt;&quot;a&quot; &amp; &apos;b&apos;&gt;</data><data key="d6">15000.0</data></node>"#
+    ));
+    assert!(xml.contains(
+        r#"<edge id="e0" source="n0" target="n1"><data key="d2">includes</data><data key="d8">2.5</data></edge>"#
+    ));
+    assert!(xml.trim_end().ends_with("</graphml>"));
+}
+
+#[test]
+fn gexf() {
+    let mut graph = city();
+    graph.remove_prop(&id(&graph, "mcdkoy"), "nufus").unwrap();
+    let gexf = graph.export().render(Format::Gexf);
+
+    assert!(gexf.contains(r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#));
+    assert!(gexf.contains(r#"<attribute id="p0" title="nufus" type="double"/>"#));
+    assert!(gexf.contains(r#"<attribute id="p1" title="tur" type="string"/>"#));
+    assert!(gexf.contains(r#"<attribute id="p0" title="km" type="double"/>"#));
+    assert!(gexf.contains(
+        r#"<node id="0" label="sisli"><attvalues><attvalue for="alias" value="sisli"/><attvalue for="labels" value="sehir"/><attvalue for="p0" value="274420"/><attvalue for="p1" value="ilce"/></attvalues></node>"#
+    ));
+    assert!(gexf.contains(
+        r#"<edge id="1" source="2" target="1" label="komsu"><attvalues><attvalue for="relation" value="komsu"/></attvalues></edge>"#
+    ));
+}
+
+#[test]
+fn filters() {
+    let graph = city();
+    let mahalle = graph.export().labels(["mahalle"]).render(Format::Dot);
+    assert!(!mahalle.contains("sisli"));
+    assert!(mahalle.contains(r#""2" -> "1""#));
+    //the includes edge lost its source
+    assert!(!mahalle.contains("includes"));
+
+    let includes = graph.export().relations(["includes"]).render(Format::Gexf);
+    assert_eq!(includes.matches("<node ").count(), 3);
+    assert_eq!(includes.matches("<edge ").count(), 1);
+    assert!(!includes.contains("komsu"));
+
+    let both = graph
+        .export()
+        .labels(["sehir", "mahalle"])
+        .relations(["includes", "komsu"])
+        .render(Format::GraphMl);
+    assert_eq!(both.matches("<node ").count(), 3);
+    assert_eq!(both.matches("<edge ").count(), 2);
+
+    let none = graph.export().labels(["yok"]).render(Format::GraphMl);
+    assert_eq!(none.matches("<node ").count(), 0);
+    assert_eq!(none.matches("<edge ").count(), 0);
+}
+
+#[test]
+fn save_is_stable() {
+    let dir = std::env::temp_dir().join(format!("graph_db_export_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let graph = renumbered();
+    for format in [Format::Dot, Format::GraphMl, Format::Gexf] {
+        let path = dir.join("city.out");
+        graph.export().save(format, &path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        assert_eq!(saved, graph.export().render(format));
+        assert_eq!(saved, renumbered().export().render(format));
+    }
+    assert!(graph
+        .export()
+        .render(Format::Dot)
+        .contains(r#""2v1" [label="kadikoy""#));
+    assert!(matches!(
+        graph
+            .export()
+            .save(Format::Dot, dir.join("missing").join("x.dot")),
+        Err(Error::Io { .. })
+    ));
+}
+
+/// Removed slots show up as generations in the ids
+fn renumbered() -> Graph {
+    let mut graph = city();
+    graph.remove_node_by_id(&id(&graph, "mcdkoy")).unwrap();
+    graph.add_node("kadikoy").unwrap();
+    graph
+}
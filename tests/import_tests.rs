@@ -0,0 +1,268 @@
+use std::{fs, path::PathBuf, process::Command};
+
+use graph_db::{
+    import::{CsvImport, ImportReport, RowError},
+    vec_graph::*,
+};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph_db_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+const NODES: &str = "id,alias,labels,nufus:int,tur,koord:float[],aktif:bool
+1,sisli,sehir;ilce,274420,ilce,41.06;28.98,true
+2,merkez,mahalle,,\"mahalle, merkez\",,
+3,,mahalle,,\"iki
+satir\",,false\r
+";
+
+const EDGES: &str = "from,to,relation,labels,km:float
+1,2,includes,,2.5
+3,2,komsu,yakin;eski,
+";
+
+fn row_error(source: &str, line: usize, message: &str) -> RowError {
+    RowError {
+        source: source.to_owned(),
+        line,
+        message: message.to_owned(),
+    }
+}
+
+#[test]
+fn imports_nodes_and_edges() {
+    let mut graph = Graph::new();
+    let mut import = CsvImport::new();
+    import.nodes(&mut graph, "nodes", NODES.as_bytes()).unwrap();
+    import.edges(&mut graph, "edges", EDGES.as_bytes()).unwrap();
+    assert_eq!(
+        import.report(),
+        &ImportReport {
+            nodes: 3,
+            edges: 2,
+            errors: vec![]
+        }
+    );
+    assert_eq!(import.report().to_string(), "3 nodes, 2 edges imported");
+
+    let ids = import.ids();
+    let sisli = graph.get_node(&ids["1"]).unwrap();
+    assert_eq!(sisli.alias, "sisli");
+    assert_eq!(sisli.labels(), ["sehir", "ilce"]);
+    assert_eq!(sisli.get_prop("nufus"), Some(&PropValue::Int(274420)));
+    assert_eq!(
+        sisli.get_prop("koord"),
+        Some(&PropValue::List(vec![41.06.into(), 28.98.into()]))
+    );
+    assert_eq!(sisli.get_prop("aktif"), Some(&PropValue::Bool(true)));
+
+    let merkez = graph.get_node(&ids["2"]).unwrap();
+    assert_eq!(merkez.get_prop("tur"), Some(&"mahalle, merkez".into()));
+    assert_eq!(merkez.get_prop("nufus"), None);
+    //missing alias falls back to the id, quoted fields keep their line breaks
+    let third = graph.get_node(&ids["3"]).unwrap();
+    assert_eq!(third.alias, "3");
+    assert_eq!(third.get_prop("tur"), Some(&"iki\nsatir".into()));
+    assert_eq!(third.get_prop("aktif"), Some(&PropValue::Bool(false)));
+
+    let includes = graph.get_edges_between(&ids["1"], &ids["2"]);
+    assert_eq!(includes[0].relation(), "includes");
+    assert_eq!(includes[0].get_prop("km"), Some(&PropValue::Float(2.5)));
+    let komsu = graph.get_edges_between(&ids["3"], &ids["2"]);
+    assert_eq!(komsu[0].labels(), ["yakin", "eski"]);
+}
+
+#[test]
+fn bad_rows_are_reported() {
+    let nodes = "id,alias,nufus:int
+1,sisli,274420
+2,merkez,cok
+3,mcdkoy
+1,tekrar,1
+,bos,2
+4,\"cok
+satirli\",5
+5,\"ok\"x,6
+6,\"bitmeyen,7
+";
+    let edges = "from,to,relation
+1,2,komsu
+1,9,komsu
+2,1,
+";
+    let mut graph = Graph::new();
+    let mut import = CsvImport::new();
+    import
+        .nodes(&mut graph, "nodes.csv", nodes.as_bytes())
+        .unwrap();
+    import
+        .edges(&mut graph, "edges.csv", edges.as_bytes())
+        .unwrap();
+
+    let report = import.report();
+    assert_eq!((report.nodes, report.edges), (2, 0));
+    assert_eq!(
+        report.errors,
+        [
+            row_error("nodes.csv", 3, "column `nufus`: `cok` is not a valid int"),
+            row_error("nodes.csv", 4, "expected 3 fields, found 2"),
+            row_error("nodes.csv", 5, "duplicate id `1`"),
+            row_error("nodes.csv", 6, "empty id"),
+            row_error("nodes.csv", 9, "unexpected `x` after a closing quote"),
+            row_error("nodes.csv", 10, "unterminated quoted field"),
+            row_error("edges.csv", 2, "unknown node id `2`"),
+            row_error("edges.csv", 3, "unknown node id `9`"),
+            row_error("edges.csv", 4, "unknown node id `2`"),
+        ]
+    );
+    assert_eq!(graph.node_count(), 2);
+    assert!(graph.get_node(&import.ids()["4"]).is_ok());
+    assert!(report
+        .to_string()
+        .starts_with("2 nodes, 0 edges imported, 9 rows skipped\nnodes.csv:3: column `nufus`"));
+}
+
+#[test]
+fn invalid_utf8_rows_are_reported() {
+    let nodes = b"id,alias\n1,sisli\n2,\xffmerkez\n3,\"iki\nsat\xfdr\"\n4,kadikoy\n";
+    let mut graph = Graph::new();
+    let mut import = CsvImport::new();
+    import.nodes(&mut graph, "nodes.csv", &nodes[..]).unwrap();
+
+    let report = import.report();
+    assert_eq!(report.nodes, 2);
+    assert_eq!(
+        report.errors,
+        [
+            row_error("nodes.csv", 3, "invalid UTF-8 at byte 3 of line 3"),
+            row_error("nodes.csv", 4, "invalid UTF-8 at byte 4 of line 5"),
+        ]
+    );
+    assert_eq!(graph.get_node(&import.ids()["4"]).unwrap().alias, "kadikoy");
+}
+
+#[test]
+fn delimiters_and_headers() {
+    let mut graph = Graph::new();
+    let mut import = CsvImport::new()
+        .delimiter(';')
+        .list_separator('|')
+        .headers(false);
+    import
+        .nodes(
+            &mut graph,
+            "nodes",
+            "a;sisli;sehir|ilce\nb;merkez;\n".as_bytes(),
+        )
+        .unwrap();
+    import
+        .edges(&mut graph, "edges", "a;b;includes\n".as_bytes())
+        .unwrap();
+    assert_eq!(
+        graph.get_node(&import.ids()["a"]).unwrap().labels(),
+        ["sehir", "ilce"]
+    );
+    assert_eq!(graph.edge_count(), 1);
+
+    //named columns replace the header row when there is one
+    let mut import = CsvImport::new()
+        .delimiter('\t')
+        .node_columns(["alias", "id", "puan:int[]"]);
+    import
+        .nodes(
+            &mut graph,
+            "nodes",
+            "ignored\theader\trow\nkadikoy\tk\t1;2;3\n".as_bytes(),
+        )
+        .unwrap();
+    let kadikoy = graph.get_node(&import.ids()["k"]).unwrap();
+    assert_eq!(kadikoy.alias, "kadikoy");
+    assert_eq!(
+        kadikoy.get_prop("puan"),
+        Some(&PropValue::List(vec![1.into(), 2.into(), 3.into()]))
+    );
+    assert_eq!(import.report().nodes, 1);
+}
+
+#[test]
+fn unusable_files_import_nothing() {
+    let mut graph = Graph::new();
+    let mut import = CsvImport::new();
+    let missing = import.nodes(&mut graph, "nodes.csv", "alias,labels\nsisli,\n".as_bytes());
+    assert!(matches!(
+        missing,
+        Err(Error::Parse { message, .. }) if message == "nodes.csv:1: missing `id` column"
+    ));
+    let typed = import.nodes(&mut graph, "nodes.csv", "id,nufus:long\n1,2\n".as_bytes());
+    assert!(matches!(typed, Err(Error::Parse { .. })));
+    let twice = import.edges(&mut graph, "e", "from,to,relation,to\n".as_bytes());
+    assert!(matches!(twice, Err(Error::Parse { .. })));
+    assert!(matches!(
+        import.nodes_file(&mut graph, "/no/such/nodes.csv"),
+        Err(Error::Io { .. })
+    ));
+    assert_eq!(graph.node_count(), 0);
+    assert_eq!(import.report(), &ImportReport::default());
+
+    //an empty file is just nothing to import
+    import.nodes(&mut graph, "empty", "".as_bytes()).unwrap();
+    assert_eq!(graph.node_count(), 0);
+}
+
+#[test]
+fn import_command() {
+    let dir = temp_dir("import");
+    let (nodes, edges) = (dir.join("n.csv"), dir.join("e.csv"));
+    fs::write(&nodes, NODES.replace(',', "\t")).unwrap();
+    fs::write(
+        &edges,
+        format!("{}1\t7\tkomsu\t\t\n", EDGES.replace(',', "\t")),
+    )
+    .unwrap();
+    let import = |db: &str, extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_graph_db"))
+            .arg("import")
+            .arg(dir.join(db))
+            .arg("--nodes")
+            .arg(&nodes)
+            .arg("--edges")
+            .arg(&edges)
+            .args(extra)
+            .output()
+            .unwrap()
+    };
+
+    let output = import("city.db", &["--delimiter", "tab"]);
+    //the good rows go in, the skipped one makes the command fail
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout,
+        format!(
+            "3 nodes, 2 edges imported, 1 row skipped\n{}:4: unknown node id `7`\n",
+            edges.display()
+        )
+    );
+    let graph = Graph::open(dir.join("city.db")).unwrap();
+    assert_eq!((graph.node_count(), graph.edge_count()), (3, 2));
+
+    fs::write(&edges, "a\tb\tc\n1\t2\tincludes\n").unwrap();
+    let output = import(
+        "other.db",
+        &["--delimiter", "tab", "--edge-columns", "from,to,relation"],
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "3 nodes, 1 edge imported\n"
+    );
+
+    let output = import("third.db", &["--bogus"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("error: unknown option `--bogus`"));
+}
@@ -0,0 +1,107 @@
+use graph_db::vec_graph::*;
+
+fn ids_to_aliases(graph: &Graph, ids: Vec<NodeIndex>) -> Vec<&str> {
+    ids.iter()
+        .map(|id| graph.get_alias_by_id(id).unwrap())
+        .collect()
+}
+
+fn districts() -> Graph {
+    let mut graph = Graph::new();
+    graph
+        .add_node("sisli")
+        .unwrap()
+        .mut_last_node(|n| n.add_label("sehir")?.add_prop("tur", "ilce"))
+        .unwrap()
+        .add_node("mcdkoy")
+        .unwrap()
+        .mut_last_node(|n| {
+            n.add_label("mahalle")?
+                .add_label("bolge")?
+                .add_prop("tur", "mahalle")
+        })
+        .unwrap()
+        .add_node("merkez")
+        .unwrap()
+        .mut_last_node(|n| n.add_label("mahalle")?.add_prop("tur", "mahalle"))
+        .unwrap();
+    graph
+}
+
+#[test]
+fn label_index_follows_mutations() {
+    let mut graph = districts();
+    assert_eq!(
+        ids_to_aliases(&graph, graph.find_ids_by_label("mahalle")),
+        vec!["mcdkoy", "merkez"]
+    );
+    let mcdkoy = graph.get_ids_by_alias("mcdkoy").unwrap()[0];
+    graph
+        .mut_node(&mcdkoy, |n| n.remove_label("mahalle"))
+        .unwrap();
+    assert_eq!(
+        ids_to_aliases(&graph, graph.find_ids_by_label("mahalle")),
+        vec!["merkez"]
+    );
+    graph.add_label(&mcdkoy, "sahil").unwrap();
+    assert_eq!(graph.find_nodes_by_label("sahil")[0].alias, "mcdkoy");
+    assert!(graph.find_nodes_by_label("yok").is_empty());
+}
+
+#[test]
+fn prop_index_is_opt_in() {
+    let mut graph = districts();
+    assert!(graph.find_nodes_by_prop("tur", "mahalle").is_err());
+    graph.create_prop_index("tur").unwrap();
+    assert!(graph.create_prop_index("tur").is_err());
+    assert_eq!(
+        ids_to_aliases(&graph, graph.find_ids_by_prop("tur", "mahalle").unwrap()),
+        vec!["mcdkoy", "merkez"]
+    );
+
+    let merkez = graph.get_ids_by_alias("merkez").unwrap()[0];
+    graph.add_prop(&merkez, "tur", "koy").unwrap();
+    assert_eq!(
+        ids_to_aliases(&graph, graph.find_ids_by_prop("tur", "mahalle").unwrap()),
+        vec!["mcdkoy"]
+    );
+    assert_eq!(
+        graph.find_nodes_by_prop("tur", "koy").unwrap()[0].alias,
+        "merkez"
+    );
+    graph.remove_prop(&merkez, "tur").unwrap();
+    assert!(graph.find_nodes_by_prop("tur", "koy").unwrap().is_empty());
+
+    graph.drop_prop_index("tur").unwrap();
+    assert!(!graph.has_prop_index("tur"));
+}
+
+#[test]
+fn remove_node_updates_indexes() {
+    let mut graph = districts();
+    graph.create_prop_index("tur").unwrap();
+    let sisli = graph.get_ids_by_alias("sisli").unwrap()[0];
+    graph.remove_node_by_id(&sisli).unwrap();
+
+    assert!(graph.find_ids_by_label("sehir").is_empty());
+    assert!(graph.find_ids_by_prop("tur", "ilce").unwrap().is_empty());
+    let mut bolge = ids_to_aliases(&graph, graph.find_ids_by_label("bolge"));
+    bolge.sort();
+    assert_eq!(bolge, vec!["mcdkoy"]);
+    let mut mahalle = ids_to_aliases(&graph, graph.find_ids_by_prop("tur", "mahalle").unwrap());
+    mahalle.sort();
+    assert_eq!(mahalle, vec!["mcdkoy", "merkez"]);
+}
+
+#[test]
+fn query_uses_indexes() {
+    let mut graph = districts();
+    graph.create_prop_index("tur").unwrap();
+    let result = graph
+        .query(r#"MATCH (n:bolge {tur: "mahalle"}) RETURN n"#)
+        .unwrap();
+    assert_eq!(
+        ids_to_aliases(&graph, result.column("n").unwrap()),
+        vec!["mcdkoy"]
+    );
+}
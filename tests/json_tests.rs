@@ -0,0 +1,311 @@
+use std::{collections::BTreeMap, fs, io::BufReader, path::PathBuf};
+
+use graph_db::vec_graph::*;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph_db_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn first_id(graph: &Graph, alias: &str) -> NodeIndex {
+    graph.get_ids_by_alias(alias).unwrap()[0]
+}
+
+/// Removed nodes and edges, reused slots, shared aliases and every kind of prop value
+fn sample() -> Graph {
+    let mut meta = BTreeMap::new();
+    meta.insert("$bytes".to_owned(), PropValue::Float(f64::INFINITY));
+    let mut graph = Graph::new();
+    graph
+        .create_prop_index("tur")
+        .unwrap()
+        .add_constraint(Constraint::unique_alias("sehir"))
+        .unwrap()
+        .add_constraint(Constraint::relation("includes", "sehir", "merkez"))
+        .unwrap()
+        .drop_constraint(&Constraint::relation("includes", "sehir", "merkez"))
+        .unwrap()
+        .add_constraint(Constraint::required_prop("ilce", "tur"))
+        .unwrap()
+        .add_node("sisli")
+        .unwrap()
+        .mut_last_node(|n| {
+            n.add_prop("tur", "ilce")?
+                .add_label("sehir")?
+                .add_label("ilce")?
+                .add_prop("kod", vec![PropValue::Int(34), PropValue::Null])?
+                .add_prop("veri", &b"\x00\xff"[..])
+        })
+        .unwrap()
+        .add_node("gecici")
+        .unwrap()
+        .add_node("merkez")
+        .unwrap()
+        .mut_last_node(|n| {
+            n.add_prop("meta", meta.clone())?
+                .add_prop("not", "\"tirnak\"\n")?
+                .add_prop("alan", 1.5)
+        })
+        .unwrap()
+        .add_node("merkez")
+        .unwrap()
+        .add_edges_by_aliases("includes", "sisli", "merkez")
+        .unwrap()
+        .mut_last_edge(|e| e.add_label("idari")?.add_prop("km", 2))
+        .unwrap()
+        .add_edges_by_aliases("komsu", "gecici", "sisli")
+        .unwrap()
+        .add_edges_by_aliases("komsu", "merkez", "sisli")
+        .unwrap();
+    graph
+        .remove_node_by_id(&first_id(&graph, "gecici"))
+        .unwrap();
+    let includes = graph.get_outgoing_edges(&first_id(&graph, "sisli"))[0].id();
+    graph.remove_edge(&includes).unwrap();
+    graph.add_node("kadikoy").unwrap();
+    graph
+}
+
+#[test]
+fn document_round_trip() {
+    let graph = sample();
+    let mut saved = Vec::new();
+    graph.write_json(&mut saved).unwrap();
+    let loaded = Graph::read_json(saved.as_slice()).unwrap();
+    assert_eq!(loaded, graph);
+
+    //same text again, and the freed slots hand out the same ids
+    let mut again = Vec::new();
+    loaded.write_json(&mut again).unwrap();
+    assert_eq!(
+        String::from_utf8(again).unwrap(),
+        String::from_utf8(saved).unwrap()
+    );
+    let (mut graph, mut loaded) = (graph, loaded);
+    graph
+        .add_node("yeni")
+        .unwrap()
+        .add_edges_by_aliases("yol", "yeni", "sisli")
+        .unwrap();
+    loaded
+        .add_node("yeni")
+        .unwrap()
+        .add_edges_by_aliases("yol", "yeni", "sisli")
+        .unwrap();
+    assert_eq!(loaded, graph);
+    assert_eq!(
+        loaded.get_last_edge().unwrap().id(),
+        EdgeIndex::from_parts(1, 1)
+    );
+    assert_eq!(loaded.find_ids_by_prop("tur", "ilce").unwrap().len(), 1);
+}
+
+#[test]
+fn json_lines_round_trip() {
+    let graph = sample();
+    let mut saved = Vec::new();
+    graph.write_json_lines(&mut saved).unwrap();
+    let text = String::from_utf8(saved).unwrap();
+    let lines: Vec<_> = text.lines().collect();
+    assert!(lines[0].starts_with(r#"{"type":"graph","format":"graph_db","version":1,"#));
+    //4 nodes, 3 edges and 4 aliases, the removed node's alias stays with no ids
+    assert_eq!(lines.len(), 1 + 4 + 3 + 4);
+    assert!(lines.contains(&r#"{"type":"alias","alias":"gecici","nodes":[]}"#));
+
+    let loaded = Graph::read_json_lines(BufReader::with_capacity(16, text.as_bytes())).unwrap();
+    assert_eq!(loaded, graph);
+    assert_eq!(
+        Graph::read_json_lines(text.as_bytes()).unwrap(),
+        Graph::read_json(json(&graph).as_bytes()).unwrap()
+    );
+}
+
+fn json(graph: &Graph) -> String {
+    let mut out = Vec::new();
+    graph.write_json(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn document_layout() {
+    let mut graph = Graph::new();
+    assert_eq!(
+        json(&graph),
+        r#"{
+  "format": "graph_db",
+  "version": 1,
+  "prop_indexes": [],
+  "constraints": [],
+  "free_nodes": [],
+  "free_edges": [],
+  "last_node": null,
+  "last_edge": null,
+  "nodes": [],
+  "edges": [],
+  "aliases": {}
+}
+"#
+    );
+    graph
+        .add_node("sisli")
+        .unwrap()
+        .add_node("merkez")
+        .unwrap()
+        .add_node("gecici")
+        .unwrap()
+        .add_edges_by_aliases("includes", "sisli", "merkez")
+        .unwrap()
+        .mut_last_edge(|e| e.add_prop("km", 2.5))
+        .unwrap();
+    graph
+        .add_prop(&first_id(&graph, "sisli"), "tur", "ilce")
+        .unwrap();
+    graph
+        .add_label(&first_id(&graph, "sisli"), "sehir")
+        .unwrap();
+    graph
+        .remove_node_by_id(&first_id(&graph, "gecici"))
+        .unwrap();
+    graph.create_prop_index("tur").unwrap();
+    assert_eq!(
+        json(&graph),
+        r#"{
+  "format": "graph_db",
+  "version": 1,
+  "prop_indexes": ["tur"],
+  "constraints": [],
+  "free_nodes": ["2v1"],
+  "free_edges": [],
+  "last_node": null,
+  "last_edge": "0",
+  "nodes": [
+    {"id":"0","alias":"sisli","labels":["sehir"],"props":{"tur":"ilce"},"out":["0"],"in":[]},
+    {"id":"1","alias":"merkez","labels":[],"props":{},"out":[],"in":["0"]}
+  ],
+  "edges": [
+    {"id":"0","relation":"includes","from":"0","to":"1","labels":[],"props":{"km":2.5}}
+  ],
+  "aliases": {
+    "gecici": [],
+    "merkez": ["1"],
+    "sisli": ["0"]
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn broken_input_is_rejected() {
+    let text = json(&sample());
+    let load = |text: &str| Graph::read_json(text.as_bytes());
+    assert!(matches!(
+        load(&text[..text.len() - 3]),
+        Err(Error::Parse { .. })
+    ));
+    assert!(matches!(
+        load(&text.replace("\"version\": 1", "\"version\": 7")),
+        Err(Error::UnsupportedVersion {
+            found: 7,
+            supported: 1
+        })
+    ));
+    let corrupt = |text: String| match load(&text) {
+        Err(Error::Corrupt { context }) => context,
+        other => panic!("expected corrupt, got {:?}", other.map(|g| g.node_count())),
+    };
+    assert_eq!(
+        corrupt(text.replace("\"graph_db\"", "\"other\"")),
+        "Not a graph_db JSON graph"
+    );
+    assert_eq!(
+        corrupt(text.replace("\"relation\"", "\"rel\"")),
+        "Missing `relation`"
+    );
+    //an edge taken off its source's list, and a removed slot that isn't free
+    assert_eq!(
+        corrupt(text.replace(r#""out":["3"]"#, r#""out":[]"#)),
+        "Edges missing from their nodes' `out` or `in` lists"
+    );
+    assert_eq!(
+        corrupt(text.replace(r#""free_edges": ["2v1","1v1"]"#, r#""free_edges": ["2v1"]"#)),
+        "Free edge list doesn't match the empty slots"
+    );
+
+    let mut lines = Vec::new();
+    sample().write_json_lines(&mut lines).unwrap();
+    let lines = String::from_utf8(lines).unwrap();
+    let first = lines.find('\n').unwrap() + 1;
+    let broken = format!("{}{{\"type\":\"node\",}}\n", &lines[..first]);
+    assert!(matches!(
+        Graph::read_json_lines(broken.as_bytes()),
+        Err(Error::Parse { pos: Some(pos), .. }) if pos == first + 15
+    ));
+    let twice = format!("{}{}", lines, &lines[..first]);
+    assert!(matches!(
+        Graph::read_json_lines(twice.as_bytes()),
+        Err(Error::Corrupt { context }) if context == "line 13: Second graph line"
+    ));
+    assert!(matches!(
+        Graph::read_json_lines("\n".as_bytes()),
+        Err(Error::Corrupt { .. })
+    ));
+}
+
+#[test]
+fn save_and_load_files() {
+    let dir = temp_dir("json_files");
+    let graph = sample();
+    graph.save_json(dir.join("graph.json")).unwrap();
+    graph.save_json_lines(dir.join("graph.jsonl")).unwrap();
+    assert_eq!(Graph::load_json(dir.join("graph.json")).unwrap(), graph);
+    assert_eq!(
+        Graph::load_json_lines(dir.join("graph.jsonl")).unwrap(),
+        graph
+    );
+    assert!(matches!(
+        Graph::load_json(dir.join("missing.json")),
+        Err(Error::Io { .. })
+    ));
+    assert!(matches!(
+        graph.save_json_lines(dir.join("missing").join("graph.jsonl")),
+        Err(Error::Io { .. })
+    ));
+}
+
+#[test]
+fn values_round_trip_at_the_depth_limit() {
+    //maps with a tag as their only key are wrapped in `$map`, the deepest JSON a value can give
+    let deepest = (0..PropValue::MAX_DEPTH).fold(PropValue::Bytes(vec![1, 2]), |v, _| {
+        PropValue::Map(BTreeMap::from([("$bytes".to_owned(), v)]))
+    });
+    let mut graph = Graph::new();
+    graph.add_node("a").unwrap();
+    let a = first_id(&graph, "a");
+    graph.add_prop(&a, "deep", deepest).unwrap();
+    graph.add_prop(&a, "list", PropValue::List(vec![])).unwrap();
+    for _ in 1..PropValue::MAX_DEPTH {
+        let list = graph
+            .get_node(&a)
+            .unwrap()
+            .get_prop("list")
+            .unwrap()
+            .clone();
+        graph
+            .add_prop(&a, "list", PropValue::List(vec![list]))
+            .unwrap();
+    }
+    assert_eq!(Graph::read_json(json(&graph).as_bytes()).unwrap(), graph);
+    let mut lines = Vec::new();
+    graph.write_json_lines(&mut lines).unwrap();
+    assert_eq!(Graph::read_json_lines(&lines[..]).unwrap(), graph);
+
+    //one more list is refused on read like it is when set
+    let text = String::from_utf8(lines).unwrap().replace("[[]]", "[[[]]]");
+    match Graph::read_json_lines(text.as_bytes()) {
+        Err(Error::Corrupt { context }) => assert!(context.contains("deeper"), "{}", context),
+        other => panic!("deeper value gave {:?}", other.map(|_| ())),
+    }
+}
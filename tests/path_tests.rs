@@ -0,0 +1,167 @@
+use graph_db::{
+    algo::{Direction, Path, Step, Weight},
+    vec_graph::*,
+};
+
+fn first_id(graph: &Graph, alias: &str) -> NodeIndex {
+    graph.get_ids_by_alias(alias).unwrap()[0]
+}
+
+fn road(graph: &mut Graph, from: &str, to: &str, km: i64) {
+    graph
+        .add_edges_by_aliases("yol", from, to)
+        .unwrap()
+        .mut_last_edge(|e| e.add_prop("km", km))
+        .unwrap();
+}
+
+/// Towns with an x coordinate, the direct sisli -> kadikoy road is the longest way
+fn towns() -> Graph {
+    let mut graph = Graph::new();
+    for (alias, x) in [
+        ("sisli", 0.0),
+        ("besiktas", 3.0),
+        ("uskudar", 6.0),
+        ("kadikoy", 8.0),
+        ("adalar", 20.0),
+    ] {
+        graph
+            .add_node(alias)
+            .unwrap()
+            .mut_last_node(|n| n.add_prop("x", x))
+            .unwrap();
+    }
+    road(&mut graph, "sisli", "kadikoy", 20);
+    road(&mut graph, "sisli", "besiktas", 4);
+    road(&mut graph, "besiktas", "uskudar", 5);
+    road(&mut graph, "uskudar", "kadikoy", 3);
+    graph
+        .add_edges_by_aliases("vapur", "adalar", "kadikoy")
+        .unwrap();
+    graph
+}
+
+fn aliases<'g>(path: &Path<'g>) -> Vec<&'g str> {
+    path.nodes().map(|n| n.alias.as_str()).collect()
+}
+
+#[test]
+fn unweighted_takes_fewest_hops() {
+    let graph = towns();
+    let (sisli, kadikoy) = (first_id(&graph, "sisli"), first_id(&graph, "kadikoy"));
+    let path = graph.shortest_path(sisli, kadikoy).unweighted().unwrap();
+    assert_eq!(aliases(&path), vec!["sisli", "kadikoy"]);
+    assert_eq!((path.hops(), path.cost()), (1, 1.0));
+    //alternating node, edge, node
+    match path.steps().as_slice() {
+        [Step::Node(a), Step::Edge(e), Step::Node(b)] => {
+            assert_eq!((*e.from(), *e.to()), (a.id, b.id));
+        }
+        other => panic!("unexpected steps {:?}", other),
+    }
+    let same = graph.shortest_path(sisli, sisli).unweighted().unwrap();
+    assert_eq!(
+        (same.hops(), same.start().id, same.end().id),
+        (0, sisli, sisli)
+    );
+}
+
+#[test]
+fn dijkstra_by_prop_and_closure() {
+    let graph = towns();
+    let (sisli, kadikoy) = (first_id(&graph, "sisli"), first_id(&graph, "kadikoy"));
+    let path = graph
+        .shortest_path(sisli, kadikoy)
+        .dijkstra(Weight::prop("km"))
+        .unwrap();
+    assert_eq!(
+        aliases(&path),
+        vec!["sisli", "besiktas", "uskudar", "kadikoy"]
+    );
+    assert_eq!(path.cost(), 12.0);
+    let kms: Vec<i64> = path
+        .edges()
+        .map(|e| e.get_prop("km").unwrap().as_int().unwrap())
+        .collect();
+    assert_eq!(kms, vec![4, 5, 3]);
+    //tolls make the long road cheaper
+    let path = graph
+        .shortest_path(sisli, kadikoy)
+        .dijkstra(Weight::with(|e| {
+            let km = e.get_prop("km").unwrap().as_number().unwrap();
+            if km < 10.0 {
+                km + 10.0
+            } else {
+                km
+            }
+        }))
+        .unwrap();
+    assert_eq!((path.hops(), path.cost()), (1, 20.0));
+}
+
+#[test]
+fn astar_matches_dijkstra() {
+    let graph = towns();
+    let (sisli, kadikoy) = (first_id(&graph, "sisli"), first_id(&graph, "kadikoy"));
+    let target_x = 8.0;
+    let path = graph
+        .shortest_path(sisli, kadikoy)
+        .astar(Weight::prop("km"), |n| {
+            (target_x - n.get_prop("x").unwrap().as_number().unwrap()).abs()
+        })
+        .unwrap();
+    let dijkstra = graph
+        .shortest_path(sisli, kadikoy)
+        .dijkstra(Weight::prop("km"))
+        .unwrap();
+    assert_eq!(aliases(&path), aliases(&dijkstra));
+    assert_eq!(path.cost(), dijkstra.cost());
+}
+
+#[test]
+fn relations_and_direction() {
+    let graph = towns();
+    let (sisli, adalar) = (first_id(&graph, "sisli"), first_id(&graph, "adalar"));
+    assert!(matches!(
+        graph.shortest_path(sisli, adalar).unweighted(),
+        Err(Error::NoPath { from, to }) if from == sisli && to == adalar
+    ));
+    let path = graph
+        .shortest_path(sisli, adalar)
+        .direction(Direction::Both)
+        .unweighted()
+        .unwrap();
+    assert_eq!(aliases(&path), vec!["sisli", "kadikoy", "adalar"]);
+    //the ferry has no km, and without it there's no way at all
+    assert!(matches!(
+        graph
+            .shortest_path(sisli, adalar)
+            .direction(Direction::Both)
+            .dijkstra(Weight::prop("km")),
+        Err(Error::InvalidWeight { .. })
+    ));
+    assert!(matches!(
+        graph
+            .shortest_path(sisli, adalar)
+            .direction(Direction::Both)
+            .relations(["yol"])
+            .dijkstra(Weight::prop("km")),
+        Err(Error::NoPath { .. })
+    ));
+}
+
+#[test]
+fn bad_weights_and_missing_nodes() {
+    let mut graph = towns();
+    let (sisli, kadikoy) = (first_id(&graph, "sisli"), first_id(&graph, "kadikoy"));
+    let err = graph
+        .shortest_path(sisli, kadikoy)
+        .dijkstra(Weight::with(|_| -1.0))
+        .unwrap_err();
+    assert!(err.to_string().contains("not a cost"), "{}", err);
+    graph.remove_node_by_id(&kadikoy).unwrap();
+    assert!(matches!(
+        graph.shortest_path(sisli, kadikoy).unweighted(),
+        Err(Error::NodeNotFound { id }) if id == kadikoy
+    ));
+}
@@ -0,0 +1,88 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use graph_db::vec_graph::*;
+
+#[test]
+fn typed_getters() {
+    assert_eq!(PropValue::from(3).as_int(), Some(3));
+    assert_eq!(PropValue::from(3).as_number(), Some(3.0));
+    assert_eq!(PropValue::from(2.5).as_float(), Some(2.5));
+    assert_eq!(PropValue::from(2.5).as_int(), None);
+    assert_eq!(PropValue::from("x").as_str(), Some("x"));
+    assert_eq!(PropValue::from(true).as_bool(), Some(true));
+    assert_eq!(
+        PropValue::from(vec![1u8, 2]).as_bytes(),
+        Some(&[1u8, 2][..])
+    );
+    assert!(PropValue::from(None::<i64>).is_null());
+    let list = PropValue::from(vec![PropValue::from(1), PropValue::from("a")]);
+    assert_eq!(list.as_list().map(|l| l.len()), Some(2));
+    assert_eq!(list.to_string(), r#"[1, "a"]"#);
+    let mut map = BTreeMap::new();
+    map.insert("k".to_owned(), PropValue::from(false));
+    assert_eq!(PropValue::from(map).to_string(), "{k: false}");
+}
+
+#[test]
+fn total_order_across_types() {
+    let mut values = vec![
+        PropValue::from("b"),
+        PropValue::from(2.5),
+        PropValue::Null,
+        PropValue::from(vec![PropValue::Null]),
+        PropValue::from(2),
+        PropValue::from(true),
+        PropValue::from(vec![0u8]),
+        PropValue::from(-1),
+        PropValue::from(2.0),
+    ];
+    values.sort();
+    assert_eq!(
+        values,
+        vec![
+            PropValue::Null,
+            PropValue::from(true),
+            PropValue::from(-1),
+            PropValue::from(2),
+            PropValue::from(2.0),
+            PropValue::from(2.5),
+            PropValue::from("b"),
+            PropValue::from(vec![0u8]),
+            PropValue::from(vec![PropValue::Null]),
+        ]
+    );
+    //structural equality keeps ints and floats apart, NaN equals itself
+    assert_ne!(PropValue::from(1), PropValue::from(1.0));
+    assert_eq!(PropValue::from(f64::NAN), PropValue::from(f64::NAN));
+}
+
+#[test]
+fn query_comparisons() {
+    let one = PropValue::from(1);
+    assert_eq!(one.equals(&PropValue::from(1.0)), Some(true));
+    assert_eq!(one.compare(&PropValue::from(1.5)), Some(Ordering::Less));
+    assert_eq!(one.compare(&PropValue::from("1")), None);
+    assert_eq!(one.equals(&PropValue::from("1")), Some(false));
+    assert_eq!(one.equals(&PropValue::Null), None);
+    assert_eq!(PropValue::Null.equals(&PropValue::Null), None);
+}
+
+#[test]
+fn index_matches_ints_and_floats() {
+    let mut graph = Graph::new();
+    graph
+        .add_node("a")
+        .unwrap()
+        .mut_last_node(|n| n.add_prop("age", 30.0))
+        .unwrap()
+        .add_node("b")
+        .unwrap()
+        .mut_last_node(|n| n.add_prop("age", 30))
+        .unwrap()
+        .create_prop_index("age")
+        .unwrap();
+    assert_eq!(graph.find_ids_by_prop("age", 30).unwrap().len(), 2);
+    assert_eq!(graph.find_ids_by_prop("age", 30.0).unwrap().len(), 2);
+    assert!(graph.find_ids_by_prop("age", "30").unwrap().is_empty());
+}
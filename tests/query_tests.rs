@@ -0,0 +1,199 @@
+use graph_db::query::{self, Direction};
+use graph_db::vec_graph::*;
+
+fn aliases(graph: &Graph, ids: &[NodeIndex]) -> Vec<String> {
+    ids.iter()
+        .map(|id| graph.get_alias_by_id(id).unwrap().to_owned())
+        .collect()
+}
+
+fn people() -> Graph {
+    let mut graph = Graph::new();
+    graph
+        .add_node("elma")
+        .unwrap()
+        .mut_last_node(|n| {
+            n.add_label("Person")?
+                .add_prop("name", "elma")?
+                .add_prop("age", 31)
+        })
+        .unwrap()
+        .add_node("kiraz")
+        .unwrap()
+        .mut_last_node(|n| {
+            n.add_label("Person")?
+                .add_prop("name", "kiraz")?
+                .add_prop("age", 24.5)
+        })
+        .unwrap()
+        .add_node("armut")
+        .unwrap()
+        .mut_last_node(|n| {
+            n.add_label("Person")?
+                .add_prop("name", "armut")?
+                .add_prop("age", 40)
+        })
+        .unwrap()
+        .add_node("ayva")
+        .unwrap()
+        .mut_last_node(|n| n.add_label("Robot")?.add_prop("name", "ayva"))
+        .unwrap()
+        .add_edges_by_aliases("KNOWS", "elma", "kiraz")
+        .unwrap()
+        .add_edges_by_aliases("KNOWS", "kiraz", "armut")
+        .unwrap()
+        .add_edges_by_aliases("KNOWS", "elma", "ayva")
+        .unwrap()
+        .add_edges_by_aliases("KNOWS", "ayva", "armut")
+        .unwrap()
+        .add_edges_by_aliases("LIKES", "armut", "elma")
+        .unwrap();
+    graph
+}
+
+#[test]
+fn parse_pattern() {
+    let q = query::parse(
+        r#"match (a:Person:Admin {name: "x", age: 3})<-[r:KNOWS|:LIKES]-(b) return a, b;"#,
+    )
+    .expect("Failed parsing query");
+    assert_eq!(q.returns, vec!["a", "b"]);
+    assert_eq!(q.patterns.len(), 1);
+    let start = &q.patterns[0].start;
+    assert_eq!(start.variable.as_deref(), Some("a"));
+    assert_eq!(start.labels, vec!["Person", "Admin"]);
+    assert_eq!(start.props.len(), 2);
+    let (rel, node) = &q.patterns[0].hops[0];
+    assert_eq!(rel.variable.as_deref(), Some("r"));
+    assert_eq!(rel.relations, vec!["KNOWS", "LIKES"]);
+    assert_eq!(rel.direction, Direction::Incoming);
+    assert_eq!(node.variable.as_deref(), Some("b"));
+}
+
+#[test]
+fn parse_errors() {
+    assert!(query::parse("MATCH (a RETURN a").is_err());
+    assert!(query::parse("MATCH (a)-[:R]-(b) RETURN a").is_err());
+    assert!(query::parse("MATCH (a) RETURN").is_err());
+    assert!(query::parse(r#"MATCH (a {name: "x}) RETURN a"#).is_err());
+}
+
+#[test]
+fn deep_nesting_is_a_parse_error() {
+    let nested = |open: &str, inner: &str, close: &str, n: usize| {
+        format!(
+            "MATCH (n) WHERE {}{}{} RETURN n",
+            open.repeat(n),
+            inner,
+            close.repeat(n)
+        )
+    };
+    for q in [
+        nested("(", "n.a = 1", ")", 200_000),
+        nested("NOT ", "n.a = 1", "", 200_000),
+        nested("", &format!("n.a = {}1", "[".repeat(200_000)), "]", 200_000),
+        format!(
+            "MATCH (n) WHERE n.a = 1{} RETURN n",
+            " OR n.a = 1".repeat(200_000)
+        ),
+    ] {
+        assert!(matches!(query::parse(&q), Err(Error::Parse { .. })));
+    }
+    //reasonable nesting still parses
+    let graph = people();
+    assert!(graph.query(&nested("(", "n.age > 30", ")", 100)).is_ok());
+    assert!(graph
+        .query(&nested("NOT NOT ", "n.age > 30", "", 50))
+        .is_ok());
+}
+
+#[test]
+fn two_hop_match() {
+    let graph = people();
+    let result = graph
+        .query(r#"MATCH (you:Person {name:"elma"})-[:KNOWS]->(n)-[:KNOWS]->(friend:Person {name:"armut"}) RETURN n"#)
+        .expect("Failed running query");
+    assert_eq!(result.columns, vec!["n"]);
+    let mut found = aliases(&graph, &result.column("n").unwrap());
+    found.sort();
+    assert_eq!(found, vec!["ayva", "kiraz"]);
+}
+
+#[test]
+fn labels_filter_and_incoming() {
+    let graph = people();
+    let result = graph
+        .query("MATCH (a:Person)<-[:KNOWS]-(b:Robot) RETURN a, b")
+        .unwrap();
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(aliases(&graph, &result.rows[0]), vec!["armut", "ayva"]);
+}
+
+#[test]
+fn shared_variables_close_cycles() {
+    let graph = people();
+    let result = graph
+        .query("MATCH (a)-[:KNOWS]->(b)-[:KNOWS]->(c)-[:LIKES]->(a) RETURN a")
+        .unwrap();
+    assert_eq!(
+        aliases(&graph, &result.column("a").unwrap()),
+        vec!["elma", "elma"]
+    );
+    let joined = graph
+        .query("MATCH (a)-->(b), (b)-[:LIKES]->(c) RETURN a, c")
+        .unwrap();
+    let mut rows: Vec<_> = joined.rows.iter().map(|r| aliases(&graph, r)).collect();
+    rows.sort();
+    assert_eq!(rows, vec![vec!["ayva", "elma"], vec!["kiraz", "elma"]]);
+}
+
+#[test]
+fn undefined_variable() {
+    let graph = people();
+    assert!(graph.query("MATCH (a) RETURN b").is_err());
+    assert!(graph.query("MATCH (a)-[r]->(b) RETURN r").is_err());
+    assert!(graph
+        .query(r#"MATCH (a {name:"nobody"}) RETURN a"#)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn where_filters() {
+    let graph = people();
+    let names = |q: &str| -> Vec<String> {
+        let mut found = aliases(&graph, &graph.query(q).unwrap().column("n").unwrap());
+        found.sort();
+        found
+    };
+    assert_eq!(
+        names("MATCH (n:Person) WHERE n.age > 30 RETURN n"),
+        vec!["armut", "elma"]
+    );
+    assert_eq!(
+        names("MATCH (n) WHERE n.age >= 24.5 AND n.age <= 31 RETURN n"),
+        vec!["elma", "kiraz"]
+    );
+    assert_eq!(
+        names("MATCH (n) WHERE n.age IS NULL RETURN n"),
+        vec!["ayva"]
+    );
+    assert_eq!(
+        names("MATCH (n) WHERE NOT n.age <> 40 RETURN n"),
+        vec!["armut"]
+    );
+    //null comparisons are neither true nor false
+    assert_eq!(
+        names("MATCH (n) WHERE n.age < 100 OR n.age > 100 RETURN n"),
+        vec!["armut", "elma", "kiraz"]
+    );
+    assert_eq!(
+        names(r#"MATCH (n) WHERE n.name = "ayva" OR (n.age = 31.0) RETURN n"#),
+        vec!["ayva", "elma"]
+    );
+    assert_eq!(
+        names(r#"MATCH (n {age: 40.0}) WHERE n.name != 1 RETURN n"#),
+        vec!["armut"]
+    );
+    assert!(graph.query("MATCH (n) WHERE m.age > 3 RETURN n").is_err());
+}
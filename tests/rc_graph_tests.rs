@@ -0,0 +1,143 @@
+use graph_db::{rc_graph::*, store::GraphStore, vec_graph::Error};
+
+fn linked_to(node: &NodeRef) -> Vec<String> {
+    node.borrow()
+        .links()
+        .iter()
+        .map(|l| l.target().unwrap().alias())
+        .collect()
+}
+
+fn linked_from(graph: &Graph, node: &NodeRef) -> Vec<String> {
+    node.borrow()
+        .incoming()
+        .iter()
+        .map(|(_, from)| graph.get_node_by_id(from).unwrap().alias())
+        .collect()
+}
+
+/// ali knows veli and ayse, veli knows ayse
+fn people() -> Graph {
+    let mut graph = Graph::new();
+    for alias in ["ali", "veli", "ayse"] {
+        graph.add_node(alias).unwrap();
+    }
+    let node = |alias| graph.get_node(alias).unwrap();
+    node("ali").add_link("knows", &node("veli")).unwrap();
+    node("ali").add_link("knows", &node("ayse")).unwrap();
+    node("veli").add_link("knows", &node("ayse")).unwrap();
+    graph
+}
+
+#[test]
+fn nodes_add_their_own_links() {
+    let graph = people();
+    let (ali, ayse) = (
+        graph.get_node("ali").unwrap(),
+        graph.get_node("ayse").unwrap(),
+    );
+    assert_eq!(linked_to(&ali), ["veli", "ayse"]);
+    assert_eq!(linked_from(&graph, &ayse), ["ali", "veli"]);
+    assert_eq!(graph.edge_count(), 3);
+
+    ali.borrow_mut().add_label("kisi").add_prop("yas", 30);
+    let same = graph.get_node_by_id(&ali.id()).unwrap();
+    assert!(same.ptr_eq(&ali));
+    assert!(same.borrow().has_label("kisi"));
+    assert_eq!(same.borrow().get_prop("yas"), Some(&30.into()));
+    assert!(matches!(
+        graph.get_node("zeynep"),
+        Err(Error::AliasNotFound { .. })
+    ));
+}
+
+#[test]
+fn nodes_remove_their_own_links() {
+    let graph = people();
+    let node = |alias| graph.get_node(alias).unwrap();
+    let link = node("ali").borrow().links()[0].id();
+    node("ali").remove_link(&link).unwrap();
+    assert_eq!(linked_to(&node("ali")), ["ayse"]);
+    assert!(linked_from(&graph, &node("veli")).is_empty());
+    assert!(matches!(
+        node("ali").remove_link(&link),
+        Err(Error::EdgeNotFound { .. })
+    ));
+    assert!(matches!(
+        graph.get_link(&link),
+        Err(Error::EdgeNotFound { .. })
+    ));
+
+    node("veli").unlink("knows", &node("ayse")).unwrap();
+    assert_eq!(linked_from(&graph, &node("ayse")), ["ali"]);
+    assert_eq!(graph.edge_count(), 1);
+}
+
+#[test]
+fn removing_a_node_drops_its_links_everywhere() {
+    let mut graph = people();
+    let (ali, veli) = (
+        graph.get_node("ali").unwrap(),
+        graph.get_node("veli").unwrap(),
+    );
+    veli.add_link("knows", &veli).unwrap();
+    let link = ali.borrow().links()[0].clone();
+    graph.remove_node("veli").unwrap();
+
+    assert_eq!(linked_to(&ali), ["ayse"]);
+    assert_eq!(
+        linked_from(&graph, &graph.get_node("ayse").unwrap()),
+        ["ali"]
+    );
+    assert_eq!(graph.edge_count(), 1);
+    //the handle outlives the graph's copy but is cut off from it
+    assert!(!veli.is_attached());
+    assert!(veli.borrow().links().is_empty() && veli.borrow().incoming().is_empty());
+    assert!(matches!(
+        ali.add_link("knows", &veli),
+        Err(Error::NodeNotFound { .. })
+    ));
+    assert!(matches!(
+        veli.add_link("knows", &ali),
+        Err(Error::NodeNotFound { .. })
+    ));
+    //links only hold weak references, the graph owned veli
+    drop(veli);
+    assert!(link.target().is_none());
+}
+
+#[test]
+fn aliases_are_unique_and_graphs_separate() {
+    let mut graph = people();
+    assert!(matches!(
+        graph.add_node("ali"),
+        Err(Error::ConstraintViolation { .. })
+    ));
+    assert_eq!(graph.node_count(), 3);
+
+    let mut other = Graph::new();
+    let zeynep = other.add_node("zeynep").unwrap();
+    assert!(matches!(
+        graph.get_node("ali").unwrap().add_link("knows", &zeynep),
+        Err(Error::NodeNotFound { .. })
+    ));
+    assert_eq!(graph.edge_count(), 3);
+    assert_eq!(other.edge_count(), 0);
+}
+
+#[test]
+fn dropping_the_graph_detaches_handles() {
+    let graph = people();
+    let ali = graph.get_node("ali").unwrap();
+    let veli = graph.get_node("veli").unwrap();
+    drop(graph);
+    assert!(!ali.is_attached());
+    //veli is still held here, ayse went with the graph
+    let links = ali.borrow().links().to_vec();
+    assert_eq!(links[0].target().unwrap().alias(), "veli");
+    assert!(links[1].target().is_none());
+    assert!(matches!(
+        ali.add_link("knows", &veli),
+        Err(Error::NodeNotFound { .. })
+    ));
+}
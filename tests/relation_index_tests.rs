@@ -0,0 +1,209 @@
+use std::{fs, path::PathBuf};
+
+use graph_db::{algo::Direction, vec_graph::*};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph_db_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn first_id(graph: &Graph, alias: &str) -> NodeIndex {
+    graph.get_ids_by_alias(alias).unwrap()[0]
+}
+
+fn aliases<'a>(graph: &'a Graph, edges: Vec<&Edge>) -> Vec<(&'a str, &'a str)> {
+    edges
+        .iter()
+        .map(|e| {
+            (
+                graph.get_alias_by_id(e.from()).unwrap(),
+                graph.get_alias_by_id(e.to()).unwrap(),
+            )
+        })
+        .collect()
+}
+
+fn names(nodes: Vec<&Node>) -> Vec<&str> {
+    nodes.iter().map(|n| n.alias.as_str()).collect()
+}
+
+/// istanbul includes two districts, which border each other and a third one
+fn districts() -> Graph {
+    let mut graph = Graph::new();
+    graph
+        .add_node("istanbul")
+        .unwrap()
+        .add_node("sisli")
+        .unwrap()
+        .add_node("kadikoy")
+        .unwrap()
+        .add_node("besiktas")
+        .unwrap()
+        .add_edges_by_aliases("includes", "istanbul", "sisli")
+        .unwrap()
+        .add_edges_by_aliases("includes", "istanbul", "kadikoy")
+        .unwrap()
+        .add_edges_by_aliases("komsu", "sisli", "besiktas")
+        .unwrap()
+        .add_edges_by_aliases("komsu", "kadikoy", "sisli")
+        .unwrap();
+    graph
+}
+
+#[test]
+fn index_follows_added_and_removed_edges() {
+    let mut graph = districts();
+    assert_eq!(graph.count_by_relation("includes"), 2);
+    assert_eq!(graph.count_by_relation("komsu"), 2);
+    assert_eq!(graph.count_by_relation("yol"), 0);
+    assert!(graph.edges_by_relation("yol").is_empty());
+    assert_eq!(
+        aliases(&graph, graph.edges_by_relation("includes")),
+        [("istanbul", "sisli"), ("istanbul", "kadikoy")]
+    );
+
+    let (istanbul, besiktas) = (first_id(&graph, "istanbul"), first_id(&graph, "besiktas"));
+    let id = graph
+        .add_edge("includes", istanbul, besiktas)
+        .unwrap()
+        .get_last_edge()
+        .unwrap()
+        .id();
+    assert_eq!(graph.count_by_relation("includes"), 3);
+    graph
+        .remove_edge(&graph.edges_by_relation("komsu")[0].id())
+        .unwrap();
+    assert_eq!(
+        aliases(&graph, graph.edges_by_relation("komsu")),
+        [("kadikoy", "sisli")]
+    );
+    //a reused slot is indexed under its new relation only
+    graph.remove_edge(&id).unwrap();
+    graph.add_edge("yol", besiktas, istanbul).unwrap();
+    assert_eq!(graph.get_last_edge().unwrap().id().to_string(), "4v1");
+    assert_eq!(graph.count_by_relation("includes"), 2);
+    assert_eq!(
+        aliases(&graph, graph.edges_by_relation("yol")),
+        [("besiktas", "istanbul")]
+    );
+}
+
+#[test]
+fn bulk_and_node_removal_update_the_index() {
+    let mut graph = districts();
+    graph
+        .remove_all_edges_from(&first_id(&graph, "istanbul"))
+        .unwrap();
+    assert_eq!(graph.count_by_relation("includes"), 0);
+    assert_eq!(graph.count_by_relation("komsu"), 2);
+
+    graph
+        .remove_all_edges_to(&first_id(&graph, "besiktas"))
+        .unwrap();
+    assert_eq!(
+        aliases(&graph, graph.edges_by_relation("komsu")),
+        [("kadikoy", "sisli")]
+    );
+    graph.remove_node_by_id(&first_id(&graph, "sisli")).unwrap();
+    assert!(graph.edges_by_relation("komsu").is_empty());
+    assert_eq!(graph.edge_count(), 0);
+}
+
+#[test]
+fn rollback_restores_the_index() {
+    let mut graph = districts();
+    let before = graph.clone();
+    let failed: GraphResult<()> = graph.transaction(|tx| {
+        let istanbul = tx.get_ids_by_alias("istanbul").unwrap()[0];
+        tx.remove_node_by_id(&istanbul)?;
+        tx.add_edges_by_aliases("yol", "sisli", "kadikoy")?;
+        tx.remove_node_by_id(&istanbul)?;
+        Ok(())
+    });
+    assert!(matches!(failed, Err(Error::NodeNotFound { .. })));
+    assert_eq!(graph, before);
+    assert_eq!(graph.count_by_relation("includes"), 2);
+    assert_eq!(graph.count_by_relation("yol"), 0);
+
+    graph
+        .transaction(|tx| {
+            tx.add_edges_by_aliases("yol", "sisli", "kadikoy")?;
+            let sp = tx.savepoint();
+            let sisli = tx.get_ids_by_alias("sisli").unwrap()[0];
+            tx.remove_all_edges_from(&sisli)?;
+            assert_eq!(tx.count_by_relation("yol"), 0);
+            tx.rollback_to(sp)
+        })
+        .unwrap();
+    assert_eq!(graph.count_by_relation("yol"), 1);
+    assert_eq!(graph.count_by_relation("komsu"), 2);
+}
+
+#[test]
+fn index_is_rebuilt_on_load() {
+    let path = temp_dir("relation_index").join("graph.db");
+    let expected;
+    {
+        let mut graph = Graph::open(&path).unwrap();
+        graph
+            .add_node("istanbul")
+            .unwrap()
+            .add_node("sisli")
+            .unwrap()
+            .add_edges_by_aliases("includes", "istanbul", "sisli")
+            .unwrap()
+            .add_edges_by_aliases("komsu", "sisli", "istanbul")
+            .unwrap();
+        graph.checkpoint().unwrap();
+        graph
+            .remove_all_edges_to(&first_id(&graph, "istanbul"))
+            .unwrap()
+            .add_edges_by_aliases("yol", "sisli", "istanbul")
+            .unwrap();
+        expected = graph.clone();
+    }
+    //the snapshot holds the first two edges, the log the rest
+    let graph = Graph::open(&path).unwrap();
+    assert_eq!(graph, expected);
+    assert_eq!(graph.count_by_relation("komsu"), 0);
+    assert_eq!(graph.count_by_relation("yol"), 1);
+
+    let mut json = Vec::new();
+    graph.write_json(&mut json).unwrap();
+    let loaded = Graph::read_json(json.as_slice()).unwrap();
+    assert_eq!(loaded, graph);
+    assert_eq!(
+        aliases(&loaded, loaded.edges_by_relation("includes")),
+        [("istanbul", "sisli")]
+    );
+}
+
+#[test]
+fn neighbors_via_one_relation() {
+    let graph = districts();
+    let sisli = first_id(&graph, "sisli");
+    let via =
+        |relation, direction| names(graph.neighbors_via(&sisli, relation, direction).unwrap());
+    assert_eq!(via("komsu", Direction::Outgoing), ["besiktas"]);
+    assert_eq!(via("komsu", Direction::Incoming), ["kadikoy"]);
+    assert_eq!(via("komsu", Direction::Both), ["besiktas", "kadikoy"]);
+    assert_eq!(via("includes", Direction::Incoming), ["istanbul"]);
+    assert!(via("includes", Direction::Outgoing).is_empty());
+    assert!(via("yol", Direction::Both).is_empty());
+
+    let istanbul = first_id(&graph, "istanbul");
+    assert_eq!(
+        names(
+            graph
+                .neighbors_via(&istanbul, "includes", Direction::default())
+                .unwrap()
+        ),
+        ["kadikoy", "sisli"]
+    );
+    assert!(matches!(
+        graph.neighbors_via(&NodeIndex::from_parts(9, 0), "komsu", Direction::Both),
+        Err(Error::NodeNotFound { .. })
+    ));
+}
@@ -0,0 +1,342 @@
+use std::{fs, path::PathBuf};
+
+use graph_db::{import::CsvImport, vec_graph::*};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph_db_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn add(graph: &mut Graph, alias: &str) -> NodeIndex {
+    graph.add_node(alias).unwrap().get_last_node().unwrap().id
+}
+
+/// (constraint, detail) of a violation
+fn violated<T>(result: GraphResult<T>) -> (String, String) {
+    match result {
+        Err(Error::ConstraintViolation { constraint, detail }) => (constraint, detail),
+        Err(e) => panic!("expected a constraint violation, got {}", e),
+        Ok(_) => panic!("expected a constraint violation"),
+    }
+}
+
+#[test]
+fn required_and_unique_props() {
+    let mut graph = Graph::new();
+    graph
+        .add_constraint(Constraint::required_prop("ilce", "nufus"))
+        .unwrap()
+        .add_constraint(Constraint::unique_prop("ilce", "kod"))
+        .unwrap();
+    let sisli = add(&mut graph, "sisli");
+    assert_eq!(
+        violated(graph.add_label(&sisli, "ilce")),
+        (
+            "(:ilce) requires `nufus`".to_owned(),
+            "node 0 has no `nufus`".to_owned()
+        )
+    );
+    graph.add_prop(&sisli, "nufus", 274420).unwrap();
+    graph.add_prop(&sisli, "kod", 1).unwrap();
+    graph.add_label(&sisli, "ilce").unwrap();
+    //setting the same value again is no clash with itself
+    graph.add_prop(&sisli, "kod", 1).unwrap();
+    assert_eq!(
+        violated(graph.remove_prop(&sisli, "nufus")).1,
+        "node 0 can't lose `nufus`"
+    );
+
+    let kadikoy = add(&mut graph, "kadikoy");
+    graph.add_prop(&kadikoy, "nufus", 467919).unwrap();
+    graph.add_prop(&kadikoy, "kod", 1.0).unwrap();
+    assert_eq!(
+        violated(graph.add_label(&kadikoy, "ilce")),
+        (
+            "(:ilce) has unique `kod`".to_owned(),
+            "node 0 already has `kod` 1".to_owned()
+        )
+    );
+    graph.add_prop(&kadikoy, "kod", 2).unwrap();
+    graph.add_label(&kadikoy, "ilce").unwrap();
+    assert!(violated(graph.add_prop(&kadikoy, "kod", 1))
+        .1
+        .contains("node 0"));
+    //nodes without the label aren't bound by it
+    let merkez = add(&mut graph, "merkez");
+    graph.add_prop(&merkez, "kod", 1).unwrap();
+    assert_eq!(
+        graph.get_node(&kadikoy).unwrap().get_prop("kod"),
+        Some(&PropValue::Int(2))
+    );
+}
+
+#[test]
+fn unique_aliases() {
+    let mut graph = Graph::new();
+    graph
+        .add_constraint(Constraint::unique_alias("sehir"))
+        .unwrap();
+    let first = add(&mut graph, "merkez");
+    let second = add(&mut graph, "merkez");
+    graph.add_label(&first, "sehir").unwrap();
+    graph.add_label(&second, "mahalle").unwrap();
+    assert_eq!(
+        violated(graph.add_label(&second, "sehir")),
+        (
+            "(:sehir) has unique aliases".to_owned(),
+            "nodes 0 and 1 are both `merkez`".to_owned()
+        )
+    );
+    //the alias frees up once the other node goes
+    graph.remove_node_by_id(&first).unwrap();
+    graph.add_label(&second, "sehir").unwrap();
+    assert_eq!(graph.find_ids_by_label("sehir"), [second]);
+}
+
+#[test]
+fn relation_endpoints() {
+    let mut graph = Graph::new();
+    let (sisli, merkez, kadikoy) = (
+        add(&mut graph, "sisli"),
+        add(&mut graph, "merkez"),
+        add(&mut graph, "kadikoy"),
+    );
+    graph.add_label(&sisli, "ilce").unwrap();
+    graph.add_label(&merkez, "mahalle").unwrap();
+    graph.add_label(&kadikoy, "ilce").unwrap();
+    graph
+        .add_constraint(Constraint::relation("includes", "ilce", "mahalle"))
+        .unwrap();
+    graph.add_edge("includes", sisli, merkez).unwrap();
+    assert_eq!(
+        violated(graph.add_edge("includes", merkez, sisli)),
+        (
+            "[:includes] links (:ilce) to (:mahalle)".to_owned(),
+            "edge from node 1 to node 0".to_owned()
+        )
+    );
+    //other relations are free, a second rule allows more
+    graph.add_edge("komsu", merkez, kadikoy).unwrap();
+    graph
+        .add_constraint(Constraint::relation("includes", "ilce", "ilce"))
+        .unwrap();
+    graph.add_edge("includes", sisli, kadikoy).unwrap();
+    assert_eq!(
+        violated(graph.add_edge("includes", merkez, kadikoy)).0,
+        "[:includes] links (:ilce) to (:mahalle) or [:includes] links (:ilce) to (:ilce)"
+    );
+
+    //taking away a label or a rule an edge stands on is rejected too
+    assert_eq!(
+        violated(graph.remove_label(&merkez, "mahalle")).1,
+        "edge 0 needs node 1 to keep :mahalle"
+    );
+    assert_eq!(
+        violated(graph.drop_constraint(&Constraint::relation("includes", "ilce", "ilce"))).1,
+        "edge 2 from node 0 to node 2"
+    );
+    graph.remove_edge(&EdgeIndex::from_parts(2, 0)).unwrap();
+    graph
+        .drop_constraint(&Constraint::relation("includes", "ilce", "ilce"))
+        .unwrap();
+    assert_eq!(
+        graph.constraints(),
+        [Constraint::relation("includes", "ilce", "mahalle")]
+    );
+}
+
+#[test]
+fn adding_checks_existing_nodes_and_edges() {
+    let mut graph = Graph::new();
+    let (a, b) = (add(&mut graph, "a"), add(&mut graph, "a"));
+    graph.add_label(&a, "ilce").unwrap();
+    graph.add_label(&b, "ilce").unwrap();
+    graph.add_prop(&a, "kod", 7).unwrap();
+    graph.add_prop(&b, "kod", 7.0).unwrap();
+    graph.add_edge("includes", a, b).unwrap();
+
+    assert_eq!(
+        violated(graph.add_constraint(Constraint::unique_alias("ilce"))).1,
+        "nodes 0 and 1 are both `a`"
+    );
+    assert_eq!(
+        violated(graph.add_constraint(Constraint::unique_prop("ilce", "kod"))).1,
+        "nodes 0 and 1 both have `kod` 7"
+    );
+    assert_eq!(
+        violated(graph.add_constraint(Constraint::required_prop("ilce", "nufus"))).1,
+        "node 0 has no `nufus`"
+    );
+    assert_eq!(
+        violated(graph.add_constraint(Constraint::relation("includes", "sehir", "ilce"))).1,
+        "edge 0 from node 0 to node 1"
+    );
+    assert!(graph.constraints().is_empty());
+
+    graph
+        .add_constraint(Constraint::required_prop("ilce", "kod"))
+        .unwrap();
+    assert_eq!(
+        violated(graph.add_constraint(Constraint::required_prop("ilce", "kod"))).1,
+        "already defined"
+    );
+    assert_eq!(
+        violated(graph.drop_constraint(&Constraint::unique_alias("ilce"))).1,
+        "not defined"
+    );
+}
+
+#[test]
+fn constraints_are_graph_state() {
+    let path = temp_dir("schema_state").join("graph.db");
+    {
+        let mut graph = Graph::open(&path).unwrap();
+        graph
+            .add_constraint(Constraint::unique_alias("sehir"))
+            .unwrap();
+        //rolled back with the transaction that added them
+        let rolled_back: GraphResult<()> = graph.transaction(|tx| {
+            tx.add_constraint(Constraint::required_prop("sehir", "plaka"))?;
+            let id = tx.add_node("istanbul")?.get_last_node().unwrap().id;
+            tx.add_label(&id, "sehir")?;
+            Ok(())
+        });
+        assert!(matches!(
+            rolled_back,
+            Err(Error::ConstraintViolation { .. })
+        ));
+        assert_eq!(graph.constraints(), [Constraint::unique_alias("sehir")]);
+        assert_eq!(graph.node_count(), 0);
+        graph
+            .add_constraint(Constraint::relation("includes", "sehir", "ilce"))
+            .unwrap();
+    }
+    let expected = [
+        Constraint::unique_alias("sehir"),
+        Constraint::relation("includes", "sehir", "ilce"),
+    ];
+    //replayed from the log, then read back from the checkpoint's snapshot
+    let mut graph = Graph::open(&path).unwrap();
+    assert_eq!(graph.constraints(), expected);
+    graph.checkpoint().unwrap();
+    drop(graph);
+    let graph = Graph::open(&path).unwrap();
+    assert_eq!(graph.constraints(), expected);
+    assert_eq!(graph.clone(), graph);
+}
+
+#[test]
+fn unique_checks_follow_rollbacks_and_reloads() {
+    let mut graph = Graph::new();
+    graph
+        .add_constraint(Constraint::unique_prop("ilce", "kod"))
+        .unwrap()
+        .add_constraint(Constraint::unique_alias("ilce"))
+        .unwrap();
+    let sisli = add(&mut graph, "sisli");
+    graph.add_prop(&sisli, "kod", 1).unwrap();
+    graph.add_label(&sisli, "ilce").unwrap();
+    let kadikoy = add(&mut graph, "kadikoy");
+    graph.add_prop(&kadikoy, "kod", 1.0).unwrap();
+
+    //sisli gives up `kod` 1 and its label, the rollback hands both back
+    let rolled_back: GraphResult<()> = graph.transaction(|tx| {
+        tx.add_prop(&sisli, "kod", 3)?;
+        tx.remove_label(&sisli, "ilce")?;
+        tx.add_label(&kadikoy, "ilce")?;
+        tx.drop_constraint(&Constraint::unique_prop("ilce", "kod"))?;
+        tx.add_node("sisli")?;
+        Err(Error::NoPath {
+            from: sisli,
+            to: kadikoy,
+        })
+    });
+    assert!(matches!(rolled_back, Err(Error::NoPath { .. })));
+    assert!(violated(graph.add_label(&kadikoy, "ilce"))
+        .1
+        .contains("`kod` 1"));
+
+    let twin = add(&mut graph, "sisli");
+    graph.add_prop(&twin, "kod", 4).unwrap();
+    assert!(violated(graph.add_label(&twin, "ilce"))
+        .1
+        .contains("both `sisli`"));
+
+    //a value that was moved away is free again, also after a reload
+    graph.add_prop(&sisli, "kod", 2).unwrap();
+    let path = temp_dir("schema_uniques").join("graph.snap");
+    graph.save_snapshot(&path).unwrap();
+    let mut graph = Graph::load_snapshot(&path).unwrap();
+    graph.add_label(&kadikoy, "ilce").unwrap();
+    assert!(violated(graph.add_prop(&kadikoy, "kod", 2))
+        .1
+        .contains("node 0"));
+    graph.remove_node_by_id(&sisli).unwrap();
+    graph.add_label(&twin, "ilce").unwrap();
+    graph.add_prop(&kadikoy, "kod", 2).unwrap();
+}
+
+#[test]
+fn endpoint_checks_follow_removed_and_rolled_back_edges() {
+    let mut graph = Graph::new();
+    let (a, b) = (add(&mut graph, "a"), add(&mut graph, "b"));
+    graph.add_label(&b, "ilce").unwrap();
+    graph.add_edge("borders", a, b).unwrap();
+    graph.add_edge("includes", a, b).unwrap();
+    let includes = graph
+        .iter_edges()
+        .find(|e| e.relation() == "includes")
+        .unwrap()
+        .id();
+    let rule = Constraint::relation("includes", "sehir", "ilce");
+    assert_eq!(
+        violated(graph.add_constraint(rule.clone())).1,
+        "edge 1 from node 0 to node 1"
+    );
+
+    graph.remove_edge(&includes).unwrap();
+    let rolled_back: GraphResult<()> = graph.transaction(|tx| {
+        tx.add_edge("includes", a, b)?;
+        Err(Error::NoPath { from: a, to: b })
+    });
+    assert!(matches!(rolled_back, Err(Error::NoPath { .. })));
+    graph.add_constraint(rule).unwrap();
+    assert!(violated(graph.add_edge("includes", a, b))
+        .1
+        .contains("from node 0 to node 1"));
+}
+
+#[test]
+fn import_skips_rows_breaking_constraints() {
+    let mut graph = Graph::new();
+    graph
+        .add_constraint(Constraint::required_prop("ilce", "nufus"))
+        .unwrap()
+        .add_constraint(Constraint::unique_alias("ilce"))
+        .unwrap();
+    let mut import = CsvImport::new();
+    import
+        .nodes(
+            &mut graph,
+            "nodes",
+            "id,alias,labels,nufus:int\n1,sisli,ilce,274420\n2,sisli,ilce,1\n3,bos,ilce,\n"
+                .as_bytes(),
+        )
+        .unwrap();
+    let report = import.report();
+    assert_eq!(report.nodes, 1);
+    assert_eq!(
+        report.errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        [
+            "nodes:3: Constraint (:ilce) has unique aliases violated: nodes 0 and 1 are both `sisli`",
+            "nodes:4: Constraint (:ilce) requires `nufus` violated: node 1 has no `nufus`",
+        ]
+    );
+    //the skipped rows left nothing behind
+    assert_eq!(graph.node_count(), 1);
+    assert!(graph
+        .get_ids_by_alias("bos")
+        .is_none_or(|ids| ids.is_empty()));
+}
@@ -0,0 +1,194 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpStream},
+    process::{Command, Stdio},
+    thread,
+};
+
+use graph_db::{
+    json::Json,
+    server::{protocol, Server, ServerHandle},
+    vec_graph::*,
+};
+
+fn start(graph: Graph) -> (ServerHandle, SharedGraph) {
+    let graph = SharedGraph::new(graph);
+    let server = Server::bind("127.0.0.1:0", graph.clone()).unwrap();
+    (server.spawn().unwrap(), graph)
+}
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    stream: TcpStream,
+}
+
+impl Connection {
+    fn open(addr: SocketAddr) -> Self {
+        let stream = TcpStream::connect(addr).unwrap();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        Connection { reader, stream }
+    }
+    fn send(&mut self, line: &str) -> Json {
+        writeln!(self.stream, "{}", line).unwrap();
+        let mut reply = String::new();
+        self.reader.read_line(&mut reply).unwrap();
+        Json::parse(&reply).unwrap()
+    }
+}
+
+#[test]
+fn ndjson_requests() {
+    let (server, graph) = start(Graph::new());
+    let mut conn = Connection::open(server.addr());
+
+    let reply = conn.send(r#"{"id":1,"op":"add_node","alias":"sisli"}"#);
+    assert_eq!(reply.to_string(), r#"{"id":1,"ok":"0"}"#);
+    conn.send(r#"{"id":"b","op":"add_label","node":"0","label":"district"}"#);
+    conn.send(r#"{"op":"add_prop","node":"0","key":"population","value":274420}"#);
+
+    let reply = conn.send(r#"{"id":2,"op":"query","text":"MATCH (d:district) RETURN d"}"#);
+    let result = protocol::result_from_json(reply.get("ok").unwrap()).unwrap();
+    assert_eq!(result.column("d"), Some(vec![NodeIndex::from_parts(0, 0)]));
+
+    let reply = conn.send(r#"{"op":"get_node","node":"0"}"#);
+    let node = protocol::node_from_json(reply.get("ok").unwrap()).unwrap();
+    assert_eq!(node.alias, "sisli");
+    assert!(node.has_label("district"));
+    assert_eq!(node.get_prop("population"), Some(&PropValue::Int(274420)));
+
+    server.shutdown();
+    assert_eq!(graph.read(Graph::node_count), 1);
+}
+
+#[test]
+fn errors_keep_connection() {
+    let (server, _) = start(Graph::new());
+    let mut conn = Connection::open(server.addr());
+
+    let reply = conn.send(r#"{"id":7,"op":"remove_node","node":"3v1"}"#);
+    assert_eq!(reply.get("id"), Some(&Json::Int(7)));
+    let (_, result) = protocol::parse_response(&reply).unwrap();
+    assert!(matches!(
+        result,
+        Err(Error::NodeNotFound { id }) if id.to_string() == "3v1"
+    ));
+
+    let reply = conn.send("{not json");
+    assert_eq!(
+        reply.get("error").and_then(|e| e.get("kind")),
+        Some(&Json::Str("Parse".to_owned()))
+    );
+    let reply = conn.send(r#"{"op":"fly"}"#);
+    assert!(reply.get("error").is_some());
+    let reply = conn.send(r#"{"op":"add_edge","relation":"r","from":"x","to":"0"}"#);
+    let (_, result) = protocol::parse_response(&reply).unwrap();
+    assert!(matches!(result, Err(Error::InvalidIndex { .. })));
+
+    assert_eq!(
+        conn.send(r#"{"op":"ping"}"#).to_string(),
+        r#"{"ok":"pong"}"#
+    );
+    server.shutdown();
+}
+
+#[test]
+fn execute_shell_lines() {
+    let (server, _) = start(Graph::new());
+    let mut conn = Connection::open(server.addr());
+
+    let reply = conn.send(r#"{"op":"execute","line":"add (a:district), (b:district)"}"#);
+    assert_eq!(reply.to_string(), r#"{"ok":{"text":"2 nodes added"}}"#);
+    let reply = conn.send(r#"{"op":"execute","line":"link (a)-[:komsu]->(b)"}"#);
+    assert_eq!(reply.to_string(), r#"{"ok":{"text":"1 edge added"}}"#);
+    let reply = conn.send(r#"{"op":"execute","line":"MATCH (x)-[:komsu]->(y) RETURN x, y"}"#);
+    let table = match protocol::output_from_json(reply.get("ok").unwrap()).unwrap() {
+        graph_db::shell::Output::Table(table) => table,
+        other => panic!("expected a table, got {:?}", other),
+    };
+    assert_eq!(table.headers, ["x", "y"]);
+    assert_eq!(table.rows, [["a (0)", "b (1)"]]);
+    server.shutdown();
+}
+
+#[test]
+fn oversize_and_binary_lines() {
+    let (server, _) = start(Graph::new());
+    let mut conn = Connection::open(server.addr());
+
+    let huge = format!(
+        r#"{{"op":"add_node","alias":"{}"}}"#,
+        "x".repeat(protocol::MAX_REQUEST_LEN)
+    );
+    let reply = conn.send(&huge);
+    let (_, result) = protocol::parse_response(&reply).unwrap();
+    assert!(matches!(result, Err(Error::Parse { message, .. }) if message.contains("bytes")));
+
+    conn.stream.write_all(b"{\"op\":\"ping\xff\"}\n").unwrap();
+    let mut reply = String::new();
+    conn.reader.read_line(&mut reply).unwrap();
+    assert!(reply.contains("UTF-8"));
+
+    assert_eq!(
+        conn.send(r#"{"op":"ping"}"#).to_string(),
+        r#"{"ok":"pong"}"#
+    );
+    server.shutdown();
+}
+
+#[test]
+fn concurrent_connections() {
+    let (server, graph) = start(Graph::new());
+    let addr = server.addr();
+    let writers: Vec<_> = (0..8)
+        .map(|t| {
+            thread::spawn(move || {
+                let mut conn = Connection::open(addr);
+                for i in 0..25 {
+                    let line = format!(r#"{{"op":"add_node","alias":"n{}_{}"}}"#, t, i);
+                    assert!(conn.send(&line).get("ok").is_some());
+                    assert!(conn.send(r#"{"op":"stats"}"#).get("ok").is_some());
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    let reply = Connection::open(addr).send(r#"{"op":"stats"}"#);
+    assert_eq!(reply.to_string(), r#"{"ok":{"edges":0,"nodes":200}}"#);
+    server.shutdown();
+    assert_eq!(graph.read(Graph::node_count), 200);
+}
+
+#[test]
+fn server_binary() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_graph_db-server"))
+        .args(["--addr", "127.0.0.1:0"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut banner = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut banner)
+        .unwrap();
+    let addr: SocketAddr = banner
+        .trim()
+        .strip_prefix("listening on ")
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    let mut conn = Connection::open(addr);
+    assert_eq!(
+        conn.send(r#"{"id":1,"op":"add_node","alias":"kadikoy"}"#)
+            .to_string(),
+        r#"{"id":1,"ok":"0"}"#
+    );
+    assert_eq!(
+        conn.send(r#"{"op":"nodes_by_alias","alias":"kadikoy"}"#)
+            .to_string(),
+        r#"{"ok":["0"]}"#
+    );
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
@@ -0,0 +1,228 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Barrier,
+    },
+    thread,
+};
+
+use graph_db::vec_graph::*;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph_db_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Adds two nodes and an edge between them, so a consistent graph always has twice as many nodes as edges
+fn add_pair(shared: &SharedGraph, alias: &str) -> GraphResult<()> {
+    shared.write(|tx| {
+        tx.add_node(alias)?.add_node(&format!("{}_to", alias))?;
+        tx.add_edges_by_aliases("pair", alias, &format!("{}_to", alias))?;
+        Ok(())
+    })
+}
+
+fn assert_consistent(graph: &Graph) {
+    assert_eq!(graph.node_count(), 2 * graph.edge_count());
+    for edge in graph.iter_edges() {
+        graph.get_node(edge.from()).unwrap();
+        graph.get_node(edge.to()).unwrap();
+    }
+}
+
+#[test]
+fn snapshots_are_isolated() {
+    let shared = SharedGraph::new(Graph::new());
+    add_pair(&shared, "sisli").unwrap();
+    let before = shared.snapshot();
+    assert_eq!(shared.version(), 1);
+
+    add_pair(&shared, "besiktas").unwrap();
+    assert_eq!(before.node_count(), 2);
+    assert_eq!(shared.snapshot().node_count(), 4);
+    assert_eq!(shared.version(), 2);
+
+    let failed = shared.write(|tx| {
+        tx.add_node("yarim")?;
+        tx.add_edges_by_aliases("pair", "yarim", "yok")?;
+        Ok(())
+    });
+    assert!(matches!(failed, Err(Error::AliasNotFound { .. })));
+    assert_eq!(shared.version(), 2);
+    assert_eq!(shared.read(Graph::node_count), 4);
+    //reads don't make writes
+    shared.write(|tx| Ok(tx.node_count())).unwrap();
+    assert_eq!(shared.version(), 2);
+}
+
+#[test]
+fn writers_are_serialized() {
+    let shared = SharedGraph::new(Graph::new());
+    let writers: Vec<_> = (0..8)
+        .map(|t| {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                for i in 0..100 {
+                    shared
+                        .write(|tx| {
+                            tx.add_node(&format!("w{}", t))?;
+                            let id = tx.get_last_node().expect("Node was just added").id;
+                            tx.add_prop(&id, "i", i)?;
+                            Ok(())
+                        })
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    let snapshot = shared.snapshot();
+    assert_eq!(snapshot.node_count(), 800);
+    assert_eq!(shared.version(), 800);
+    for t in 0..8 {
+        let ids = snapshot.get_ids_by_alias(&format!("w{}", t)).unwrap();
+        let order: Vec<_> = ids
+            .iter()
+            .map(|id| snapshot.get_node(id).unwrap().get_prop("i").cloned())
+            .collect();
+        let expected: Vec<_> = (0..100).map(|i| Some(PropValue::Int(i))).collect();
+        assert_eq!(order, expected);
+    }
+    //the published copy matches the graph the writers worked on
+    assert_eq!(*snapshot, shared.into_inner().unwrap());
+}
+
+#[test]
+fn readers_see_whole_writes_under_load() {
+    let shared = SharedGraph::new(Graph::new());
+    let done = Arc::new(AtomicBool::new(false));
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let shared = shared.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                let mut last_version = 0;
+                let mut reads = 0;
+                while !done.load(Ordering::SeqCst) || reads == 0 {
+                    let version = shared.version();
+                    assert!(version >= last_version);
+                    last_version = version;
+                    shared.read(assert_consistent);
+                    reads += 1;
+                }
+            })
+        })
+        .collect();
+    let writers: Vec<_> = (0..4)
+        .map(|t| {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                for i in 0..150 {
+                    let alias = format!("w{}_{}", t, i);
+                    add_pair(&shared, &alias).unwrap();
+                    if i % 3 == 0 {
+                        //removing a pair keeps the invariant, half of it would break it
+                        shared
+                            .write(|tx| {
+                                let ids = tx.get_ids_by_alias(&alias).cloned().unwrap_or_default();
+                                let to = tx
+                                    .get_ids_by_alias(&format!("{}_to", alias))
+                                    .cloned()
+                                    .unwrap_or_default();
+                                for id in ids.iter().chain(to.iter()) {
+                                    tx.remove_node_by_id(id)?;
+                                }
+                                Ok(())
+                            })
+                            .unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    done.store(true, Ordering::SeqCst);
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    assert_eq!(shared.read(Graph::edge_count), 4 * 100);
+    shared.read(assert_consistent);
+}
+
+#[test]
+fn long_reads_keep_their_view() {
+    let shared = SharedGraph::new(Graph::new());
+    add_pair(&shared, "sisli").unwrap();
+    let start = Arc::new(Barrier::new(2));
+    let reader = {
+        let shared = shared.clone();
+        let start = start.clone();
+        thread::spawn(move || {
+            let snapshot = shared.snapshot();
+            let copy = (*snapshot).clone();
+            start.wait();
+            //writes commit while this snapshot is held
+            start.wait();
+            assert_eq!(*snapshot, copy);
+            assert_eq!(snapshot.node_count(), 2);
+        })
+    };
+    start.wait();
+    for i in 0..50 {
+        add_pair(&shared, &format!("n{}", i)).unwrap();
+    }
+    start.wait();
+    reader.join().unwrap();
+    assert_eq!(shared.read(Graph::node_count), 102);
+}
+
+#[test]
+fn panicking_writer_rolls_back() {
+    let shared = SharedGraph::new(Graph::new());
+    add_pair(&shared, "sisli").unwrap();
+    let writer = shared.clone();
+    let result = thread::spawn(move || {
+        writer
+            .write(|tx| -> GraphResult<()> {
+                tx.add_node("yarim")?;
+                panic!("writer died mid transaction");
+            })
+            .unwrap();
+    })
+    .join();
+    assert!(result.is_err());
+    assert_eq!(shared.read(Graph::node_count), 2);
+    add_pair(&shared, "besiktas").unwrap();
+    shared.read(assert_consistent);
+    assert_eq!(shared.into_inner().unwrap().node_count(), 4);
+}
+
+#[test]
+fn writes_reach_the_log() {
+    let path = temp_dir("shared_graph").join("city.db");
+    let shared = SharedGraph::new(Graph::open(&path).unwrap());
+    let writers: Vec<_> = (0..4)
+        .map(|t| {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                for i in 0..20 {
+                    add_pair(&shared, &format!("w{}_{}", t, i)).unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    let expected = shared.snapshot();
+    drop(shared);
+    assert_eq!(Graph::open(&path).unwrap(), *expected);
+}
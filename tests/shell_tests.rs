@@ -0,0 +1,215 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use graph_db::{
+    shell::{self, Output, Table},
+    vec_graph::*,
+};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph_db_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(graph: &mut Graph, line: &str) -> String {
+    shell::execute(graph, line)
+        .unwrap_or_else(|e| panic!("`{}` failed: {}", line, e))
+        .to_string()
+}
+
+fn table(graph: &mut Graph, line: &str) -> Table {
+    match shell::execute(graph, line).unwrap() {
+        Output::Table(table) => table,
+        other => panic!("`{}` gave {:?}", line, other),
+    }
+}
+
+/// Runs the binary on `db` with `script` as stdin
+fn repl(db: &Path, script: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_graph_db"))
+        .arg(db)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn commands_build_the_graph() {
+    let mut graph = Graph::new();
+    assert_eq!(
+        run(
+            &mut graph,
+            r#"add (sisli:sehir {tur: "ilce"}), (merkez:mahalle), (mcdkoy:mahalle)"#
+        ),
+        "3 nodes added"
+    );
+    assert_eq!(
+        run(
+            &mut graph,
+            "link (sisli)-[:includes]->(merkez)<-[:komsu]-(mcdkoy)"
+        ),
+        "2 edges added"
+    );
+    assert_eq!(
+        run(&mut graph, "SET (merkez:bolge {nufus: 12, eski: true})"),
+        "1 node updated"
+    );
+    let mut expected = Graph::new();
+    expected
+        .add_node("sisli")
+        .unwrap()
+        .mut_last_node(|n| n.add_label("sehir")?.add_prop("tur", "ilce"))
+        .unwrap()
+        .add_node("merkez")
+        .unwrap()
+        .mut_last_node(|n| {
+            n.add_label("mahalle")?
+                .add_label("bolge")?
+                .add_prop("nufus", 12)?
+                .add_prop("eski", true)
+        })
+        .unwrap()
+        .add_node("mcdkoy")
+        .unwrap()
+        .mut_last_node(|n| n.add_label("mahalle"))
+        .unwrap()
+        .add_edges_by_aliases("includes", "sisli", "merkez")
+        .unwrap()
+        .add_edges_by_aliases("komsu", "mcdkoy", "merkez")
+        .unwrap();
+    assert_eq!(graph, expected);
+    assert_eq!(
+        run(&mut graph, "unlink (mcdkoy)-[:komsu]->(merkez)"),
+        "1 edge removed"
+    );
+    assert_eq!(run(&mut graph, "delete merkez"), "1 node deleted");
+    assert_eq!((graph.node_count(), graph.edge_count()), (2, 0));
+}
+
+#[test]
+fn failing_command_changes_nothing() {
+    let mut graph = Graph::new();
+    let mut before = Graph::new();
+    run(&mut graph, "add (sisli), (merkez)");
+    run(&mut before, "add (sisli), (merkez)");
+    for line in [
+        "link (sisli)-[:includes]->(merkez)<-[:komsu]-(yok)",
+        "add (a:x), (b {k: 1}), (:nolabel)",
+        "delete merkez, yok",
+        "link (sisli)-[:a|b]->(merkez)",
+        "frobnicate sisli",
+        ":nope",
+    ] {
+        assert!(shell::execute(&mut graph, line).is_err(), "{}", line);
+        assert_eq!(graph, before, "{}", line);
+    }
+}
+
+#[test]
+fn aligned_tables() {
+    let mut graph = Graph::new();
+    run(
+        &mut graph,
+        r#"add (sisli:sehir {tur: "ilce"}), (merkez:mahalle), (mcdkoy:mahalle)"#,
+    );
+    run(&mut graph, "link (sisli)-[:includes]->(merkez)");
+    run(&mut graph, "link (merkez)-[:komsu]->(mcdkoy)");
+    let result = table(
+        &mut graph,
+        "MATCH p = (s:sehir)-[:includes]->(m)-[:komsu*0..1]->(n) RETURN s, n, p",
+    );
+    assert_eq!(result.headers, vec!["s", "n", "p"]);
+    let text = result.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "s         | n          | p");
+    let longest = "sisli (0) -[:includes]-> merkez (1) -[:komsu]-> mcdkoy (2)";
+    assert_eq!(
+        lines[1],
+        format!("----------+------------+-{}", "-".repeat(longest.len()))
+    );
+    assert!(lines.contains(&"sisli (0) | merkez (1) | sisli (0) -[:includes]-> merkez (1)"));
+    assert!(lines.contains(
+        &"sisli (0) | mcdkoy (2) | sisli (0) -[:includes]-> merkez (1) -[:komsu]-> mcdkoy (2)"
+    ));
+    assert_eq!(lines.last(), Some(&"(2 rows)"));
+    let shown = table(&mut graph, "show sisli");
+    assert_eq!(shown.rows, vec![vec!["0", "sisli", "sehir", "tur: ilce"]]);
+}
+
+#[test]
+fn schema_and_stats() {
+    let mut graph = Graph::new();
+    graph.create_prop_index("tur").unwrap();
+    run(
+        &mut graph,
+        r#"add (sisli:sehir:sehir {tur: "ilce"}), (merkez:mahalle {tur: "mahalle"}), (mcdkoy:mahalle)"#,
+    );
+    run(&mut graph, "link (sisli)-[:includes]->(merkez)");
+    run(&mut graph, "link (sisli)-[:includes]->(mcdkoy)");
+    assert_eq!(
+        table(&mut graph, ":schema").rows,
+        vec![
+            vec!["label", "mahalle", "2"],
+            vec!["label", "sehir", "1"],
+            vec!["relation", "includes", "2"],
+            vec!["node prop (indexed)", "tur", "2"],
+        ]
+    );
+    let stats = table(&mut graph, ":stats");
+    let stat = |name: &str| {
+        stats
+            .rows
+            .iter()
+            .find(|row| row[0] == name)
+            .map(|row| row[1].clone())
+            .unwrap()
+    };
+    assert_eq!(
+        (stat("nodes"), stat("edges"), stat("prop indexes")),
+        ("3".to_owned(), "2".to_owned(), "1".to_owned())
+    );
+}
+
+#[test]
+fn repl_persists_graph_and_history() {
+    let db = temp_dir("repl").join("city.db");
+    let out = repl(
+        &db,
+        "add (sisli), (merkez)\nlink (sisli)-[:includes]->(merkez)\nlink (sisli)-[:includes]->(yok)\n:quit\nshow\n",
+    );
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        vec![
+            "2 nodes added",
+            "1 edge added",
+            "error: No node with alias `yok`"
+        ]
+    );
+    let out = repl(
+        &db,
+        "MATCH (a)-[:includes]->(b) RETURN b\n:history\n!1\n!9\n",
+    );
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[2], "merkez (1)");
+    assert_eq!(lines[4], "   1  add (sisli), (merkez)");
+    assert!(lines.contains(&"   4  MATCH (a)-[:includes]->(b) RETURN b"));
+    assert!(lines.contains(&"error: no history entry `!9`, see :history"));
+    let graph = Graph::open(&db).unwrap();
+    assert_eq!(graph.node_count(), 4);
+}
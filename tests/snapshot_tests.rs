@@ -0,0 +1,224 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use graph_db::vec_graph::*;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph_db_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn first_id(graph: &Graph, alias: &str) -> NodeIndex {
+    graph.get_ids_by_alias(alias).unwrap()[0]
+}
+
+fn sample() -> Graph {
+    let mut graph = Graph::new();
+    graph
+        .create_prop_index("tur")
+        .unwrap()
+        .add_node("sisli")
+        .unwrap()
+        .mut_last_node(|n| {
+            n.add_label("sehir")?
+                .add_prop("tur", "ilce")?
+                .add_prop("kod", vec![PropValue::Int(34), PropValue::Null])?
+                .add_prop("veri", &b"\x00\xff"[..])
+        })
+        .unwrap()
+        .add_node("gecici")
+        .unwrap()
+        .add_node("merkez")
+        .unwrap()
+        .mut_last_node(|n| {
+            let mut meta = BTreeMap::new();
+            meta.insert("alan".to_owned(), PropValue::Float(1.5));
+            n.add_label("mahalle")?
+                .add_prop("tur", "mahalle")?
+                .add_prop("meta", meta)?
+                .add_prop("aktif", true)
+        })
+        .unwrap()
+        .add_node("merkez")
+        .unwrap()
+        .add_edges_by_aliases("includes", "sisli", "merkez")
+        .unwrap()
+        .mut_last_edge(|e| e.add_label("idari")?.add_prop("km", 2))
+        .unwrap()
+        .add_edges_by_aliases("komsu", "gecici", "sisli")
+        .unwrap();
+    let gecici = first_id(&graph, "gecici");
+    graph.remove_node_by_id(&gecici).unwrap();
+    graph
+}
+
+#[test]
+fn save_and_load_round_trip() {
+    let path = temp_dir("snap_round_trip").join("graph.snap");
+    let graph = sample();
+    graph.save_snapshot(&path).unwrap();
+    let loaded = Graph::load_snapshot(&path).unwrap();
+    assert_eq!(loaded, graph);
+    //removed slot keeps its bumped generation, so the next node gets the same id in both
+    let (mut graph, mut loaded) = (graph, loaded);
+    graph.add_node("yeni").unwrap();
+    loaded.add_node("yeni").unwrap();
+    assert_eq!(first_id(&loaded, "yeni"), first_id(&graph, "yeni"));
+    assert_eq!(first_id(&loaded, "yeni").generation(), 1);
+    assert_eq!(loaded.find_ids_by_prop("tur", "ilce").unwrap().len(), 1);
+    assert_eq!(loaded, graph);
+}
+
+#[test]
+fn every_flipped_byte_is_rejected() {
+    let path = temp_dir("snap_flip").join("graph.snap");
+    sample().save_snapshot(&path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    for at in 0..bytes.len() {
+        let mut damaged = bytes.clone();
+        damaged[at] ^= 0x01;
+        fs::write(&path, &damaged).unwrap();
+        match Graph::load_snapshot(&path) {
+            Err(Error::Corrupt { .. }) => {}
+            //a flipped version bit reads as a newer format
+            Err(Error::UnsupportedVersion { .. }) if (8..12).contains(&at) => {}
+            other => panic!("flipped byte {} gave {:?}", at, other.map(|_| ())),
+        }
+    }
+}
+
+#[test]
+fn truncated_file_is_rejected() {
+    let path = temp_dir("snap_truncated").join("graph.snap");
+    sample().save_snapshot(&path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    for len in 0..bytes.len() {
+        fs::write(&path, &bytes[..len]).unwrap();
+        assert!(
+            matches!(Graph::load_snapshot(&path), Err(Error::Corrupt { .. })),
+            "truncated to {} bytes",
+            len
+        );
+    }
+}
+
+#[test]
+fn future_version_is_rejected() {
+    let path = temp_dir("snap_version").join("graph.snap");
+    Graph::new().save_snapshot(&path).unwrap();
+    let mut bytes = fs::read(&path).unwrap();
+    assert_eq!(&bytes[..8], b"GDBSNAP\0");
+    bytes[8..12].copy_from_slice(&7u32.to_le_bytes());
+    fs::write(&path, &bytes).unwrap();
+    assert!(matches!(
+        Graph::load_snapshot(&path),
+        Err(Error::UnsupportedVersion { found: 7, .. })
+    ));
+    assert!(matches!(
+        Graph::load_snapshot(path.with_extension("missing")),
+        Err(Error::Io { .. })
+    ));
+}
+
+/// CRC-32 (IEEE) like the snapshot's own, to re-sign edited files
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |mut crc, &b| {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+#[test]
+fn broken_edge_lists_are_rejected() {
+    let path = temp_dir("snap_lists").join("graph.snap");
+    let mut graph = Graph::new();
+    graph
+        .add_node("a")
+        .unwrap()
+        .add_node("b")
+        .unwrap()
+        .add_node("c")
+        .unwrap()
+        .add_edges_by_aliases("x", "a", "b")
+        .unwrap()
+        .add_edges_by_aliases("y", "a", "b")
+        .unwrap()
+        .add_edges_by_aliases("z", "c", "a")
+        .unwrap();
+    graph.save_snapshot(&path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    //edge 1 `y` holds next outgoing and next incoming keys both pointing at edge 0,
+    //after its relation, from and to keys and empty label and prop counts
+    let y = bytes.windows(5).position(|w| w == b"\x01\0\0\0y").unwrap();
+    let next_outgoing = y + 5 + 12 + 12 + 8 + 8 + 1;
+    let next_incoming = next_outgoing + 12 + 1;
+    assert_eq!(bytes[next_outgoing], 0);
+    assert_eq!(bytes[next_incoming], 0);
+
+    //y -> y, a loop in a's outgoing list; y -> z, an edge into a in b's incoming list
+    for (at, slot, problem) in [
+        (next_outgoing, 1, "Cycle"),
+        (next_incoming, 2, "list of node 1"),
+    ] {
+        let mut edited = bytes.clone();
+        edited[at] = slot;
+        let body = crc32(&edited[36..]);
+        edited[28..32].copy_from_slice(&body.to_le_bytes());
+        let header = crc32(&edited[..32]);
+        edited[32..36].copy_from_slice(&header.to_le_bytes());
+        fs::write(&path, &edited).unwrap();
+        match Graph::load_snapshot(&path) {
+            Err(Error::Corrupt { context }) => assert!(context.contains(problem), "{}", context),
+            other => panic!("edited slot {} gave {:?}", slot, other.map(|_| ())),
+        }
+    }
+}
+
+fn nested(depth: usize) -> PropValue {
+    (0..depth).fold(PropValue::Null, |v, _| PropValue::List(vec![v]))
+}
+
+#[test]
+fn values_nest_at_most_max_depth() {
+    let path = temp_dir("snap_depth").join("graph.snap");
+    let mut graph = Graph::new();
+    graph.add_node("a").unwrap();
+    let a = first_id(&graph, "a");
+    assert!(matches!(
+        graph.add_prop(&a, "deep", nested(PropValue::MAX_DEPTH + 1)),
+        Err(Error::InvalidOperation { .. })
+    ));
+    graph
+        .add_prop(&a, "deep", nested(PropValue::MAX_DEPTH))
+        .unwrap();
+    graph.save_snapshot(&path).unwrap();
+    assert_eq!(Graph::load_snapshot(&path).unwrap(), graph);
+
+    //one more list around the innermost null, in a re-signed file
+    let bytes = fs::read(&path).unwrap();
+    let list = [6, 1, 0, 0, 0].repeat(PropValue::MAX_DEPTH);
+    let at = bytes.windows(list.len()).position(|w| w == list).unwrap() + list.len();
+    assert_eq!(bytes[at], 0);
+    let mut edited = bytes[..at].to_vec();
+    edited.extend_from_slice(&[6, 1, 0, 0, 0]);
+    edited.extend_from_slice(&bytes[at..]);
+    let len = (edited.len() - 36) as u64;
+    edited[20..28].copy_from_slice(&len.to_le_bytes());
+    let body = crc32(&edited[36..]);
+    edited[28..32].copy_from_slice(&body.to_le_bytes());
+    let header = crc32(&edited[..32]);
+    edited[32..36].copy_from_slice(&header.to_le_bytes());
+    fs::write(&path, &edited).unwrap();
+    match Graph::load_snapshot(&path) {
+        Err(Error::Corrupt { context }) => assert!(context.contains("too deep"), "{}", context),
+        other => panic!("deeper value gave {:?}", other.map(|_| ())),
+    }
+}
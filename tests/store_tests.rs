@@ -0,0 +1,329 @@
+//! The same checks for every backend through `GraphStore`, each one is instantiated with `store_suite!`
+
+use std::collections::BTreeMap;
+
+use graph_db::{
+    algo::{Bfs, Direction, ShortestPath, VarLengthPaths, Weight},
+    export::{Export, Format},
+    query,
+    rc_graph::Graph as RcGraph,
+    store::{self, GraphStore},
+    unsafe_graph::Graph as UnsafeGraph,
+    vec_graph::*,
+    vgraph::Graph as VGraph,
+};
+
+fn id<G: GraphStore + ?Sized>(store: &G, alias: &str) -> NodeIndex {
+    store.ids_with_alias(alias)[0]
+}
+
+fn aliases<G: GraphStore + ?Sized>(store: &G, ids: &[NodeIndex]) -> Vec<String> {
+    let mut aliases: Vec<String> = ids.iter().map(|id| store.node_alias(id).unwrap()).collect();
+    aliases.sort();
+    aliases
+}
+
+/// istanbul includes sisli and kadikoy, sisli borders kadikoy and besiktas, with km on every edge
+fn districts<G: GraphStore + ?Sized>(store: &mut G) {
+    for alias in ["istanbul", "sisli", "kadikoy", "besiktas"] {
+        store.create_node(alias).unwrap();
+    }
+    let istanbul = id(store, "istanbul");
+    store.set_node_prop(&istanbul, "plaka", 34.into()).unwrap();
+    store.label_node(&istanbul, "sehir").unwrap();
+    for alias in ["sisli", "kadikoy", "besiktas"] {
+        let district = id(store, alias);
+        store.label_node(&district, "ilce").unwrap();
+    }
+    for (relation, from, to, km) in [
+        ("includes", "istanbul", "sisli", 1),
+        ("includes", "istanbul", "kadikoy", 1),
+        ("komsu", "sisli", "kadikoy", 12),
+        ("komsu", "sisli", "besiktas", 3),
+        ("komsu", "besiktas", "kadikoy", 4),
+    ] {
+        let edge = store
+            .create_edge(relation, &id(store, from), &id(store, to))
+            .unwrap();
+        store.set_edge_prop(&edge, "km", km.into()).unwrap();
+    }
+}
+
+fn nodes<G: GraphStore>(mut store: G) {
+    let sisli = store.create_node("sisli").unwrap();
+    let merkez = store.create_node("merkez").unwrap();
+    assert_eq!(store.node_count(), 2);
+    assert!(store.has_node(&sisli));
+    assert_eq!(store.node_alias(&sisli).unwrap(), "sisli");
+    assert_eq!(store.ids_with_alias("merkez"), [merkez]);
+    assert!(store.ids_with_alias("yok").is_empty());
+
+    store.label_node(&sisli, "ilce").unwrap();
+    store.label_node(&sisli, "merkez").unwrap();
+    store.unlabel_node(&sisli, "merkez").unwrap();
+    assert_eq!(store.node_labels(&sisli).unwrap(), ["ilce"]);
+    assert!(store.node_has_label(&sisli, "ilce").unwrap());
+    assert_eq!(store.ids_with_label("ilce"), [sisli]);
+
+    store.set_node_prop(&sisli, "nufus", 1.into()).unwrap();
+    store.set_node_prop(&sisli, "nufus", 274420.into()).unwrap();
+    store.set_node_prop(&sisli, "ad", "Şişli".into()).unwrap();
+    store.set_node_prop(&sisli, "gecici", true.into()).unwrap();
+    store.unset_node_prop(&sisli, "gecici").unwrap();
+    assert_eq!(
+        store.node_prop(&sisli, "nufus").unwrap(),
+        Some(PropValue::Int(274420))
+    );
+    assert_eq!(store.node_prop(&sisli, "gecici").unwrap(), None);
+    let node = store.node(&sisli).unwrap();
+    assert_eq!((node.id, node.alias.as_str()), (sisli, "sisli"));
+    assert_eq!(
+        node.props().keys().collect::<Vec<_>>(),
+        ["ad", "nufus"],
+        "props come sorted"
+    );
+
+    store.delete_node(&sisli).unwrap();
+    assert!(!store.has_node(&sisli));
+    assert_eq!(store.node_ids(), [merkez]);
+    assert!(store.ids_with_alias("sisli").is_empty());
+    assert!(matches!(
+        store.node_alias(&sisli),
+        Err(Error::NodeNotFound { id }) if id == sisli
+    ));
+    assert!(matches!(
+        store.label_node(&sisli, "ilce"),
+        Err(Error::NodeNotFound { .. })
+    ));
+    assert!(matches!(
+        store.delete_node(&sisli),
+        Err(Error::NodeNotFound { .. })
+    ));
+}
+
+fn edges<G: GraphStore>(mut store: G) {
+    districts(&mut store);
+    assert_eq!(store.edge_count(), 5);
+    let (sisli, kadikoy) = (id(&store, "sisli"), id(&store, "kadikoy"));
+    let edge = store.create_edge("yol", &sisli, &kadikoy).unwrap();
+    assert!(store.has_edge(&edge));
+    assert_eq!(store.edge_relation(&edge).unwrap(), "yol");
+    assert_eq!(store.edge_ends(&edge).unwrap(), (sisli, kadikoy));
+
+    store.label_edge(&edge, "otoyol").unwrap();
+    store.set_edge_prop(&edge, "km", 9.5.into()).unwrap();
+    let data = store.edge(&edge).unwrap();
+    assert_eq!(
+        (data.id(), data.relation(), *data.from(), *data.to()),
+        (edge, "yol", sisli, kadikoy)
+    );
+    assert_eq!(data.labels(), ["otoyol"]);
+    assert_eq!(data.get_prop("km"), Some(&PropValue::Float(9.5)));
+    store.unlabel_edge(&edge, "otoyol").unwrap();
+    store.unset_edge_prop(&edge, "km").unwrap();
+    assert!(store.edge_labels(&edge).unwrap().is_empty());
+    assert_eq!(store.edge_props(&edge).unwrap(), BTreeMap::new());
+
+    store.delete_edge(&edge).unwrap();
+    assert!(!store.has_edge(&edge));
+    assert!(matches!(
+        store.edge_relation(&edge),
+        Err(Error::EdgeNotFound { .. })
+    ));
+    let missing = store.create_node("gecici").unwrap();
+    store.delete_node(&missing).unwrap();
+    assert!(matches!(
+        store.create_edge("yol", &sisli, &missing),
+        Err(Error::NodeNotFound { .. })
+    ));
+
+    //a node takes its edges, both ways, with it
+    store.delete_node(&sisli).unwrap();
+    assert_eq!(store.edge_count(), 2);
+    for edge in store.edge_ids() {
+        let (from, to) = store.edge_ends(&edge).unwrap();
+        assert!(from != sisli && to != sisli);
+    }
+    assert_eq!(
+        aliases(&store, &store.neighbors(&kadikoy, Direction::Both).unwrap()),
+        ["besiktas", "istanbul"]
+    );
+}
+
+fn adjacency<G: GraphStore>(mut store: G) {
+    districts(&mut store);
+    let sisli = id(&store, "sisli");
+    let via = |direction, relations: &[&str]| {
+        let relations: Vec<String> = relations.iter().map(|r| r.to_string()).collect();
+        let ends: Vec<NodeIndex> = store
+            .adjacent(&sisli, direction, &relations)
+            .unwrap()
+            .into_iter()
+            .map(|(edge, end)| {
+                let (from, to) = store.edge_ends(&edge).unwrap();
+                assert!(from == sisli || to == sisli);
+                end
+            })
+            .collect();
+        aliases(&store, &ends)
+    };
+    assert_eq!(via(Direction::Outgoing, &[]), ["besiktas", "kadikoy"]);
+    assert_eq!(via(Direction::Incoming, &[]), ["istanbul"]);
+    assert_eq!(
+        via(Direction::Both, &[]),
+        ["besiktas", "istanbul", "kadikoy"]
+    );
+    assert_eq!(via(Direction::Both, &["includes"]), ["istanbul"]);
+    assert!(via(Direction::Outgoing, &["includes", "yol"]).is_empty());
+
+    //outgoing edges come first
+    let both = store.adjacent(&sisli, Direction::Both, &[]).unwrap();
+    assert_eq!(store.edge_ends(&both[2].0).unwrap().1, sisli);
+    let gone = store.create_node("gecici").unwrap();
+    store.delete_node(&gone).unwrap();
+    assert!(matches!(
+        store.adjacent(&gone, Direction::Both, &[]),
+        Err(Error::NodeNotFound { .. })
+    ));
+}
+
+fn algorithms_and_queries<G: GraphStore>(mut store: G) {
+    districts(&mut store);
+    let (istanbul, besiktas) = (id(&store, "istanbul"), id(&store, "besiktas"));
+    let reached: Vec<NodeIndex> = Bfs::new(&store, istanbul)
+        .unwrap()
+        .map(|v| v.node)
+        .collect();
+    assert_eq!(reached.len(), 4);
+
+    let path = ShortestPath::new(&store, istanbul, besiktas)
+        .unweighted()
+        .unwrap();
+    assert_eq!(
+        path.node_ids()
+            .iter()
+            .map(|id| store.node_alias(id).unwrap())
+            .collect::<Vec<_>>(),
+        ["istanbul", "sisli", "besiktas"]
+    );
+    let kadikoy = id(&store, "kadikoy");
+    let path = ShortestPath::new(&store, istanbul, kadikoy)
+        .dijkstra(Weight::prop("km"))
+        .unwrap();
+    assert_eq!((path.hops(), path.cost()), (1, 1.0));
+    let guided = ShortestPath::new(&store, istanbul, kadikoy)
+        .astar(Weight::prop("km"), |n| {
+            n.get_prop("plaka").map_or(0.0, |_| 0.5)
+        })
+        .unwrap();
+    assert_eq!(guided.node_ids(), path.node_ids());
+    let via_komsu = ShortestPath::new(&store, id(&store, "sisli"), kadikoy)
+        .relations(["komsu"])
+        .dijkstra(Weight::with(|e| {
+            e.get_prop("km").unwrap().as_number().unwrap()
+        }))
+        .unwrap();
+    assert_eq!((via_komsu.hops(), via_komsu.cost()), (2, 7.0));
+    let ends = VarLengthPaths::new(&store, istanbul)
+        .unwrap()
+        .hops(2..=2)
+        .count();
+    assert_eq!(ends, 2);
+
+    let result = query::execute(
+        &store,
+        &query::parse(
+            "MATCH (s:sehir {plaka: 34})-[:includes]->(a)-[:komsu*1..2]->(b) \
+             WHERE b.plaka IS NULL RETURN b",
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        aliases(&store, &result.column("b").unwrap()),
+        ["besiktas", "kadikoy", "kadikoy"]
+    );
+}
+
+fn copy_and_export<G: GraphStore + Default>(mut store: G) {
+    districts(&mut store);
+    //through vec_graph for persistence, and back into a fresh store of the same kind
+    let mut graph = Graph::new();
+    let ids = store::copy(&store, &mut graph).unwrap();
+    assert_eq!((graph.node_count(), graph.edge_count()), (4, 5));
+    let istanbul = ids[&id(&store, "istanbul")];
+    assert_eq!(graph.get_node(&istanbul).unwrap().alias, "istanbul");
+    assert_eq!(
+        graph.get_node(&istanbul).unwrap().get_prop("plaka"),
+        Some(&PropValue::Int(34))
+    );
+    let mut back = G::default();
+    store::copy(&graph, &mut back).unwrap();
+    assert_eq!(
+        aliases(&back, &back.ids_with_label("ilce")),
+        ["besiktas", "kadikoy", "sisli"]
+    );
+
+    let dot = Export::new(&store)
+        .relations(["includes"])
+        .render(Format::Dot);
+    assert_eq!(dot.matches(" -> ").count(), 2);
+    assert!(dot.contains(r#""plaka"="34", label="istanbul\n:sehir""#));
+    let gexf = Export::new(&store).labels(["ilce"]).render(Format::Gexf);
+    assert_eq!(gexf.matches("<edge ").count(), 3);
+}
+
+/// Trait objects work too, so the backend can be picked at runtime
+fn boxed(mut store: Box<dyn GraphStore>) {
+    districts(store.as_mut());
+    let sisli = id(store.as_ref(), "sisli");
+    let reached = Bfs::new(store.as_ref(), sisli)
+        .unwrap()
+        .direction(Direction::Both)
+        .count();
+    assert_eq!(reached, 4);
+    let result = query::execute(
+        store.as_ref(),
+        &query::parse("MATCH (a:ilce)-[:komsu]->(b:ilce) RETURN a").unwrap(),
+    )
+    .unwrap();
+    assert_eq!(result.rows.len(), 3);
+}
+
+macro_rules! store_suite {
+    ($backend:ident, $new:expr) => {
+        mod $backend {
+            use super::*;
+
+            #[test]
+            fn nodes() {
+                super::nodes($new);
+            }
+            #[test]
+            fn edges() {
+                super::edges($new);
+            }
+            #[test]
+            fn adjacency() {
+                super::adjacency($new);
+            }
+            #[test]
+            fn algorithms_and_queries() {
+                super::algorithms_and_queries($new);
+            }
+            #[test]
+            fn copy_and_export() {
+                super::copy_and_export($new);
+            }
+            #[test]
+            fn boxed() {
+                super::boxed(Box::new($new));
+            }
+        }
+    };
+}
+
+store_suite!(vec_graph, Graph::new());
+store_suite!(vgraph, VGraph::new());
+store_suite!(rc_graph, RcGraph::new());
+store_suite!(unsafe_graph, UnsafeGraph::new());
@@ -0,0 +1,242 @@
+use std::{fs, panic, path::PathBuf};
+
+use graph_db::vec_graph::*;
+
+/// Error a caller returns to give up on a transaction
+fn abort() -> Error {
+    Error::ConstraintViolation {
+        constraint: "test".to_owned(),
+        detail: "abort".to_owned(),
+    }
+}
+
+fn first_id(graph: &Graph, alias: &str) -> NodeIndex {
+    graph.get_ids_by_alias(alias).unwrap()[0]
+}
+
+/// Graph with freed slots, several edges per node and an indexed prop, so rollbacks have state to get wrong
+fn city() -> Graph {
+    let mut graph = Graph::new();
+    graph
+        .create_prop_index("tur")
+        .unwrap()
+        .add_node("sisli")
+        .unwrap()
+        .mut_last_node(|n| n.add_label("sehir")?.add_prop("tur", "ilce"))
+        .unwrap()
+        .add_node("gecici")
+        .unwrap()
+        .add_node("merkez")
+        .unwrap()
+        .add_node("mcdkoy")
+        .unwrap()
+        .mut_last_node(|n| n.add_prop("tur", "mahalle"))
+        .unwrap()
+        .add_edges_by_aliases("includes", "sisli", "merkez")
+        .unwrap()
+        .add_edges_by_aliases("includes", "sisli", "mcdkoy")
+        .unwrap()
+        .add_edges_by_aliases("komsu", "merkez", "mcdkoy")
+        .unwrap()
+        .mut_last_edge(|e| e.add_prop("km", 1))
+        .unwrap();
+    let gecici = first_id(&graph, "gecici");
+    graph.remove_node_by_id(&gecici).unwrap();
+    graph
+}
+
+fn build(graph: &mut Graph) {
+    graph
+        .add_node("yeni")
+        .unwrap()
+        .add_edges_by_aliases("includes", "sisli", "yeni")
+        .unwrap();
+}
+
+#[test]
+fn failing_chain_changes_nothing() {
+    let mut graph = city();
+    let result = graph.transaction(|tx| {
+        let merkez = first_id(tx, "merkez");
+        let sisli = first_id(tx, "sisli");
+        tx.add_node("yeni")?
+            .mut_last_node(|n| n.add_label("mahalle")?.add_prop("tur", "mahalle"))?
+            .add_edges_by_aliases("komsu", "yeni", "merkez")?
+            .add_prop(&sisli, "tur", "il")?
+            .remove_label(&sisli, "sehir")?
+            .remove_node_by_id(&merkez)?
+            .drop_prop_index("tur")?
+            .add_edges_by_aliases("komsu", "yeni", "yok")?;
+        Ok(())
+    });
+    assert!(result.is_err());
+    assert!(!graph.in_transaction());
+    assert_eq!(graph, city());
+    //slots, generations and free lists are back too, so new ids match a graph that never saw the transaction
+    let mut fresh = city();
+    build(&mut graph);
+    build(&mut fresh);
+    assert_eq!(first_id(&graph, "yeni"), first_id(&fresh, "yeni"));
+    assert_eq!(graph, fresh);
+}
+
+#[test]
+fn commit_keeps_everything() {
+    let mut graph = city();
+    let id = graph
+        .transaction(|tx| {
+            build(tx);
+            Ok(first_id(tx, "yeni"))
+        })
+        .unwrap();
+    let mut expected = city();
+    build(&mut expected);
+    assert_eq!(graph, expected);
+    assert_eq!(graph.get_alias_by_id(&id), Some("yeni"));
+}
+
+#[test]
+fn removed_node_comes_back_with_its_edges_in_order() {
+    let mut graph = city();
+    let sisli = first_id(&graph, "sisli");
+    let before: Vec<EdgeIndex> = graph.outgoing_edges(&sisli).map(|e| e.id()).collect();
+    let _ = graph.transaction(|tx| {
+        let merkez = first_id(tx, "merkez");
+        tx.remove_node_by_id(&merkez)?.remove_node_by_id(&sisli)?;
+        assert_eq!(tx.edge_count(), 0);
+        Err::<(), _>(abort())
+    });
+    let after: Vec<EdgeIndex> = graph.outgoing_edges(&sisli).map(|e| e.id()).collect();
+    assert_eq!(before, after);
+    assert_eq!(graph.find_ids_by_label("sehir"), vec![sisli]);
+    assert_eq!(graph, city());
+}
+
+#[test]
+fn savepoints_and_nested_transactions() {
+    let mut graph = Graph::new();
+    graph
+        .transaction(|tx| {
+            tx.add_node("a")?;
+            let savepoint = tx.savepoint();
+            tx.add_node("b")?.add_edges_by_aliases("r", "a", "b")?;
+            tx.rollback_to(savepoint)?;
+            assert!(tx.get_ids_by_alias("b").is_none());
+            //a failing nested transaction only undoes its own part
+            let inner = tx.transaction(|inner| {
+                inner.add_node("c")?;
+                inner.add_edges_by_aliases("r", "a", "yok")?;
+                Ok(())
+            });
+            assert!(inner.is_err());
+            tx.transaction(|inner| {
+                inner.add_node("d")?;
+                Ok(())
+            })?;
+            //savepoint taken before the nested one started can't be used from inside it
+            tx.transaction(|inner| {
+                assert!(inner.rollback_to(savepoint).is_err());
+                Ok(())
+            })?;
+            tx.add_node("e")?;
+            Ok(())
+        })
+        .unwrap();
+    let mut expected = Graph::new();
+    expected
+        .add_node("a")
+        .unwrap()
+        .add_node("d")
+        .unwrap()
+        .add_node("e")
+        .unwrap();
+    assert_eq!(graph, expected);
+}
+
+#[test]
+fn panic_rolls_back() {
+    let mut graph = city();
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let _ = graph.transaction(|tx| {
+            build(tx);
+            panic!("boom");
+            #[allow(unreachable_code)]
+            Ok(())
+        });
+    }));
+    assert!(result.is_err());
+    assert!(!graph.in_transaction());
+    assert_eq!(graph, city());
+}
+
+#[test]
+fn replacing_the_graph_ends_the_transaction() {
+    let mut graph = city();
+    let result = graph.transaction(|tx| {
+        tx.add_node("gecici")?;
+        **tx = Graph::new();
+        tx.add_node("yeni")?;
+        let savepoint = tx.savepoint();
+        assert!(matches!(
+            tx.rollback_to(savepoint),
+            Err(Error::InvalidOperation { .. })
+        ));
+        Ok(())
+    });
+    assert!(matches!(result, Err(Error::InvalidOperation { .. })));
+    //the replacement stays, outside any transaction
+    assert!(!graph.in_transaction());
+    assert_eq!(graph.node_count(), 1);
+
+    let result: GraphResult<()> = graph.transaction(|tx| {
+        let mut other = city();
+        std::mem::swap(&mut **tx, &mut other);
+        Err(abort())
+    });
+    assert!(result.is_err());
+    assert!(!graph.in_transaction());
+    graph
+        .transaction(|tx| tx.add_node("son").map(|_| ()))
+        .unwrap();
+    assert_eq!(graph.node_count(), city().node_count() + 1);
+}
+
+#[test]
+fn transaction_is_one_log_record() {
+    let dir = std::env::temp_dir().join(format!("graph_db_tx_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("db");
+    let wal = PathBuf::from(format!("{}-wal", path.display()));
+    let committed_len = {
+        let mut graph = Graph::open(&path).unwrap();
+        graph.add_node("sisli").unwrap();
+        let len = fs::metadata(&wal).unwrap().len();
+        let _ = graph.transaction(|tx| {
+            tx.add_node("hata")?;
+            Err::<(), _>(abort())
+        });
+        //rolled back transactions never reach the log
+        assert_eq!(fs::metadata(&wal).unwrap().len(), len);
+        graph
+            .transaction(|tx| {
+                build(tx);
+                Ok(())
+            })
+            .unwrap();
+        len
+    };
+    let mut expected = Graph::new();
+    expected.add_node("sisli").unwrap();
+    build(&mut expected);
+    assert_eq!(Graph::open(&path).unwrap(), expected);
+
+    //any cut inside the transaction's record loses all of it
+    let log = fs::read(&wal).unwrap();
+    let mut before = Graph::new();
+    before.add_node("sisli").unwrap();
+    for cut in committed_len as usize..log.len() {
+        fs::write(&wal, &log[..cut]).unwrap();
+        assert_eq!(Graph::open(&path).unwrap(), before, "cut at byte {}", cut);
+    }
+}
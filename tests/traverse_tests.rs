@@ -0,0 +1,170 @@
+use graph_db::{
+    algo::{Bfs, Direction, Visit},
+    vec_graph::*,
+};
+
+fn first_id(graph: &Graph, alias: &str) -> NodeIndex {
+    graph.get_ids_by_alias(alias).unwrap()[0]
+}
+
+/// a -r-> b -r-> d, a -r-> c -s-> d, e -r-> a
+fn sample() -> Graph {
+    let mut graph = Graph::new();
+    graph
+        .add_node("a")
+        .unwrap()
+        .add_node("b")
+        .unwrap()
+        .add_node("c")
+        .unwrap()
+        .add_node("d")
+        .unwrap()
+        .add_node("e")
+        .unwrap()
+        .add_edges_by_aliases("r", "a", "b")
+        .unwrap()
+        .add_edges_by_aliases("r", "a", "c")
+        .unwrap()
+        .add_edges_by_aliases("r", "b", "d")
+        .unwrap()
+        .add_edges_by_aliases("s", "c", "d")
+        .unwrap()
+        .add_edges_by_aliases("r", "e", "a")
+        .unwrap();
+    graph
+}
+
+fn aliases<'g>(graph: &'g Graph, visits: &[Visit]) -> Vec<(&'g str, usize)> {
+    visits
+        .iter()
+        .map(|v| (graph.get_alias_by_id(&v.node).unwrap(), v.depth))
+        .collect()
+}
+
+#[test]
+fn bfs_reports_order_and_depth() {
+    let graph = sample();
+    let a = first_id(&graph, "a");
+    let visits: Vec<Visit> = graph.bfs(a).unwrap().collect();
+    //adjacency lists are newest first
+    assert_eq!(
+        aliases(&graph, &visits),
+        vec![("a", 0), ("c", 1), ("b", 1), ("d", 2)]
+    );
+    assert!(visits.iter().enumerate().all(|(i, v)| v.order == i));
+    assert_eq!(visits[0].edge, None);
+    let via = graph.get_edge(&visits[3].edge.unwrap()).unwrap();
+    assert_eq!(*via.to(), visits[3].node);
+    assert_eq!(via.relation(), "s");
+}
+
+#[test]
+fn dfs_goes_deep_first() {
+    let graph = sample();
+    let a = first_id(&graph, "a");
+    let visits: Vec<Visit> = graph.dfs(a).unwrap().collect();
+    assert_eq!(
+        aliases(&graph, &visits),
+        vec![("a", 0), ("c", 1), ("d", 2), ("b", 1)]
+    );
+    let visits: Vec<Visit> = graph.dfs(a).unwrap().direction(Direction::Both).collect();
+    assert_eq!(
+        aliases(&graph, &visits),
+        vec![("a", 0), ("c", 1), ("d", 2), ("b", 3), ("e", 1)]
+    );
+}
+
+#[test]
+fn directions() {
+    let graph = sample();
+    let d = first_id(&graph, "d");
+    assert_eq!(graph.bfs(d).unwrap().count(), 1);
+    let visits: Vec<Visit> = graph
+        .bfs(d)
+        .unwrap()
+        .direction(Direction::Incoming)
+        .collect();
+    assert_eq!(
+        aliases(&graph, &visits),
+        vec![("d", 0), ("c", 1), ("b", 1), ("a", 2), ("e", 3)]
+    );
+    let c = first_id(&graph, "c");
+    let visits: Vec<Visit> = Bfs::new(&graph, c)
+        .unwrap()
+        .direction(Direction::Both)
+        .collect();
+    assert_eq!(
+        aliases(&graph, &visits),
+        vec![("c", 0), ("d", 1), ("a", 1), ("b", 2), ("e", 2)]
+    );
+}
+
+#[test]
+fn relation_filter_and_max_depth() {
+    let graph = sample();
+    let a = first_id(&graph, "a");
+    let visits: Vec<Visit> = graph.bfs(a).unwrap().relations(["r"]).collect();
+    assert_eq!(
+        aliases(&graph, &visits),
+        vec![("a", 0), ("c", 1), ("b", 1), ("d", 2)]
+    );
+    let visits: Vec<Visit> = graph.dfs(a).unwrap().relations(["r"]).collect();
+    assert_eq!(
+        aliases(&graph, &visits),
+        vec![("a", 0), ("c", 1), ("b", 1), ("d", 2)]
+    );
+    let visits: Vec<Visit> = graph.bfs(a).unwrap().relations(["s"]).collect();
+    assert_eq!(aliases(&graph, &visits), vec![("a", 0)]);
+    assert_eq!(graph.bfs(a).unwrap().max_depth(0).count(), 1);
+    assert_eq!(graph.dfs(a).unwrap().max_depth(1).count(), 3);
+}
+
+#[test]
+fn dfs_max_depth_reaches_what_bfs_does() {
+    //a -> b -> c -> d and a -> c, adjacency lists are newest first so dfs takes the long way to c
+    let mut graph = Graph::new();
+    graph
+        .add_node("a")
+        .unwrap()
+        .add_node("b")
+        .unwrap()
+        .add_node("c")
+        .unwrap()
+        .add_node("d")
+        .unwrap()
+        .add_edges_by_aliases("r", "a", "c")
+        .unwrap()
+        .add_edges_by_aliases("r", "a", "b")
+        .unwrap()
+        .add_edges_by_aliases("r", "b", "c")
+        .unwrap()
+        .add_edges_by_aliases("r", "c", "d")
+        .unwrap();
+    let a = first_id(&graph, "a");
+    let visits: Vec<Visit> = graph.dfs(a).unwrap().max_depth(2).collect();
+    assert_eq!(
+        aliases(&graph, &visits),
+        vec![("a", 0), ("b", 1), ("c", 2), ("d", 2)]
+    );
+    assert!(visits.iter().enumerate().all(|(i, v)| v.order == i));
+    let mut dfs: Vec<_> = visits.iter().map(|v| v.node).collect();
+    let mut bfs: Vec<_> = graph.bfs(a).unwrap().max_depth(2).map(|v| v.node).collect();
+    dfs.sort();
+    bfs.sort();
+    assert_eq!(dfs, bfs);
+}
+
+#[test]
+fn lazy_and_checked_start() {
+    let mut graph = sample();
+    let a = first_id(&graph, "a");
+    let mut bfs = graph.bfs(a).unwrap();
+    assert_eq!(bfs.next().map(|v| v.node), Some(a));
+    assert_eq!(bfs.next().map(|v| v.depth), Some(1));
+    drop(bfs);
+    graph.remove_node_by_id(&a).unwrap();
+    assert!(matches!(
+        graph.dfs(a),
+        Err(Error::NodeNotFound { id }) if id == a
+    ));
+}
@@ -0,0 +1,195 @@
+//! Also meant for `cargo +nightly miri test --test unsafe_graph_tests`, keep the graphs small
+
+use graph_db::{store::GraphStore, unsafe_graph::*, vec_graph::Error};
+
+fn linked_to(graph: &Graph, alias: &str) -> Vec<String> {
+    let node = graph.get_node(alias).unwrap();
+    node.links()
+        .iter()
+        .map(|l| l.target().alias().to_owned())
+        .collect()
+}
+
+fn linked_from(graph: &Graph, alias: &str) -> Vec<String> {
+    let node = graph.get_node(alias).unwrap();
+    node.incoming()
+        .map(|(_, from)| from.alias().to_owned())
+        .collect()
+}
+
+/// istanbul includes two districts, which border each other and a third one
+fn districts() -> Graph {
+    let mut graph = Graph::new();
+    graph
+        .add_node("istanbul")
+        .unwrap()
+        .add_node("sisli")
+        .unwrap()
+        .add_node("kadikoy")
+        .unwrap()
+        .add_node("besiktas")
+        .unwrap()
+        .link("includes", "istanbul", "sisli")
+        .unwrap()
+        .link("includes", "istanbul", "kadikoy")
+        .unwrap()
+        .link("komsu", "sisli", "besiktas")
+        .unwrap()
+        .link("komsu", "kadikoy", "sisli")
+        .unwrap();
+    graph
+}
+
+#[test]
+fn links_point_at_their_targets() {
+    let graph = districts();
+    assert_eq!(linked_to(&graph, "istanbul"), ["sisli", "kadikoy"]);
+    assert_eq!(linked_from(&graph, "sisli"), ["istanbul", "kadikoy"]);
+    //two hops by pointer alone
+    let istanbul = graph.get_node("istanbul").unwrap();
+    let besiktas = istanbul.links()[0].target().links()[0].target();
+    assert_eq!(besiktas.alias(), "besiktas");
+    assert!(std::ptr::eq(besiktas, graph.get_node("besiktas").unwrap()));
+    assert_eq!(*istanbul.links()[1].to(), graph.id_of("kadikoy").unwrap());
+}
+
+#[test]
+fn addresses_stay_put_as_the_graph_grows() {
+    let mut graph = districts();
+    let sisli: *const Node = graph.get_node("sisli").unwrap();
+    //several chunks worth of nodes, each linked to sisli
+    for i in 0..150 {
+        let alias = format!("mahalle{}", i);
+        graph
+            .add_node(&alias)
+            .unwrap()
+            .link("in", &alias, "sisli")
+            .unwrap();
+    }
+    assert!(std::ptr::eq(sisli, graph.get_node("sisli").unwrap()));
+    let last = graph.get_node("mahalle149").unwrap();
+    assert!(std::ptr::eq(last.links()[0].target(), sisli));
+    assert_eq!(graph.get_node("sisli").unwrap().incoming().count(), 152);
+    assert_eq!(graph.node_count(), 154);
+}
+
+#[test]
+fn removing_a_node_keeps_links_consistent() {
+    let mut graph = districts();
+    graph.link("yol", "sisli", "sisli").unwrap();
+    let sisli = graph.id_of("sisli").unwrap();
+    graph.remove_node("sisli").unwrap();
+
+    assert_eq!(graph.edge_count(), 1);
+    assert_eq!(linked_to(&graph, "istanbul"), ["kadikoy"]);
+    assert!(linked_to(&graph, "kadikoy").is_empty());
+    assert!(linked_from(&graph, "besiktas").is_empty());
+    assert!(matches!(
+        graph.get_node_by_id(&sisli),
+        Err(Error::NodeNotFound { .. })
+    ));
+    //the slot is reused in place under a new id, nothing still points at it
+    graph
+        .add_node("uskudar")
+        .unwrap()
+        .link("komsu", "uskudar", "kadikoy")
+        .unwrap();
+    let uskudar = graph.id_of("uskudar").unwrap();
+    assert_eq!(uskudar.index(), sisli.index());
+    assert_ne!(uskudar, sisli);
+    assert_eq!(linked_from(&graph, "kadikoy"), ["istanbul", "uskudar"]);
+    for node in graph.iter_nodes() {
+        for link in node.links() {
+            assert!(graph.has_node(link.to()));
+        }
+    }
+}
+
+#[test]
+fn unlink_and_delete_edge() {
+    let mut graph = districts();
+    graph
+        .link("komsu", "sisli", "kadikoy")
+        .unwrap()
+        .unlink("komsu", "kadikoy", "sisli")
+        .unwrap();
+    assert_eq!(linked_to(&graph, "sisli"), ["besiktas", "kadikoy"]);
+    assert_eq!(linked_from(&graph, "sisli"), ["istanbul"]);
+
+    let link = graph.get_node("sisli").unwrap().links()[1].id();
+    graph.delete_edge(&link).unwrap();
+    assert_eq!(linked_from(&graph, "kadikoy"), ["istanbul"]);
+    assert!(matches!(
+        graph.get_link(&link),
+        Err(Error::EdgeNotFound { .. })
+    ));
+    assert!(matches!(
+        graph.delete_edge(&link),
+        Err(Error::EdgeNotFound { .. })
+    ));
+}
+
+#[test]
+fn labels_props_and_unique_aliases() {
+    let mut graph = districts();
+    graph
+        .mut_node("sisli", |n| {
+            n.add_label("ilce").add_prop("nufus", 274420);
+        })
+        .unwrap();
+    let link = graph.get_node("sisli").unwrap().links()[0].id();
+    graph.set_edge_prop(&link, "km", 3.into()).unwrap();
+
+    let istanbul = graph.get_node("istanbul").unwrap();
+    let sisli = istanbul.links()[0].target();
+    assert!(sisli.has_label("ilce"));
+    assert_eq!(sisli.get_prop("nufus"), Some(&274420.into()));
+    assert_eq!(sisli.links()[0].get_prop("km"), Some(&3.into()));
+    let (_, from) = graph.get_link(&link).unwrap();
+    assert!(std::ptr::eq(from, sisli));
+    assert!(matches!(
+        graph.add_node("sisli"),
+        Err(Error::ConstraintViolation { .. })
+    ));
+}
+
+#[test]
+fn mutating_a_node_on_a_cycle() {
+    let mut graph = Graph::new();
+    graph
+        .add_node("a")
+        .unwrap()
+        .add_node("b")
+        .unwrap()
+        .link("self", "a", "a")
+        .unwrap()
+        .link("to", "a", "b")
+        .unwrap()
+        .link("back", "b", "a")
+        .unwrap();
+    //the node is reachable from itself while it's being changed
+    graph
+        .mut_node("a", |n| {
+            let len = n.links()[0].target().alias().len();
+            n.add_label("x");
+            let back = n.links()[1].target().links()[0].target();
+            assert!(back.has_label("x"));
+            let sources = n
+                .incoming()
+                .map(|(_, from)| from.alias().len())
+                .sum::<usize>();
+            n.add_prop("len", len as i64)
+                .add_prop("sources", sources as i64);
+        })
+        .unwrap();
+    let mut b = graph.get_node_mut("b").unwrap();
+    let a_len = b.links()[0].target().get_prop("len").cloned();
+    b.add_prop("a_len", a_len.unwrap());
+    b.remove_label("x");
+
+    let a = graph.get_node("a").unwrap();
+    assert_eq!(a.get_prop("len"), Some(&1.into()));
+    assert_eq!(a.get_prop("sources"), Some(&2.into()));
+    assert_eq!(a.links()[1].target().get_prop("a_len"), Some(&1.into()));
+    assert!(a.links()[0].target().has_label("x"));
+}
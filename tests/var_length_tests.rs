@@ -0,0 +1,205 @@
+use graph_db::{
+    algo::{Direction, Uniqueness},
+    query::{self, HopRange},
+    vec_graph::*,
+};
+
+fn first_id(graph: &Graph, alias: &str) -> NodeIndex {
+    graph.get_ids_by_alias(alias).unwrap()[0]
+}
+
+fn sorted_aliases(graph: &Graph, ids: &[NodeIndex]) -> Vec<String> {
+    let mut found: Vec<String> = ids
+        .iter()
+        .map(|id| graph.get_alias_by_id(id).unwrap().to_owned())
+        .collect();
+    found.sort();
+    found
+}
+
+/// istanbul includes sisli and kadikoy, sisli includes merkez which includes mahalle,
+/// merkez and mahalle are neighbors both ways
+fn city() -> Graph {
+    let mut graph = Graph::new();
+    for alias in ["istanbul", "sisli", "kadikoy", "merkez", "mahalle"] {
+        graph
+            .add_node(alias)
+            .unwrap()
+            .mut_last_node(|n| n.add_label(if alias == "istanbul" { "Il" } else { "Yer" }))
+            .unwrap();
+    }
+    graph
+        .add_edges_by_aliases("includes", "istanbul", "sisli")
+        .unwrap()
+        .add_edges_by_aliases("includes", "istanbul", "kadikoy")
+        .unwrap()
+        .add_edges_by_aliases("includes", "sisli", "merkez")
+        .unwrap()
+        .add_edges_by_aliases("includes", "merkez", "mahalle")
+        .unwrap()
+        .add_edges_by_aliases("komsu", "merkez", "mahalle")
+        .unwrap()
+        .add_edges_by_aliases("komsu", "mahalle", "merkez")
+        .unwrap();
+    graph
+}
+
+#[test]
+fn parse_hop_ranges() {
+    let range = |q: &str| {
+        query::parse(&format!("MATCH (a)-[:includes{}]->(b) RETURN b", q))
+            .unwrap()
+            .patterns[0]
+            .hops[0]
+            .0
+            .length
+    };
+    let bounds = |min, max| Some(HopRange { min, max });
+    assert_eq!(range(""), None);
+    assert_eq!(range("*"), bounds(1, None));
+    assert_eq!(range("*2"), bounds(2, Some(2)));
+    assert_eq!(range("*1..3"), bounds(1, Some(3)));
+    assert_eq!(range("*2.."), bounds(2, None));
+    assert_eq!(range("*..3"), bounds(1, Some(3)));
+    assert_eq!(range("*0..1"), bounds(0, Some(1)));
+    assert!(query::parse("MATCH (a)-[*3..1]->(b) RETURN b").is_err());
+    assert!(query::parse("MATCH (a)-[*1.5]->(b) RETURN b").is_err());
+    let q = query::parse("MATCH UNIQUE NODES p = (a)-[*]->(b) RETURN p").unwrap();
+    assert_eq!(q.uniqueness, Uniqueness::Nodes);
+    assert_eq!(q.patterns[0].variable.as_deref(), Some("p"));
+}
+
+#[test]
+fn transitive_includes() {
+    let graph = city();
+    let result = graph
+        .query("MATCH (il:Il)-[:includes*1..3]->(d) RETURN d")
+        .unwrap();
+    assert_eq!(
+        sorted_aliases(&graph, &result.column("d").unwrap()),
+        vec!["kadikoy", "mahalle", "merkez", "sisli"]
+    );
+    let result = graph
+        .query("MATCH (il:Il)-[:includes*2]->(d) RETURN d")
+        .unwrap();
+    assert_eq!(
+        sorted_aliases(&graph, &result.column("d").unwrap()),
+        vec!["merkez"]
+    );
+    //incoming: everything above mahalle
+    let result = graph
+        .query("MATCH (m:Yer)<-[:includes*]-(up) RETURN m, up")
+        .unwrap();
+    let mahalle = first_id(&graph, "mahalle");
+    let above: Vec<NodeIndex> = result
+        .rows
+        .iter()
+        .filter(|row| row[0] == mahalle)
+        .map(|row| row[1])
+        .collect();
+    assert_eq!(
+        sorted_aliases(&graph, &above),
+        vec!["istanbul", "merkez", "sisli"]
+    );
+}
+
+#[test]
+fn returns_paths() {
+    let graph = city();
+    let result = graph
+        .query("MATCH p = (il:Il)-[:includes*3]->(d) RETURN d, p")
+        .unwrap();
+    assert_eq!(result.columns, vec!["d"]);
+    assert_eq!(result.path_columns, vec!["p"]);
+    let paths = result.path_column("p").unwrap();
+    assert_eq!(paths.len(), 1);
+    assert_eq!(
+        sorted_aliases(&graph, &paths[0].nodes),
+        vec!["istanbul", "mahalle", "merkez", "sisli"]
+    );
+    let path = paths[0].resolve(&graph).unwrap();
+    let order: Vec<&str> = path.nodes().map(|n| n.alias.as_str()).collect();
+    assert_eq!(order, vec!["istanbul", "sisli", "merkez", "mahalle"]);
+    assert!(path.edges().all(|e| e.relation() == "includes"));
+    assert_eq!(path.end().id, result.rows[0][0]);
+    //fixed hops make up the path too
+    let result = graph
+        .query("MATCH p = (a:Il)-[:includes]->(b)-[:includes*0..]->(c:Yer) RETURN p")
+        .unwrap();
+    assert_eq!(result.rows.len(), 4);
+    assert!(result
+        .path_column("p")
+        .unwrap()
+        .iter()
+        .all(|p| p.nodes.len() == p.edges.len() + 1 && p.nodes[0] == first_id(&graph, "istanbul")));
+    assert!(graph
+        .query("MATCH p = (a)-->(b) WHERE p.x = 1 RETURN a")
+        .is_err());
+    assert!(graph.query("MATCH a = (a)-->(b) RETURN a").is_err());
+}
+
+#[test]
+fn uniqueness_modes() {
+    let graph = city();
+    //merkez -> mahalle -> merkez comes back to merkez, allowed when only edges must be unique
+    let edges = graph.query("MATCH (a)-[:komsu*]->(b) RETURN a, b").unwrap();
+    assert_eq!(edges.rows.len(), 4);
+    let nodes = graph
+        .query("MATCH UNIQUE NODES (a)-[:komsu*]->(b) RETURN a, b")
+        .unwrap();
+    assert_eq!(nodes.rows.len(), 2);
+    assert!(nodes.rows.iter().all(|row| row[0] != row[1]));
+    //an edge walked by one relationship can't be reused by another
+    let chained = graph
+        .query("MATCH (a)-[:komsu]->(b)-[:komsu*]->(c) RETURN c")
+        .unwrap();
+    assert_eq!(chained.rows.len(), 2);
+}
+
+#[test]
+fn rust_api() {
+    let graph = city();
+    let merkez = first_id(&graph, "merkez");
+    let ends = |paths: Vec<graph_db::algo::Path>| -> Vec<(String, usize)> {
+        paths
+            .iter()
+            .map(|p| (p.end().alias.clone(), p.hops()))
+            .collect()
+    };
+    let all: Vec<_> = graph
+        .paths_from(merkez)
+        .unwrap()
+        .direction(Direction::Both)
+        .relations(["komsu"])
+        .hops(1..=2)
+        .collect();
+    assert_eq!(all.len(), 4);
+    let unique: Vec<_> = graph
+        .paths_from(merkez)
+        .unwrap()
+        .direction(Direction::Both)
+        .relations(["komsu"])
+        .uniqueness(Uniqueness::Nodes)
+        .collect();
+    assert_eq!(
+        ends(unique),
+        vec![("mahalle".to_owned(), 1), ("mahalle".to_owned(), 1)]
+    );
+    let up: Vec<_> = graph
+        .paths_from(merkez)
+        .unwrap()
+        .direction(Direction::Incoming)
+        .relations(["includes"])
+        .hops(0..)
+        .collect();
+    assert_eq!(
+        ends(up),
+        vec![
+            ("merkez".to_owned(), 0),
+            ("sisli".to_owned(), 1),
+            ("istanbul".to_owned(), 2)
+        ]
+    );
+    assert_eq!(graph.paths_from(merkez).unwrap().hops(..1).count(), 1);
+    assert_eq!(graph.paths_from(merkez).unwrap().hops(..0).count(), 0);
+}
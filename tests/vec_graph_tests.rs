@@ -79,7 +79,7 @@ fn check_node_eq() {
         .add_node("test")
         .expect("Error adding node to graph2");
 
-    ///equality derived -> Checks if all fields of all structs are equal
+    //equality derived -> Checks if all fields of all structs are equal
     assert_eq!(graph1, graph2);
     assert_eq!(
         graph1
@@ -94,7 +94,6 @@ fn check_node_eq() {
 #[test]
 fn node_remove() {
     let mut graph1 = Graph::new();
-    let mut graph2 = Graph::new();
 
     graph1
         .add_node("ali")
@@ -108,12 +107,7 @@ fn node_remove() {
         .add_node("sss")
         .unwrap();
 
-    let did = graph1
-        .get_ids_by_alias("deli")
-        .unwrap()
-        .first()
-        .unwrap()
-        .clone();
+    let did = *graph1.get_ids_by_alias("deli").unwrap().first().unwrap();
 
     println!("pre remove graph:\n{}", graph1);
     println!("{:#?}", graph1.aliases);
@@ -127,5 +121,87 @@ fn node_remove() {
     let sss = *graph1.get_nodes_by_alias("sss").unwrap().first().unwrap();
     assert_eq!(veli, &Node::new(1.into(), "veli".to_owned()));
     assert_eq!(kkk, &Node::new(3.into(), "kkk".to_owned()));
-    assert_eq!(sss, &Node::new(2.into(), "sss".to_owned()));
+    //ids are stable, removing deli doesn't move sss into its slot
+    assert_eq!(sss, &Node::new(4.into(), "sss".to_owned()));
+}
+
+#[test]
+fn stale_index_after_remove() {
+    let mut graph = Graph::new();
+    graph.add_node("ali").unwrap().add_node("veli").unwrap();
+    let ali = graph.get_ids_by_alias("ali").unwrap()[0];
+    let veli = graph.get_ids_by_alias("veli").unwrap()[0];
+    graph.add_edge("knows", ali, veli).unwrap();
+
+    graph.remove_node_by_id(&ali).unwrap();
+    assert!(graph.get_node_by_idx(&ali).is_none());
+    assert!(matches!(graph.get_node(&ali), Err(Error::NodeNotFound { id }) if id == ali));
+    assert!(matches!(
+        graph.remove_node_by_id(&ali),
+        Err(Error::NodeNotFound { .. })
+    ));
+    assert!(matches!(
+        graph.add_edge("knows", ali, veli),
+        Err(Error::NodeNotFound { .. })
+    ));
+    assert_eq!(graph.edge_count(), 0);
+
+    //the freed slot is reused under a new generation, the old handle still misses
+    graph.add_node("deli").unwrap();
+    let deli = graph.get_ids_by_alias("deli").unwrap()[0];
+    assert_eq!(deli.index(), ali.index());
+    assert_ne!(deli, ali);
+    assert!(graph.get_node_by_idx(&ali).is_none());
+    assert_eq!(graph.get_node_by_idx(&deli).unwrap().alias, "deli");
+    assert_eq!(graph.get_node_by_idx(&veli).unwrap().alias, "veli");
+}
+
+#[test]
+fn adjacency_lists_after_edge_removal() {
+    let mut graph = Graph::new();
+    graph
+        .add_node("hub")
+        .unwrap()
+        .add_node("a")
+        .unwrap()
+        .add_node("b")
+        .unwrap()
+        .add_node("c")
+        .unwrap()
+        .add_edges_by_aliases("to", "hub", "a")
+        .unwrap()
+        .add_edges_by_aliases("to", "hub", "b")
+        .unwrap()
+        .add_edges_by_aliases("to", "hub", "c")
+        .unwrap()
+        .add_edges_by_aliases("self", "hub", "hub")
+        .unwrap()
+        .add_edges_by_aliases("back", "b", "hub")
+        .unwrap();
+    let hub = *graph.get_ids_by_alias("hub").unwrap().first().unwrap();
+    let b = *graph.get_ids_by_alias("b").unwrap().first().unwrap();
+    let outgoing_aliases = |graph: &Graph| -> Vec<String> {
+        graph
+            .outgoing_edges(&hub)
+            .map(|e| graph.get_alias_by_id(e.to()).unwrap().to_owned())
+            .collect()
+    };
+    //most recent first
+    assert_eq!(outgoing_aliases(&graph), vec!["hub", "c", "b", "a"]);
+    assert_eq!(graph.incoming_edges(&hub).count(), 2);
+
+    //unlink from the middle of both lists
+    let to_b = graph.get_edges_between(&hub, &b)[0].id();
+    graph.remove_edge(&to_b).unwrap();
+    assert_eq!(outgoing_aliases(&graph), vec!["hub", "c", "a"]);
+    assert_eq!(graph.incoming_edges(&b).count(), 0);
+
+    graph.remove_node_by_id(&b).unwrap();
+    assert_eq!(graph.incoming_edges(&hub).count(), 1);
+    let neighbors = graph
+        .get_incoming_neighbors(graph.get_node(&hub).unwrap())
+        .unwrap();
+    assert_eq!(neighbors.len(), 1);
+    assert_eq!(neighbors[0].alias, "hub");
+    assert_eq!(graph.edge_count(), 3);
 }
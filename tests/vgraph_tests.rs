@@ -0,0 +1,148 @@
+use graph_db::{
+    store::GraphStore,
+    vec_graph::{Error, NodeIndex},
+    vgraph::*,
+};
+
+fn aliases(graph: &Graph, ids: impl Iterator<Item = NodeIndex>) -> Vec<String> {
+    ids.map(|id| graph.get_node_by_id(&id).unwrap().alias().to_owned())
+        .collect()
+}
+
+fn linked_to(graph: &Graph, alias: &str) -> Vec<String> {
+    let node = graph.get_node(alias).unwrap();
+    aliases(graph, node.links().iter().map(|l| *l.to()))
+}
+
+fn linked_from(graph: &Graph, alias: &str) -> Vec<String> {
+    let node = graph.get_node(alias).unwrap();
+    aliases(graph, node.incoming().iter().map(|(_, from)| *from))
+}
+
+/// istanbul includes two districts, which border each other and a third one
+fn districts() -> Graph {
+    let mut graph = Graph::new();
+    graph
+        .add_node("istanbul")
+        .unwrap()
+        .add_node("sisli")
+        .unwrap()
+        .add_node("kadikoy")
+        .unwrap()
+        .add_node("besiktas")
+        .unwrap()
+        .link("includes", "istanbul", "sisli")
+        .unwrap()
+        .link("includes", "istanbul", "kadikoy")
+        .unwrap()
+        .link("komsu", "sisli", "besiktas")
+        .unwrap()
+        .link("komsu", "kadikoy", "sisli")
+        .unwrap();
+    graph
+}
+
+#[test]
+fn aliases_are_unique() {
+    let mut graph = districts();
+    assert!(matches!(
+        graph.add_node("sisli"),
+        Err(Error::ConstraintViolation { .. })
+    ));
+    assert!(matches!(
+        graph.create_node("kadikoy"),
+        Err(Error::ConstraintViolation { .. })
+    ));
+    assert_eq!(graph.node_count(), 4);
+    assert!(matches!(
+        graph.get_node("uskudar"),
+        Err(Error::AliasNotFound { .. })
+    ));
+    //a removed alias is free again
+    graph
+        .remove_node("besiktas")
+        .unwrap()
+        .add_node("besiktas")
+        .unwrap();
+    assert!(graph.get_node("besiktas").unwrap().incoming().is_empty());
+}
+
+#[test]
+fn nodes_resolve_to_themselves() {
+    let graph = districts();
+    for alias in ["istanbul", "sisli", "kadikoy", "besiktas"] {
+        let node = graph.get_node(alias).unwrap();
+        assert_eq!(node.alias(), alias);
+        assert_eq!(graph.get_node_by_id(&node.id()).unwrap().alias(), alias);
+        assert_eq!(graph.id_of(alias).unwrap(), node.id());
+    }
+}
+
+#[test]
+fn links_are_tracked_at_both_ends() {
+    let mut graph = districts();
+    assert_eq!(linked_to(&graph, "istanbul"), ["sisli", "kadikoy"]);
+    assert_eq!(linked_from(&graph, "sisli"), ["istanbul", "kadikoy"]);
+
+    graph
+        .link("komsu", "sisli", "kadikoy")
+        .unwrap()
+        .unlink("komsu", "kadikoy", "sisli")
+        .unwrap();
+    assert_eq!(linked_to(&graph, "sisli"), ["besiktas", "kadikoy"]);
+    assert_eq!(linked_from(&graph, "sisli"), ["istanbul"]);
+    assert_eq!(linked_from(&graph, "kadikoy"), ["istanbul", "sisli"]);
+    assert!(linked_to(&graph, "kadikoy").is_empty());
+
+    let link = graph.get_node("sisli").unwrap().links()[1].id();
+    graph.delete_edge(&link).unwrap();
+    assert_eq!(linked_from(&graph, "kadikoy"), ["istanbul"]);
+    assert!(matches!(
+        graph.get_link(&link),
+        Err(Error::EdgeNotFound { .. })
+    ));
+}
+
+#[test]
+fn removing_a_node_keeps_links_consistent() {
+    let mut graph = districts();
+    graph.link("yol", "sisli", "sisli").unwrap();
+    let sisli = graph.id_of("sisli").unwrap();
+    graph.remove_node("sisli").unwrap();
+
+    assert_eq!(graph.edge_count(), 1);
+    assert_eq!(linked_to(&graph, "istanbul"), ["kadikoy"]);
+    assert!(linked_to(&graph, "kadikoy").is_empty());
+    assert!(linked_from(&graph, "besiktas").is_empty());
+    assert!(matches!(
+        graph.get_node_by_id(&sisli),
+        Err(Error::NodeNotFound { .. })
+    ));
+    //the freed slot comes back under a new id, old links can't reach it
+    graph.add_node("uskudar").unwrap();
+    let uskudar = graph.id_of("uskudar").unwrap();
+    assert_ne!(uskudar, sisli);
+    assert!(graph.get_node("uskudar").unwrap().incoming().is_empty());
+    for node in graph.iter_nodes() {
+        for link in node.links() {
+            assert!(graph.has_node(link.to()));
+        }
+    }
+}
+
+#[test]
+fn link_props_and_labels() {
+    let mut graph = districts();
+    let link = graph.get_node("sisli").unwrap().links()[0].id();
+    graph.set_edge_prop(&link, "km", 3.into()).unwrap();
+    graph.label_edge(&link, "kara").unwrap();
+    let (stored, from) = graph.get_link(&link).unwrap();
+    assert_eq!(from, graph.id_of("sisli").unwrap());
+    assert_eq!(stored.relation(), "komsu");
+    assert_eq!(stored.labels(), ["kara"]);
+    assert_eq!(stored.get_prop("km"), Some(&3.into()));
+    assert_eq!(
+        graph.edge(&link).unwrap().to(),
+        &graph.id_of("besiktas").unwrap()
+    );
+}
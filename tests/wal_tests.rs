@@ -0,0 +1,189 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use graph_db::vec_graph::*;
+
+/// Fresh directory per test so parallel tests don't share database files
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graph_db_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn wal_of(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}-wal", path.display()))
+}
+
+fn first_id(graph: &Graph, alias: &str) -> NodeIndex {
+    graph.get_ids_by_alias(alias).unwrap()[0]
+}
+
+/// One logged mutation per step, the same script builds both the logged and the reference graphs
+const STEPS: usize = 9;
+
+fn step(graph: &mut Graph, i: usize) {
+    match i {
+        0 => graph.add_node("sisli").map(|_| ()),
+        1 => graph.mut_last_node(|n| n.add_label("sehir")).map(|_| ()),
+        2 => graph.add_node("merkez").map(|_| ()),
+        3 => graph.create_prop_index("tur").map(|_| ()),
+        4 => graph
+            .add_prop(&first_id(graph, "merkez"), "tur", "mahalle")
+            .map(|_| ()),
+        5 => graph
+            .add_edges_by_aliases("includes", "sisli", "merkez")
+            .map(|_| ()),
+        6 => graph.mut_last_edge(|e| e.add_prop("km", 2.5)).map(|_| ()),
+        7 => graph
+            .remove_node_by_id(&first_id(graph, "merkez"))
+            .map(|_| ()),
+        8 => graph.add_node("mcdkoy").map(|_| ()),
+        _ => unreachable!(),
+    }
+    .unwrap();
+}
+
+fn reference(steps: usize) -> Graph {
+    let mut graph = Graph::new();
+    (0..steps).for_each(|i| step(&mut graph, i));
+    graph
+}
+
+#[test]
+fn reopen_restores_graph() {
+    let path = temp_dir("reopen").join("db");
+    {
+        let mut graph = Graph::open(&path).unwrap();
+        (0..STEPS).for_each(|i| step(&mut graph, i));
+    }
+    let graph = Graph::open(&path).unwrap();
+    assert_eq!(graph, reference(STEPS));
+    //merkez was removed, mcdkoy reuses its slot with a bumped generation
+    let mcdkoy = first_id(&graph, "mcdkoy");
+    assert_eq!((mcdkoy.index(), mcdkoy.generation()), (1, 1));
+    assert!(graph.get_node(&NodeIndex::from(1)).is_err());
+    assert!(graph.has_prop_index("tur"));
+}
+
+#[test]
+fn recovery_at_every_cut() {
+    let dir = temp_dir("cuts");
+    let path = dir.join("db");
+    //log length after each step, a cut recovers every step whose record fits before it
+    let mut ends = Vec::new();
+    {
+        let options = WalOptions {
+            checkpoint_every: None,
+            sync: false,
+        };
+        let mut graph = Graph::open_with(&path, options).unwrap();
+        for i in 0..STEPS {
+            step(&mut graph, i);
+            ends.push(fs::metadata(wal_of(&path)).unwrap().len());
+        }
+    }
+    let log = fs::read(wal_of(&path)).unwrap();
+    let references: Vec<Graph> = (0..=STEPS).map(reference).collect();
+    let cut_path = dir.join("cut");
+    for cut in 0..=log.len() {
+        fs::write(wal_of(&cut_path), &log[..cut]).unwrap();
+        let graph = Graph::open(&cut_path).unwrap();
+        let steps = ends.iter().filter(|end| **end as usize <= cut).count();
+        assert_eq!(graph, references[steps], "cut at byte {}", cut);
+        drop(graph);
+        //recovery trims the torn tail so new records follow whole ones
+        let trimmed = fs::metadata(wal_of(&cut_path)).unwrap().len() as usize;
+        assert!(trimmed == 16 || ends.contains(&(trimmed as u64)));
+    }
+}
+
+#[test]
+fn checkpoint_folds_log_into_snapshot() {
+    let path = temp_dir("checkpoint").join("db");
+    let options = WalOptions {
+        checkpoint_every: Some(3),
+        sync: false,
+    };
+    {
+        let mut graph = Graph::open_with(&path, options).unwrap();
+        (0..STEPS).for_each(|i| step(&mut graph, i));
+    }
+    assert!(path.exists());
+    //checkpoints kept the log short
+    assert!(fs::metadata(wal_of(&path)).unwrap().len() < 200);
+    let mut graph = Graph::open_with(&path, options).unwrap();
+    assert_eq!(graph, reference(STEPS));
+
+    let inside = graph.transaction(|tx| Ok(tx.checkpoint()));
+    assert!(matches!(inside, Ok(Err(Error::InvalidOperation { .. }))));
+    assert!(matches!(
+        Graph::new().checkpoint(),
+        Err(Error::InvalidOperation { .. })
+    ));
+
+    graph.checkpoint().unwrap();
+    drop(graph);
+    assert_eq!(fs::metadata(wal_of(&path)).unwrap().len(), 16);
+    assert_eq!(Graph::open(&path).unwrap(), reference(STEPS));
+}
+
+#[test]
+fn transactions_checkpoint_too() {
+    let path = temp_dir("tx_checkpoint").join("db");
+    let options = WalOptions {
+        checkpoint_every: Some(2),
+        sync: false,
+    };
+    let shared = SharedGraph::new(Graph::open_with(&path, options).unwrap());
+    for i in 0..STEPS {
+        shared
+            .write(|tx| {
+                step(tx, i);
+                Ok(())
+            })
+            .unwrap();
+    }
+    drop(shared);
+    assert!(path.exists());
+    //reset by the last checkpoint, only the odd step after it is left
+    let wal_len = fs::metadata(wal_of(&path)).unwrap().len();
+    assert!(wal_len > 16 && wal_len < 100, "log is {} bytes", wal_len);
+    assert_eq!(Graph::open(&path).unwrap(), reference(STEPS));
+}
+
+#[test]
+fn stale_log_after_crashed_checkpoint() {
+    let path = temp_dir("stale").join("db");
+    let options = WalOptions {
+        checkpoint_every: None,
+        sync: false,
+    };
+    let mut graph = Graph::open_with(&path, options).unwrap();
+    (0..STEPS).for_each(|i| step(&mut graph, i));
+    let old_log = fs::read(wal_of(&path)).unwrap();
+    graph.checkpoint().unwrap();
+    drop(graph);
+    //crash between renaming the new snapshot in and resetting the log: old records must not be replayed twice
+    fs::write(wal_of(&path), old_log).unwrap();
+    assert_eq!(Graph::open(&path).unwrap(), reference(STEPS));
+}
+
+#[test]
+fn corrupt_record_ends_replay() {
+    let path = temp_dir("corrupt").join("db");
+    {
+        let mut graph = Graph::open(&path).unwrap();
+        (0..STEPS).for_each(|i| step(&mut graph, i));
+    }
+    let mut log = fs::read(wal_of(&path)).unwrap();
+    let last = log.len() - 1;
+    log[last] ^= 0xff;
+    fs::write(wal_of(&path), log).unwrap();
+    assert_eq!(Graph::open(&path).unwrap(), reference(STEPS - 1));
+
+    fs::write(&path, b"not a snapshot").unwrap();
+    assert!(matches!(Graph::open(&path), Err(Error::Corrupt { .. })));
+}